@@ -1,11 +1,15 @@
 use crate::error::{FendError, Interrupt};
 use crate::eval::evaluate_to_value;
 use crate::ident::Ident;
-use crate::interrupt::test_int;
-use crate::num::{Base, FormattingStyle, Number};
+use crate::interrupt::{self, test_int};
+use crate::num::{Base, FormattingStyle, Number, UnitSystem};
+use crate::ratio::Ratio;
 use crate::scope::Scope;
-use crate::serialize::{deserialize_u8, serialize_u8};
-use crate::value::{built_in_function::BuiltInFunction, ApplyMulHandling, Value};
+use crate::serialize::{deserialize_u8, deserialize_usize, serialize_u8, serialize_usize};
+use crate::value::{
+    built_in_function::BuiltInFunction, uncertain::Uncertain, ApplyMulHandling, Value,
+};
+use crate::Feature;
 use std::sync::Arc;
 use std::{fmt, io};
 
@@ -16,6 +20,79 @@ pub(crate) enum BitwiseBop {
     Xor,
     LeftShift,
     RightShift,
+    Nand,
+    Nor,
+    Xnor,
+}
+
+impl BitwiseBop {
+    /// Applies this operator as a logic gate, e.g. `true xor false`
+    fn apply_to_bools(self, a: bool, b: bool) -> Result<bool, FendError> {
+        Ok(match self {
+            Self::And => a && b,
+            Self::Or => a || b,
+            Self::Xor => a != b,
+            Self::Nand => !(a && b),
+            Self::Nor => !(a || b),
+            Self::Xnor => a == b,
+            Self::LeftShift | Self::RightShift => return Err(FendError::ExpectedANumber),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CompareBop {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    ApproxEqual,
+}
+
+impl CompareBop {
+    pub(crate) fn serialize(self, write: &mut impl io::Write) -> Result<(), FendError> {
+        let n = match self {
+            Self::Equal => 0,
+            Self::NotEqual => 1,
+            Self::Less => 2,
+            Self::LessOrEqual => 3,
+            Self::Greater => 4,
+            Self::GreaterOrEqual => 5,
+            Self::ApproxEqual => 6,
+        };
+        serialize_u8(n, write)?;
+        Ok(())
+    }
+
+    pub(crate) fn deserialize(read: &mut impl io::Read) -> Result<Self, FendError> {
+        Ok(match deserialize_u8(read)? {
+            0 => Self::Equal,
+            1 => Self::NotEqual,
+            2 => Self::Less,
+            3 => Self::LessOrEqual,
+            4 => Self::Greater,
+            5 => Self::GreaterOrEqual,
+            6 => Self::ApproxEqual,
+            _ => return Err(FendError::DeserializationError),
+        })
+    }
+}
+
+impl fmt::Display for CompareBop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Equal => "==",
+            Self::NotEqual => "!=",
+            Self::Less => "<",
+            Self::LessOrEqual => "<=",
+            Self::Greater => ">",
+            Self::GreaterOrEqual => ">=",
+            Self::ApproxEqual => "~=",
+        };
+        write!(f, "{s}")
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -28,6 +105,8 @@ pub(crate) enum Bop {
     Mod,
     Pow,
     Bitwise(BitwiseBop),
+    FloorDiv,
+    PlusMinus,
 }
 
 impl Bop {
@@ -45,6 +124,11 @@ impl Bop {
             Self::Bitwise(BitwiseBop::Xor) => 9,
             Self::Bitwise(BitwiseBop::LeftShift) => 10,
             Self::Bitwise(BitwiseBop::RightShift) => 11,
+            Self::FloorDiv => 12,
+            Self::PlusMinus => 13,
+            Self::Bitwise(BitwiseBop::Nand) => 14,
+            Self::Bitwise(BitwiseBop::Nor) => 15,
+            Self::Bitwise(BitwiseBop::Xnor) => 16,
         };
         serialize_u8(n, write)?;
         Ok(())
@@ -64,6 +148,11 @@ impl Bop {
             9 => Self::Bitwise(BitwiseBop::Xor),
             10 => Self::Bitwise(BitwiseBop::LeftShift),
             11 => Self::Bitwise(BitwiseBop::RightShift),
+            12 => Self::FloorDiv,
+            13 => Self::PlusMinus,
+            14 => Self::Bitwise(BitwiseBop::Nand),
+            15 => Self::Bitwise(BitwiseBop::Nor),
+            16 => Self::Bitwise(BitwiseBop::Xnor),
             _ => return Err(FendError::DeserializationError),
         })
     }
@@ -82,8 +171,13 @@ impl fmt::Display for Bop {
             Self::Bitwise(BitwiseBop::And) => "&",
             Self::Bitwise(BitwiseBop::Or) => "|",
             Self::Bitwise(BitwiseBop::Xor) => " xor ",
+            Self::Bitwise(BitwiseBop::Nand) => " nand ",
+            Self::Bitwise(BitwiseBop::Nor) => " nor ",
+            Self::Bitwise(BitwiseBop::Xnor) => " xnor ",
             Self::Bitwise(BitwiseBop::LeftShift) => "<<",
             Self::Bitwise(BitwiseBop::RightShift) => ">>",
+            Self::FloorDiv => "//",
+            Self::PlusMinus => "\u{b1}",
         };
         write!(f, "{s}")
     }
@@ -99,6 +193,7 @@ pub(crate) enum Expr {
     UnaryDiv(Box<Expr>),
     Factorial(Box<Expr>),
     Bop(Bop, Box<Expr>, Box<Expr>),
+    Compare(CompareBop, Box<Expr>, Box<Expr>),
     // Call a function or multiply the expressions
     Apply(Box<Expr>, Box<Expr>),
     // Call a function, or throw an error if lhs is not a function
@@ -112,10 +207,124 @@ pub(crate) enum Expr {
     Of(Ident, Box<Expr>),
 
     Assign(Ident, Box<Expr>),
+    // e.g. `x += 5`, which requires `x` to already be defined
+    CompoundAssign(Ident, Bop, Box<Self>),
+    // e.g. `xs[2] = 9`, which replaces an item of the list `xs`
+    IndexAssign(Ident, Box<Self>, Box<Self>),
     Statements(Box<Expr>, Box<Expr>),
+    List(Vec<Self>),
 }
 
 impl Expr {
+    /// Returns the variable that this expression (or the last of a sequence
+    /// of statements) assigns to, e.g. `x` for `x = 5` or `y = 2; x = y`
+    pub(crate) fn assigned_variable(&self) -> Option<&Ident> {
+        match self {
+            Self::Assign(ident, _)
+            | Self::CompoundAssign(ident, _, _)
+            | Self::IndexAssign(ident, _, _) => Some(ident),
+            Self::Parens(x) | Self::Statements(_, x) => x.assigned_variable(),
+            _ => None,
+        }
+    }
+
+    /// Collects all identifiers referenced by this expression. Function bodies
+    /// are skipped, because they are only evaluated when the function is called.
+    fn referenced_idents<'a>(&'a self, idents: &mut Vec<&'a str>) {
+        match self {
+            Self::Literal(_) | Self::Fn(_, _) => (),
+            Self::Ident(ident) => idents.push(ident.as_str()),
+            Self::Parens(x)
+            | Self::UnaryMinus(x)
+            | Self::UnaryPlus(x)
+            | Self::UnaryDiv(x)
+            | Self::Factorial(x)
+            | Self::Of(_, x)
+            | Self::Assign(_, x) => x.referenced_idents(idents),
+            Self::CompoundAssign(ident, _, x) => {
+                idents.push(ident.as_str());
+                x.referenced_idents(idents);
+            }
+            Self::IndexAssign(ident, index, x) => {
+                idents.push(ident.as_str());
+                index.referenced_idents(idents);
+                x.referenced_idents(idents);
+            }
+            Self::Bop(_, a, b)
+            | Self::Compare(_, a, b)
+            | Self::Apply(a, b)
+            | Self::ApplyFunctionCall(a, b)
+            | Self::ApplyMul(a, b)
+            | Self::As(a, b)
+            | Self::Statements(a, b) => {
+                a.referenced_idents(idents);
+                b.referenced_idents(idents);
+            }
+            Self::List(items) => {
+                for item in items {
+                    item.referenced_idents(idents);
+                }
+            }
+        }
+    }
+
+    /// Returns the base shared by all number literals in this expression,
+    /// or decimal if they use different bases. Returns `None` if there are
+    /// no number literals, or if the expression converts a value with `to`.
+    pub(crate) fn inferred_base(&self) -> Option<Base> {
+        let mut bases = vec![];
+        if !self.literal_bases(&mut bases) {
+            return None;
+        }
+        let first = *bases.first()?;
+        Some(if bases.iter().all(|&base| base == first) {
+            first
+        } else {
+            Base::default()
+        })
+    }
+
+    /// Returns false if a `to` conversion was found
+    fn literal_bases(&self, bases: &mut Vec<Base>) -> bool {
+        match self {
+            Self::Literal(Value::Num(n)) => {
+                bases.push(n.base());
+                true
+            }
+            Self::Literal(_) | Self::Ident(_) => true,
+            Self::As(_, _) => false,
+            Self::Parens(x)
+            | Self::UnaryMinus(x)
+            | Self::UnaryPlus(x)
+            | Self::UnaryDiv(x)
+            | Self::Factorial(x)
+            | Self::Fn(_, x)
+            | Self::Of(_, x)
+            | Self::Assign(_, x)
+            | Self::CompoundAssign(_, _, x) => x.literal_bases(bases),
+            Self::Bop(_, a, b)
+            | Self::Compare(_, a, b)
+            | Self::Apply(a, b)
+            | Self::ApplyFunctionCall(a, b)
+            | Self::ApplyMul(a, b)
+            | Self::IndexAssign(_, a, b)
+            | Self::Statements(a, b) => a.literal_bases(bases) && b.literal_bases(bases),
+            Self::List(items) => items.iter().all(|item| item.literal_bases(bases)),
+        }
+    }
+
+    fn flatten_statements<'a>(&'a self, statements: &mut Vec<&'a Self>) {
+        match self {
+            Self::Parens(x) => x.flatten_statements(statements),
+            Self::Statements(a, b) => {
+                a.flatten_statements(statements);
+                b.flatten_statements(statements);
+            }
+            _ => statements.push(self),
+        }
+    }
+
+    #[allow(clippy::too_many_lines)]
     pub(crate) fn serialize(&self, write: &mut impl io::Write) -> Result<(), FendError> {
         match self {
             Self::Literal(x) => {
@@ -192,6 +401,31 @@ impl Expr {
                 a.serialize(write)?;
                 b.serialize(write)?;
             }
+            Self::Compare(op, a, b) => {
+                serialize_u8(16, write)?;
+                op.serialize(write)?;
+                a.serialize(write)?;
+                b.serialize(write)?;
+            }
+            Self::List(items) => {
+                serialize_u8(17, write)?;
+                serialize_usize(items.len(), write)?;
+                for item in items {
+                    item.serialize(write)?;
+                }
+            }
+            Self::CompoundAssign(a, op, b) => {
+                serialize_u8(18, write)?;
+                a.serialize(write)?;
+                op.serialize(write)?;
+                b.serialize(write)?;
+            }
+            Self::IndexAssign(a, index, b) => {
+                serialize_u8(19, write)?;
+                a.serialize(write)?;
+                index.serialize(write)?;
+                b.serialize(write)?;
+            }
         }
         Ok(())
     }
@@ -242,10 +476,60 @@ impl Expr {
                 Box::new(Self::deserialize(read)?),
                 Box::new(Self::deserialize(read)?),
             ),
+            16 => Self::Compare(
+                CompareBop::deserialize(read)?,
+                Box::new(Self::deserialize(read)?),
+                Box::new(Self::deserialize(read)?),
+            ),
+            17 => {
+                let len = deserialize_usize(read)?;
+                let mut items = Vec::with_capacity(len);
+                for _ in 0..len {
+                    items.push(Self::deserialize(read)?);
+                }
+                Self::List(items)
+            }
+            18 => Self::CompoundAssign(
+                Ident::deserialize(read)?,
+                Bop::deserialize(read)?,
+                Box::new(Self::deserialize(read)?),
+            ),
+            19 => Self::IndexAssign(
+                Ident::deserialize(read)?,
+                Box::new(Self::deserialize(read)?),
+                Box::new(Self::deserialize(read)?),
+            ),
             _ => return Err(FendError::DeserializationError),
         })
     }
 
+    /// Removes parentheses that only group subexpressions. They don't need to
+    /// be kept because `format` parenthesizes every operator anyway.
+    pub(crate) fn without_parens(self) -> Self {
+        let strip = |x: Box<Self>| Box::new(x.without_parens());
+        match self {
+            Self::Parens(x) => x.without_parens(),
+            Self::Literal(_) | Self::Ident(_) => self,
+            Self::UnaryMinus(x) => Self::UnaryMinus(strip(x)),
+            Self::UnaryPlus(x) => Self::UnaryPlus(strip(x)),
+            Self::UnaryDiv(x) => Self::UnaryDiv(strip(x)),
+            Self::Factorial(x) => Self::Factorial(strip(x)),
+            Self::Bop(op, a, b) => Self::Bop(op, strip(a), strip(b)),
+            Self::Compare(op, a, b) => Self::Compare(op, strip(a), strip(b)),
+            Self::Apply(a, b) => Self::Apply(strip(a), strip(b)),
+            Self::ApplyFunctionCall(a, b) => Self::ApplyFunctionCall(strip(a), strip(b)),
+            Self::ApplyMul(a, b) => Self::ApplyMul(strip(a), strip(b)),
+            Self::As(a, b) => Self::As(strip(a), strip(b)),
+            Self::Fn(param, body) => Self::Fn(param, strip(body)),
+            Self::Of(ident, x) => Self::Of(ident, strip(x)),
+            Self::Assign(ident, x) => Self::Assign(ident, strip(x)),
+            Self::CompoundAssign(ident, op, x) => Self::CompoundAssign(ident, op, strip(x)),
+            Self::IndexAssign(ident, index, x) => Self::IndexAssign(ident, strip(index), strip(x)),
+            Self::Statements(a, b) => Self::Statements(strip(a), strip(b)),
+            Self::List(items) => Self::List(items.into_iter().map(Self::without_parens).collect()),
+        }
+    }
+
     pub(crate) fn format<I: Interrupt>(
         &self,
         ctx: &crate::Context,
@@ -253,6 +537,7 @@ impl Expr {
     ) -> Result<String, FendError> {
         Ok(match self {
             Self::Literal(Value::String(s)) => format!(r#""{}""#, s.as_ref()),
+            Self::Literal(Value::Date(d)) => format!("@{d}"),
             Self::Literal(v) => v.format_to_plain_string(0, ctx, int)?,
             Self::Ident(ident) => ident.to_string(),
             Self::Parens(x) => format!("({})", x.format(ctx, int)?),
@@ -263,6 +548,9 @@ impl Expr {
             Self::Bop(op, a, b) => {
                 format!("({}{op}{})", a.format(ctx, int)?, b.format(ctx, int)?)
             }
+            Self::Compare(op, a, b) => {
+                format!("({} {op} {})", a.format(ctx, int)?, b.format(ctx, int)?)
+            }
             Self::Apply(a, b) => format!("({} ({}))", a.format(ctx, int)?, b.format(ctx, int)?),
             Self::ApplyFunctionCall(a, b) | Self::ApplyMul(a, b) => {
                 format!("({} {})", a.format(ctx, int)?, b.format(ctx, int)?)
@@ -277,11 +565,74 @@ impl Expr {
             }
             Self::Of(a, b) => format!("{a} of {}", b.format(ctx, int)?),
             Self::Assign(a, b) => format!("{a} = {}", b.format(ctx, int)?),
+            Self::CompoundAssign(a, op, b) => format!("{a} {op}= {}", b.format(ctx, int)?),
+            Self::IndexAssign(a, index, b) => {
+                format!("{a}[{}] = {}", index.format(ctx, int)?, b.format(ctx, int)?)
+            }
             Self::Statements(a, b) => format!("{}; {}", a.format(ctx, int)?, b.format(ctx, int)?),
+            Self::List(items) => {
+                let mut res = "[".to_string();
+                for (i, item) in items.iter().enumerate() {
+                    if i != 0 {
+                        res.push_str(", ");
+                    }
+                    res.push_str(&item.format(ctx, int)?);
+                }
+                res.push(']');
+                res
+            }
         })
     }
 }
 
+/// Called when evaluating `expr` failed because the identifier `missing` is
+/// not defined. If `missing` was referenced while assigning a variable that
+/// `missing` itself depends on (e.g. `x = x + 1` or `a = b; b = a`), this
+/// returns the name of that variable.
+pub(crate) fn find_assignment_cycle(expr: &Expr, missing: &str) -> Option<Ident> {
+    fn depends_on(
+        statements: &[&Expr],
+        var: &str,
+        target: &str,
+        visited: &mut Vec<String>,
+    ) -> bool {
+        if var == target {
+            return true;
+        }
+        if visited.iter().any(|v| v == var) {
+            return false;
+        }
+        visited.push(var.to_string());
+        for (i, statement) in statements.iter().enumerate() {
+            if let Expr::Assign(name, rhs) = statement {
+                if name.as_str() == var {
+                    let mut idents = vec![];
+                    rhs.referenced_idents(&mut idents);
+                    return idents
+                        .iter()
+                        .any(|ident| depends_on(&statements[i + 1..], ident, target, visited));
+                }
+            }
+        }
+        false
+    }
+
+    let mut statements = vec![];
+    expr.flatten_statements(&mut statements);
+    // evaluation stops at the first statement that references `missing`
+    let (i, statement) = statements.iter().enumerate().find(|(_, statement)| {
+        let mut idents = vec![];
+        statement.referenced_idents(&mut idents);
+        idents.contains(&missing)
+    })?;
+    if let Expr::Assign(name, _) = statement {
+        if depends_on(&statements[i + 1..], missing, name.as_str(), &mut vec![]) {
+            return Some(name.clone());
+        }
+    }
+    None
+}
+
 /// returns true if rhs is '-1' or '(-1)'
 fn should_compute_inverse<I: Interrupt>(rhs: &Expr, int: &I) -> Result<bool, FendError> {
     if let Expr::UnaryMinus(inner) = rhs {
@@ -316,10 +667,24 @@ pub(crate) fn evaluate<I: Interrupt>(
     }
     test_int(int)?;
     Ok(match expr {
-        Expr::Literal(v) => v,
-        Expr::Ident(ident) => resolve_identifier(&ident, scope, context, int)?,
+        Expr::Literal(v) => {
+            if let Value::Date(_) = v {
+                context.use_feature(Feature::Dates)?;
+            }
+            v
+        }
+        Expr::Ident(ident) => {
+            let value = resolve_identifier(&ident, scope, context, int)?;
+            if let Value::Date(_) = value {
+                context.use_feature(Feature::Dates)?;
+            }
+            value
+        }
         Expr::Parens(x) => eval!(*x)?,
-        Expr::UnaryMinus(x) => eval!(*x)?.handle_num(|x| Ok(-x), Expr::UnaryMinus, scope)?,
+        Expr::UnaryMinus(x) => match eval!(*x)? {
+            Value::Uncertain(u) => Value::Uncertain(Box::new(u.neg())),
+            x => x.handle_num(|x| Ok(-x), Expr::UnaryMinus, scope)?,
+        },
         Expr::UnaryPlus(x) => eval!(*x)?.handle_num(Ok, Expr::UnaryPlus, scope)?,
         Expr::UnaryDiv(x) => {
             eval!(*x)?.handle_num(|x| Number::from(1).div(x, int), Expr::UnaryDiv, scope)?
@@ -328,20 +693,7 @@ pub(crate) fn evaluate<I: Interrupt>(
             eval!(*x)?.handle_num(|x| x.factorial(int), Expr::Factorial, scope)?
         }
         Expr::Bop(Bop::Plus, a, b) => evaluate_add(eval!(*a)?, eval!(*b)?, scope, int)?,
-        Expr::Bop(Bop::Minus, a, b) => {
-            let a = eval!(*a)?;
-            match a {
-                Value::Num(a) => Value::Num(Box::new(a.sub(eval!(*b)?.expect_num()?, int)?)),
-                f @ (Value::BuiltInFunction(_) | Value::Fn(_, _, _)) => f.apply(
-                    Expr::UnaryMinus(b),
-                    ApplyMulHandling::OnlyApply,
-                    scope,
-                    context,
-                    int,
-                )?,
-                _ => return Err(FendError::InvalidOperandsForSubtraction),
-            }
-        }
+        Expr::Bop(Bop::Minus, a, b) => evaluate_sub(eval!(*a)?, *b, scope, context, int)?,
         Expr::Bop(Bop::Pow, a, b) => {
             let lhs = eval!(*a)?;
             if should_compute_inverse(&*b, int)? {
@@ -354,8 +706,12 @@ pub(crate) fn evaluate<I: Interrupt>(
                     return Ok(res);
                 }
             }
+            let rhs = eval!(*b)?;
+            if let (Value::Uncertain(_), _) | (_, Value::Uncertain(_)) = (&lhs, &rhs) {
+                return Uncertain::bop(Bop::Pow, lhs, rhs, int);
+            }
             lhs.handle_two_nums(
-                eval!(*b)?,
+                rhs,
                 |a, b| a.pow(b, int),
                 |a| {
                     |f| {
@@ -378,13 +734,15 @@ pub(crate) fn evaluate<I: Interrupt>(
                 scope,
             )?
         }
-        Expr::Bop(bop, a, b) => eval!(*a)?.handle_two_nums(
-            eval!(*b)?,
-            |a, b| a.bop(bop, b, context, int),
-            |a| |f| Expr::Bop(bop, f, Box::new(Expr::Literal(Value::Num(Box::new(a))))),
-            |a| |f| Expr::Bop(bop, Box::new(Expr::Literal(Value::Num(Box::new(a)))), f),
-            scope,
-        )?,
+        Expr::Bop(Bop::PlusMinus, a, b) => Value::Uncertain(Box::new(Uncertain::new(
+            eval!(*a)?.expect_num()?,
+            eval!(*b)?.expect_num()?,
+            int,
+        )?)),
+        Expr::Bop(bop, a, b) => evaluate_bop(bop, eval!(*a)?, eval!(*b)?, scope, context, int)?,
+        Expr::Compare(op, a, b) => {
+            Value::Bool(eval!(*a)?.compare(&eval!(*b)?, op, context, int)?)
+        }
         Expr::Apply(a, b) | Expr::ApplyMul(a, b) => {
             if let (Expr::Ident(a), Expr::Ident(b)) = (&*a, &*b) {
                 let ident = format!("{a}_{b}");
@@ -392,23 +750,173 @@ pub(crate) fn evaluate<I: Interrupt>(
                     return Ok(val);
                 }
             }
-            eval!(*a)?.apply(*b, ApplyMulHandling::Both, scope, context, int)?
+            evaluate_apply(*a, *b, ApplyMulHandling::Both, scope, context, int)?
         }
         Expr::ApplyFunctionCall(a, b) => {
-            eval!(*a)?.apply(*b, ApplyMulHandling::OnlyApply, scope, context, int)?
+            evaluate_apply(*a, *b, ApplyMulHandling::OnlyApply, scope, context, int)?
         }
         Expr::As(a, b) => evaluate_as(*a, *b, scope, context, int)?,
         Expr::Fn(a, b) => Value::Fn(a, b, scope),
         Expr::Of(a, b) => eval!(*b)?.get_object_member(&a)?,
         Expr::Assign(a, b) => {
+            context.use_feature(Feature::Variables)?;
             let rhs = evaluate(*b, scope, context, int)?;
-            context.variables.insert(a.to_string(), rhs.clone());
+            Arc::make_mut(&mut context.variables).insert(a.to_string(), rhs.clone());
+            // unit definitions may refer to the newly assigned variable
+            Arc::make_mut(&mut context.unit_cache)
+                .retain(|_, (_, dependencies)| !dependencies.iter().any(|d| d == a.as_str()));
             rhs
         }
+        Expr::CompoundAssign(a, op, b) => {
+            if !context.variables.contains_key(a.as_str()) {
+                return Err(FendError::UndefinedVariable(a));
+            }
+            let rhs = Expr::Bop(op, Box::new(Expr::Ident(a.clone())), b);
+            evaluate(Expr::Assign(a, Box::new(rhs)), scope, context, int)?
+        }
+        Expr::IndexAssign(a, index, b) => {
+            evaluate_index_assign(a, *index, *b, scope, context, int)?
+        }
         Expr::Statements(a, b) => {
             let _lhs = evaluate(*a, scope.clone(), context, int)?;
             evaluate(*b, scope, context, int)?
         }
+        Expr::List(items) => Value::List(
+            items
+                .into_iter()
+                .map(|item| eval!(item))
+                .collect::<Result<_, _>>()?,
+        ),
+    })
+}
+
+/// Replaces an item of a list variable, e.g. for `xs[2] = 9`
+fn evaluate_index_assign<I: Interrupt>(
+    name: Ident,
+    index: Expr,
+    item: Expr,
+    scope: Option<Arc<Scope>>,
+    context: &mut crate::Context,
+    int: &I,
+) -> Result<Value, FendError> {
+    let Some(list) = context.variables.get(name.as_str()).cloned() else {
+        return Err(FendError::UndefinedVariable(name));
+    };
+    let index = evaluate(index, scope.clone(), context, int)?.expect_num()?;
+    let item = evaluate(item, scope.clone(), context, int)?;
+    let list = list.with_item_replaced(index, item, int)?;
+    evaluate(
+        Expr::Assign(name, Box::new(Expr::Literal(list))),
+        scope,
+        context,
+        int,
+    )
+}
+
+/// Applies `f` to `arg`. `sec` is usually a unit (seconds), but when it's
+/// applied directly to an argument, e.g. `sec(pi/3)` or `sec 0`, it's the
+/// secant function.
+fn evaluate_apply<I: Interrupt>(
+    f: Expr,
+    arg: Expr,
+    apply_mul_handling: ApplyMulHandling,
+    scope: Option<Arc<Scope>>,
+    context: &mut crate::Context,
+    int: &I,
+) -> Result<Value, FendError> {
+    let ident = match &f {
+        Expr::Ident(ident) => ident.as_str(),
+        _ => "",
+    };
+    // `describe` and `help` take the name of a function or unit, rather
+    // than its value
+    if matches!(ident, "describe" | "help") && !context.variables.contains_key(ident) {
+        if let Expr::Ident(name) = arg.clone().without_parens() {
+            return Ok(Value::String(
+                crate::describe::describe(name.as_str(), context, int)?.into(),
+            ));
+        }
+    }
+    // `truthtable` evaluates its expression once per row, so the expression
+    // and its variables are passed unevaluated
+    if ident == "truthtable" && !context.variables.contains_key(ident) {
+        return crate::truth_table::truth_table(arg, scope.as_ref(), context, int);
+    }
+    if !context.variables.contains_key(ident) {
+        if let Some(host_fn) = context.host_functions.get(ident).cloned() {
+            let arg = evaluate(arg, scope, context, int)?;
+            return crate::host_function::call(ident, &host_fn, arg, context, int);
+        }
+    }
+    let (is_sec, is_min, is_bits) = (ident == "sec", ident == "min", ident == "bits");
+    let f = match evaluate(f, scope.clone(), context, int)? {
+        Value::Num(_) if is_sec => Value::BuiltInFunction(BuiltInFunction::Sec),
+        // `min` is minutes and `bits` is a unit unless they're applied to a list
+        Value::Num(n) if is_min || is_bits => {
+            let arg = evaluate(arg, scope.clone(), context, int)?;
+            let f = match arg {
+                Value::List(_) if is_min => Value::BuiltInFunction(BuiltInFunction::Min),
+                Value::List(_) => Value::BuiltInFunction(BuiltInFunction::Bits),
+                _ => Value::Num(n),
+            };
+            return f.apply(Expr::Literal(arg), apply_mul_handling, scope, context, int);
+        }
+        value => value,
+    };
+    f.apply(arg, apply_mul_handling, scope, context, int)
+}
+
+fn evaluate_bop<I: Interrupt>(
+    bop: Bop,
+    lhs: Value,
+    rhs: Value,
+    scope: Option<Arc<Scope>>,
+    context: &mut crate::Context,
+    int: &I,
+) -> Result<Value, FendError> {
+    if let (Value::Uncertain(_), _) | (_, Value::Uncertain(_)) = (&lhs, &rhs) {
+        return Uncertain::bop(bop, lhs, rhs, int);
+    }
+    if let (Bop::Bitwise(op), Value::Bool(a), Value::Bool(b)) = (bop, &lhs, &rhs) {
+        return Ok(Value::Bool(op.apply_to_bools(*a, *b)?));
+    }
+    lhs.handle_two_nums(
+        rhs,
+        |a, b| a.bop(bop, b, context, int),
+        |a| |f| Expr::Bop(bop, f, Box::new(Expr::Literal(Value::Num(Box::new(a))))),
+        |a| |f| Expr::Bop(bop, Box::new(Expr::Literal(Value::Num(Box::new(a)))), f),
+        scope,
+    )
+}
+
+fn evaluate_sub<I: Interrupt>(
+    a: Value,
+    b: Expr,
+    scope: Option<Arc<Scope>>,
+    context: &mut crate::Context,
+    int: &I,
+) -> Result<Value, FendError> {
+    macro_rules! eval {
+        ($e:expr) => {
+            evaluate($e, scope.clone(), context, int)
+        };
+    }
+    Ok(match a {
+        Value::Num(a) => match eval!(b)? {
+            b @ Value::Uncertain(_) => Uncertain::bop(Bop::Minus, Value::Num(a), b, int)?,
+            b => Value::Num(Box::new(a.sub(b.expect_num()?, int)?)),
+        },
+        a @ Value::Uncertain(_) => Uncertain::bop(Bop::Minus, a, eval!(b)?, int)?,
+        Value::Date(a) => a.sub(eval!(b)?, context, int)?,
+        Value::Ip(a) => a.sub(eval!(b)?, int)?,
+        f @ (Value::BuiltInFunction(_) | Value::Fn(_, _, _)) => f.apply(
+            Expr::UnaryMinus(Box::new(b)),
+            ApplyMulHandling::OnlyApply,
+            scope,
+            context,
+            int,
+        )?,
+        _ => return Err(FendError::InvalidOperandsForSubtraction),
     })
 }
 
@@ -450,10 +958,17 @@ fn evaluate_add<I: Interrupt>(
             scope,
         ),
         (Value::Date(d), b) => d.add(b)?,
+        (Value::Ip(ip), Value::Num(n)) | (Value::Num(n), Value::Ip(ip)) => {
+            Value::Ip(ip.add(*n, int)?)
+        }
+        (a @ Value::Uncertain(_), b) | (a, b @ Value::Uncertain(_)) => {
+            Uncertain::bop(Bop::Plus, a, b, int)?
+        }
         _ => return Err(FendError::ExpectedANumber),
     })
 }
 
+#[allow(clippy::too_many_lines)]
 fn evaluate_as<I: Interrupt>(
     a: Expr,
     b: Expr,
@@ -461,6 +976,16 @@ fn evaluate_as<I: Interrupt>(
     context: &mut crate::Context,
     int: &I,
 ) -> Result<Value, FendError> {
+    // e.g. `@2024-03-15 14:37 to start of day`
+    if let Expr::Of(position, period) = &b {
+        if let ("start" | "end", Expr::Ident(period)) = (position.as_str(), &**period) {
+            context.use_feature(Feature::Dates)?;
+            let date = evaluate(a, scope, context, int)?.expect_date()?;
+            return Ok(Value::Date(
+                date.start_or_end_of(period.as_str(), position.as_str() == "end")?,
+            ));
+        }
+    }
     if let Expr::Ident(ident) = &b {
         match ident.as_str() {
             "bool" | "boolean" => {
@@ -468,6 +993,7 @@ fn evaluate_as<I: Interrupt>(
                 return Ok(Value::Bool(!num.is_zero()));
             }
             "date" => {
+                context.use_feature(Feature::Dates)?;
                 let a = evaluate(a, scope, context, int)?;
                 return if let Value::String(s) = a {
                     Ok(Value::Date(crate::date::Date::parse(s.as_ref())?))
@@ -475,6 +1001,45 @@ fn evaluate_as<I: Interrupt>(
                     Err(FendError::ExpectedAString)
                 };
             }
+            "unix" | "timestamp" => {
+                context.use_feature(Feature::Dates)?;
+                return match evaluate(a, scope, context, int)? {
+                    Value::Date(d) => {
+                        // dates are in UTC, like `@unix:` literals
+                        let secs = d.seconds_since_epoch();
+                        let num = Number::from(secs.unsigned_abs());
+                        Ok(Value::Num(Box::new(if secs < 0 { -num } else { num })))
+                    }
+                    _ => Err(FendError::CannotConvertValueTo("unix timestamp")),
+                };
+            }
+            "polar" => return evaluate(a, scope, context, int)?.into_polar(int),
+            "si" | "SI" => {
+                context.use_feature(Feature::UnitConversion)?;
+                return evaluate(a, scope, context, int)?.convert_to_unit_system(
+                    UnitSystem::Si,
+                    context,
+                    int,
+                );
+            }
+            "cgs" | "CGS" => {
+                context.use_feature(Feature::UnitConversion)?;
+                return evaluate(a, scope, context, int)?.convert_to_unit_system(
+                    UnitSystem::Cgs,
+                    context,
+                    int,
+                );
+            }
+            "cartesian" => {
+                return evaluate(a, scope.clone(), context, int)?
+                    .into_cartesian(scope, context, int);
+            }
+            "weekday" | "day_of_week" => {
+                return match evaluate(a, scope, context, int)? {
+                    Value::Date(d) => Ok(Value::DayOfWeek(d.day_of_week())),
+                    _ => Err(FendError::CannotConvertValueTo("weekday")),
+                };
+            }
             "string" => {
                 return Ok(Value::String(
                     evaluate(a, scope, context, int)?
@@ -500,37 +1065,103 @@ fn evaluate_as<I: Interrupt>(
                 }
                 return Err(FendError::ExpectedAString);
             }
+            "base64" => {
+                let a = evaluate(a, scope, context, int)?.expect_string()?;
+                return Ok(Value::String(crate::encoding::encode_base64(&a).into()));
+            }
+            "words" => {
+                let num = evaluate(a, scope, context, int)?.expect_num()?;
+                if !num.is_unitless(int)? {
+                    return Err(FendError::NumberWithUnitToInt);
+                }
+                let negative = num.compare(&0.into(), int)? == std::cmp::Ordering::Less;
+                let n = if negative { -num } else { num }.try_as_usize(int)?;
+                let words = crate::words::to_words(n);
+                return Ok(Value::String(
+                    if negative {
+                        format!("negative {words}")
+                    } else {
+                        words
+                    }
+                    .into(),
+                ));
+            }
+            "ratio" => {
+                return Ok(Value::Ratio(Box::new(
+                    match evaluate(a, scope, context, int)? {
+                        Value::Ratio(r) => r.simplify(int)?,
+                        a => Ratio::from_number(a.expect_num()?, int)?,
+                    },
+                )));
+            }
+            "ordinal" => {
+                let n = evaluate(a, scope, context, int)?
+                    .expect_num()?
+                    .try_as_usize(int)?;
+                return Ok(Value::String(crate::words::to_ordinal(n).into()));
+            }
             _ => (),
         }
     }
     Ok(match evaluate(b, scope.clone(), context, int)? {
-        Value::Num(b) => Value::Num(Box::new(
-            evaluate(a, scope, context, int)?
-                .expect_num()?
-                .convert_to(*b, int)?,
-        )),
-        Value::Format(fmt) => Value::Num(Box::new(
-            evaluate(a, scope, context, int)?
-                .expect_num()?
-                .with_format(fmt),
-        )),
+        Value::Num(b) => {
+            context.use_feature(Feature::UnitConversion)?;
+            match evaluate(a, scope, context, int)?.ratio_to_num(int)? {
+                Value::Uncertain(u) => Value::Uncertain(Box::new(u.convert_to(*b, int)?)),
+                a => {
+                    let result = a.expect_num()?.convert_to(*b, int)?;
+                    if let Some(unit) = result.fractional_discrete_unit(int)? {
+                        context.add_warning(&format!("result is not a whole number of {unit}"));
+                    }
+                    Value::Num(Box::new(result))
+                }
+            }
+        }
+        Value::Format(fmt) => {
+            let a = match fmt.digits() {
+                // compute functions like `sin` to the requested number of
+                // digits
+                Some(digits) if int.precision() < Some(digits) => {
+                    let int = interrupt::WithPrecision::new(int, digits);
+                    evaluate(a, scope, context, &int)?
+                }
+                _ => evaluate(a, scope, context, int)?,
+            };
+            match a.ratio_to_num(int)? {
+                Value::Uncertain(u) => Value::Uncertain(Box::new(u.with_format(fmt))),
+                a => Value::Num(Box::new(a.expect_num()?.with_format(fmt))),
+            }
+        }
+        // e.g. `192.168.1.5 in 192.168.1.0/24`
+        Value::Ip(network) => match evaluate(a, scope, context, int)? {
+            Value::Ip(ip) => Value::Bool(network.contains(ip)),
+            _ => return Err(FendError::CannotConvertValueTo("IP address")),
+        },
         Value::Dp => {
             return Err(FendError::SpecifyNumDp);
         }
         Value::Sf => {
             return Err(FendError::SpecifyNumSf);
         }
-        Value::Base(base) => Value::Num(Box::new(
-            evaluate(a, scope, context, int)?
-                .expect_num()?
-                .with_base(base),
-        )),
+        Value::BuiltInFunction(BuiltInFunction::Fixed) => {
+            return Err(FendError::SpecifyNumFixed);
+        }
+        Value::Base(base) => {
+            context.base_converted = true;
+            match evaluate(a, scope, context, int)?.ratio_to_num(int)? {
+                Value::String(s) if base.base_as_u8() == 16 => {
+                    Value::String(crate::encoding::encode_hex(&s).into())
+                }
+                a => Value::Num(Box::new(a.expect_num()?.with_base(base))),
+            }
+        }
         other => {
             return Err(FendError::CannotConvertValueTo(other.type_name()));
         }
     })
 }
 
+#[allow(clippy::too_many_lines)]
 pub(crate) fn resolve_identifier<I: Interrupt>(
     ident: &Ident,
     scope: Option<Arc<Scope>>,
@@ -547,31 +1178,64 @@ pub(crate) fn resolve_identifier<I: Interrupt>(
             return Ok(val);
         }
     }
+    if let Some(dependencies) = &mut context.unit_dependencies {
+        dependencies.push(ident.to_string());
+    }
     if let Some(val) = context.variables.get(ident.as_str()) {
-        return Ok(val.clone());
+        let val = val.clone();
+        context.use_feature(Feature::Variables)?;
+        return Ok(val);
+    }
+    if context.host_functions.contains_key(ident.as_str()) {
+        // calls like `price(1)` are handled by `evaluate_apply`, but the
+        // function can also be used as a value, e.g. in `3 price(1)`
+        let x = Ident::new_str("x");
+        return Ok(Value::Fn(
+            x.clone(),
+            Box::new(Expr::ApplyFunctionCall(
+                Box::new(Expr::Ident(ident.clone())),
+                Box::new(Expr::Ident(x)),
+            )),
+            scope,
+        ));
     }
     Ok(match ident.as_str() {
         "pi" | "\u{3c0}" => Value::Num(Box::new(Number::pi())),
         "tau" | "\u{3c4}" => Value::Num(Box::new(Number::pi().mul(2.into(), int)?)),
-        "e" => evaluate_to_value("approx. 2.718281828459045235", scope, context, int)?,
+        "e" => Value::Num(Box::new(Number::e(int)?)),
         "phi" => evaluate_to_value("(1 + sqrt(5))/2", scope, context, int)?,
-        "i" => Value::Num(Box::new(Number::i())),
+        "i" => {
+            context.use_feature(Feature::ComplexNumbers)?;
+            Value::Num(Box::new(Number::i()))
+        }
         "true" => Value::Bool(true),
         "false" => Value::Bool(false),
         "sample" | "roll" => Value::BuiltInFunction(BuiltInFunction::Sample),
         "sqrt" => evaluate_to_value("x: x^(1/2)", scope, context, int)?,
         "cbrt" => evaluate_to_value("x: x^(1/3)", scope, context, int)?,
-        "conjugate" => Value::BuiltInFunction(BuiltInFunction::Conjugate),
+        "conjugate" | "conj" => Value::BuiltInFunction(BuiltInFunction::Conjugate),
+        "arg" => Value::BuiltInFunction(BuiltInFunction::Arg),
+        "re" => Value::BuiltInFunction(BuiltInFunction::Re),
+        "im" => Value::BuiltInFunction(BuiltInFunction::Im),
         "abs" => Value::BuiltInFunction(BuiltInFunction::Abs),
         "sin" => Value::BuiltInFunction(BuiltInFunction::Sin),
         "cos" => Value::BuiltInFunction(BuiltInFunction::Cos),
         "tan" => Value::BuiltInFunction(BuiltInFunction::Tan),
+        "csc" => Value::BuiltInFunction(BuiltInFunction::Csc),
+        "cot" => Value::BuiltInFunction(BuiltInFunction::Cot),
         "asin" => Value::BuiltInFunction(BuiltInFunction::Asin),
         "acos" => Value::BuiltInFunction(BuiltInFunction::Acos),
         "atan" => Value::BuiltInFunction(BuiltInFunction::Atan),
+        "atan2" => Value::BuiltInFunction(BuiltInFunction::Atan2),
+        "asec" => Value::BuiltInFunction(BuiltInFunction::Asec),
+        "acsc" => Value::BuiltInFunction(BuiltInFunction::Acsc),
+        "acot" => Value::BuiltInFunction(BuiltInFunction::Acot),
         "sinh" => Value::BuiltInFunction(BuiltInFunction::Sinh),
         "cosh" => Value::BuiltInFunction(BuiltInFunction::Cosh),
         "tanh" => Value::BuiltInFunction(BuiltInFunction::Tanh),
+        "sech" => Value::BuiltInFunction(BuiltInFunction::Sech),
+        "csch" => Value::BuiltInFunction(BuiltInFunction::Csch),
+        "coth" => Value::BuiltInFunction(BuiltInFunction::Coth),
         "asinh" => Value::BuiltInFunction(BuiltInFunction::Asinh),
         "acosh" => Value::BuiltInFunction(BuiltInFunction::Acosh),
         "atanh" => Value::BuiltInFunction(BuiltInFunction::Atanh),
@@ -587,6 +1251,13 @@ pub(crate) fn resolve_identifier<I: Interrupt>(
         "frac" | "fraction" => Value::Format(FormattingStyle::ImproperFraction),
         "mixed_frac" | "mixed_fraction" => Value::Format(FormattingStyle::MixedFraction),
         "float" => Value::Format(FormattingStyle::ExactFloat),
+        "sci" | "scientific" => Value::Format(FormattingStyle::Scientific(
+            context.scientific_notation_style,
+        )),
+        "eng" | "engineering" => Value::Format(FormattingStyle::Engineering(
+            context.scientific_notation_style,
+        )),
+        "fixed" => Value::BuiltInFunction(BuiltInFunction::Fixed),
         "dp" => Value::Dp,
         "sf" => Value::Sf,
         "base" => Value::BuiltInFunction(BuiltInFunction::Base),
@@ -608,6 +1279,45 @@ pub(crate) fn resolve_identifier<I: Interrupt>(
             ("volume".into(), eval_box!("1.08321e12 km^3")),
         ]),
         "today" => Value::Date(crate::date::Date::today(context)?),
+        "is_leap_year" => Value::BuiltInFunction(BuiltInFunction::IsLeapYear),
+        "divmod" => Value::BuiltInFunction(BuiltInFunction::DivMod),
+        "approx_equal" => Value::BuiltInFunction(BuiltInFunction::ApproxEqual),
+        "change" | "delta" => Value::BuiltInFunction(BuiltInFunction::Change),
+        "pct_change" => Value::BuiltInFunction(BuiltInFunction::PctChange),
+        "set_bits" => Value::BuiltInFunction(BuiltInFunction::SetBits),
+        "sum" => Value::BuiltInFunction(BuiltInFunction::Sum),
+        "product" => Value::BuiltInFunction(BuiltInFunction::Product),
+        "max" => Value::BuiltInFunction(BuiltInFunction::Max),
+        "argmin" => Value::BuiltInFunction(BuiltInFunction::ArgMin),
+        "argmax" => Value::BuiltInFunction(BuiltInFunction::ArgMax),
+        "span" | "range" => Value::BuiltInFunction(BuiltInFunction::Span),
+        "percentile" => Value::BuiltInFunction(BuiltInFunction::Percentile),
+        "quartile" => Value::BuiltInFunction(BuiltInFunction::Quartile),
+        "distance" => Value::BuiltInFunction(BuiltInFunction::Distance),
+        "solve" => Value::BuiltInFunction(BuiltInFunction::Solve),
+        "zip" => Value::BuiltInFunction(BuiltInFunction::Zip),
+        "enumerate" => Value::BuiltInFunction(BuiltInFunction::Enumerate),
+        "hist" => Value::BuiltInFunction(BuiltInFunction::Hist),
+        "simplify" => Value::BuiltInFunction(BuiltInFunction::Simplify),
+        "choose" => Value::BuiltInFunction(BuiltInFunction::Choose),
+        "shuffle" => Value::BuiltInFunction(BuiltInFunction::Shuffle),
+        "numerator" => Value::BuiltInFunction(BuiltInFunction::Numerator),
+        "denominator" => Value::BuiltInFunction(BuiltInFunction::Denominator),
+        "round" => Value::BuiltInFunction(BuiltInFunction::Round),
+        "nearest" => Value::BuiltInFunction(BuiltInFunction::Nearest),
+        "period" => Value::BuiltInFunction(BuiltInFunction::Period),
+        "is_nan" => Value::BuiltInFunction(BuiltInFunction::IsNan),
+        "is_infinite" => Value::BuiltInFunction(BuiltInFunction::IsInfinite),
+        "is_finite" => Value::BuiltInFunction(BuiltInFunction::IsFinite),
+        "is_integer" => Value::BuiltInFunction(BuiltInFunction::IsInteger),
+        "is_rational" => Value::BuiltInFunction(BuiltInFunction::IsRational),
+        "is_irrational" => Value::BuiltInFunction(BuiltInFunction::IsIrrational),
+        "is_complex" => Value::BuiltInFunction(BuiltInFunction::IsComplex),
+        "from_base64" => Value::BuiltInFunction(BuiltInFunction::FromBase64),
+        "from_hex" => Value::BuiltInFunction(BuiltInFunction::FromHex),
+        "md5" => Value::BuiltInFunction(BuiltInFunction::Md5),
+        "sha256" => Value::BuiltInFunction(BuiltInFunction::Sha256),
+        "crc32" => Value::BuiltInFunction(BuiltInFunction::Crc32),
         "tomorrow" => Value::Date(crate::date::Date::today(context)?.next()),
         "yesterday" => Value::Date(crate::date::Date::today(context)?.prev()),
         _ => return crate::units::query_unit(ident.as_str(), context, int),
@@ -4,20 +4,34 @@ mod day;
 mod day_of_week;
 mod month;
 mod parser;
+mod time;
 mod year;
 
 use day::Day;
 pub(crate) use day_of_week::DayOfWeek;
 pub(crate) use month::Month;
+use time::Time;
 use year::Year;
 
-use crate::{error::FendError, ident::Ident, value::Value};
+use crate::{
+    error::{FendError, Interrupt},
+    ident::Ident,
+    num::Number,
+    units::query_unit_static,
+    value::Value,
+};
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+/// Returns whether the given year is a leap year in the Gregorian calendar
+pub(crate) fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+#[derive(Copy, Clone, Eq)]
 pub(crate) struct Date {
     year: Year,
     month: Month,
     day: Day,
+    time: Option<Time>,
 }
 
 impl Date {
@@ -29,43 +43,70 @@ impl Date {
         };
         let mut ms_since_epoch: i64 = current_time_info.elapsed_unix_time_ms.try_into().unwrap();
         ms_since_epoch -= current_time_info.timezone_offset_secs * 1000;
-        let mut days = ms_since_epoch / 86_400_000; // no leap seconds
-        let mut year = Year::new(1970);
-        while days >= year.number_of_days().into() {
-            year = year.next();
-            days -= i64::from(year.number_of_days());
-        }
-        let mut month = Month::January;
-        while days >= month.number_of_days(year).into() {
-            month = month.next();
-            days -= i64::from(month.number_of_days(year));
-        }
+        let days = ms_since_epoch.div_euclid(86_400_000); // no leap seconds
+        Self::from_days_since_epoch(days)
+    }
+
+    /// Returns the date `days` days after 1 January 1970, using the algorithm
+    /// from <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+    fn from_days_since_epoch(days: i64) -> Result<Self, FendError> {
+        let days = days.checked_add(719_468).ok_or(FendError::DateOutOfRange)?;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let month = if month < 10 { month + 3 } else { month - 9 };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        // there is no year 0, so year 0 becomes 1 BC
+        let year = if year <= 0 { year - 1 } else { year };
         Ok(Self {
-            year,
-            month,
-            day: Day::new(days.try_into().unwrap()),
+            year: Year::new(year.try_into().map_err(|_| FendError::DateOutOfRange)?),
+            month: Month::try_from(u8::try_from(month).unwrap())
+                .map_err(|_| FendError::DateOutOfRange)?,
+            day: Day::new(day.try_into().unwrap()),
+            time: None,
         })
     }
 
-    fn day_of_week(self) -> DayOfWeek {
-        let d1 = (1
-            + 5 * ((self.year.value() - 1) % 4)
-            + 4 * ((self.year.value() - 1) % 100)
-            + 6 * ((self.year.value() - 1) % 400))
-            % 7;
-        let ms = match self.month {
-            Month::January => (0, 0),
-            Month::February => (3, 3),
-            Month::March | Month::November => (3, 4),
-            Month::April | Month::July => (6, 0),
-            Month::May => (1, 2),
-            Month::June => (4, 5),
-            Month::August => (2, 3),
-            Month::September | Month::December => (5, 6),
-            Month::October => (0, 1),
-        };
-        let m = if self.year.is_leap_year() { ms.1 } else { ms.0 };
-        match (d1 + m + i32::from(self.day.value() - 1)) % 7 {
+    /// Returns the number of days since 1 January 1970, using the algorithm
+    /// from <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+    fn days_since_epoch(self) -> i64 {
+        // there is no year 0, so 1 BC becomes year 0
+        let year = i64::from(self.year.value()) + i64::from(self.year.value() < 0);
+        let month = i64::from(self.month.as_u8());
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + i64::from(self.day.value()) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    pub(crate) fn seconds_since_epoch(self) -> i64 {
+        self.days_since_epoch() * 86_400
+            + i64::from(self.time.map_or(0, Time::seconds_since_midnight))
+    }
+
+    /// Converts a Unix timestamp (in seconds) to a UTC date and time
+    fn from_unix_timestamp(secs: i64) -> Result<Self, FendError> {
+        let date = Self::from_days_since_epoch(secs.div_euclid(86_400))?;
+        let time = Time::from_seconds_since_midnight(secs.rem_euclid(86_400).try_into().unwrap());
+        Ok(date.with_time(time))
+    }
+
+    fn with_time(self, time: Time) -> Self {
+        Self {
+            time: Some(time),
+            ..self
+        }
+    }
+
+    pub(crate) fn day_of_week(self) -> DayOfWeek {
+        // 1 January 1970 was a Thursday
+        match (self.days_since_epoch() + 4).rem_euclid(7) {
             0 => DayOfWeek::Sunday,
             1 => DayOfWeek::Monday,
             2 => DayOfWeek::Tuesday,
@@ -83,18 +124,21 @@ impl Date {
                 day: Day::new(self.day.value() + 1),
                 month: self.month,
                 year: self.year,
+                time: self.time,
             }
         } else if self.month == Month::December {
             Self {
                 day: Day::new(1),
                 month: Month::January,
                 year: self.year.next(),
+                time: self.time,
             }
         } else {
             Self {
                 day: Day::new(1),
                 month: self.month.next(),
                 year: self.year,
+                time: self.time,
             }
         }
     }
@@ -105,12 +149,14 @@ impl Date {
                 day: Day::new(self.day.value() - 1),
                 month: self.month,
                 year: self.year,
+                time: self.time,
             }
         } else if self.month == Month::January {
             Self {
                 day: Day::new(31),
                 month: Month::December,
                 year: self.year.prev(),
+                time: self.time,
             }
         } else {
             let month = self.month.prev();
@@ -118,6 +164,7 @@ impl Date {
                 day: Day::new(Month::number_of_days(month, self.year)),
                 month,
                 year: self.year,
+                time: self.time,
             }
         }
     }
@@ -126,10 +173,20 @@ impl Date {
         parser::parse_date(s)
     }
 
+    /// Parses a date literal (e.g. `2024-01-01` or `1 Jan 2024 15:30`) at the
+    /// start of the input, returning the remaining input
+    pub(crate) fn parse_prefix(s: &str) -> Result<(Self, &str), FendError> {
+        parser::parse_date_prefix(s)
+    }
+
     pub(crate) fn serialize(self, write: &mut impl io::Write) -> Result<(), FendError> {
         self.year.serialize(write)?;
         self.month.serialize(write)?;
         self.day.serialize(write)?;
+        crate::serialize::serialize_bool(self.time.is_some(), write)?;
+        if let Some(time) = self.time {
+            time.serialize(write)?;
+        }
         Ok(())
     }
 
@@ -138,6 +195,11 @@ impl Date {
             year: Year::deserialize(read)?,
             month: Month::deserialize(read)?,
             day: Day::deserialize(read)?,
+            time: if crate::serialize::deserialize_bool(read)? {
+                Some(Time::deserialize(read)?)
+            } else {
+                None
+            },
         })
     }
 
@@ -154,15 +216,204 @@ impl Date {
         let int = &crate::interrupt::Never::default();
         if rhs.unit_equal_to("day") {
             let num_days = rhs.try_as_usize_unit(int)?;
-            let mut result = self;
-            for _ in 0..num_days {
-                result = result.next();
+            Ok(Value::Date(self.add_days(num_days, true)?))
+        } else {
+            Err(FendError::ExpectedANumber)
+        }
+    }
+
+    /// Moves the date forwards (or backwards) by the given number of days,
+    /// keeping the time of day
+    fn add_days(self, num_days: usize, forwards: bool) -> Result<Self, FendError> {
+        let num_days: i64 = num_days.try_into().map_err(|_| FendError::DateOutOfRange)?;
+        let days = if forwards {
+            self.days_since_epoch().checked_add(num_days)
+        } else {
+            self.days_since_epoch().checked_sub(num_days)
+        };
+        let result = Self::from_days_since_epoch(days.ok_or(FendError::DateOutOfRange)?)?;
+        Ok(Self {
+            time: self.time,
+            ..result
+        })
+    }
+
+    /// Subtracts either a number of days, or another date (returning the
+    /// difference in days)
+    pub(crate) fn sub<I: Interrupt>(
+        self,
+        rhs: Value,
+        context: &mut crate::Context,
+        int: &I,
+    ) -> Result<Value, FendError> {
+        if let Value::Date(rhs) = rhs {
+            let seconds = self.seconds_since_epoch() - rhs.seconds_since_epoch();
+            let mut days = Number::from(seconds.unsigned_abs())
+                .div(Number::from(86_400), int)?
+                .mul(query_unit_static("day", context, int)?.expect_num()?, int)?;
+            if seconds < 0 {
+                days = -days;
             }
-            Ok(Value::Date(result))
+            return Ok(Value::Num(Box::new(days)));
+        }
+        let rhs = rhs.expect_num()?;
+        if rhs.unit_equal_to("day") {
+            let num_days = rhs.try_as_usize_unit(int)?;
+            Ok(Value::Date(self.add_days(num_days, false)?))
         } else {
             Err(FendError::ExpectedANumber)
         }
     }
+
+    pub(crate) fn is_leap_year(self) -> bool {
+        self.year.is_leap_year()
+    }
+
+    /// Returns the next `day` after this date (for `next friday`), the last
+    /// one before it (for `last friday`) or, if `direction` is 0, the first
+    /// one on or after it (for `this friday`)
+    pub(crate) fn relative_day_of_week(
+        self,
+        day: DayOfWeek,
+        direction: i64,
+    ) -> Result<Self, FendError> {
+        let current = i64::from(self.day_of_week().as_u8());
+        let target = i64::from(day.as_u8());
+        let days = match direction {
+            0 => (target - current).rem_euclid(7),
+            1 => (target - current - 1).rem_euclid(7) + 1,
+            _ => -((current - target - 1).rem_euclid(7) + 1),
+        };
+        let result = Self::from_days_since_epoch(self.days_since_epoch() + days)?;
+        Ok(Self {
+            time: self.time,
+            ..result
+        })
+    }
+
+    /// Moves the date by `direction` (1, -1 or 0) times the given period,
+    /// which must be a number of days, weeks, months or years. Moving by
+    /// months keeps the day of the month where possible, e.g. `next month`
+    /// on 31 January is 29 February in a leap year.
+    pub(crate) fn relative_period<I: Interrupt>(
+        self,
+        period: &Number,
+        direction: i64,
+        int: &I,
+    ) -> Result<Self, FendError> {
+        let (days, months) = if period.unit_equal_to("day") {
+            (1, 0)
+        } else if period.unit_equal_to("week") {
+            (7, 0)
+        } else if period.unit_equal_to("month") {
+            (0, 1)
+        } else if period.unit_equal_to("year") {
+            (0, 12)
+        } else {
+            return Err(FendError::ExpectedANumber);
+        };
+        let count = period.clone().try_as_usize_unit(int)?;
+        let count: i64 = count.try_into().map_err(|_| FendError::DateOutOfRange)?;
+        let count = count * direction;
+        let date = self.add_months(months * count)?;
+        let result = Self::from_days_since_epoch(date.days_since_epoch() + days * count)?;
+        Ok(Self {
+            time: self.time,
+            ..result
+        })
+    }
+
+    fn add_months(self, months: i64) -> Result<Self, FendError> {
+        // there is no year 0, so 1 BC becomes year 0
+        let year = i64::from(self.year.value()) + i64::from(self.year.value() < 0);
+        let month_index = year * 12 + i64::from(self.month.as_u8()) - 1 + months;
+        let year = month_index.div_euclid(12);
+        let year = if year <= 0 { year - 1 } else { year };
+        let year = Year::new(year.try_into().map_err(|_| FendError::DateOutOfRange)?);
+        let month = Month::try_from(u8::try_from(month_index.rem_euclid(12) + 1).unwrap())
+            .map_err(|_| FendError::DateOutOfRange)?;
+        let day = Day::new(self.day.value().min(month.number_of_days(year)));
+        Ok(Self {
+            year,
+            month,
+            day,
+            time: self.time,
+        })
+    }
+
+    /// Moves the date to the start or end of the given `minute`, `hour`,
+    /// `day`, `week` (starting on Monday), `month` or `year`, for e.g.
+    /// `to start of month`. The start of a period is midnight (or the start
+    /// of the minute or hour), and the end is its last second. Dates without
+    /// a time stay without one unless the period is shorter than a day.
+    pub(crate) fn start_or_end_of(self, period: &str, end: bool) -> Result<Self, FendError> {
+        let time = self.time.unwrap_or(Time::MIDNIGHT);
+        let day_time = self.time.map(|time| time.truncate(86_400, end));
+        Ok(match period {
+            "minute" => self.with_time(time.truncate(60, end)),
+            "hour" => self.with_time(time.truncate(3600, end)),
+            "day" => Self {
+                time: day_time,
+                ..self
+            },
+            "week" => {
+                let days_since_monday = (i64::from(self.day_of_week().as_u8()) + 6) % 7;
+                let days = if end { 6 } else { 0 } - days_since_monday;
+                Self {
+                    time: day_time,
+                    ..Self::from_days_since_epoch(self.days_since_epoch() + days)?
+                }
+            }
+            "month" => Self {
+                time: day_time,
+                ..if end {
+                    self.last_day_of_month()
+                } else {
+                    self.first_day_of_month()
+                }
+            },
+            "year" => {
+                let (month, day) = if end {
+                    (Month::December, 31)
+                } else {
+                    (Month::January, 1)
+                };
+                Self {
+                    month,
+                    day: Day::new(day),
+                    time: day_time,
+                    ..self
+                }
+            }
+            _ => return Err(FendError::UnknownTimePeriod(period.to_string())),
+        })
+    }
+
+    /// Returns the first day of this date's month
+    pub(crate) fn first_day_of_month(self) -> Self {
+        Self {
+            day: Day::new(1),
+            ..self
+        }
+    }
+
+    /// Returns the last day of this date's month
+    pub(crate) fn last_day_of_month(self) -> Self {
+        Self {
+            day: Day::new(self.month.number_of_days(self.year)),
+            ..self
+        }
+    }
+}
+
+impl PartialEq for Date {
+    fn eq(&self, other: &Self) -> bool {
+        // dates without a time are treated as midnight
+        self.year == other.year
+            && self.month == other.month
+            && self.day == other.day
+            && self.time.unwrap_or(Time::MIDNIGHT) == other.time.unwrap_or(Time::MIDNIGHT)
+    }
 }
 
 impl fmt::Debug for Date {
@@ -174,7 +425,11 @@ impl fmt::Debug for Date {
             self.day,
             self.month,
             self.year
-        )
+        )?;
+        if let Some(time) = self.time {
+            write!(f, " {time}")?;
+        }
+        Ok(())
     }
 }
 
@@ -183,3 +438,33 @@ impl fmt::Display for Date {
         write!(f, "{self:?}")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_days_since_epoch() {
+        let date = |days| Date::from_days_since_epoch(days).unwrap().to_string();
+        assert_eq!(date(0), "Thursday, 1 January 1970");
+        assert_eq!(date(30), "Saturday, 31 January 1970");
+        assert_eq!(date(31), "Sunday, 1 February 1970");
+        assert_eq!(date(365), "Friday, 1 January 1971");
+        assert_eq!(date(-1), "Wednesday, 31 December 1969");
+        assert_eq!(date(18_718), "Thursday, 1 April 2021");
+        assert_eq!(date(-719_162), "Monday, 1 January 1");
+        assert_eq!(date(-719_163), "Sunday, 31 December 1 BC");
+        assert_eq!(date(11_016), "Tuesday, 29 February 2000");
+        assert!(Date::from_days_since_epoch(i64::MAX).is_err());
+    }
+    #[test]
+    fn invalid_time() {
+        assert!(Time::new(23, 59, 59).is_ok());
+        assert_eq!(
+            Time::new(24, 0, 0).unwrap_err().to_string(),
+            "24:00:00 is not a valid time"
+        );
+        assert!(Time::new(12, 60, 0).is_err());
+        assert!(Time::new(12, 0, 60).is_err());
+    }
+}
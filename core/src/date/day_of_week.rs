@@ -43,6 +43,21 @@ impl DayOfWeek {
         }
     }
 
+    /// Parses the full English name of a day, ignoring case
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        [
+            Self::Sunday,
+            Self::Monday,
+            Self::Tuesday,
+            Self::Wednesday,
+            Self::Thursday,
+            Self::Friday,
+            Self::Saturday,
+        ]
+        .into_iter()
+        .find(|day| day.to_string().eq_ignore_ascii_case(name))
+    }
+
     pub(crate) fn serialize(self, write: &mut impl io::Write) -> Result<(), FendError> {
         serialize_u8(self.as_u8(), write)?;
         Ok(())
@@ -68,7 +68,7 @@ impl Month {
         }
     }
 
-    fn as_str(self) -> &'static str {
+    pub(crate) fn as_str(self) -> &'static str {
         match self {
             Self::January => "January",
             Self::February => "February",
@@ -94,7 +94,7 @@ impl Month {
         Self::try_from(deserialize_u8(read)?).map_err(|_| FendError::DeserializationError)
     }
 
-    fn as_u8(self) -> u8 {
+    pub(crate) fn as_u8(self) -> u8 {
         match self {
             Self::January => 1,
             Self::February => 2,
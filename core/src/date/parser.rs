@@ -1,5 +1,5 @@
 use crate::{
-    date::{Date, Day, Month, Year},
+    date::{Date, Day, Month, Time, Year},
     error::FendError,
 };
 use std::convert;
@@ -39,29 +39,175 @@ fn parse_num(s: &str, leading_zeroes: bool) -> Result<(i32, &str), ()> {
     Ok((num, s))
 }
 
-fn parse_yyyymmdd(s: &str) -> Result<(Date, &str), ()> {
-    let (year, s) = parse_num(s, false)?;
-    let s = parse_specific_char(s, '-')?;
+fn skip_whitespace(s: &str) -> Result<&str, ()> {
+    let trimmed = s.trim_start();
+    if trimmed.len() == s.len() {
+        return Err(());
+    }
+    Ok(trimmed)
+}
+
+fn parse_word(s: &str) -> Result<(&str, &str), ()> {
+    let len = s
+        .find(|ch: char| !ch.is_ascii_alphabetic())
+        .unwrap_or(s.len());
+    if len == 0 {
+        return Err(());
+    }
+    Ok(s.split_at(len))
+}
+
+/// Parses a full or abbreviated month name, e.g. `January` or `jan`
+fn parse_month_name(s: &str) -> Result<(Month, &str), ()> {
+    let (word, s) = parse_word(s)?;
+    let mut month = Month::January;
+    for _ in 0..12 {
+        let name = month.as_str();
+        if word.eq_ignore_ascii_case(name)
+            || word.eq_ignore_ascii_case(&name[..3])
+            || (month == Month::September && word.eq_ignore_ascii_case("Sept"))
+        {
+            return Ok((month, s));
+        }
+        month = month.next();
+    }
+    Err(())
+}
+
+/// Parses a full or abbreviated day of the week, followed by an optional comma
+fn parse_day_of_week(s: &str) -> Result<(String, &str), ()> {
+    let (word, s) = parse_word(s)?;
+    if word.len() < 3 {
+        return Err(());
+    }
+    let s = parse_specific_char(s, ',').unwrap_or(s);
+    Ok((word.to_ascii_lowercase(), skip_whitespace(s)?))
+}
+
+fn make_date(year: i32, month: Month, day: i32) -> Result<Date, ()> {
     if year < 1000 {
         return Err(());
     }
     let year = Year::new(year);
+    if day < 1 || day > i32::from(month.number_of_days(year)) {
+        return Err(());
+    }
+    let day: u8 = convert::TryInto::try_into(day).map_err(|_| ())?;
+    let day = Day::new(day);
+    Ok(Date {
+        year,
+        month,
+        day,
+        time: None,
+    })
+}
+
+fn parse_yyyymmdd(s: &str) -> Result<(Date, &str), ()> {
+    let (year, s) = parse_num(s, false)?;
+    let s = parse_specific_char(s, '-')?;
     let (month, s) = parse_num(s, true)?;
     let s = parse_specific_char(s, '-')?;
     let month: u8 = month.try_into().map_err(|_| ())?;
     let month: Month = convert::TryInto::try_into(month).map_err(|_| ())?;
     let (day, s) = parse_num(s, true)?;
-    if day < 1 || day > i32::from(month.number_of_days(year)) {
+    Ok((make_date(year, month, day)?, s))
+}
+
+/// Parses dates like `1 January 2024` or `1 Jan, 2024`
+fn parse_day_month_year(s: &str) -> Result<(Date, &str), ()> {
+    let (day, s) = parse_num(s, true)?;
+    let (month, s) = parse_month_name(skip_whitespace(s)?)?;
+    let s = parse_specific_char(s, ',').unwrap_or(s);
+    let (year, s) = parse_num(skip_whitespace(s)?, false)?;
+    Ok((make_date(year, month, day)?, s))
+}
+
+/// Parses dates like `January 1 2024` or `Jan 1, 2024`
+fn parse_month_day_year(s: &str) -> Result<(Date, &str), ()> {
+    let (month, s) = parse_month_name(s)?;
+    let (day, s) = parse_num(skip_whitespace(s)?, true)?;
+    let s = parse_specific_char(s, ',').unwrap_or(s);
+    let (year, s) = parse_num(skip_whitespace(s)?, false)?;
+    Ok((make_date(year, month, day)?, s))
+}
+
+/// Parses a time of day like `15:30` or `15:30:05`
+fn parse_time(s: &str) -> Result<(Time, &str), ()> {
+    let parse_component = |s, max| -> Result<(u8, &str), ()> {
+        let (tens, s) = parse_digit(s)?;
+        let (ones, s) = parse_digit(s)?;
+        let value = tens * 10 + ones;
+        if value > max {
+            return Err(());
+        }
+        Ok((convert::TryInto::try_into(value).map_err(|_| ())?, s))
+    };
+    let (hour, s) = parse_num(s, true)?;
+    if hour > 23 {
         return Err(());
     }
-    let day: u8 = convert::TryInto::try_into(day).map_err(|_| ())?;
-    let day = Day::new(day);
-    Ok((Date { year, month, day }, s))
+    let hour: u8 = convert::TryInto::try_into(hour).map_err(|_| ())?;
+    let (minute, s) = parse_component(parse_specific_char(s, ':')?, 59)?;
+    let (second, s) = match parse_specific_char(s, ':') {
+        Ok(s) => parse_component(s, 59)?,
+        Err(()) => (0, s),
+    };
+    Ok((Time::new(hour, minute, second).map_err(|_| ())?, s))
+}
+
+/// Parses Unix timestamps like `unix:1704067200` or `unix 1704067200`
+fn parse_unix_timestamp(s: &str) -> Result<(Date, &str), ()> {
+    let s = s.strip_prefix("unix").ok_or(())?;
+    let s = parse_specific_char(s, ':').or_else(|()| skip_whitespace(s))?;
+    let (negative, s) = match parse_specific_char(s, '-') {
+        Ok(s) => (true, s),
+        Err(()) => (false, s),
+    };
+    let len = s.find(|ch: char| !ch.is_ascii_digit()).unwrap_or(s.len());
+    let (digits, s) = s.split_at(len);
+    let mut secs: i64 = digits.parse().map_err(|_| ())?;
+    if negative {
+        secs = -secs;
+    }
+    // only allow years 1 to 9999
+    if !(-62_135_596_800..=253_402_300_799).contains(&secs) {
+        return Err(());
+    }
+    Ok((Date::from_unix_timestamp(secs).map_err(|_| ())?, s))
+}
+
+fn parse_date_and_time(s: &str) -> Result<(Date, &str), ()> {
+    if let Ok(res) = parse_unix_timestamp(s) {
+        return Ok(res);
+    }
+    let (day_of_week, s) = match parse_day_of_week(s) {
+        Ok((day_of_week, remaining)) if parse_month_name(s).is_err() => {
+            (Some(day_of_week), remaining)
+        }
+        _ => (None, s),
+    };
+    let (date, s) = parse_yyyymmdd(s)
+        .or_else(|()| parse_day_month_year(s))
+        .or_else(|()| parse_month_day_year(s))?;
+    if let Some(day_of_week) = day_of_week {
+        if !date
+            .day_of_week()
+            .to_string()
+            .to_ascii_lowercase()
+            .starts_with(&day_of_week)
+        {
+            return Err(());
+        }
+    }
+    if let Ok((time, remaining)) = skip_whitespace(s).and_then(parse_time) {
+        return Ok((date.with_time(time), remaining));
+    }
+    Ok((date, s))
 }
 
 pub(crate) fn parse_date(s: &str) -> Result<Date, FendError> {
     let trimmed = s.trim();
-    if let Ok((date, remaining)) = parse_yyyymmdd(trimmed) {
+    if let Ok((date, remaining)) = parse_date_and_time(trimmed) {
         if remaining.is_empty() {
             return Ok(date);
         }
@@ -69,6 +215,31 @@ pub(crate) fn parse_date(s: &str) -> Result<Date, FendError> {
     Err(FendError::ParseDateError(s.to_string()))
 }
 
+/// Returns true for invalid times like `25:00`, so that they aren't parsed as
+/// a separate expression
+fn looks_like_time(s: &str) -> bool {
+    matches!(parse_num(s, true), Ok((_, s)) if s.starts_with(':'))
+}
+
+/// Parses a date literal at the start of `s` (after the `@`), returning the
+/// date and the remaining input
+pub(crate) fn parse_date_prefix(s: &str) -> Result<(Date, &str), FendError> {
+    match parse_date_and_time(s) {
+        Ok((date, remaining))
+            if !remaining.starts_with(|ch: char| ch.is_alphanumeric() || ch == ':')
+                && !looks_like_time(remaining.trim_start()) =>
+        {
+            Ok((date, remaining))
+        }
+        _ => {
+            let len = s
+                .find(|ch: char| !ch.is_alphanumeric() && !matches!(ch, '-' | ':' | ',' | ' '))
+                .unwrap_or(s.len());
+            Err(FendError::ParseDateError(s[..len].trim_end().to_string()))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -90,4 +261,40 @@ mod tests {
         parse_date("7453-13-01").unwrap_err();
         parse_date("2147483648-1-1").unwrap_err();
     }
+
+    #[test]
+    fn parse_date_formats() {
+        let date = parse_date("2024-01-01").unwrap();
+        assert_eq!(parse_date("January 1 2024").unwrap(), date);
+        assert_eq!(parse_date("jan 1, 2024").unwrap(), date);
+        assert_eq!(parse_date("1 Jan 2024").unwrap(), date);
+        assert_eq!(parse_date("Monday, 1 January 2024").unwrap(), date);
+        assert_eq!(parse_date("unix:1704067200").unwrap(), date);
+        assert_eq!(parse_date("2024-01-01 00:00").unwrap(), date);
+        assert_eq!(
+            parse_date("2024-01-01 15:30").unwrap(),
+            parse_date("unix:1704123000").unwrap()
+        );
+
+        parse_date("Tuesday, 1 January 2024").unwrap_err();
+        parse_date("30 February 2024").unwrap_err();
+        parse_date("Foo 1 2024").unwrap_err();
+        parse_date("2024-01-01 24:00").unwrap_err();
+        parse_date("2024-01-01 15:3").unwrap_err();
+        parse_date("unix:").unwrap_err();
+        parse_date("unix:99999999999999999999").unwrap_err();
+    }
+
+    #[test]
+    fn parse_unix_timestamps() {
+        for (timestamp, expected) in [
+            (0, "Thursday, 1 January 1970 00:00"),
+            (-1, "Wednesday, 31 December 1969 23:59:59"),
+            (951_782_400, "Tuesday, 29 February 2000 00:00"),
+            (1_704_067_230, "Monday, 1 January 2024 00:00:30"),
+        ] {
+            let date = parse_date(&format!("unix:{timestamp}")).unwrap();
+            assert_eq!(date.to_string(), expected);
+        }
+    }
 }
@@ -0,0 +1,82 @@
+use crate::serialize::{deserialize_u8, serialize_u8};
+use crate::FendError;
+use std::{fmt, io};
+
+/// A time of day, e.g. `15:30` or `08:00:05`
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub(crate) struct Time {
+    hour: u8,
+    minute: u8,
+    second: u8,
+}
+
+impl Time {
+    pub(crate) const MIDNIGHT: Self = Self {
+        hour: 0,
+        minute: 0,
+        second: 0,
+    };
+
+    pub(crate) fn new(hour: u8, minute: u8, second: u8) -> Result<Self, FendError> {
+        if hour >= 24 || minute >= 60 || second >= 60 {
+            return Err(FendError::InvalidTime(hour, minute, second));
+        }
+        Ok(Self {
+            hour,
+            minute,
+            second,
+        })
+    }
+
+    /// Wraps around at midnight, e.g. 86,401 seconds is `00:00:01`
+    pub(crate) fn from_seconds_since_midnight(seconds: u32) -> Self {
+        let seconds = seconds % 86_400;
+        Self {
+            hour: (seconds / 3600).try_into().unwrap(),
+            minute: (seconds / 60 % 60).try_into().unwrap(),
+            second: (seconds % 60).try_into().unwrap(),
+        }
+    }
+
+    pub(crate) fn seconds_since_midnight(self) -> u32 {
+        u32::from(self.hour) * 3600 + u32::from(self.minute) * 60 + u32::from(self.second)
+    }
+
+    /// Rounds down to a multiple of `seconds` (e.g. 3600 for the start of
+    /// the hour), or to the last second before the next multiple if `end`
+    /// is true
+    pub(crate) fn truncate(self, seconds: u32, end: bool) -> Self {
+        let start = self.seconds_since_midnight() / seconds * seconds;
+        Self::from_seconds_since_midnight(if end { start + seconds - 1 } else { start })
+    }
+
+    pub(crate) fn serialize(self, write: &mut impl io::Write) -> Result<(), FendError> {
+        serialize_u8(self.hour, write)?;
+        serialize_u8(self.minute, write)?;
+        serialize_u8(self.second, write)?;
+        Ok(())
+    }
+
+    pub(crate) fn deserialize(read: &mut impl io::Read) -> Result<Self, FendError> {
+        let hour = deserialize_u8(read)?;
+        let minute = deserialize_u8(read)?;
+        let second = deserialize_u8(read)?;
+        Self::new(hour, minute, second).map_err(|_| FendError::DeserializationError)
+    }
+}
+
+impl fmt::Debug for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)?;
+        if self.second != 0 {
+            write!(f, ":{:02}", self.second)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
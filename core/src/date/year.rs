@@ -35,21 +35,7 @@ impl Year {
     }
 
     pub(crate) fn is_leap_year(self) -> bool {
-        if self.value() % 400 == 0 {
-            true
-        } else if self.value() % 100 == 0 {
-            false
-        } else {
-            self.value() % 4 == 0
-        }
-    }
-
-    pub(crate) fn number_of_days(self) -> u16 {
-        if self.is_leap_year() {
-            366
-        } else {
-            365
-        }
+        super::is_leap_year(self.value().into())
     }
 
     pub(crate) fn serialize(self, write: &mut impl io::Write) -> Result<(), FendError> {
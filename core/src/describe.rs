@@ -0,0 +1,539 @@
+use crate::error::{FendError, Interrupt};
+
+/// (names, signature, number of arguments, domain, description)
+const FUNCTIONS: &[(&[&str], &str, &str, &str, &str)] = &[
+    (&["sqrt"], "sqrt(x)", "1", "any number", "square root of x"),
+    (&["cbrt"], "cbrt(x)", "1", "any number", "cube root of x"),
+    (&["square"], "square(x)", "1", "any number", "x squared"),
+    (&["cubic"], "cubic(x)", "1", "any number", "x cubed"),
+    (&["abs"], "abs(x)", "1", "any number", "absolute value of x"),
+    (
+        &["conjugate", "conj"],
+        "conjugate(z)",
+        "1",
+        "any number",
+        "complex conjugate of z",
+    ),
+    (
+        &["arg"],
+        "arg(z)",
+        "1",
+        "any number",
+        "argument (phase) of z, between -pi and pi",
+    ),
+    (&["re"], "re(z)", "1", "any number", "real part of z"),
+    (&["im"], "im(z)", "1", "any number", "imaginary part of z"),
+    (&["sin"], "sin(x)", "1", "any number or angle", "sine of x"),
+    (
+        &["cos"],
+        "cos(x)",
+        "1",
+        "any number or angle",
+        "cosine of x",
+    ),
+    (
+        &["tan"],
+        "tan(x)",
+        "1",
+        "any number or angle",
+        "tangent of x",
+    ),
+    (
+        &["sec"],
+        "sec(x)",
+        "1",
+        "any number or angle",
+        "secant of x",
+    ),
+    (
+        &["csc"],
+        "csc(x)",
+        "1",
+        "any number or angle",
+        "cosecant of x",
+    ),
+    (
+        &["cot"],
+        "cot(x)",
+        "1",
+        "any number or angle",
+        "cotangent of x",
+    ),
+    (&["asin"], "asin(x)", "1", "-1 to 1", "inverse sine of x"),
+    (&["acos"], "acos(x)", "1", "-1 to 1", "inverse cosine of x"),
+    (
+        &["atan"],
+        "atan(x)",
+        "1",
+        "any number",
+        "inverse tangent of x",
+    ),
+    (
+        &["atan2"],
+        "atan2(y, x)",
+        "2",
+        "any real numbers",
+        "angle of the point (x, y) from the positive x axis",
+    ),
+    (&["asec"], "asec(x)", "1", "|x| >= 1", "inverse secant of x"),
+    (
+        &["acsc"],
+        "acsc(x)",
+        "1",
+        "|x| >= 1",
+        "inverse cosecant of x",
+    ),
+    (
+        &["acot"],
+        "acot(x)",
+        "1",
+        "any number",
+        "inverse cotangent of x",
+    ),
+    (
+        &["sinh"],
+        "sinh(x)",
+        "1",
+        "any number",
+        "hyperbolic sine of x",
+    ),
+    (
+        &["cosh"],
+        "cosh(x)",
+        "1",
+        "any number",
+        "hyperbolic cosine of x",
+    ),
+    (
+        &["tanh"],
+        "tanh(x)",
+        "1",
+        "any number",
+        "hyperbolic tangent of x",
+    ),
+    (
+        &["sech"],
+        "sech(x)",
+        "1",
+        "any number",
+        "hyperbolic secant of x",
+    ),
+    (
+        &["csch"],
+        "csch(x)",
+        "1",
+        "any non-zero number",
+        "hyperbolic cosecant of x",
+    ),
+    (
+        &["coth"],
+        "coth(x)",
+        "1",
+        "any non-zero number",
+        "hyperbolic cotangent of x",
+    ),
+    (
+        &["asinh"],
+        "asinh(x)",
+        "1",
+        "any number",
+        "inverse hyperbolic sine of x",
+    ),
+    (
+        &["acosh"],
+        "acosh(x)",
+        "1",
+        "x >= 1",
+        "inverse hyperbolic cosine of x",
+    ),
+    (
+        &["atanh"],
+        "atanh(x)",
+        "1",
+        "-1 < x < 1",
+        "inverse hyperbolic tangent of x",
+    ),
+    (
+        &["cis"],
+        "cis(theta)",
+        "1",
+        "any angle",
+        "cos theta + i sin theta",
+    ),
+    (&["exp"], "exp(x)", "1", "any number", "e to the power of x"),
+    (
+        &["ln"],
+        "ln(x)",
+        "1",
+        "positive numbers",
+        "natural logarithm of x",
+    ),
+    (
+        &["log2"],
+        "log2(x)",
+        "1",
+        "positive numbers",
+        "base-2 logarithm of x",
+    ),
+    (
+        &["log", "log10"],
+        "log(x)",
+        "1",
+        "positive numbers",
+        "base-10 logarithm of x",
+    ),
+    (
+        &["not"],
+        "not(b)",
+        "1",
+        "true or false",
+        "logical negation of b",
+    ),
+    (
+        &["approx_equal"],
+        "approx_equal(a, b, tolerance)",
+        "3",
+        "any numbers",
+        "whether a and b are equal within a relative tolerance",
+    ),
+    (
+        &["approx.", "approximately"],
+        "approximately(x)",
+        "1",
+        "any number",
+        "marks x as approximate",
+    ),
+    (
+        &["base"],
+        "base(n)",
+        "1",
+        "integers from 2 to 36",
+        "number base to convert to, e.g. `255 to base 16`",
+    ),
+    (
+        &["fixed"],
+        "fixed(n)",
+        "1",
+        "non-negative integers",
+        "format with exactly n decimal places, e.g. `1234.5 to fixed 2`",
+    ),
+    (
+        &["sample", "roll"],
+        "roll(d)",
+        "1",
+        "a probability distribution, e.g. `d20`",
+        "a random value drawn from d",
+    ),
+    (
+        &["is_leap_year"],
+        "is_leap_year(year)",
+        "1",
+        "integers",
+        "whether year is a leap year",
+    ),
+    (
+        &["divmod"],
+        "divmod(a, b)",
+        "2",
+        "numbers with b non-zero",
+        "quotient and remainder of a / b",
+    ),
+    (
+        &["change", "delta"],
+        "change(old, new)",
+        "2",
+        "numbers with compatible units",
+        "absolute change from old to new, i.e. new - old",
+    ),
+    (
+        &["pct_change"],
+        "pct_change(old, new)",
+        "2",
+        "numbers with compatible units and old non-zero",
+        "percentage change from old to new, i.e. (new - old) / old",
+    ),
+    (
+        &["bits"],
+        "bits(x, pos, len)",
+        "3",
+        "non-negative integers, with len at least 1",
+        "the len bits of x starting at bit pos, counting from the least significant bit",
+    ),
+    (
+        &["set_bits"],
+        "set_bits(x, pos, len, value)",
+        "4",
+        "non-negative integers, with value fitting in len bits",
+        "x with the len bits starting at bit pos replaced by value",
+    ),
+    (
+        &["sum"],
+        "sum(list)",
+        "1",
+        "a list of numbers",
+        "sum of the list",
+    ),
+    (
+        &["product"],
+        "product(list)",
+        "1",
+        "a list of numbers",
+        "product of the list",
+    ),
+    (
+        &["min"],
+        "min(list)",
+        "1",
+        "a list of numbers",
+        "smallest element of the list",
+    ),
+    (
+        &["max"],
+        "max(list)",
+        "1",
+        "a list of numbers",
+        "largest element of the list",
+    ),
+    (
+        &["argmin"],
+        "argmin(list)",
+        "1",
+        "a list of numbers",
+        "index of the smallest element of the list",
+    ),
+    (
+        &["argmax"],
+        "argmax(list)",
+        "1",
+        "a list of numbers",
+        "index of the largest element of the list",
+    ),
+    (
+        &["span", "range"],
+        "span(list)",
+        "1",
+        "a list of numbers",
+        "difference between the largest and smallest elements of the list",
+    ),
+    (
+        &["percentile"],
+        "percentile(p, list)",
+        "2",
+        "p from 0 to 100 and a list of numbers",
+        "pth percentile of the list, interpolating linearly between items",
+    ),
+    (
+        &["quartile"],
+        "quartile(n, list)",
+        "2",
+        "n from 0 to 4 and a list of numbers",
+        "nth quartile of the list, i.e. its (25n)th percentile",
+    ),
+    (
+        &["distance"],
+        "distance((lat1, lon1), (lat2, lon2), radius)",
+        "2 or 3",
+        "coordinates in degrees, with an optional radius",
+        "great-circle distance between two points on a sphere, by default the Earth",
+    ),
+    (
+        &["solve"],
+        "solve(matrix, vector)",
+        "2",
+        "a square matrix as a list of rows, and a vector of the same size",
+        "the vector x that solves the linear system matrix x = vector",
+    ),
+    (
+        &["zip"],
+        "zip(list1, list2)",
+        "2",
+        "two lists, truncated to the shorter one",
+        "list of pairs of corresponding items",
+    ),
+    (
+        &["enumerate"],
+        "enumerate(list)",
+        "1",
+        "any list",
+        "list of [position, item] pairs, with positions starting at 1",
+    ),
+    (
+        &["hist"],
+        "hist(n)",
+        "1",
+        "integers from 1 to 100",
+        "the nth most recent result, so `hist(1)` is `ans`",
+    ),
+    (
+        &["truthtable"],
+        "truthtable(expr, [vars])",
+        "2",
+        "a boolean expression and a list of its variables",
+        "a table of expr's value for every combination of its variables",
+    ),
+    (
+        &["simplify"],
+        "simplify(x)",
+        "1",
+        "any number",
+        "x with its units simplified",
+    ),
+    (
+        &["choose"],
+        "choose(list)",
+        "1",
+        "a non-empty list",
+        "a random element of the list",
+    ),
+    (
+        &["shuffle"],
+        "shuffle(list)",
+        "1",
+        "a non-empty list",
+        "the list in random order",
+    ),
+    (
+        &["numerator"],
+        "numerator(x)",
+        "1",
+        "exact rational numbers",
+        "numerator of x in lowest terms",
+    ),
+    (
+        &["denominator"],
+        "denominator(x)",
+        "1",
+        "exact rational numbers",
+        "denominator of x in lowest terms",
+    ),
+    (
+        &["round"],
+        "round(x, nearest increment)",
+        "1 or 2",
+        "real numbers, with a non-zero increment",
+        "x rounded to the nearest integer, or to the nearest multiple of increment",
+    ),
+    (
+        &["period"],
+        "period(x)",
+        "1",
+        "exact rational numbers",
+        "length of the repeating part of x's expansion in its base",
+    ),
+    (
+        &["is_nan"],
+        "is_nan(x)",
+        "1",
+        "any number",
+        "whether x is not a number, which is never the case",
+    ),
+    (
+        &["is_infinite"],
+        "is_infinite(x)",
+        "1",
+        "any number",
+        "whether x is infinite, which is never the case",
+    ),
+    (
+        &["is_finite"],
+        "is_finite(x)",
+        "1",
+        "any number",
+        "whether x is finite, which is always the case",
+    ),
+    (
+        &["is_integer"],
+        "is_integer(x)",
+        "1",
+        "unitless numbers",
+        "whether x is an integer",
+    ),
+    (
+        &["is_rational"],
+        "is_rational(x)",
+        "1",
+        "unitless numbers",
+        "whether x is an exact rational number",
+    ),
+    (
+        &["is_irrational"],
+        "is_irrational(x)",
+        "1",
+        "unitless numbers",
+        "whether x is real but not rational, like pi, sqrt 2 or e",
+    ),
+    (
+        &["is_complex"],
+        "is_complex(x)",
+        "1",
+        "any number",
+        "whether x has a nonzero imaginary part",
+    ),
+    (
+        &["from_base64"],
+        "from_base64(s)",
+        "1",
+        "base64 strings",
+        "the UTF-8 string encoded by s, e.g. `from_base64(\"aGk=\")`",
+    ),
+    (
+        &["from_hex"],
+        "from_hex(s)",
+        "1",
+        "strings of hex digit pairs",
+        "the UTF-8 string encoded by s, e.g. `from_hex(\"6869\")`",
+    ),
+    (
+        &["md5"],
+        "md5(s)",
+        "1",
+        "strings",
+        "MD5 hash of the UTF-8 bytes of s, as hex",
+    ),
+    (
+        &["sha256"],
+        "sha256(s)",
+        "1",
+        "strings",
+        "SHA-256 hash of the UTF-8 bytes of s, as hex",
+    ),
+    (
+        &["crc32"],
+        "crc32(s)",
+        "1",
+        "strings",
+        "CRC-32 checksum of the UTF-8 bytes of s, as hex",
+    ),
+    (
+        &["describe", "help"],
+        "describe(name)",
+        "1",
+        "names of functions or units",
+        "a short description of a function or unit",
+    ),
+];
+
+/// Names of all described built-in functions
+pub(crate) fn function_names() -> impl Iterator<Item = &'static str> {
+    FUNCTIONS
+        .iter()
+        .flat_map(|(names, _, _, _, _)| names.iter().copied())
+}
+
+/// Returns a short description of the built-in function or unit `name`, as
+/// used by `describe`/`help`
+pub(crate) fn describe<I: Interrupt>(
+    name: &str,
+    context: &mut crate::Context,
+    int: &I,
+) -> Result<String, FendError> {
+    for &(names, signature, args, domain, description) in FUNCTIONS {
+        if names.contains(&name) {
+            let plural = if args == "1" { "" } else { "s" };
+            return Ok(format!(
+                "{signature}: {description} ({args} argument{plural}; domain: {domain})"
+            ));
+        }
+    }
+    crate::units::describe_unit(name, context, int)
+}
@@ -0,0 +1,70 @@
+use crate::error::FendError;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes the UTF-8 bytes of `s` as padded base64
+pub(crate) fn encode_base64(s: &str) -> String {
+    let mut result = String::new();
+    for chunk in s.as_bytes().chunks(3) {
+        let bytes = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                result.push(char::from(
+                    BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize],
+                ));
+            } else {
+                result.push('=');
+            }
+        }
+    }
+    result
+}
+
+/// Decodes base64 (with or without padding) into a UTF-8 string
+pub(crate) fn decode_base64(s: &str) -> Result<String, FendError> {
+    let trimmed = s.trim_end_matches('=');
+    // padding, if present, must complete the last group of four characters
+    let padding = s.len() - trimmed.len();
+    if trimmed.len() % 4 == 1 || padding > 0 && padding != 4 - trimmed.len() % 4 {
+        return Err(FendError::InvalidBase64);
+    }
+    let mut bytes = vec![];
+    let mut n = 0_u32;
+    let mut bits = 0;
+    for ch in trimmed.bytes() {
+        let value = BASE64_ALPHABET
+            .iter()
+            .position(|&c| c == ch)
+            .ok_or(FendError::InvalidBase64)?;
+        n = n << 6 | u32::try_from(value).unwrap();
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push(u8::try_from(n >> bits & 0xff).unwrap());
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| FendError::DecodedBytesNotUtf8)
+}
+
+/// Encodes the UTF-8 bytes of `s` as lowercase hex, two digits per byte
+pub(crate) fn encode_hex(s: &str) -> String {
+    crate::hash::to_hex(s.as_bytes())
+}
+
+/// Decodes pairs of hex digits into a UTF-8 string
+pub(crate) fn decode_hex(s: &str) -> Result<String, FendError> {
+    if s.len() % 2 == 1 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(FendError::InvalidHex);
+    }
+    let bytes = (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|_| FendError::InvalidHex))
+        .collect::<Result<Vec<u8>, _>>()?;
+    String::from_utf8(bytes).map_err(|_| FendError::DecodedBytesNotUtf8)
+}
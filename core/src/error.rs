@@ -6,13 +6,14 @@ use crate::num::Range;
 #[non_exhaustive]
 pub(crate) enum FendError {
     Interrupted,
+    MemoryLimitExceeded,
     InvalidBasePrefix,
     BaseTooSmall,
     BaseTooLarge,
     UnableToConvertToBase,
     DivideByZero,
+    UndefinedAtPole(&'static str),
     ExponentTooLarge,
-    ZeroToThePowerOfZero,
     FactorialComplex,
     DeserializationError,
     Wrap(Box<dyn error::Error + Send + Sync + 'static>),
@@ -21,6 +22,10 @@ pub(crate) enum FendError {
         value: Box<dyn crate::format::DisplayDebug>,
         range: Range<Box<dyn crate::format::DisplayDebug>>,
     },
+    InsideRange {
+        value: Box<dyn crate::format::DisplayDebug>,
+        range: Range<Box<dyn crate::format::DisplayDebug>>,
+    },
     NegativeNumbersNotAllowed,
     ProbabilityDistributionsNotAllowed,
     FractionToInteger,
@@ -34,9 +39,12 @@ pub(crate) enum FendError {
     InexactNumberToInt,
     ExpectedANumber,
     ExpectedABool(&'static str),
+    ExpectedAList(&'static str),
+    ExpectedADate(&'static str),
     InvalidDiceSyntax,
     SpecifyNumDp,
     SpecifyNumSf,
+    SpecifyNumFixed,
     UnableToInvertFunction(&'static str),
     InvalidOperandsForSubtraction,
     InversesOfLambdasUnsupported,
@@ -44,6 +52,10 @@ pub(crate) enum FendError {
     CouldNotFindKey(String),
     CannotFormatWithZeroSf,
     UnableToGetCurrentDate,
+    InvalidTime(u8, u8, u8),
+    DateOutOfRange,
+    UncertainExponent,
+    UnsupportedUncertainOperation(String),
     IsNotAFunction(String),
     IsNotAFunctionOrNumber(String),
     IdentifierNotFound(crate::ident::Ident),
@@ -55,6 +67,7 @@ pub(crate) enum FendError {
     ExpectedDigitSeparator(char),
     DigitSeparatorsNotAllowed,
     DigitSeparatorsOnlyBetweenDigits,
+    DigitSeparatorCommaInBrackets,
     InvalidCharAtBeginningOfIdent(char),
     UnexpectedChar(char),
     UnterminatedStringLiteral,
@@ -75,6 +88,15 @@ pub(crate) enum FendError {
     FactorialUnitless,
     RootsComplex,
     ModuloForPositiveInts,
+    FloorDivisionForIntegers,
+    ZeroRoundingIncrement,
+    WrongNumberOfArguments(&'static str, usize),
+    EmptyList(&'static str),
+    NoUnitInSystem {
+        unit: String,
+        system: &'static str,
+    },
+    ExpectedCoordinates,
     ExpUnitless,
     IncompatibleConversion {
         from: String,
@@ -86,6 +108,46 @@ pub(crate) enum FendError {
     RootsOfNegativeNumbers,
     NonIntegerNegRoots,
     CannotConvertValueTo(&'static str),
+    ComplexNumbersNotOrdered,
+    CannotCompare(&'static str, &'static str),
+    CannotOrder(&'static str),
+    MaxRecursionDepthExceeded,
+    VariableDependsOnItself(crate::ident::Ident),
+    UndefinedVariable(crate::ident::Ident),
+    UnknownBase(String),
+    UnknownFormattingStyle(String),
+    ResultIsInfinite,
+    ResultIsNaN,
+    InvalidBase64,
+    InvalidHex,
+    DecodedBytesNotUtf8,
+    InvalidVariableName(String),
+    IdentifierNotFoundDidYouMean(crate::ident::Ident, String),
+    AmbiguousCompoundUnit(String, String, String),
+    UnknownTimePeriod(String),
+    CannotPassToHostFunction(&'static str),
+    HostFunctionFailed(String),
+    HostFunctionPanicked(String),
+    HistoryIndexOutOfRange(usize, usize),
+    InvalidTruthTableArgs,
+    TooManyTruthTableVariables(usize),
+    EmptyBitField,
+    ValueDoesNotFitInBits(String, usize),
+    InvalidIpAddress(String),
+    InvalidCidrPrefix(String),
+    IpAddressOutOfRange,
+    FeatureDisabled(crate::Feature),
+    InvalidLinearSystem,
+    NoUniqueSolution,
+    ZeroInRatio,
+    PercentChangeFromZero,
+    NoConversionFactor,
+    UnknownLocale(String),
+    InvalidListIndex,
+    IndexOutOfBounds {
+        index: String,
+        len: usize,
+    },
 }
 
 impl fmt::Display for FendError {
@@ -93,6 +155,7 @@ impl fmt::Display for FendError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Interrupted => write!(f, "interrupted"),
+            Self::MemoryLimitExceeded => write!(f, "computation exceeded memory limit"),
             Self::ParseError(e) => write!(f, "{e}"),
             Self::FactorialUnitless => {
                 write!(f, "factorial is only supported for unitless numbers")
@@ -124,10 +187,104 @@ impl fmt::Display for FendError {
             Self::RootsOfNegativeNumbers => {
                 write!(f, "roots of negative numbers are not supported")
             }
+            Self::WrongNumberOfArguments(func, n) => write!(f, "{func} expects {n} arguments"),
+            Self::EmptyList(func) => write!(f, "{func} is undefined for an empty list"),
+            Self::NoUnitInSystem { unit, system } => {
+                write!(f, "{unit} has no equivalent in {system} units")
+            }
+            Self::ExpectedCoordinates => {
+                write!(f, "expected a list of two coordinates, e.g. (3, 4)")
+            }
+            Self::ZeroRoundingIncrement => {
+                write!(f, "cannot round to the nearest multiple of zero")
+            }
+            Self::FloorDivisionForIntegers => {
+                write!(f, "floor division is only supported for integers")
+            }
             Self::ModuloForPositiveInts => {
                 write!(f, "modulo is only supported for positive integers")
             }
             Self::CannotConvertValueTo(ty) => write!(f, "cannot convert value to {ty}"),
+            Self::ComplexNumbersNotOrdered => write!(f, "complex numbers are not ordered"),
+            Self::CannotCompare(a, b) => write!(f, "cannot compare {a} with {b}"),
+            Self::CannotOrder(t) => write!(f, "{t} values cannot be ordered"),
+            Self::MaxRecursionDepthExceeded => write!(f, "maximum recursion depth exceeded"),
+            Self::VariableDependsOnItself(s) => write!(f, "variable '{s}' depends on itself"),
+            Self::UndefinedVariable(s) => write!(f, "variable '{s}' is not defined"),
+            Self::UnknownBase(s) => write!(f, "unknown base '{s}'"),
+            Self::UnknownFormattingStyle(s) => write!(f, "unknown formatting style '{s}'"),
+            Self::ResultIsInfinite => write!(f, "result is infinite or too large to approximate"),
+            Self::ResultIsNaN => write!(f, "result is undefined (not a number)"),
+            Self::InvalidBase64 => write!(f, "invalid base64 string"),
+            Self::InvalidHex => write!(f, "invalid hex string: expected pairs of hex digits"),
+            Self::DecodedBytesNotUtf8 => write!(f, "decoded bytes are not valid UTF-8"),
+            Self::InvalidVariableName(s) => write!(f, "'{s}' is not a valid variable name"),
+            Self::IdentifierNotFoundDidYouMean(s, suggestion) => {
+                write!(f, "unknown identifier '{s}' (did you mean '{suggestion}'?)")
+            }
+            Self::AmbiguousCompoundUnit(s, a, b) => {
+                write!(f, "ambiguous unit '{s}': could be '{a}' or '{b}'")
+            }
+            Self::UnknownTimePeriod(s) => write!(
+                f,
+                "unknown period '{s}': expected minute, hour, day, week, month or year"
+            ),
+            Self::CannotPassToHostFunction(t) => {
+                write!(f, "cannot pass a {t} to a host function")
+            }
+            Self::HostFunctionFailed(msg) => write!(f, "{msg}"),
+            Self::HostFunctionPanicked(name) => write!(f, "{name} panicked"),
+            Self::HistoryIndexOutOfRange(_, 0) => write!(f, "there are no previous results"),
+            Self::HistoryIndexOutOfRange(index, len) => write!(
+                f,
+                "hist({index}) is out of range: expected an index from 1 to {len}"
+            ),
+            Self::InvalidTruthTableArgs => write!(
+                f,
+                "truthtable expects an expression and a list of variable names, e.g. `truthtable(a and b, [a, b])`"
+            ),
+            Self::TooManyTruthTableVariables(max) => {
+                write!(f, "truth tables can have at most {max} variables")
+            }
+            Self::EmptyBitField => write!(f, "bit fields must be at least 1 bit long"),
+            Self::ValueDoesNotFitInBits(value, 1) => write!(f, "{value} does not fit in 1 bit"),
+            Self::ValueDoesNotFitInBits(value, len) => {
+                write!(f, "{value} does not fit in {len} bits")
+            }
+            Self::InvalidIpAddress(ip) => {
+                write!(f, "invalid IP address '{ip}': each part must be from 0 to 255")
+            }
+            Self::InvalidCidrPrefix(network) => write!(
+                f,
+                "invalid network '{network}': the prefix length must be from 0 to 32"
+            ),
+            Self::IpAddressOutOfRange => write!(f, "IP address is out of range"),
+            Self::FeatureDisabled(feature) => {
+                write!(f, "feature disabled in restricted mode: {feature}")
+            }
+            Self::InvalidLinearSystem => write!(
+                f,
+                "solve expects a square matrix and a vector of the same size, e.g. `solve([[2, 1], [1, 3]], [5, 10])`"
+            ),
+            Self::NoUniqueSolution => write!(f, "no unique solution"),
+            Self::ZeroInRatio => write!(f, "a ratio cannot have a zero component"),
+            Self::PercentChangeFromZero => {
+                write!(f, "the percentage change from zero is undefined")
+            }
+            Self::NoConversionFactor => write!(
+                f,
+                "there is no single conversion factor between units with different zero points, like \u{b0}C and \u{b0}F"
+            ),
+            Self::UnknownLocale(locale) => write!(f, "unknown locale: {locale}"),
+            Self::InvalidListIndex => write!(
+                f,
+                "lists are indexed with a single number, e.g. `[4, 5, 6][2]` is 5"
+            ),
+            Self::IndexOutOfBounds { index, len } => write!(
+                f,
+                "index {index} is out of bounds for a list of {len} item{}",
+                if *len == 1 { "" } else { "s" }
+            ),
             Self::BaseTooSmall => write!(f, "base must be at least 2"),
             Self::ConversionRhsNumerical => write!(
                 f,
@@ -136,11 +293,14 @@ impl fmt::Display for FendError {
             Self::BaseTooLarge => write!(f, "base cannot be larger than 36"),
             Self::UnableToConvertToBase => write!(f, "unable to convert number to a valid base"),
             Self::DivideByZero => write!(f, "division by zero"),
+            Self::UndefinedAtPole(func) => write!(f, "{func} is undefined for this value"),
             Self::ExponentTooLarge => write!(f, "exponent too large"),
-            Self::ZeroToThePowerOfZero => write!(f, "zero to the power of zero is undefined"),
             Self::OutOfRange { range, value } => {
                 write!(f, "{value} must lie in the interval {range}")
             }
+            Self::InsideRange { range, value } => {
+                write!(f, "{value} must not lie in the interval {range}")
+            }
             Self::ModuloByZero => write!(f, "modulo by zero"),
             Self::SpecifyNumDp => write!(
                 f,
@@ -150,11 +310,25 @@ impl fmt::Display for FendError {
                 f,
                 "you need to specify what number of significant figures to use, e.g. '10 sf'"
             ),
+            Self::SpecifyNumFixed => write!(
+                f,
+                "expected a number of decimal places, e.g. 'to fixed 2'"
+            ),
             Self::ExpectedAUnitlessNumber => write!(f, "expected a unitless number"),
             Self::ExpectedARealNumber => write!(f, "expected a real number"),
             Self::StringCannotBeLonger => write!(f, "string cannot be longer than one codepoint"),
             Self::StringCannotBeEmpty => write!(f, "string cannot be empty"),
             Self::UnableToGetCurrentDate => write!(f, "unable to get the current date"),
+            Self::InvalidTime(hour, minute, second) => {
+                write!(f, "{hour}:{minute:02}:{second:02} is not a valid time")
+            }
+            Self::DateOutOfRange => write!(f, "date is out of range"),
+            Self::UncertainExponent => {
+                write!(f, "exponents with an uncertainty are not supported")
+            }
+            Self::UnsupportedUncertainOperation(op) => {
+                write!(f, "'{op}' is not supported for numbers with an uncertainty")
+            }
             Self::NegativeNumbersNotAllowed => write!(f, "negative numbers are not allowed"),
             Self::ProbabilityDistributionsNotAllowed => {
                 write!(
@@ -169,6 +343,8 @@ impl fmt::Display for FendError {
             Self::RandomNumbersNotAvailable => write!(f, "random numbers are not available"),
             Self::MustBeAnInteger(x) => write!(f, "{x} is not an integer"),
             Self::ExpectedABool(t) => write!(f, "expected a bool (found {t})"),
+            Self::ExpectedAList(t) => write!(f, "expected a list (found {t})"),
+            Self::ExpectedADate(t) => write!(f, "expected a date (found {t})"),
             Self::CouldNotFindKeyInObject => write!(f, "could not find key in object"),
             Self::CouldNotFindKey(k) => write!(f, "could not find key {k}"),
             Self::InversesOfLambdasUnsupported => write!(
@@ -199,6 +375,10 @@ impl fmt::Display for FendError {
             Self::DigitSeparatorsOnlyBetweenDigits => {
                 write!(f, "digit separators can only occur between digits")
             }
+            Self::DigitSeparatorCommaInBrackets => write!(
+                f,
+                "commas separate items inside brackets, so use '_' as a digit separator (e.g. 1_000)"
+            ),
             Self::InvalidCharAtBeginningOfIdent(ch) => {
                 write!(f, "'{ch}' is not valid at the beginning of an identifier")
             }
@@ -258,4 +438,4 @@ impl From<Box<dyn error::Error + Send + Sync + 'static>> for FendError {
     }
 }
 
-pub(crate) use crate::interrupt::Interrupt;
+pub(crate) use crate::interrupt::Limits as Interrupt;
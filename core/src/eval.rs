@@ -1,60 +1,204 @@
-use std::sync::Arc;
+use std::sync::{Arc, PoisonError};
 
 use crate::{
     ast,
     error::{FendError, Interrupt},
-    lexer, parser,
+    lexer,
+    num::Base,
+    parser,
     scope::Scope,
     value::Value,
     Span,
 };
 
-pub(crate) fn evaluate_to_value<'a, I: Interrupt>(
-    input: &'a str,
-    scope: Option<Arc<Scope>>,
-    context: &mut crate::Context,
+pub(crate) fn parse_input<I: Interrupt>(input: &str, int: &I) -> Result<ast::Expr, FendError> {
+    Ok(parse_input_internal(input, None, int)?.0)
+}
+
+/// Like [`parse_input`], but looks the input up in the context's parse
+/// cache first (if it has one)
+fn parse_input_cached<I: Interrupt>(
+    input: &str,
+    context: &crate::Context,
     int: &I,
-) -> Result<Value, FendError> {
-    let lex = lexer::lex(input, int);
+) -> Result<ast::Expr, FendError> {
+    let Some(cache) = &context.parse_cache else {
+        return parse_input(input, int);
+    };
+    if let Some(expr) = cache
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(input)
+    {
+        return Ok(expr);
+    }
+    let expr = parse_input(input, int)?;
+    cache
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(input, expr.clone());
+    Ok(expr)
+}
+
+/// Also returns the number of opening parentheses that were inserted at the
+/// start of the input to balance any closing parentheses, and the source
+/// text of each number, IP address and ratio literal in the order they appear in the input
+pub(crate) fn parse_input_with_literal_lexemes<I: Interrupt>(
+    input: &str,
+    int: &I,
+) -> Result<(ast::Expr, i32, Vec<String>), FendError> {
+    let mut literal_lexemes = vec![];
+    let (expr, missing_open_parens) = parse_input_internal(input, Some(&mut literal_lexemes), int)?;
+    Ok((expr, missing_open_parens, literal_lexemes))
+}
+
+fn parse_input_internal<I: Interrupt>(
+    input: &str,
+    mut literal_lexemes: Option<&mut Vec<String>>,
+    int: &I,
+) -> Result<(ast::Expr, i32), FendError> {
+    let mut lex = lexer::lex(input, int);
     let mut tokens = vec![];
     let mut missing_open_parens: i32 = 0;
-    for token in lex {
-        let token = token?;
+    loop {
+        let before = lex.remaining_input();
+        let token = match lex.next() {
+            Some(token) => token?,
+            None => break,
+        };
         if let lexer::Token::Symbol(lexer::Symbol::CloseParens) = token {
             missing_open_parens += 1;
         }
+        if let (
+            lexer::Token::Num(_) | lexer::Token::Ip(_) | lexer::Token::Ratio(_),
+            Some(literal_lexemes),
+        ) = (&token, &mut literal_lexemes)
+        {
+            // literals may be preceded by whitespace or comments, and number
+            // words like `one hundred` contain whitespace themselves
+            let mut lexeme = &before[..before.len() - lex.remaining_input().len()];
+            lexer::skip_whitespace_and_comments(&mut lexeme);
+            literal_lexemes.push(lexeme.trim_end().to_string());
+        }
         tokens.push(token);
     }
     for _ in 0..missing_open_parens {
         tokens.insert(0, lexer::Token::Symbol(lexer::Symbol::OpenParens));
     }
-    let parsed = parser::parse_tokens(&tokens)?;
+    if parser::exceeds_max_nesting_depth(&tokens) {
+        return Err(FendError::MaxRecursionDepthExceeded);
+    }
+    Ok((parser::parse_tokens(&tokens)?, missing_open_parens))
+}
+
+/// Returns true if the input only consists of whitespace and comments
+pub(crate) fn is_blank<I: Interrupt>(input: &str, int: &I) -> bool {
+    lexer::lex(input, int).next().is_none()
+}
+
+/// Returns true if the input ends with an operator or an opening
+/// parenthesis, i.e. if the user is probably still typing
+pub(crate) fn is_incomplete<I: Interrupt>(input: &str, int: &I) -> bool {
+    let mut last = None;
+    for token in lexer::lex(input, int) {
+        match token {
+            Ok(token) => last = Some(token),
+            Err(_) => return false,
+        }
+    }
+    matches!(last, Some(lexer::Token::Symbol(s)) if s.expects_more_input())
+}
+
+pub(crate) fn evaluate_to_value<'a, I: Interrupt>(
+    input: &'a str,
+    scope: Option<Arc<Scope>>,
+    context: &mut crate::Context,
+    int: &I,
+) -> Result<Value, FendError> {
+    let parsed = parse_input(input, int)?;
     let result = ast::evaluate(parsed, scope, context, int)?;
     Ok(result)
 }
 
-/// This also saves the calculation result in a variable `_` and `ans`
+/// The formatted result of an evaluation, along with details about it
+#[derive(Default)]
+pub(crate) struct EvaluatedSpans {
+    pub(crate) spans: Vec<Span>,
+    // whether the result is the `()` type
+    pub(crate) is_unit: bool,
+    pub(crate) assigned_variable: Option<String>,
+    pub(crate) approx_value: Option<f64>,
+    // the number of steps the evaluation took
+    pub(crate) steps: u64,
+}
+
+/// This also saves the calculation result in the variables `_`, `ans` and `prev`,
+/// unless the input is an assignment.
 pub(crate) fn evaluate_to_spans<'a, I: Interrupt>(
     mut input: &'a str,
     scope: Option<Arc<Scope>>,
     context: &mut crate::Context,
     int: &I,
-) -> Result<(Vec<Span>, bool), FendError> {
+) -> Result<EvaluatedSpans, FendError> {
     let debug = input.strip_prefix("!debug ").map_or(false, |remaining| {
         input = remaining;
         true
     });
-    let value = evaluate_to_value(input, scope, context, int)?;
-    context.variables.insert("_".to_string(), value.clone());
-    context.variables.insert("ans".to_string(), value.clone());
-    Ok((
-        if debug {
+    let parsed = parse_input_cached(input, context, int)?;
+    let assigned_variable = parsed.assigned_variable().map(|a| a.as_str().to_string());
+    let inferred_base = if context.base_inference {
+        parsed.inferred_base()
+    } else {
+        None
+    };
+    let value = match ast::evaluate(parsed, scope, context, int) {
+        Err(FendError::IdentifierNotFound(missing)) => {
+            // evaluation consumed the AST, so parse the input again to look
+            // for an assignment cycle (this is only needed on errors)
+            let parsed = parse_input(input, int)?;
+            if let Some(var) = ast::find_assignment_cycle(&parsed, missing.as_str()) {
+                return Err(FendError::VariableDependsOnItself(var));
+            }
+            return Err(
+                match crate::suggest::did_you_mean(missing.as_str(), context) {
+                    Some(suggestion) => {
+                        FendError::IdentifierNotFoundDidYouMean(missing, suggestion)
+                    }
+                    None => FendError::IdentifierNotFound(missing),
+                },
+            );
+        }
+        value => value?,
+    };
+    let value = match (value, inferred_base) {
+        (Value::Num(n), Some(base)) => Value::Num(Box::new(n.with_base(base))),
+        // results are shown in the output base unless converted with `to`,
+        // with a prefix so that e.g. `0xff` can be entered again (`ff`
+        // would be an identifier)
+        (Value::Num(n), None)
+            if context.output_base != Base::default() && !context.base_converted =>
+        {
+            Value::Num(Box::new(n.with_base(context.output_base.with_prefix())))
+        }
+        (value, _) => value,
+    };
+    if assigned_variable.is_none() {
+        for name in ["_", "ans", "prev"] {
+            Arc::make_mut(&mut context.variables).insert(name.to_string(), value.clone());
+        }
+        context.push_history(value.clone());
+    }
+    Ok(EvaluatedSpans {
+        spans: if debug {
             vec![Span::from_string(format!("{:?}", value))]
         } else {
             let mut spans = vec![];
             value.format(0, &mut spans, context, int)?;
             spans
         },
-        value.is_unit(),
-    ))
+        is_unit: value.is_unit(),
+        assigned_variable,
+        approx_value: value.approx_value(context, int),
+        steps: 0,
+    })
 }
@@ -0,0 +1,80 @@
+use std::fmt;
+
+/// A category of functionality that an expression can use, as reported by
+/// [`crate::FendResult::features_used`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Feature {
+    /// Trigonometric or hyperbolic functions, e.g. `sin 30°`
+    Trigonometry,
+    /// Units, e.g. `5 kg`
+    Units,
+    /// Converting a value to another unit, e.g. `5 km to miles`
+    UnitConversion,
+    /// Complex numbers, e.g. `2 + 3i`
+    ComplexNumbers,
+    /// Dates, e.g. `today` or `@2000-01-01`
+    Dates,
+    /// Random numbers, e.g. `roll d6`
+    Random,
+    /// Variables, e.g. `a = 5; a * 2`
+    Variables,
+}
+
+impl Feature {
+    const ALL: [Self; 7] = [
+        Self::Trigonometry,
+        Self::Units,
+        Self::UnitConversion,
+        Self::ComplexNumbers,
+        Self::Dates,
+        Self::Random,
+        Self::Variables,
+    ];
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Trigonometry => "trigonometry",
+            Self::Units => "units",
+            Self::UnitConversion => "unit conversion",
+            Self::ComplexNumbers => "complex numbers",
+            Self::Dates => "dates",
+            Self::Random => "random numbers",
+            Self::Variables => "variables",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// The set of [`Feature`]s used while evaluating an expression.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FeatureSet(u8);
+
+impl FeatureSet {
+    /// Returns whether the given feature was used.
+    #[must_use]
+    pub fn contains(self, feature: Feature) -> bool {
+        self.0 & feature.bit() != 0
+    }
+
+    /// Returns whether no features were used, e.g. for plain arithmetic.
+    #[must_use]
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the features that were used.
+    pub fn iter(self) -> impl Iterator<Item = Feature> {
+        Feature::ALL.into_iter().filter(move |&f| self.contains(f))
+    }
+
+    pub(crate) fn insert(&mut self, feature: Feature) {
+        self.0 |= feature.bit();
+    }
+}
@@ -0,0 +1,94 @@
+//! Functions defined by the host application with
+//! [`crate::Context::define_function`]
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+
+use crate::error::{FendError, Interrupt};
+use crate::value;
+
+pub(crate) type HostFn = Arc<dyn Fn(&[Value]) -> Result<Value, String> + Send + Sync>;
+
+/// A value passed to or returned from a function defined with
+/// [`crate::Context::define_function`]
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Value {
+    /// A real number without units
+    Number(f64),
+    /// A string
+    String(String),
+    /// `true` or `false`
+    Bool(bool),
+    /// A list of values, e.g. `[1, 2, 3]`
+    List(Vec<Self>),
+}
+
+impl Value {
+    fn from_fend<I: Interrupt>(value: value::Value, int: &I) -> Result<Self, FendError> {
+        Ok(match value {
+            value::Value::Num(n) => {
+                let n = n.simplify(int)?;
+                if !n.is_unitless(int)? {
+                    return Err(FendError::ExpectedAUnitlessNumber);
+                }
+                Self::Number(n.into_f64(int)?)
+            }
+            value::Value::String(s) => Self::String(s.into_owned()),
+            value::Value::Bool(b) => Self::Bool(b),
+            value::Value::List(items) => Self::List(
+                items
+                    .into_iter()
+                    .map(|item| Self::from_fend(item, int))
+                    .collect::<Result<_, _>>()?,
+            ),
+            value => return Err(FendError::CannotPassToHostFunction(value.type_name())),
+        })
+    }
+
+    fn into_fend<I: Interrupt>(
+        self,
+        context: &mut crate::Context,
+        int: &I,
+    ) -> Result<value::Value, FendError> {
+        Ok(match self {
+            Self::Number(f) => {
+                if !f.is_finite() {
+                    return Err(FendError::ResultIsInfinite);
+                }
+                // like exchange rates, numbers are parsed from their shortest
+                // decimal representation, so e.g. 0.1 is exact
+                crate::eval::evaluate_to_value(&format!("{f}"), None, context, int)?
+            }
+            Self::String(s) => value::Value::String(s.into()),
+            Self::Bool(b) => value::Value::Bool(b),
+            Self::List(items) => value::Value::List(
+                items
+                    .into_iter()
+                    .map(|item| item.into_fend(context, int))
+                    .collect::<Result<_, _>>()?,
+            ),
+        })
+    }
+}
+
+/// Calls the host function `name` with `arg`. Lists are passed as separate
+/// arguments, so `f(1, 2)` passes two numbers. Errors returned by the
+/// function become fend errors, and so do panics.
+pub(crate) fn call<I: Interrupt>(
+    name: &str,
+    f: &HostFn,
+    arg: value::Value,
+    context: &mut crate::Context,
+    int: &I,
+) -> Result<value::Value, FendError> {
+    let args = match Value::from_fend(arg, int)? {
+        Value::List(items) => items,
+        arg => vec![arg],
+    };
+    match panic::catch_unwind(AssertUnwindSafe(|| f(&args))) {
+        Ok(Ok(result)) => result.into_fend(context, int),
+        Ok(Err(msg)) => Err(FendError::HostFunctionFailed(msg)),
+        Err(_) => Err(FendError::HostFunctionPanicked(name.to_string())),
+    }
+}
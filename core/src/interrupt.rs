@@ -1,9 +1,162 @@
 use crate::error::FendError;
+use std::cell::Cell;
 
 pub trait Interrupt {
     fn should_interrupt(&self) -> bool;
 }
 
+/// Wraps another interrupt, and only asks it whether to interrupt once every
+/// `period` checks. This is useful if checking is expensive, e.g. if it
+/// reads the current time: calculations call `should_interrupt` very often,
+/// so a timeout can instead be checked every few hundred calls. Once the
+/// wrapped interrupt has returned true, this always returns true.
+pub struct PeriodicInterrupt<I: Interrupt> {
+    inner: I,
+    period: u32,
+    count: Cell<u32>,
+    interrupted: Cell<bool>,
+}
+
+impl<I: Interrupt> PeriodicInterrupt<I> {
+    /// A period of 0 or 1 checks the wrapped interrupt every time.
+    pub fn new(inner: I, period: u32) -> Self {
+        Self {
+            inner,
+            period,
+            count: Cell::new(0),
+            interrupted: Cell::new(false),
+        }
+    }
+
+    /// Returns the wrapped interrupt
+    pub fn into_inner(self) -> I {
+        self.inner
+    }
+}
+
+impl<I: Interrupt> Interrupt for PeriodicInterrupt<I> {
+    fn should_interrupt(&self) -> bool {
+        if self.interrupted.get() {
+            return true;
+        }
+        let count = self.count.get() + 1;
+        if count < self.period {
+            self.count.set(count);
+            return false;
+        }
+        self.count.set(0);
+        let interrupted = self.inner.should_interrupt();
+        self.interrupted.set(interrupted);
+        interrupted
+    }
+}
+
+/// The interrupt that is used internally during evaluation. Besides
+/// cancellation, it carries the limits that were set on the context.
+pub(crate) trait Limits {
+    fn should_interrupt(&self) -> bool;
+
+    /// The maximum size of a single number, in bytes
+    fn memory_limit(&self) -> Option<usize> {
+        None
+    }
+
+    /// The number of decimal places that transcendental functions like
+    /// `sin` and `ln` should be computed to
+    fn precision(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl<T: Interrupt + ?Sized> Limits for T {
+    fn should_interrupt(&self) -> bool {
+        Interrupt::should_interrupt(self)
+    }
+}
+
+/// The maximum size of a single number in restricted mode, in bytes
+const RESTRICTED_MEMORY_LIMIT: usize = 4096;
+
+/// Wraps an interrupt passed in through the public API with the limits of
+/// the context. It also counts how often it is checked, which happens at
+/// every step of a calculation (such as each iteration of a bignum
+/// multiplication), as a measure of how expensive an evaluation was.
+pub(crate) struct WithLimits<'a, I: Interrupt> {
+    int: &'a I,
+    memory_limit: Option<usize>,
+    precision: Option<usize>,
+    steps: Cell<u64>,
+}
+
+impl<'a, I: Interrupt> WithLimits<'a, I> {
+    pub(crate) fn new(int: &'a I, context: &crate::Context) -> Self {
+        Self {
+            int,
+            memory_limit: if context.restricted_mode {
+                Some(
+                    context
+                        .memory_limit
+                        .map_or(RESTRICTED_MEMORY_LIMIT, |limit| {
+                            limit.min(RESTRICTED_MEMORY_LIMIT)
+                        }),
+                )
+            } else {
+                context.memory_limit
+            },
+            precision: context.precision,
+            steps: Cell::new(0),
+        }
+    }
+
+    /// The number of steps taken so far
+    pub(crate) fn steps(&self) -> u64 {
+        self.steps.get()
+    }
+}
+
+impl<I: Interrupt> Limits for WithLimits<'_, I> {
+    fn should_interrupt(&self) -> bool {
+        self.steps.set(self.steps.get().saturating_add(1));
+        self.int.should_interrupt()
+    }
+
+    fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit
+    }
+
+    fn precision(&self) -> Option<usize> {
+        self.precision
+    }
+}
+
+/// Raises the precision of another limits object, e.g. while evaluating the
+/// left-hand side of `sin 1 to 30 dp`. The wrapped limits are a trait object
+/// so that nesting this doesn't create a new type.
+pub(crate) struct WithPrecision<'a> {
+    int: &'a dyn Limits,
+    precision: usize,
+}
+
+impl<'a> WithPrecision<'a> {
+    pub(crate) fn new(int: &'a dyn Limits, precision: usize) -> Self {
+        Self { int, precision }
+    }
+}
+
+impl Limits for WithPrecision<'_> {
+    fn should_interrupt(&self) -> bool {
+        self.int.should_interrupt()
+    }
+
+    fn memory_limit(&self) -> Option<usize> {
+        self.int.memory_limit()
+    }
+
+    fn precision(&self) -> Option<usize> {
+        Some(self.precision)
+    }
+}
+
 pub(crate) fn test_int<I: crate::error::Interrupt>(int: &I) -> Result<(), FendError> {
     if int.should_interrupt() {
         Err(FendError::Interrupted)
@@ -12,6 +165,18 @@ pub(crate) fn test_int<I: crate::error::Interrupt>(int: &I) -> Result<(), FendEr
     }
 }
 
+/// Fails if a number of the given size (in bytes) would exceed the memory
+/// limit
+pub(crate) fn test_memory_limit<I: crate::error::Interrupt>(
+    bytes: usize,
+    int: &I,
+) -> Result<(), FendError> {
+    match int.memory_limit() {
+        Some(limit) if bytes > limit => Err(FendError::MemoryLimitExceeded),
+        _ => Ok(()),
+    }
+}
+
 #[derive(Default)]
 pub(crate) struct Never {}
 impl Interrupt for Never {
@@ -0,0 +1,199 @@
+//! IPv4 addresses and networks, e.g. `192.168.1.5` or `192.168.1.0/24`
+
+use std::{fmt, io};
+
+use crate::error::{FendError, Interrupt};
+use crate::ident::Ident;
+use crate::num::Number;
+use crate::serialize::{
+    deserialize_bool, deserialize_u64, deserialize_u8, serialize_bool, serialize_u64, serialize_u8,
+};
+use crate::value::Value;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Ipv4 {
+    addr: u32,
+    // e.g. 24 for `192.168.1.0/24`, or `None` for a single address
+    prefix: Option<u8>,
+}
+
+impl Ipv4 {
+    /// Parses a dotted quad with an optional CIDR prefix length, returning
+    /// `None` if the input doesn't start with four groups of digits
+    /// separated by dots, so that other numbers are lexed as usual
+    pub(crate) fn parse_prefix(s: &str) -> Result<Option<(Self, &str)>, FendError> {
+        let mut rest = s;
+        let mut octets = [0; 4];
+        for (i, octet) in octets.iter_mut().enumerate() {
+            if i > 0 {
+                let Some(after_dot) = rest.strip_prefix('.') else {
+                    return Ok(None);
+                };
+                rest = after_dot;
+            }
+            let len = rest
+                .find(|ch: char| !ch.is_ascii_digit())
+                .unwrap_or(rest.len());
+            if len == 0 || len > 3 {
+                return Ok(None);
+            }
+            *octet = rest[..len].parse::<u32>().unwrap();
+            rest = &rest[len..];
+        }
+        // e.g. `1.2.3.4.5` or `1.2.3.4e5`
+        if rest.starts_with(|ch: char| ch == '.' || ch.is_alphanumeric()) {
+            return Ok(None);
+        }
+        let literal = &s[..s.len() - rest.len()];
+        if octets.iter().any(|&octet| octet > 255) {
+            return Err(FendError::InvalidIpAddress(literal.to_string()));
+        }
+        let addr = octets.iter().fold(0, |addr, &octet| addr << 8 | octet);
+        // a slash directly followed by digits is a prefix length, while e.g.
+        // `10.0.0.1 / 2` is still a division
+        let Some(after_slash) = rest
+            .strip_prefix('/')
+            .filter(|r| r.starts_with(|ch: char| ch.is_ascii_digit()))
+        else {
+            return Ok(Some((Self { addr, prefix: None }, rest)));
+        };
+        let len = after_slash
+            .find(|ch: char| !ch.is_ascii_digit())
+            .unwrap_or(after_slash.len());
+        let (prefix, rest) = after_slash.split_at(len);
+        match prefix.parse::<u8>() {
+            Ok(prefix) if prefix <= 32 => Ok(Some((
+                Self {
+                    addr,
+                    prefix: Some(prefix),
+                },
+                rest,
+            ))),
+            _ => Err(FendError::InvalidCidrPrefix(format!("{literal}/{prefix}"))),
+        }
+    }
+
+    /// The number of leading bits that identify the network, which is 32
+    /// for a single address
+    fn prefix_len(self) -> u8 {
+        self.prefix.unwrap_or(32)
+    }
+
+    fn netmask(self) -> u32 {
+        match self.prefix_len() {
+            0 => 0,
+            len => u32::MAX << (32 - len),
+        }
+    }
+
+    /// Whether `other` is an address in this network, or a network inside
+    /// it
+    pub(crate) fn contains(self, other: Self) -> bool {
+        other.prefix_len() >= self.prefix_len()
+            && other.addr & self.netmask() == self.addr & self.netmask()
+    }
+
+    /// The number of usable host addresses. The network and broadcast
+    /// addresses are excluded, except in /31 point-to-point networks.
+    fn num_hosts(self) -> u64 {
+        match self.prefix_len() {
+            32 => 1,
+            31 => 2,
+            len => (1 << (32 - len)) - 2,
+        }
+    }
+
+    /// Moves the address by `offset` addresses, keeping the prefix length
+    pub(crate) fn add<I: Interrupt>(self, offset: Number, int: &I) -> Result<Self, FendError> {
+        if !offset.is_unitless(int)? {
+            return Err(FendError::NumberWithUnitToInt);
+        }
+        let negative = offset.compare(&0.into(), int)? == std::cmp::Ordering::Less;
+        let magnitude = if negative { -offset } else { offset }.try_as_usize(int)?;
+        let magnitude = u32::try_from(magnitude).map_err(|_| FendError::IpAddressOutOfRange)?;
+        let addr = if negative {
+            self.addr.checked_sub(magnitude)
+        } else {
+            self.addr.checked_add(magnitude)
+        };
+        Ok(Self {
+            addr: addr.ok_or(FendError::IpAddressOutOfRange)?,
+            prefix: self.prefix,
+        })
+    }
+
+    /// Subtracts a number of addresses, or returns the number of addresses
+    /// between two addresses
+    pub(crate) fn sub<I: Interrupt>(self, rhs: Value, int: &I) -> Result<Value, FendError> {
+        match rhs {
+            Value::Ip(rhs) => {
+                let difference = Number::from(u64::from(self.addr.abs_diff(rhs.addr)));
+                Ok(Value::Num(Box::new(if self.addr < rhs.addr {
+                    -difference
+                } else {
+                    difference
+                })))
+            }
+            rhs => Ok(Value::Ip(self.add(-rhs.expect_num()?, int)?)),
+        }
+    }
+
+    pub(crate) fn order(self, other: Self) -> std::cmp::Ordering {
+        (self.addr, self.prefix_len()).cmp(&(other.addr, other.prefix_len()))
+    }
+
+    pub(crate) fn get_object_member(self, key: &Ident) -> Result<Value, FendError> {
+        Ok(match key.as_str() {
+            "network" => Value::Ip(Self {
+                addr: self.addr & self.netmask(),
+                prefix: self.prefix,
+            }),
+            "broadcast" => Value::Ip(Self {
+                addr: self.addr | !self.netmask(),
+                prefix: None,
+            }),
+            "netmask" => Value::Ip(Self {
+                addr: self.netmask(),
+                prefix: None,
+            }),
+            "hosts" => Value::Num(Box::new(self.num_hosts().into())),
+            _ => return Err(FendError::CouldNotFindKey(key.to_string())),
+        })
+    }
+
+    pub(crate) fn serialize(self, write: &mut impl io::Write) -> Result<(), FendError> {
+        serialize_u64(self.addr.into(), write)?;
+        serialize_bool(self.prefix.is_some(), write)?;
+        if let Some(prefix) = self.prefix {
+            serialize_u8(prefix, write)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn deserialize(read: &mut impl io::Read) -> Result<Self, FendError> {
+        let addr = deserialize_u64(read)?
+            .try_into()
+            .map_err(|_| FendError::DeserializationError)?;
+        let prefix = if deserialize_bool(read)? {
+            let prefix = deserialize_u8(read)?;
+            if prefix > 32 {
+                return Err(FendError::DeserializationError);
+            }
+            Some(prefix)
+        } else {
+            None
+        };
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl fmt::Display for Ipv4 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let octets = self.addr.to_be_bytes().map(|octet| octet.to_string());
+        write!(f, "{}", octets.join("."))?;
+        if let Some(prefix) = self.prefix {
+            write!(f, "/{prefix}")?;
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,10 @@
+use crate::date::Date;
 use crate::error::{FendError, Interrupt};
 use crate::ident::Ident;
+use crate::ip::Ipv4;
 use crate::num::{Base, Number};
+use crate::ratio::Ratio;
+use crate::words;
 use std::{borrow, convert, fmt};
 
 #[derive(Clone, Debug)]
@@ -9,6 +13,9 @@ pub(crate) enum Token {
     Ident(Ident),
     Symbol(Symbol),
     StringLiteral(borrow::Cow<'static, str>),
+    Date(Date),
+    Ip(Ipv4),
+    Ratio(Ratio),
 }
 
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
@@ -24,7 +31,11 @@ pub(crate) enum Symbol {
     BitwiseAnd,
     BitwiseOr,
     BitwiseXor,
+    BitwiseNand,
+    BitwiseNor,
+    BitwiseXnor,
     UnitConversion,
+    SigFigs,
     Factorial,
     Fn,
     Backslash,
@@ -34,6 +45,24 @@ pub(crate) enum Symbol {
     ShiftRight,
     Semicolon,
     Equals, // used for assignment
+    AddAssign,
+    SubAssign,
+    MulAssign,
+    DivAssign,
+    DoubleEquals,
+    NotEquals,
+    Less,
+    LessOrEquals,
+    Greater,
+    GreaterOrEquals,
+    ApproxEquals,
+    FloorDiv,
+    Comma,
+    OpenBracket,
+    CloseBracket,
+    Sqrt,
+    Until,
+    PlusMinus,
 }
 
 impl fmt::Display for Symbol {
@@ -50,7 +79,11 @@ impl fmt::Display for Symbol {
             Self::BitwiseAnd => "&",
             Self::BitwiseOr => "|",
             Self::BitwiseXor => " xor ",
+            Self::BitwiseNand => " nand ",
+            Self::BitwiseNor => " nor ",
+            Self::BitwiseXnor => " xnor ",
             Self::UnitConversion => "to",
+            Self::SigFigs => "sigfigs",
             Self::Factorial => "!",
             Self::Fn => ":",
             Self::Backslash => "\"",
@@ -60,12 +93,40 @@ impl fmt::Display for Symbol {
             Self::ShiftRight => ">>",
             Self::Semicolon => ";",
             Self::Equals => "=",
+            Self::AddAssign => "+=",
+            Self::SubAssign => "-=",
+            Self::MulAssign => "*=",
+            Self::DivAssign => "/=",
+            Self::DoubleEquals => "==",
+            Self::NotEquals => "!=",
+            Self::Less => "<",
+            Self::LessOrEquals => "<=",
+            Self::Greater => ">",
+            Self::GreaterOrEquals => ">=",
+            Self::ApproxEquals => "~=",
+            Self::FloorDiv => "//",
+            Self::Comma => ",",
+            Self::OpenBracket => "[",
+            Self::CloseBracket => "]",
+            Self::Sqrt => "\u{221a}",
+            Self::Until => "until",
+            Self::PlusMinus => "\u{b1}",
         };
         write!(f, "{s}")?;
         Ok(())
     }
 }
 
+impl Symbol {
+    /// Returns true if input ending with this symbol is incomplete, e.g. `2 +`
+    pub(crate) fn expects_more_input(self) -> bool {
+        !matches!(
+            self,
+            Self::CloseParens | Self::CloseBracket | Self::Factorial | Self::Semicolon
+        )
+    }
+}
+
 fn parse_char(input: &str) -> Result<(char, &str), FendError> {
     input
         .chars()
@@ -95,9 +156,9 @@ fn parse_fixed_char(input: &str, ch: char) -> Result<((), &str), FendError> {
     }
 }
 
-fn parse_digit_separator(input: &str) -> Result<((), &str), FendError> {
+fn parse_digit_separator(input: &str, allow_comma: bool) -> Result<((), &str), FendError> {
     let (parsed_ch, input) = parse_char(input)?;
-    if parsed_ch == '_' || parsed_ch == ',' {
+    if parsed_ch == '_' || (parsed_ch == ',' && allow_comma) {
         Ok(((), input))
     } else {
         Err(FendError::ExpectedDigitSeparator(parsed_ch))
@@ -106,9 +167,26 @@ fn parse_digit_separator(input: &str) -> Result<((), &str), FendError> {
 
 // Parses a plain integer with no whitespace and no base prefix.
 // Leading minus sign is not allowed.
+fn parse_dice_count(input: &str, base: Base) -> Result<(u32, &str), FendError> {
+    let mut count = 0_u32;
+    let (_, remaining) = parse_integer(input, false, false, base, &mut |digit| -> Result<
+        (),
+        FendError,
+    > {
+        count = count
+            .checked_mul(base.base_as_u8().into())
+            .ok_or(FendError::InvalidDiceSyntax)?
+            .checked_add(digit.into())
+            .ok_or(FendError::InvalidDiceSyntax)?;
+        Ok(())
+    })?;
+    Ok((count, remaining))
+}
+
 fn parse_integer<'a, E: From<FendError>>(
     input: &'a str,
     allow_digit_separator: bool,
+    allow_comma: bool,
     base: Base,
     process_digit: &mut impl FnMut(u8) -> Result<(), E>,
 ) -> Result<((), &'a str), E> {
@@ -116,7 +194,8 @@ fn parse_integer<'a, E: From<FendError>>(
     process_digit(digit)?;
     let mut parsed_digit_separator;
     loop {
-        if let Ok((_, remaining)) = parse_digit_separator(input) {
+        let before_separator = input;
+        if let Ok((_, remaining)) = parse_digit_separator(input, allow_comma) {
             input = remaining;
             parsed_digit_separator = true;
             if !allow_digit_separator {
@@ -128,6 +207,11 @@ fn parse_integer<'a, E: From<FendError>>(
         match parse_ascii_digit(input, base) {
             Err(_) => {
                 if parsed_digit_separator {
+                    if before_separator.starts_with(',') {
+                        // this comma separates list items, e.g. `(1, 2)`
+                        input = before_separator;
+                        break;
+                    }
                     return Err(FendError::DigitSeparatorsOnlyBetweenDigits.into());
                 }
                 break;
@@ -152,20 +236,23 @@ fn parse_base_prefix(input: &str) -> Result<(Base, &str), FendError> {
         Ok((Base::from_zero_based_prefix_char(ch)?, input))
     } else {
         let mut custom_base: u8 = 0;
-        let (_, input) = parse_integer(input, false, Base::default(), &mut |digit| -> Result<
-            (),
-            FendError,
-        > {
-            let error = FendError::BaseTooLarge;
-            if custom_base > 3 {
-                return Err(error);
-            }
-            custom_base = 10 * custom_base + digit;
-            if custom_base > 36 {
-                return Err(error);
-            }
-            Ok(())
-        })?;
+        let (_, input) = parse_integer(
+            input,
+            false,
+            false,
+            Base::default(),
+            &mut |digit| -> Result<(), FendError> {
+                let error = FendError::BaseTooLarge;
+                if custom_base > 3 {
+                    return Err(error);
+                }
+                custom_base = 10 * custom_base + digit;
+                if custom_base > 36 {
+                    return Err(error);
+                }
+                Ok(())
+            },
+        )?;
         if custom_base < 2 {
             return Err(FendError::BaseTooSmall);
         }
@@ -184,6 +271,7 @@ fn parse_recurring_digits<'a, I: Interrupt>(
     number: &mut Number,
     num_nonrec_digits: usize,
     base: Base,
+    allow_comma: bool,
     int: &I,
 ) -> Result<((), &'a str), FendError> {
     let original_input = input;
@@ -199,7 +287,10 @@ fn parse_recurring_digits<'a, I: Interrupt>(
     let mut recurring_number_num = Number::from(0);
     let mut recurring_number_den = Number::from(1);
     let base_as_u64 = u64::from(base.base_as_u8());
-    let (_, input) = parse_integer(input, true, base, &mut |digit| -> Result<(), FendError> {
+    let (_, input) = parse_integer(input, true, allow_comma, base, &mut |digit| -> Result<
+        (),
+        FendError,
+    > {
         let digit_as_u64 = u64::from(digit);
         recurring_number_num = recurring_number_num
             .clone()
@@ -224,6 +315,7 @@ fn parse_recurring_digits<'a, I: Interrupt>(
 fn parse_basic_number<'a, I: Interrupt>(
     mut input: &'a str,
     base: Base,
+    allow_comma: bool,
     int: &I,
 ) -> Result<(Number, &'a str), FendError> {
     let mut is_dice_with_no_count = false;
@@ -243,7 +335,10 @@ fn parse_basic_number<'a, I: Interrupt>(
 
     if parse_fixed_char(input, '.').is_err() && !is_dice_with_no_count {
         let (_, remaining) =
-            parse_integer(input, true, base, &mut |digit| -> Result<(), FendError> {
+            parse_integer(input, true, allow_comma, base, &mut |digit| -> Result<
+                (),
+                FendError,
+            > {
                 res = res
                     .clone()
                     .mul(base_as_u64.into(), int)?
@@ -260,18 +355,19 @@ fn parse_basic_number<'a, I: Interrupt>(
         let mut numerator = Number::zero_with_base(base);
         let mut denominator = Number::zero_with_base(base).add(1.into(), int)?;
         if parse_fixed_char(remaining, '(').is_err() {
-            let (_, remaining) = parse_integer(remaining, true, base, &mut |digit| -> Result<
-                (),
-                FendError,
-            > {
-                numerator = numerator
-                    .clone()
-                    .mul(base_as_u64.into(), int)?
-                    .add(u64::from(digit).into(), int)?;
-                denominator = denominator.clone().mul(base_as_u64.into(), int)?;
-                num_nonrec_digits += 1;
-                Ok(())
-            })?;
+            let (_, remaining) =
+                parse_integer(remaining, true, allow_comma, base, &mut |digit| -> Result<
+                    (),
+                    FendError,
+                > {
+                    numerator = numerator
+                        .clone()
+                        .mul(base_as_u64.into(), int)?
+                        .add(u64::from(digit).into(), int)?;
+                    denominator = denominator.clone().mul(base_as_u64.into(), int)?;
+                    num_nonrec_digits += 1;
+                    Ok(())
+                })?;
             input = remaining;
         } else {
             input = remaining;
@@ -279,7 +375,8 @@ fn parse_basic_number<'a, I: Interrupt>(
         res = res.add(numerator.div(denominator, int)?, int)?;
 
         // try parsing recurring decimals
-        let (_, remaining) = parse_recurring_digits(input, &mut res, num_nonrec_digits, base, int)?;
+        let (_, remaining) =
+            parse_recurring_digits(input, &mut res, num_nonrec_digits, base, allow_comma, int)?;
         input = remaining;
     }
 
@@ -294,23 +391,30 @@ fn parse_basic_number<'a, I: Interrupt>(
                     convert::TryFrom::try_from(res.try_as_usize(int)?)
                         .map_err(|_| FendError::InvalidDiceSyntax)?
                 };
-                let mut face_count = 0_u32;
-                let (_, remaining2) = parse_integer(
-                    remaining,
-                    false,
-                    base,
-                    &mut |digit| -> Result<(), FendError> {
-                        face_count = face_count
-                            .checked_mul(base.base_as_u8().into())
-                            .ok_or(FendError::InvalidDiceSyntax)?
-                            .checked_add(digit.into())
-                            .ok_or(FendError::InvalidDiceSyntax)?;
-                        Ok(())
-                    },
-                )?;
+                let (face_count, remaining2) = parse_dice_count(remaining, base)?;
                 if dice_count == 0 || face_count == 0 {
                     return Err(FendError::InvalidDiceSyntax);
                 }
+                // optional `kh<n>` or `kl<n>` suffix to keep the highest or
+                // lowest n dice, e.g. `4d6kh3`
+                let keep = parse_fixed_char(remaining2, 'k').ok().and_then(|((), r)| {
+                    let (highest, r) = if let Ok(((), r)) = parse_fixed_char(r, 'h') {
+                        (true, r)
+                    } else {
+                        (false, parse_fixed_char(r, 'l').ok()?.1)
+                    };
+                    parse_ascii_digit(r, base).ok()?;
+                    Some((highest, r))
+                });
+                if let Some((highest, remaining3)) = keep {
+                    let (keep_count, remaining3) = parse_dice_count(remaining3, base)?;
+                    if keep_count == 0 || keep_count > dice_count {
+                        return Err(FendError::InvalidDiceSyntax);
+                    }
+                    res = Number::new_die_keep(dice_count, face_count, keep_count, highest, int)?;
+                    res = res.with_base(base);
+                    return Ok((res, remaining3));
+                }
                 res = Number::new_die(dice_count, face_count, int)?;
                 res = res.with_base(base);
                 return Ok((res, remaining2));
@@ -353,7 +457,10 @@ fn parse_basic_number<'a, I: Interrupt>(
                 let mut exp = Number::zero_with_base(base);
                 let base_num = Number::from(u64::from(base.base_as_u8()));
                 let (_, remaining2) =
-                    parse_integer(input, true, base, &mut |digit| -> Result<(), FendError> {
+                    parse_integer(input, true, allow_comma, base, &mut |digit| -> Result<
+                        (),
+                        FendError,
+                    > {
                         exp = (exp.clone().mul(base_num.clone(), int)?)
                             .add(u64::from(digit).into(), int)?;
                         Ok(())
@@ -371,25 +478,53 @@ fn parse_basic_number<'a, I: Interrupt>(
     Ok((res, input))
 }
 
-fn parse_number<'a, I: Interrupt>(input: &'a str, int: &I) -> Result<(Number, &'a str), FendError> {
+// Commas are only treated as digit separators if `allow_comma` is true,
+// since inside brackets (e.g. `[1,2]` or `divmod(7,2)`) they separate
+// items instead
+fn parse_number<'a, I: Interrupt>(
+    input: &'a str,
+    allow_comma: bool,
+    int: &I,
+) -> Result<(Number, &'a str), FendError> {
     let (base, input) = parse_base_prefix(input).unwrap_or((Base::default(), input));
-    let (res, input) = parse_basic_number(input, base, int)?;
+    let (res, input) = parse_basic_number(input, base, allow_comma, int)?;
     Ok((res, input))
 }
 
-fn is_valid_in_ident(ch: char, prev: Option<char>) -> bool {
+// A `k`, `M` or `B` directly after a number scales it (e.g. `1.5k` is 1500),
+// but only if it is the last token, so that e.g. `2 B` or `2B to KB` are
+// still bytes
+fn parse_scale_suffix(input: &str) -> Option<(u64, &str)> {
+    let mut chars = input.chars();
+    let scale = match chars.next()? {
+        'k' => 1_000,
+        'M' => 1_000_000,
+        'B' => 1_000_000_000,
+        _ => return None,
+    };
+    let remaining = chars.as_str();
+    let mut rest = remaining;
+    skip_whitespace_and_comments(&mut rest);
+    if rest.is_empty() {
+        Some((scale, remaining))
+    } else {
+        None
+    }
+}
+
+pub(crate) fn is_valid_in_ident(ch: char, prev: Option<char>) -> bool {
     let allowed_chars = [
-        ',', '_', '⅛', '¼', '⅜', '½', '⅝', '¾', '⅞', '⅙', '⅓', '⅔', '⅚', '⅕', '⅖', '⅗', '⅘', '°',
-        '$', '℃', '℉', '℧', '℈', '℥', '℔', '¢', '£', '¥', '€', '₩', '₪', '₤', '₨', '฿', '₡', '₣',
-        '₦', '₧', '₫', '₭', '₮', '₯', '₱', '﷼', '﹩', '￠', '￡', '￥', '￦', '㍱', '㍲', '㍳',
-        '㍴', '㍶', '㎀', '㎁', '㎂', '㎃', '㎄', '㎅', '㎆', '㎇', '㎈', '㎉', '㎊', '㎋', '㎌',
-        '㎍', '㎎', '㎏', '㎐', '㎑', '㎒', '㎓', '㎔', '㎕', '㎖', '㎗', '㎘', '㎙', '㎚', '㎛',
-        '㎜', '㎝', '㎞', '㎟', '㎠', '㎡', '㎢', '㎣', '㎤', '㎥', '㎦', '㎧', '㎨', '㎩', '㎪',
-        '㎫', '㎬', '㎭', '㎮', '㎯', '㎰', '㎱', '㎲', '㎳', '㎴', '㎵', '㎶', '㎷', '㎸', '㎹',
-        '㎺', '㎻', '㎼', '㎽', '㎾', '㎿', '㏀', '㏁', '㏃', '㏄', '㏅', '㏆', '㏈', '㏉', '㏊',
-        '㏌', '㏏', '㏐', '㏓', '㏔', '㏕', '㏖', '㏗', '㏙', '㏛', '㏜', '㏝',
+        '_', '⅛', '¼', '⅜', '½', '⅝', '¾', '⅞', '⅙', '⅓', '⅔', '⅚', '⅕', '⅖', '⅗', '⅘', '°', '$',
+        '℃', '℉', '℧', '℈', '℥', '℔', '¢', '£', '¥', '€', '₩', '₪', '₤', '₨', '฿', '₡', '₣', '₦',
+        '₧', '₫', '₭', '₮', '₯', '₱', '﷼', '﹩', '￠', '￡', '￥', '￦', '㍱', '㍲', '㍳', '㍴',
+        '㍶', '㎀', '㎁', '㎂', '㎃', '㎄', '㎅', '㎆', '㎇', '㎈', '㎉', '㎊', '㎋', '㎌', '㎍',
+        '㎎', '㎏', '㎐', '㎑', '㎒', '㎓', '㎔', '㎕', '㎖', '㎗', '㎘', '㎙', '㎚', '㎛', '㎜',
+        '㎝', '㎞', '㎟', '㎠', '㎡', '㎢', '㎣', '㎤', '㎥', '㎦', '㎧', '㎨', '㎩', '㎪', '㎫',
+        '㎬', '㎭', '㎮', '㎯', '㎰', '㎱', '㎲', '㎳', '㎴', '㎵', '㎶', '㎷', '㎸', '㎹', '㎺',
+        '㎻', '㎼', '㎽', '㎾', '㎿', '㏀', '㏁', '㏃', '㏄', '㏅', '㏆', '㏈', '㏉', '㏊', '㏌',
+        '㏏', '㏐', '㏓', '㏔', '㏕', '㏖', '㏗', '㏙', '㏛', '㏜', '㏝',
     ];
-    let only_valid_by_themselves = ['%', '‰', '‱', '′', '″', '’', '”', 'π'];
+    let only_valid_by_themselves = ['%', '‰', '‱', '′', '″', '’', '”', 'π', 'Σ', 'Π'];
     let split_on_subsequent_digit = ['$', '£'];
     let always_invalid = ['λ'];
     if always_invalid.contains(&ch) {
@@ -431,12 +566,22 @@ fn parse_ident(input: &str, allow_dots: bool) -> Result<(Token, &str), FendError
     Ok((
         match ident {
             "to" | "as" | "in" => Token::Symbol(Symbol::UnitConversion),
+            "plus" => Token::Symbol(Symbol::Add),
+            "minus" => Token::Symbol(Symbol::Sub),
+            "times" => Token::Symbol(Symbol::Mul),
             "per" => Token::Symbol(Symbol::Div),
+            "sigfigs" => Token::Symbol(Symbol::SigFigs),
             "of" => Token::Symbol(Symbol::Of),
             "mod" => Token::Symbol(Symbol::Mod),
+            "until" => Token::Symbol(Symbol::Until),
             "xor" | "XOR" => Token::Symbol(Symbol::BitwiseXor),
             "and" | "AND" => Token::Symbol(Symbol::BitwiseAnd),
             "or" | "OR" => Token::Symbol(Symbol::BitwiseOr),
+            "nand" | "NAND" => Token::Symbol(Symbol::BitwiseNand),
+            "nor" | "NOR" => Token::Symbol(Symbol::BitwiseNor),
+            "xnor" | "XNOR" => Token::Symbol(Symbol::BitwiseXnor),
+            "Σ" => Token::Ident(Ident::new_str("sum")),
+            "Π" => Token::Ident(Ident::new_str("product")),
             _ => Token::Ident(Ident::new_string(ident.to_string())),
         },
         input,
@@ -456,25 +601,41 @@ fn parse_symbol(ch: char, input: &mut &str) -> Result<Token, FendError> {
     Ok(Token::Symbol(match ch {
         '(' => Symbol::OpenParens,
         ')' => Symbol::CloseParens,
+        '+' if test_next('=') => Symbol::AddAssign,
         '+' => Symbol::Add,
-        '!' => Symbol::Factorial,
+        '!' => {
+            if test_next('=') {
+                Symbol::NotEquals
+            } else {
+                Symbol::Factorial
+            }
+        }
         // unicode minus sign
+        '-' | '\u{2212}' if test_next('=') => Symbol::SubAssign,
         '-' | '\u{2212}' => Symbol::Sub,
         '*' | '\u{d7}' | '\u{2715}' => {
             if test_next('*') {
                 Symbol::Pow
+            } else if test_next('=') {
+                Symbol::MulAssign
             } else {
                 Symbol::Mul
             }
         }
+        '/' if test_next('/') => Symbol::FloorDiv,
+        '/' if test_next('=') => Symbol::DivAssign,
         '/' | '\u{f7}' | '\u{2215}' => Symbol::Div, // unicode division symbol and slash
         '^' => Symbol::Pow,
+        '\u{221a}' => Symbol::Sqrt, // square root symbol
+        '\u{b1}' => Symbol::PlusMinus,
         '&' => Symbol::BitwiseAnd,
         '|' => Symbol::BitwiseOr,
         ':' => Symbol::Fn,
         '=' => {
             if test_next('>') {
                 Symbol::Fn
+            } else if test_next('=') {
+                Symbol::DoubleEquals
             } else {
                 Symbol::Equals
             }
@@ -484,18 +645,30 @@ fn parse_symbol(ch: char, input: &mut &str) -> Result<Token, FendError> {
         '<' => {
             if test_next('<') {
                 Symbol::ShiftLeft
+            } else if test_next('=') {
+                Symbol::LessOrEquals
             } else {
-                return Err(FendError::UnexpectedChar(ch));
+                Symbol::Less
             }
         }
         '>' => {
             if test_next('>') {
                 Symbol::ShiftRight
+            } else if test_next('=') {
+                Symbol::GreaterOrEquals
             } else {
-                return Err(FendError::UnexpectedChar(ch));
+                Symbol::Greater
             }
         }
+        '\u{2260}' => Symbol::NotEquals,
+        '\u{2264}' => Symbol::LessOrEquals,
+        '\u{2265}' => Symbol::GreaterOrEquals,
+        '~' if test_next('=') => Symbol::ApproxEquals,
+        '\u{2248}' => Symbol::ApproxEquals,
         ';' => Symbol::Semicolon,
+        ',' => Symbol::Comma,
+        '[' => Symbol::OpenBracket,
+        ']' => Symbol::CloseBracket,
         _ => return Err(FendError::UnexpectedChar(ch)),
     }))
 }
@@ -653,10 +826,16 @@ pub(crate) struct Lexer<'a, 'b, I: Interrupt> {
     // normally 0; 1 after backslash; 2 after ident after backslash
     after_backslash_state: u8,
     after_number_or_to: bool,
+    // after a number, identifier or closing parenthesis, i.e. where an
+    // infix operator can follow
+    after_operand: bool,
+    after_comma: bool,
+    // number of unclosed `(` or `[` brackets
+    bracket_depth: usize,
     int: &'b I,
 }
 
-fn skip_whitespace_and_comments(input: &mut &str) {
+pub(crate) fn skip_whitespace_and_comments(input: &mut &str) {
     while !input.is_empty() {
         if input.starts_with("# ") || input.starts_with("#!") {
             if let Some(idx) = input.find('\n') {
@@ -666,6 +845,12 @@ fn skip_whitespace_and_comments(input: &mut &str) {
             }
             *input = "";
             return;
+        } else if let Some(remaining) = input.strip_prefix("/*") {
+            // an unterminated block comment continues to the end of the input
+            *input = remaining
+                .find("*/")
+                .map_or("", |idx| remaining.split_at(idx + 2).1);
+            continue;
         } else if let Some(ch) = input.chars().next() {
             if ch.is_whitespace() {
                 let (_, remaining) = input.split_at(ch.len_utf8());
@@ -677,8 +862,28 @@ fn skip_whitespace_and_comments(input: &mut &str) {
     }
 }
 
+/// Returns true if the input (after any whitespace) starts with a number or
+/// an opening parenthesis. Identifiers don't count, since e.g. `5% °C` is a
+/// percentage of a unit.
+fn starts_with_number_or_parens(mut input: &str) -> bool {
+    skip_whitespace_and_comments(&mut input);
+    let mut chars = input.chars();
+    match chars.next() {
+        Some('(') => true,
+        Some('.') => chars.next().is_some_and(|ch| ch.is_ascii_digit()),
+        Some(ch) => ch.is_ascii_digit(),
+        None => false,
+    }
+}
+
 impl<'a, 'b, I: Interrupt> Lexer<'a, 'b, I> {
+    /// Returns the input that hasn't been lexed yet
+    pub(crate) fn remaining_input(&self) -> &'a str {
+        self.input
+    }
+
     fn next_token(&mut self) -> Result<Option<Token>, FendError> {
+        let directly_after_comma = self.after_comma && !self.input.starts_with(char::is_whitespace);
         skip_whitespace_and_comments(&mut self.input);
         let (ch, following) = {
             let mut chars = self.input.chars();
@@ -686,13 +891,44 @@ impl<'a, 'b, I: Interrupt> Lexer<'a, 'b, I> {
             let following = chars.next();
             (ch, following)
         };
+        if self.input.starts_with(|ch: char| ch.is_ascii_digit()) {
+            // e.g. `192.168.1.0/24`
+            if let Some((ip, remaining)) = Ipv4::parse_prefix(self.input)? {
+                self.input = remaining;
+                return Ok(Some(Token::Ip(ip)));
+            }
+        }
         Ok(Some(match ch {
             Some(ch) => {
                 if ch.is_ascii_digit()
                     || (ch == '.' && self.after_backslash_state == 0)
                     || (ch == 'd' && following.is_some() && following.unwrap().is_ascii_digit())
                 {
-                    let (num, remaining) = parse_number(self.input, self.int)?;
+                    // e.g. `[1,000]` is ambiguous, because inside brackets
+                    // commas separate items rather than digits
+                    if self.bracket_depth > 0
+                        && directly_after_comma
+                        && ch == '0'
+                        && following.is_some_and(|ch| ch.is_ascii_digit())
+                    {
+                        return Err(FendError::DigitSeparatorCommaInBrackets);
+                    }
+                    let (mut num, mut remaining) =
+                        parse_number(self.input, self.bracket_depth == 0, self.int)?;
+                    if let Some((scale, after_suffix)) = parse_scale_suffix(remaining) {
+                        num = num.mul(scale.into(), self.int)?;
+                        remaining = after_suffix;
+                    }
+                    // e.g. `16:9`
+                    if let Some(after_colon) = remaining
+                        .strip_prefix(':')
+                        .filter(|r| r.starts_with(|ch: char| ch.is_ascii_digit()))
+                    {
+                        let (consequent, remaining) =
+                            parse_number(after_colon, self.bracket_depth == 0, self.int)?;
+                        self.input = remaining;
+                        return Ok(Some(Token::Ratio(Ratio::new(num, consequent)?)));
+                    }
                     self.input = remaining;
                     Token::Num(num)
                 } else if ch == '\'' || ch == '"' {
@@ -718,12 +954,35 @@ impl<'a, 'b, I: Interrupt> Lexer<'a, 'b, I> {
                     let (_terminator, remaining) = remaining.split_at(2);
                     self.input = remaining;
                     Token::StringLiteral(literal.to_string().into())
+                } else if let Some(remaining) = self.input.strip_prefix('@') {
+                    // date literal, e.g. `@2024-01-01`
+                    let (date, remaining) = Date::parse_prefix(remaining)?;
+                    self.input = remaining;
+                    Token::Date(date)
+                } else if let Some((num, remaining)) = words::parse_number_words(self.input)
+                    .filter(|_| self.after_backslash_state != 1)
+                {
+                    // e.g. `one hundred twenty-three`
+                    self.input = remaining;
+                    Token::Num(num.into())
                 } else if is_valid_in_ident(ch, None) {
                     // dots aren't allowed in idents after a backslash
                     let (ident, remaining) =
                         parse_ident(self.input, self.after_backslash_state != 1)?;
                     self.input = remaining;
-                    ident
+                    match ident {
+                        // `%` between an operand and a number is modulo,
+                        // e.g. `7 % 3`, otherwise it's a percentage, e.g.
+                        // `50% + 5%`
+                        Token::Ident(i)
+                            if i.as_str() == "%"
+                                && self.after_operand
+                                && starts_with_number_or_parens(remaining) =>
+                        {
+                            Token::Symbol(Symbol::Mod)
+                        }
+                        ident => ident,
+                    }
                 } else {
                     let (_, remaining) = self.input.split_at(ch.len_utf8());
                     self.input = remaining;
@@ -752,6 +1011,22 @@ impl<'a, I: Interrupt> Iterator for Lexer<'a, '_, I> {
         } else {
             self.after_number_or_to = false;
         }
+        self.after_operand = matches!(
+            res,
+            Some(Ok(Token::Num(_)
+                | Token::Ident(_)
+                | Token::Symbol(Symbol::CloseParens)))
+        );
+        self.after_comma = matches!(res, Some(Ok(Token::Symbol(Symbol::Comma))));
+        match res {
+            Some(Ok(Token::Symbol(Symbol::OpenParens | Symbol::OpenBracket))) => {
+                self.bracket_depth += 1;
+            }
+            Some(Ok(Token::Symbol(Symbol::CloseParens | Symbol::CloseBracket))) => {
+                self.bracket_depth = self.bracket_depth.saturating_sub(1);
+            }
+            _ => (),
+        }
         if let Some(Ok(Token::Symbol(Symbol::Backslash))) = res {
             self.after_backslash_state = 1;
         } else if self.after_backslash_state == 1 {
@@ -772,6 +1047,9 @@ pub(crate) fn lex<'a, 'b, I: Interrupt>(input: &'a str, int: &'b I) -> Lexer<'a,
         input,
         after_backslash_state: 0,
         after_number_or_to: false,
+        after_operand: false,
+        after_comma: false,
+        bracket_depth: 0,
         int,
     }
 }
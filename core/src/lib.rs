@@ -22,36 +22,66 @@
 
 mod ast;
 mod date;
+mod describe;
+mod encoding;
 mod error;
 mod eval;
+mod feature;
 mod format;
+mod hash;
+mod host_function;
 mod ident;
 mod inline_substitutions;
 mod interrupt;
+mod ip;
 /// This module is not meant to be used by other crates. It may change or be removed at any point.
 pub mod json;
 mod lexer;
+mod locale;
+mod markup;
 mod num;
+mod parse_cache;
 mod parser;
+mod ratio;
 mod scope;
 mod serialize;
+mod suggest;
+pub mod syntax;
+mod truth_table;
 mod units;
 mod value;
+mod words;
 
-use std::{collections::HashMap, fmt, io};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt, io,
+    sync::{Arc, Mutex, PoisonError},
+};
 
 use error::FendError;
-pub use interrupt::Interrupt;
+pub use feature::{Feature, FeatureSet};
+pub use host_function::Value;
+pub use interrupt::{Interrupt, PeriodicInterrupt};
+pub use num::{Base, FormattingStyle, ScientificNotationStyle, UnitDisplayPreference};
 use serialize::{deserialize_string, deserialize_usize, serialize_string, serialize_usize};
+pub use units::UnknownUnitBehavior;
 
 /// This contains the result of a computation.
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Debug)]
 pub struct FendResult {
     plain_result: String,
     span_result: Vec<Span>,
     is_unit: bool, // is this the () type
+    warnings: Vec<String>,
+    assigned_variable: Option<String>,
+    features_used: FeatureSet,
+    approx_value: Option<f64>,
+    steps: u64,
 }
 
+// `approx_value` is never NaN, so equality is still reflexive
+impl Eq for FendResult {}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 #[non_exhaustive]
 pub enum SpanKind {
@@ -70,6 +100,8 @@ pub enum SpanKind {
 struct Span {
     string: String,
     kind: SpanKind,
+    /// How a number was formatted, which is needed to convert it to markup
+    number_format: Option<markup::NumberFormat>,
 }
 
 impl Span {
@@ -77,6 +109,7 @@ impl Span {
         Self {
             string: s,
             kind: SpanKind::Other,
+            number_format: None,
         }
     }
 }
@@ -115,6 +148,20 @@ impl FendResult {
         })
     }
 
+    /// Returns the main result as LaTeX markup, e.g. `\frac{1}{3}` or
+    /// `5\ \mathrm{kg}`, for embedding results in documents.
+    #[must_use]
+    pub fn to_latex(&self) -> String {
+        markup::spans_to_latex(&self.span_result)
+    }
+
+    /// Returns the main result as a `MathML` `<math>` element, e.g.
+    /// `<math><mfrac><mrow><mn>1</mn></mrow><mrow><mn>3</mn></mrow></mfrac></math>`.
+    #[must_use]
+    pub fn to_mathml(&self) -> String {
+        markup::spans_to_mathml(&self.span_result)
+    }
+
     /// Returns whether or not the result is the `()` type. It can sometimes
     /// be useful to hide these values.
     #[must_use]
@@ -122,11 +169,60 @@ impl FendResult {
         self.is_unit
     }
 
+    /// Returns advisories about the input that didn't prevent it from being
+    /// evaluated, e.g. that `l` was interpreted as litres rather than `1`.
+    #[must_use]
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// If the input was an assignment like `x = 5`, returns the name of the
+    /// variable together with its formatted value (e.g. `("x", "5")`), so that
+    /// frontends can echo the assignment back.
+    #[must_use]
+    pub fn assignment(&self) -> Option<(&str, &str)> {
+        self.assigned_variable
+            .as_deref()
+            .map(|name| (name, self.plain_result.as_str()))
+    }
+
+    /// Returns the kinds of functionality the input used, e.g. trigonometry,
+    /// unit conversion or variables. Units that are defined in terms of other
+    /// units don't count towards the features of the input.
+    #[must_use]
+    pub fn features_used(&self) -> FeatureSet {
+        self.features_used
+    }
+
+    /// Returns an approximation of a numeric result, e.g. `3.14159...` for
+    /// `pi` or `5.0` for `5 kg`, so that frontends can use the value without
+    /// evaluating the input again. Returns `None` for non-numeric results
+    /// such as strings or dates, and for complex or non-finite numbers.
+    #[must_use]
+    pub fn approx_value(&self) -> Option<f64> {
+        self.approx_value
+    }
+
+    /// Returns the number of steps that the evaluation took, e.g. the
+    /// iterations of bignum multiplications and divisions. Unlike a
+    /// timeout, this doesn't depend on how fast the machine is, so it can
+    /// be used to rate-limit expensive inputs. Trivial inputs like `1 + 1`
+    /// only take a few steps.
+    #[must_use]
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
     fn empty() -> Self {
         Self {
             plain_result: String::new(),
             span_result: vec![],
             is_unit: true,
+            warnings: vec![],
+            assigned_variable: None,
+            features_used: FeatureSet::default(),
+            approx_value: None,
+            steps: 0,
         }
     }
 }
@@ -149,18 +245,68 @@ enum OutputMode {
     TerminalFixedWidth,
 }
 
+/// Number of previous results that can be recalled with `hist`
+const HISTORY_LENGTH: usize = 100;
+
+/// Number of digits shown for recurring decimals unless changed with
+/// [`Context::set_max_recurring_digits`]
+const DEFAULT_MAX_RECURRING_DIGITS: usize = 200;
+
+/// Number of digits shown when converting to `float` unless changed with
+/// [`Context::set_max_float_digits`]
+const DEFAULT_MAX_FLOAT_DIGITS: usize = 500;
+
+// shared between clones of a context, so that they don't repeat each
+// other's random numbers
+type RandomU32Fn = Arc<Mutex<dyn FnMut() -> u32 + Send>>;
 type ExchangeRateFn = fn(&str) -> Result<f64, Box<dyn std::error::Error + Send + Sync + 'static>>;
 
 /// This struct contains context used for `fend`. It should only be created once
 /// at startup.
 #[derive(Clone)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Context {
     current_time: Option<CurrentTimeInfo>,
-    variables: HashMap<String, value::Value>,
+    // variables and cached units are shared between clones until one of
+    // them is modified, so that e.g. previews can cheaply copy the context
+    variables: Arc<HashMap<String, value::Value>>,
+    // previous results for `hist`, most recent first
+    history: Arc<VecDeque<value::Value>>,
     fc_mode: FCMode,
-    random_u32: Option<fn() -> u32>,
+    random_u32: Option<RandomU32Fn>,
+    host_functions: Arc<HashMap<String, host_function::HostFn>>,
+    // shared between clones of a context, like the random number generator
+    parse_cache: Option<Arc<Mutex<parse_cache::ParseCache>>>,
     output_mode: OutputMode,
     get_exchange_rate: Option<ExchangeRateFn>,
+    max_recursion_depth: usize,
+    recursion_depth: usize,
+    scientific_notation_style: ScientificNotationStyle,
+    formatting_style: FormattingStyle,
+    output_base: Base,
+    unit_display_preference: UnitDisplayPreference,
+    unknown_unit_behavior: UnknownUnitBehavior,
+    // resolved units, along with the identifiers their definitions refer to
+    unit_cache: Arc<HashMap<String, (value::Value, Vec<String>)>>,
+    // identifiers resolved while looking up a unit that isn't cached yet
+    unit_dependencies: Option<Vec<String>>,
+    exchange_rate_used: bool,
+    // warnings about the input that is currently being evaluated
+    warnings: Vec<String>,
+    // features used by the input that is currently being evaluated
+    features_used: FeatureSet,
+    // whether the input that is currently being evaluated converted a
+    // number to a base with `to`
+    base_converted: bool,
+    memory_limit: Option<usize>,
+    restricted_mode: bool,
+    base_inference: bool,
+    max_recurring_digits: Option<usize>,
+    max_float_digits: Option<usize>,
+    precision: Option<usize>,
+    display_rounding: bool,
+    locale: Option<locale::Locale>,
+    digit_grouping: locale::DigitGrouping,
 }
 
 impl fmt::Debug for Context {
@@ -168,9 +314,39 @@ impl fmt::Debug for Context {
         f.debug_struct("Context")
             .field("current_time", &self.current_time)
             .field("variables", &self.variables)
+            .field("history", &self.history)
             .field("fc_mode", &self.fc_mode)
-            .field("random_u32", &self.random_u32)
+            .field("random_u32", &self.random_u32.is_some())
+            .field("host_functions", &{
+                let mut names: Vec<_> = self.host_functions.keys().collect();
+                names.sort_unstable();
+                names
+            })
+            .field(
+                "parse_cache_size",
+                &self.parse_cache.as_ref().map_or(0, |cache| {
+                    cache
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .capacity()
+                }),
+            )
             .field("output_mode", &self.output_mode)
+            .field("max_recursion_depth", &self.max_recursion_depth)
+            .field("scientific_notation_style", &self.scientific_notation_style)
+            .field("formatting_style", &self.formatting_style)
+            .field("output_base", &self.output_base)
+            .field("unit_display_preference", &self.unit_display_preference)
+            .field("unknown_unit_behavior", &self.unknown_unit_behavior)
+            .field("memory_limit", &self.memory_limit)
+            .field("restricted_mode", &self.restricted_mode)
+            .field("base_inference", &self.base_inference)
+            .field("max_recurring_digits", &self.max_recurring_digits)
+            .field("max_float_digits", &self.max_float_digits)
+            .field("precision", &self.precision)
+            .field("display_rounding", &self.display_rounding)
+            .field("locale", &self.locale)
+            .field("digit_grouping", &self.digit_grouping)
             .finish_non_exhaustive()
     }
 }
@@ -188,16 +364,132 @@ impl Context {
     pub fn new() -> Self {
         Self {
             current_time: None,
-            variables: HashMap::new(),
+            variables: Arc::new(HashMap::new()),
+            history: Arc::new(VecDeque::new()),
             fc_mode: FCMode::CelsiusFahrenheit,
             random_u32: None,
+            host_functions: Arc::new(HashMap::new()),
+            parse_cache: None,
             output_mode: OutputMode::SimpleText,
             get_exchange_rate: None,
+            max_recursion_depth: 50,
+            recursion_depth: 0,
+            scientific_notation_style: ScientificNotationStyle::Exponent,
+            formatting_style: FormattingStyle::Auto,
+            output_base: Base::default(),
+            unit_display_preference: UnitDisplayPreference::AsEntered,
+            unknown_unit_behavior: UnknownUnitBehavior::Error,
+            unit_cache: Arc::new(HashMap::new()),
+            unit_dependencies: None,
+            exchange_rate_used: false,
+            warnings: vec![],
+            features_used: FeatureSet::default(),
+            base_converted: false,
+            memory_limit: None,
+            restricted_mode: false,
+            base_inference: false,
+            max_recurring_digits: Some(DEFAULT_MAX_RECURRING_DIGITS),
+            max_float_digits: Some(DEFAULT_MAX_FLOAT_DIGITS),
+            precision: None,
+            display_rounding: false,
+            locale: None,
+            digit_grouping: locale::DigitGrouping::default(),
         }
     }
 
-    /// This method currently has no effect!
+    /// Create a new context instance that formats numbers using the given
+    /// style by default, e.g. always as fractions.
+    #[must_use]
+    pub fn with_formatting_style(style: FormattingStyle) -> Self {
+        let mut context = Self::new();
+        context.set_formatting_style(style);
+        context
+    }
+
+    /// Set the default formatting style for numbers. This is used unless a
+    /// different style is chosen with `to`, e.g. `0.5 to fraction`. Defaults
+    /// to [`FormattingStyle::Auto`].
+    pub fn set_formatting_style(&mut self, style: FormattingStyle) {
+        self.formatting_style = style;
+    }
+
+    /// Returns the default formatting style for numbers.
+    pub fn formatting_style(&self) -> FormattingStyle {
+        self.formatting_style
+    }
+
+    /// Set the base that numeric results are shown in, e.g. hex for a
+    /// programmer mode. This is used unless a different base is chosen
+    /// with `to`, e.g. `255 to decimal`. Defaults to decimal.
     ///
+    /// Results are always written with a prefix, so that they can be
+    /// entered again: with `hex`, `255` is shown as `0xff`, and with
+    /// `base 36` it is shown as `36#73`.
+    pub fn set_output_base(&mut self, base: Base) {
+        self.output_base = base;
+    }
+
+    /// Returns the base that numeric results are shown in.
+    #[must_use]
+    pub fn output_base(&self) -> Base {
+        self.output_base
+    }
+
+    /// Set how the units of results are displayed, e.g. whether `1 W * 1 s`
+    /// is shown as `1 W s`, `1 J` or `1 kg m^2 / s^2`. Results that were
+    /// converted with `to` always keep the chosen unit. Defaults to
+    /// [`UnitDisplayPreference::AsEntered`].
+    pub fn set_unit_display_preference(&mut self, preference: UnitDisplayPreference) {
+        self.unit_display_preference = preference;
+    }
+
+    /// Returns how the units of results are displayed.
+    pub fn unit_display_preference(&self) -> UnitDisplayPreference {
+        self.unit_display_preference
+    }
+
+    /// Set what happens when an expression uses an unknown identifier like
+    /// `foo`: either it's an error, or it becomes a symbolic unit that
+    /// cancels out, so that `5 foo / foo` is `5`. Defaults to
+    /// [`UnknownUnitBehavior::Error`].
+    pub fn set_unknown_unit_behavior(&mut self, behavior: UnknownUnitBehavior) {
+        self.unknown_unit_behavior = behavior;
+    }
+
+    fn push_history(&mut self, value: value::Value) {
+        let history = Arc::make_mut(&mut self.history);
+        history.push_front(value);
+        history.truncate(HISTORY_LENGTH);
+    }
+
+    /// Returns the `index`th most recent result for `hist`, starting at 1
+    fn history_entry(&self, index: usize) -> Result<value::Value, FendError> {
+        index
+            .checked_sub(1)
+            .and_then(|i| self.history.get(i))
+            .cloned()
+            .ok_or(FendError::HistoryIndexOutOfRange(index, self.history.len()))
+    }
+
+    fn add_warning(&mut self, warning: &str) {
+        if !self.warnings.iter().any(|w| w == warning) {
+            self.warnings.push(warning.to_string());
+        }
+    }
+
+    /// Records that the input used a feature. Features used while evaluating
+    /// unit definitions are ignored. Fails if the feature is disabled in
+    /// restricted mode.
+    fn use_feature(&mut self, feature: Feature) -> Result<(), FendError> {
+        if self.restricted_mode && matches!(feature, Feature::Dates | Feature::Random) {
+            return Err(FendError::FeatureDisabled(feature));
+        }
+        if self.unit_dependencies.is_none() {
+            self.features_used.insert(feature);
+        }
+        Ok(())
+    }
+
     /// Set the current time. This API will likely change in the future!
     ///
     /// The first argument (`ms_since_1970`) must be the number of elapsed milliseconds
@@ -205,24 +497,128 @@ impl Context {
     /// as unix time.
     ///
     /// The second argument (`tz_offset_secs`) is the current time zone
-    /// offset to UTC, in seconds.
-    pub fn set_current_time_v1(&mut self, _ms_since_1970: u64, _tz_offset_secs: i64) {
-        // self.current_time = Some(CurrentTimeInfo {
-        //     elapsed_unix_time_ms: ms_since_1970,
-        //     timezone_offset_secs: tz_offset_secs,
-        // });
-        self.current_time = None;
+    /// offset to UTC, in seconds. Like JavaScript's `getTimezoneOffset`, this
+    /// is positive for time zones behind UTC, so e.g. UTC+12 is `-43200`.
+    pub fn set_current_time_v1(&mut self, ms_since_1970: u64, tz_offset_secs: i64) {
+        self.current_time = Some(CurrentTimeInfo {
+            elapsed_unix_time_ms: ms_since_1970,
+            timezone_offset_secs: tz_offset_secs,
+        });
     }
 
     /// Define the units `C` and `F` as coulomb and farad instead of degrees
     /// celsius and degrees fahrenheit.
     pub fn use_coulomb_and_farad(&mut self) {
         self.fc_mode = FCMode::CoulombFarad;
+        Arc::make_mut(&mut self.unit_cache).clear();
+        self.clear_parse_cache();
     }
 
-    /// Set a random number generator
-    pub fn set_random_u32_fn(&mut self, random_u32: fn() -> u32) {
-        self.random_u32 = Some(random_u32);
+    /// Set a random number generator. It can keep its own state, e.g. to
+    /// produce the same sequence of numbers from a fixed seed:
+    ///
+    /// ```rust
+    /// let mut context = fend_core::Context::new();
+    /// // xorshift32
+    /// let mut state: u32 = 12345;
+    /// context.set_random_u32_fn(move || {
+    ///     state ^= state << 13;
+    ///     state ^= state >> 17;
+    ///     state ^= state << 5;
+    ///     state
+    /// });
+    /// let result = fend_core::evaluate("roll d6", &mut context).unwrap();
+    /// assert_eq!(result.get_main_result(), "5");
+    /// ```
+    ///
+    /// Copies of this context share the same generator.
+    pub fn set_random_u32_fn(&mut self, random_u32: impl FnMut() -> u32 + Send + 'static) {
+        self.random_u32 = Some(Arc::new(Mutex::new(random_u32)));
+    }
+
+    /// Define a function that can be called from expressions, e.g. to look
+    /// up prices:
+    ///
+    /// ```rust
+    /// use fend_core::Value;
+    ///
+    /// let mut context = fend_core::Context::new();
+    /// context.define_function("price", |args| match args {
+    ///     [Value::String(item)] if item == "apple" => Ok(Value::Number(0.5)),
+    ///     [Value::String(item)] => Err(format!("unknown item '{item}'")),
+    ///     _ => Err("price expects an item name".to_string()),
+    /// });
+    /// let result = fend_core::evaluate("3 price('apple')", &mut context).unwrap();
+    /// assert_eq!(result.get_main_result(), "1.5");
+    /// ```
+    ///
+    /// A call with several arguments like `f(1, 2)`, or with a list, passes
+    /// each item as a separate argument. Numbers must be unitless. Checking
+    /// the number and types of the arguments is up to the function: errors
+    /// it returns are reported as evaluation errors, and so are panics.
+    /// Variables with the same name take precedence over the function, and
+    /// the function takes precedence over built-in functions and units.
+    ///
+    /// Copies of this context share the same functions.
+    pub fn define_function(
+        &mut self,
+        name: &str,
+        f: impl Fn(&[Value]) -> Result<Value, String> + Send + Sync + 'static,
+    ) {
+        Arc::make_mut(&mut self.host_functions).insert(name.to_string(), Arc::new(f));
+        self.clear_parse_cache();
+    }
+
+    /// Cache the parsed expressions of up to `entries` distinct inputs, so
+    /// that evaluating the same input again (e.g. a line that appears many
+    /// times in a worksheet) skips parsing it. The least recently used
+    /// input is dropped when the cache is full. Defaults to 0, which
+    /// disables the cache.
+    ///
+    /// Copies of this context share the same cache.
+    pub fn set_parse_cache_size(&mut self, entries: usize) {
+        self.parse_cache = if entries == 0 {
+            None
+        } else {
+            Some(Arc::new(Mutex::new(parse_cache::ParseCache::new(entries))))
+        };
+    }
+
+    // parsing doesn't depend on the context, but cached inputs are
+    // dropped whenever the names that are known change, so that the cache
+    // can never return an outdated parse
+    fn clear_parse_cache(&self) {
+        if let Some(cache) = &self.parse_cache {
+            cache.lock().unwrap_or_else(PoisonError::into_inner).clear();
+        }
+    }
+
+    fn next_random_u32(&self) -> Option<u32> {
+        let random_u32 = self.random_u32.as_ref()?;
+        let mut random_u32 = random_u32.lock().unwrap_or_else(PoisonError::into_inner);
+        Some(random_u32())
+    }
+
+    /// Returns a uniformly distributed random number in `0..n`. Draws that
+    /// would make smaller numbers more likely (modulo bias) are rejected.
+    fn random_below(&self, n: usize) -> Result<usize, FendError> {
+        assert!(n != 0);
+        let n = n as u128;
+        let draws = if n > 1 << 32 { 2 } else { 1 };
+        let range = 1_u128 << (32 * draws);
+        let limit = range - range % n;
+        loop {
+            let mut random = 0_u128;
+            for _ in 0..draws {
+                let next = self
+                    .next_random_u32()
+                    .ok_or(FendError::RandomNumbersNotAvailable)?;
+                random = random << 32 | u128::from(next);
+            }
+            if random < limit {
+                return Ok(usize::try_from(random % n).unwrap());
+            }
+        }
     }
 
     /// Clear the random number generator after setting it with via [`Self::set_random_u32_fn`]
@@ -236,16 +632,311 @@ impl Context {
         self.output_mode = OutputMode::TerminalFixedWidth;
     }
 
+    /// Set the maximum number of nested function calls (e.g. when a function
+    /// calls itself) before evaluation fails with an error. Defaults to 50.
+    pub fn set_max_recursion_depth(&mut self, max_recursion_depth: usize) {
+        self.max_recursion_depth = max_recursion_depth;
+    }
+
+    /// Limit the size of numbers during a calculation to approximately the
+    /// given number of bytes. Calculations that would create larger numbers
+    /// (e.g. `10^10^10`) fail with an error instead of running out of
+    /// memory. There is no limit by default.
+    pub fn set_memory_limit(&mut self, bytes: usize) {
+        self.memory_limit = Some(bytes);
+    }
+
+    /// Restrict evaluation to plain calculations, e.g. for sandboxed or
+    /// shared environments. Random numbers and dates fail with an error, and
+    /// numbers are limited to a few kilobytes unless a smaller memory limit
+    /// has been set. Restricted mode is disabled by default.
+    pub fn set_restricted_mode(&mut self, enabled: bool) {
+        self.restricted_mode = enabled;
+    }
+
+    /// Limit how many digits are shown for recurring decimals, e.g. with
+    /// `1/97 to float`. Longer expansions show the given number of digits
+    /// after the decimal point, followed by `...`. Defaults to 200 digits.
+    /// The length of the repeating part can be found with `period`.
+    pub fn set_max_recurring_digits(&mut self, digits: usize) {
+        self.max_recurring_digits = Some(digits);
+    }
+
+    /// Limit how many digits are shown after the decimal point when
+    /// converting to `float`, e.g. with `1/1024 to float`. Longer expansions
+    /// are cut off after the given number of digits, followed by `...`, and
+    /// are marked as approximate. This also stops `float` from searching for
+    /// very long repeating parts, e.g. with `1/999983 to float`. Defaults to
+    /// 500 digits.
+    pub fn set_max_float_digits(&mut self, digits: usize) {
+        self.max_float_digits = Some(digits);
+    }
+
+    /// Compute transcendental functions like `sin`, `ln` and `atan`, and the
+    /// constants `pi` and `e`, to at least the given number of decimal
+    /// places. By default they are computed using `f64`, which is accurate
+    /// to about 15 significant digits; higher precisions are slower.
+    /// Converting a result to a number of decimal places or significant
+    /// figures (e.g. `sin 1 to 30 dp`) also raises the precision.
+    pub fn set_precision(&mut self, digits: usize) {
+        self.precision = Some(digits);
+    }
+
+    /// If enabled, approximate results are rounded to 13 significant figures
+    /// before they are shown, which hides the noise that `f64` computations
+    /// leave in the last digits, e.g. `sinh(asinh 2)` is shown as `approx. 2`
+    /// instead of `approx. 1.9999999999`. Exact results and results computed
+    /// with a higher [precision](Context::set_precision) are unaffected, as
+    /// are results converted to a specific format like `to 20 dp`. Disabled
+    /// by default.
+    pub fn set_display_rounding(&mut self, enabled: bool) {
+        self.display_rounding = enabled;
+    }
+
+    /// Write decimal numbers in results using the separators of the given
+    /// locale, e.g. `de-DE` shows `1234.5` as `1.234,5` and `en-US` shows it
+    /// as `1,234.5`. Supported locales include `en-US`, `en-GB`, `de-DE`,
+    /// `de-CH`, `fr-FR`, `es-ES`, `it-IT`, `nl-NL`, `pt-BR`, `ru-RU`, `sv-SE`,
+    /// `ja-JP` and `zh-CN`. This only changes how results are shown: input
+    /// always uses `.` as the decimal point. By default, numbers are written
+    /// with a `.` and without digit grouping.
+    ///
+    /// # Errors
+    /// It returns an error if the locale is not supported, in which case the
+    /// previous locale is kept.
+    pub fn set_locale(&mut self, locale: &str) -> Result<(), String> {
+        let Some(locale) = locale::Locale::from_tag(locale) else {
+            return Err(FendError::UnknownLocale(locale.to_string()).to_string());
+        };
+        self.locale = Some(locale);
+        Ok(())
+    }
+
+    /// Group the digits of results, e.g. `1,000,000` or `0b1111_1111`. By
+    /// default, binary numbers are grouped into nibbles, hexadecimal numbers
+    /// into groups of four digits, and octal and decimal numbers into groups
+    /// of three. Other bases are not grouped unless a group size is set with
+    /// [`Context::set_digit_group_size`]. Decimal numbers are grouped with
+    /// commas (or the separator of the [locale](Context::set_locale)), and
+    /// other bases with the separator set by
+    /// [`Context::set_digit_group_separator`]. Disabled by default.
+    pub fn set_digit_grouping(&mut self, enabled: bool) {
+        self.digit_grouping.enabled = enabled;
+    }
+
+    /// Set the number of digits per group in the given base when digit
+    /// grouping is enabled, e.g. 2 to group hexadecimal numbers into bytes
+    /// like `0xff_ff`. A size of 0 turns off grouping for that base.
+    pub fn set_digit_group_size(&mut self, base: u8, size: usize) {
+        let sizes = &mut self.digit_grouping.group_sizes;
+        sizes.retain(|&(b, _)| b != base);
+        sizes.push((base, size));
+    }
+
+    /// Set the separator between groups of digits in bases other than 10.
+    /// The default is `_`, which can also be used in input.
+    pub fn set_digit_group_separator(&mut self, separator: char) {
+        self.digit_grouping.separator = separator;
+    }
+
+    /// If enabled, results are shown in the base of the number literals in
+    /// the input, so `0xff + 0x01` is `0x100`. Inputs that mix literals in
+    /// different bases (e.g. `0xff + 1`) are shown in decimal, and results
+    /// converted with `to` keep their chosen base. Disabled by default, in
+    /// which case results use the base of the first number.
+    pub fn set_base_inference(&mut self, enabled: bool) {
+        self.base_inference = enabled;
+    }
+
+    /// Choose how numbers are written when converting them to scientific
+    /// notation (e.g. `1.5e10` or `1.5 × 10^10`).
+    pub fn set_scientific_notation_style(&mut self, style: ScientificNotationStyle) {
+        self.scientific_notation_style = style;
+    }
+
+    /// Evaluate the given string, writing the main result into `out` instead
+    /// of building a `FendResult`. The output is identical to
+    /// `get_main_result()`. This skips the combined result string, but
+    /// evaluating and formatting the result still allocate internally, as
+    /// do error messages.
+    ///
+    /// # Errors
+    /// It returns an error if the given string is invalid, or if writing
+    /// to `out` fails.
+    pub fn evaluate_into(
+        &mut self,
+        input: &str,
+        out: &mut impl fmt::Write,
+        int: &impl Interrupt,
+    ) -> Result<(), String> {
+        let result = evaluate_to_spans_unless_empty(input, self, int)?;
+        for span in &result.spans {
+            out.write_str(&span.string).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Evaluate the given string without changing this context, returning
+    /// what the result would be. Assignments (e.g. `x = 5`) are discarded
+    /// afterwards, as are `_`/`ans`/`prev`, so this is suitable for
+    /// evaluating input that the user is still typing.
+    ///
+    /// Unlike [`evaluate_preview_with_interrupt`], the result is not
+    /// filtered and errors are returned. If `allow_incomplete` is true,
+    /// input that ends with an operator or an opening parenthesis (e.g.
+    /// `2 +`) returns an empty result instead of an error, since the user
+    /// has probably not finished typing it.
+    ///
+    /// # Errors
+    /// It returns an error if the given string is invalid.
+    pub fn evaluate_preview(
+        &self,
+        input: &str,
+        allow_incomplete: bool,
+        int: &impl Interrupt,
+    ) -> Result<FendResult, String> {
+        // variables are only copied if the input assigns to one
+        let mut context = self.clone();
+        match evaluate_with_interrupt_internal(input, &mut context, int) {
+            Err(_) if allow_incomplete && eval::is_incomplete(input, int) => {
+                Ok(FendResult::empty())
+            }
+            result => result,
+        }
+    }
+
+    /// Evaluate each line of `input` in turn, as in a worksheet, returning
+    /// one result per line. Later lines can refer to variables assigned on
+    /// earlier lines (e.g. `a = 2` followed by `a * 3`). A line that fails
+    /// to evaluate doesn't stop the remaining lines from being evaluated.
+    pub fn evaluate_all_lines(&mut self, input: &str, int: &impl Interrupt) -> Vec<LineResult> {
+        input
+            .lines()
+            .map(
+                |line| match evaluate_with_interrupt_internal(line, self, int) {
+                    Ok(res) => LineResult {
+                        echo: res
+                            .assignment()
+                            .map(|(name, value)| format!("{name} = {value}")),
+                        result: res.plain_result,
+                        is_error: false,
+                    },
+                    Err(msg) => LineResult {
+                        echo: None,
+                        result: msg,
+                        is_error: true,
+                    },
+                },
+            )
+            .collect()
+    }
+
+    fn load_prelude_line(&mut self, line: &str) -> Result<(), FendError> {
+        let int = interrupt::Never::default();
+        let int = interrupt::WithLimits::new(&int, self);
+        let expr = eval::parse_input(line, &int)?;
+        ast::evaluate(expr, None, self, &int)?;
+        Ok(())
+    }
+
+    /// Evaluates each line of `prelude`, e.g. a file of definitions like
+    /// `smoot = 1.7018 m` or `double = x: 2x` that an application ships
+    /// with. Unlike [`Context::evaluate_all_lines`], the results are
+    /// discarded, and don't change `ans` or the previous results. A line
+    /// that fails to evaluate doesn't stop the remaining lines from being
+    /// evaluated.
+    ///
+    /// # Errors
+    /// It returns an error listing every line that failed, with its line
+    /// number, e.g. `line 3: unknown identifier 'x'`.
+    pub fn load_prelude(&mut self, prelude: &str) -> Result<(), String> {
+        let errors: Vec<String> = prelude
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .filter_map(|(i, line)| {
+                let err = self.load_prelude_line(line).err()?;
+                Some(format!("line {}: {err}", i + 1))
+            })
+            .collect();
+        self.warnings.clear();
+        self.features_used = FeatureSet::default();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+
+    fn set_variable_internal(&mut self, name: &str, value: &str) -> Result<(), FendError> {
+        let int = interrupt::Never::default();
+        let int = interrupt::WithLimits::new(&int, self);
+        let ast::Expr::Ident(ident) = eval::parse_input(name, &int)? else {
+            return Err(FendError::InvalidVariableName(name.to_string()));
+        };
+        let value = eval::parse_input(value, &int)?;
+        ast::evaluate(ast::Expr::Assign(ident, Box::new(value)), None, self, &int)?;
+        Ok(())
+    }
+
+    /// Evaluates `value` and assigns it to the variable `name`, as if
+    /// `name = value` had been evaluated. Unlike
+    /// [`Context::deserialize_variables`], this keeps all other variables,
+    /// so host applications can inject individual values directly.
+    ///
+    /// # Errors
+    /// It returns an error if `name` is not a valid identifier, or if
+    /// `value` cannot be evaluated.
+    pub fn set_variable(&mut self, name: &str, value: &str) -> Result<(), String> {
+        self.set_variable_internal(name, value)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Returns the current value of the variable `name`, formatted in the
+    /// same way as an evaluation result, or `None` if it is not defined.
+    #[must_use]
+    pub fn get_variable(&self, name: &str) -> Option<FendResult> {
+        let value = self.variables.get(name)?;
+        let int = interrupt::Never::default();
+        let mut spans = vec![];
+        value.format(0, &mut spans, self, &int).ok()?;
+        Some(FendResult {
+            plain_result: spans.iter().map(|s| s.string.as_str()).collect(),
+            span_result: spans,
+            is_unit: value.is_unit(),
+            warnings: vec![],
+            assigned_variable: None,
+            features_used: FeatureSet::default(),
+            approx_value: value.approx_value(self, &int),
+            steps: 0,
+        })
+    }
+
     fn serialize_variables_internal(&self, write: &mut impl io::Write) -> Result<(), FendError> {
         serialize_usize(self.variables.len(), write)?;
-        for (k, v) in &self.variables {
+        for (k, v) in self.variables.iter() {
             serialize_string(k.as_str(), write)?;
             v.serialize(write)?;
         }
+        self.formatting_style.serialize(write)?;
+        // the output base and history are left out if they are decimal and
+        // empty respectively, so that the data can still be read by older
+        // versions
+        if self.output_base != Base::default() || !self.history.is_empty() {
+            self.output_base.serialize(write)?;
+        }
+        if !self.history.is_empty() {
+            serialize_usize(self.history.len(), write)?;
+            for value in self.history.iter() {
+                value.serialize(write)?;
+            }
+        }
         Ok(())
     }
 
-    /// Serializes all variables defined in this context to a stream of bytes.
+    /// Serializes all variables defined in this context, along with the
+    /// default formatting style, output base and previous results (see
+    /// `hist`), to a stream of bytes.
     /// Note that the specific format is NOT stable, and can change with any
     /// minor update. It is also not cross-platform compatible.
     ///
@@ -263,17 +954,40 @@ impl Context {
         read: &mut impl io::Read,
     ) -> Result<(), FendError> {
         let len = deserialize_usize(read)?;
-        self.variables.clear();
-        self.variables.reserve(len);
+        let variables = Arc::make_mut(&mut self.variables);
+        variables.clear();
+        variables.reserve(len);
+        Arc::make_mut(&mut self.unit_cache).clear();
         for _ in 0..len {
-            self.variables
-                .insert(deserialize_string(read)?, value::Value::deserialize(read)?);
+            variables.insert(deserialize_string(read)?, value::Value::deserialize(read)?);
+        }
+        // data from older versions ends without a formatting style
+        let mut tag = [0; 1];
+        if read.read(&mut tag)? == 1 {
+            self.formatting_style = FormattingStyle::deserialize_with_tag(tag[0], read)?;
+        }
+        self.output_base = if read.read(&mut tag)? == 1 {
+            Base::deserialize_with_tag(tag[0], read)?
+        } else {
+            Base::default()
+        };
+        let history = Arc::make_mut(&mut self.history);
+        history.clear();
+        if read.read(&mut tag)? == 1 {
+            // the first byte of the length has already been read
+            let mut len = [0; std::mem::size_of::<usize>()];
+            len[0] = tag[0];
+            read.read_exact(&mut len[1..])?;
+            for _ in 0..usize::from_be_bytes(len) {
+                history.push_back(value::Value::deserialize(read)?);
+            }
         }
         Ok(())
     }
 
     /// Deserializes the given variables, replacing all prior variables in
-    /// the given context.
+    /// the given context. This also restores the default formatting style,
+    /// output base and previous results.
     ///
     /// # Errors
     /// Returns an error if the input byte stream is invalid and cannot be
@@ -302,30 +1016,72 @@ pub fn evaluate(input: &str, context: &mut Context) -> Result<FendResult, String
     evaluate_with_interrupt(input, context, &interrupt::Never::default())
 }
 
+fn evaluate_to_spans_unless_empty(
+    input: &str,
+    context: &mut Context,
+    int: &impl Interrupt,
+) -> Result<eval::EvaluatedSpans, String> {
+    context.warnings.clear();
+    context.features_used = FeatureSet::default();
+    context.base_converted = false;
+    if eval::is_blank(input, int) {
+        // no or blank input (or only comments): return no output
+        return Ok(eval::EvaluatedSpans {
+            is_unit: true,
+            ..Default::default()
+        });
+    }
+    let int = interrupt::WithLimits::new(int, context);
+    let mut result =
+        eval::evaluate_to_spans(input, None, context, &int).map_err(|e| e.to_string())?;
+    result.steps = int.steps();
+    Ok(result)
+}
+
 fn evaluate_with_interrupt_internal(
     input: &str,
     context: &mut Context,
     int: &impl Interrupt,
 ) -> Result<FendResult, String> {
-    if input.is_empty() {
-        // no or blank input: return no output
-        return Ok(FendResult::empty());
-    }
-    let (result, is_unit) = match eval::evaluate_to_spans(input, None, context, int) {
-        Ok(value) => value,
-        Err(e) => return Err(e.to_string()),
-    };
+    let result = evaluate_to_spans_unless_empty(input, context, int)?;
     let mut plain_result = String::new();
-    for s in &result {
+    for s in &result.spans {
         plain_result.push_str(&s.string);
     }
     Ok(FendResult {
         plain_result,
-        span_result: result,
-        is_unit,
+        span_result: result.spans,
+        is_unit: result.is_unit,
+        warnings: std::mem::take(&mut context.warnings),
+        assigned_variable: result.assigned_variable,
+        features_used: std::mem::take(&mut context.features_used),
+        approx_value: result.approx_value,
+        steps: result.steps,
     })
 }
 
+/// Parses the given string without evaluating it, returning its syntax tree.
+/// Identifiers such as units, variables and functions are not resolved.
+///
+/// # Errors
+/// It returns an error if the given string cannot be parsed.
+pub fn parse(input: &str) -> Result<syntax::AstNode, String> {
+    let int = interrupt::Never::default();
+    let parse_internal = || -> Result<syntax::AstNode, FendError> {
+        let (mut expr, mut inserted_parens, literal_lexemes) =
+            eval::parse_input_with_literal_lexemes(input, &int)?;
+        while let (ast::Expr::Parens(inner), 1..) = (&mut expr, inserted_parens) {
+            expr = std::mem::replace(&mut **inner, ast::Expr::Literal(value::Value::Unit));
+            inserted_parens -= 1;
+        }
+        Ok(syntax::AstNode::from_expr(
+            expr,
+            &mut literal_lexemes.into_iter(),
+        ))
+    };
+    parse_internal().map_err(|e| e.to_string())
+}
+
 /// This function evaluates a string using the given context and the provided
 /// Interrupt object.
 ///
@@ -355,9 +1111,9 @@ pub fn evaluate_preview_with_interrupt(
     int: &impl Interrupt,
 ) -> FendResult {
     let empty = FendResult::empty();
-    // unfortunately making a complete copy of the context is necessary
-    // because we want variables to still work in multi-statement inputs
-    // like `a = 2; 5a`.
+    // the context is copied (which doesn't copy variables until they're
+    // modified) because we want variables to still work in multi-statement
+    // inputs like `a = 2; 5a`.
     let context_clone = context.clone();
     context.random_u32 = None;
     context.get_exchange_rate = None;
@@ -379,6 +1135,26 @@ pub fn evaluate_preview_with_interrupt(
     result
 }
 
+/// Parses the given string and writes it back out in a canonical form,
+/// without evaluating it. Inputs that only differ in spacing, redundant
+/// parentheses or in how operators are written (e.g. `2*3`, `2 × 3` and
+/// `(2 * 3)`) all return the same string, which makes this useful for
+/// caching results or detecting repeated calculations.
+///
+/// The canonical form parenthesizes every operation, e.g. `2+2*3` becomes
+/// `(2+(2*3))`. It can be evaluated again, but the exact format may change
+/// in future versions.
+///
+/// # Errors
+/// It returns an error if the given string can't be parsed.
+pub fn normalize(input: &str) -> Result<String, String> {
+    let int = interrupt::Never::default();
+    let expr = eval::parse_input(input, &int).map_err(|e| e.to_string())?;
+    expr.without_parens()
+        .format(&Context::new(), &int)
+        .map_err(|e| e.to_string())
+}
+
 #[derive(Debug)]
 pub struct Completion {
     display: String,
@@ -397,6 +1173,98 @@ impl Completion {
     }
 }
 
+/// The result of evaluating a single line, as returned by
+/// [`Context::evaluate_all_lines`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineResult {
+    echo: Option<String>,
+    result: String,
+    is_error: bool,
+}
+
+impl LineResult {
+    /// If the line was an assignment, returns it with the assigned value,
+    /// e.g. `b = 6` for `b = a * 3`
+    #[must_use]
+    pub fn echo(&self) -> Option<&str> {
+        self.echo.as_deref()
+    }
+
+    /// The result of the line, or the error message if it could not be
+    /// evaluated. Blank lines have an empty result.
+    #[must_use]
+    pub fn result(&self) -> &str {
+        &self.result
+    }
+
+    /// Whether the line could not be evaluated
+    #[must_use]
+    pub fn is_error(&self) -> bool {
+        self.is_error
+    }
+}
+
+/// Information about a unit, as returned by [`list_units`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnitInfo {
+    name: String,
+    aliases: Vec<String>,
+    dimension: String,
+    definition: String,
+}
+
+impl UnitInfo {
+    /// The unit's main name, e.g. `meter`
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Other names that refer to the same unit, such as plurals,
+    /// abbreviations and alternative spellings, e.g. `meters`, `m` and `metre`
+    #[must_use]
+    pub fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+
+    /// A description of what the unit measures, e.g. `length` or
+    /// `mass·length/time²`
+    #[must_use]
+    pub fn dimension(&self) -> &str {
+        &self.dimension
+    }
+
+    /// The expression that defines the unit, e.g. `1/1000 kilogram` for
+    /// `gram`, or an empty string for base units like `meter`
+    #[must_use]
+    pub fn definition(&self) -> &str {
+        &self.definition
+    }
+}
+
+/// Lists all built-in units, e.g. to let users browse them grouped by
+/// dimension. Units that depend on unavailable features (such as currencies
+/// without an exchange rate handler) are left out.
+#[must_use]
+pub fn list_units(context: &Context) -> Vec<UnitInfo> {
+    let mut context = context.clone();
+    units::list_units(&mut context, &interrupt::Never::default())
+}
+
+/// Returns the number that converts a value in the unit `from` into the
+/// unit `to`, e.g. `1000.0` from `km` to `m` or `0.277...` from `km/h` to
+/// `m/s`. This is useful for generating conversion tables.
+///
+/// # Errors
+/// It returns an error if either unit is unknown, if the units have
+/// different dimensions, or if they have different zero points (like
+/// `°C` and `°F`), so that no single factor converts between them.
+pub fn conversion_factor(from: &str, to: &str, context: &Context) -> Result<f64, String> {
+    let mut context = context.clone();
+    units::conversion_factor(from, to, &mut context, &interrupt::Never::default())
+        .map_err(|e| e.to_string())
+}
+
 #[must_use]
 pub fn get_completions_for_prefix(mut prefix: &str) -> (usize, Vec<Completion>) {
     let mut prepend = "";
@@ -0,0 +1,181 @@
+//! Locale-dependent number formatting, e.g. `1.234,5` in German, and
+//! digit grouping, e.g. `0b1111_1111`
+
+/// The separators that a locale uses when writing decimal numbers
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Locale {
+    decimal_separator: char,
+    group_separator: char,
+}
+
+const NARROW_NO_BREAK_SPACE: char = '\u{202f}';
+const NO_BREAK_SPACE: char = '\u{a0}';
+
+// (tags, decimal separator, group separator)
+const LOCALES: &[(&[&str], char, char)] = &[
+    (
+        &[
+            "en-US", "en-GB", "en-AU", "en-CA", "en-IE", "en-NZ", "ja-JP", "ko-KR", "zh-CN",
+            "zh-TW", "he-IL", "th-TH",
+        ],
+        '.',
+        ',',
+    ),
+    (
+        &[
+            "de-DE", "de-AT", "es-ES", "it-IT", "nl-NL", "pt-BR", "da-DK", "id-ID", "tr-TR",
+            "el-GR",
+        ],
+        ',',
+        '.',
+    ),
+    (&["de-CH", "it-CH"], '.', '\''),
+    (
+        &["fr-FR", "fr-CA", "fr-CH", "nb-NO"],
+        ',',
+        NARROW_NO_BREAK_SPACE,
+    ),
+    (
+        &[
+            "ru-RU", "uk-UA", "pl-PL", "cs-CZ", "sv-SE", "fi-FI", "pt-PT", "hu-HU",
+        ],
+        ',',
+        NO_BREAK_SPACE,
+    ),
+];
+
+impl Locale {
+    /// Looks up a locale by its tag, e.g. `de-DE`. Tags are case-insensitive
+    /// and may use an underscore instead of a hyphen, e.g. `de_de`.
+    pub(crate) fn from_tag(tag: &str) -> Option<Self> {
+        let tag = tag.trim().replace('_', "-");
+        LOCALES
+            .iter()
+            .find(|(tags, _, _)| tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)))
+            .map(|&(_, decimal_separator, group_separator)| Self {
+                decimal_separator,
+                group_separator,
+            })
+    }
+
+    /// Rewrites a formatted decimal number with this locale's separators,
+    /// e.g. `1234.5` as `1.234,5`. Only the integer parts of numbers with
+    /// four or more digits are grouped, so exponents and the digits after
+    /// the decimal point are left alone.
+    pub(crate) fn localize(self, number: &str) -> String {
+        let chars: Vec<char> = number.chars().collect();
+        let mut result = String::with_capacity(number.len());
+        let mut i = 0;
+        while i < chars.len() {
+            if chars[i].is_ascii_digit() {
+                let start = i;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+                let digits = &chars[start..i];
+                let preceding = start.checked_sub(1).map(|j| chars[j]);
+                let is_integer_part = !matches!(preceding, Some('.' | '(' | '^' | 'e' | 'E'));
+                for (idx, &digit) in digits.iter().enumerate() {
+                    let remaining = digits.len() - idx;
+                    if is_integer_part
+                        && digits.len() >= 4
+                        && idx > 0
+                        && remaining.is_multiple_of(3)
+                    {
+                        result.push(self.group_separator);
+                    }
+                    result.push(digit);
+                }
+                continue;
+            }
+            let is_decimal_point = chars[i] == '.'
+                && i > 0
+                && chars[i - 1].is_ascii_digit()
+                && chars
+                    .get(i + 1)
+                    .is_some_and(|&next| next.is_ascii_digit() || next == '(');
+            result.push(if is_decimal_point {
+                self.decimal_separator
+            } else {
+                chars[i]
+            });
+            i += 1;
+        }
+        result
+    }
+}
+
+/// How the digits of results are grouped, e.g. `1,000,000` or `0b1111_1111`
+#[derive(Clone, Debug)]
+pub(crate) struct DigitGrouping {
+    pub(crate) enabled: bool,
+    // (base, group size) for bases that don't use the default group size
+    pub(crate) group_sizes: Vec<(u8, usize)>,
+    // the separator used in bases other than 10
+    pub(crate) separator: char,
+}
+
+impl Default for DigitGrouping {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            group_sizes: vec![],
+            separator: '_',
+        }
+    }
+}
+
+impl DigitGrouping {
+    /// Binary is grouped into nibbles, hex into 16-bit groups, and octal and
+    /// decimal into groups of three. Other bases are not grouped by default.
+    fn group_size(&self, base: u8) -> usize {
+        if let Some(&(_, size)) = self.group_sizes.iter().find(|&&(b, _)| b == base) {
+            return size;
+        }
+        match base {
+            2 | 16 => 4,
+            8 | 10 => 3,
+            _ => 0,
+        }
+    }
+
+    /// Inserts separators into the integer parts of a formatted number in
+    /// the given base, e.g. `0b11111111` becomes `0b1111_1111`. Decimal
+    /// numbers are grouped with commas.
+    pub(crate) fn group(&self, number: &str, base: u8) -> String {
+        let size = self.group_size(base);
+        if !self.enabled || size == 0 {
+            return number.to_string();
+        }
+        let separator = if base == 10 { ',' } else { self.separator };
+        let chars: Vec<char> = number.chars().collect();
+        let mut result = String::with_capacity(number.len());
+        let mut i = 0;
+        while i < chars.len() {
+            if !chars[i].is_digit(u32::from(base)) {
+                result.push(chars[i]);
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < chars.len() && chars[i].is_digit(u32::from(base)) {
+                i += 1;
+            }
+            let digits = &chars[start..i];
+            // digits after the point and exponents are not grouped
+            let is_integer_part = match start.checked_sub(1).map(|j| chars[j]) {
+                Some('.' | '(' | '^') => false,
+                Some('e' | 'E') => base != 10,
+                _ => true,
+            };
+            for (idx, &digit) in digits.iter().enumerate() {
+                let remaining = digits.len() - idx;
+                if is_integer_part && idx > 0 && remaining.is_multiple_of(size) {
+                    result.push(separator);
+                }
+                result.push(digit);
+            }
+        }
+        result
+    }
+}
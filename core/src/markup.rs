@@ -0,0 +1,402 @@
+//! Converts formatted results into LaTeX or `MathML` markup. The result spans
+//! (as returned by `Value::format`) are first parsed into a list of `Node`s,
+//! which are then written out in the requested markup language.
+
+use crate::{Span, SpanKind};
+
+/// How a number was formatted. This can't always be recovered from the
+/// formatted string, e.g. `1e5` could be in scientific notation or base 16.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum NumberFormat {
+    Decimal,
+    Scientific,
+    /// Any base other than 10 (given here), with or without a prefix like `0x`
+    OtherBase(u8),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Node<'a> {
+    /// Digits, possibly including a decimal point
+    Number(&'a str),
+    /// Recurring digits, e.g. the `3` in `0.(3)`
+    Recurring(&'a str),
+    /// A number in a different base (e.g. `0x1f`), shown in a monospace font
+    Monospace(&'a str),
+    Fraction(Vec<Self>, Vec<Self>),
+    /// An exponent of 10, e.g. `× 10^5`
+    TimesTenTo(&'a str),
+    /// `+`, `-` or `/`
+    Operator(&'a str),
+    ImaginaryUnit,
+    Pi,
+    /// A square root of an integer, e.g. the `√2` in `3√2`
+    Sqrt(&'a str),
+    Approx,
+    /// Separates a number from its unit
+    Space,
+    /// Separates the components of a unit
+    ThinSpace,
+    Unit {
+        name: &'a str,
+        degree: bool,
+        exponent: Option<&'a str>,
+    },
+    Function(&'a str),
+    Text(&'a str),
+}
+
+fn parse_spans(spans: &[Span]) -> Vec<Node<'_>> {
+    let mut nodes = vec![];
+    for span in spans {
+        match span.kind {
+            SpanKind::Number => parse_number(
+                &span.string,
+                span.number_format.unwrap_or(NumberFormat::Decimal),
+                &mut nodes,
+            ),
+            SpanKind::Ident if span.string == "approx. " => nodes.push(Node::Approx),
+            SpanKind::Ident => parse_unit(&span.string, &mut nodes),
+            SpanKind::BuiltInFunction => nodes.push(Node::Function(&span.string)),
+            _ => {
+                if !span.string.is_empty() {
+                    nodes.push(Node::Text(&span.string));
+                }
+            }
+        }
+    }
+    nodes
+}
+
+fn parse_number<'a>(s: &'a str, format: NumberFormat, nodes: &mut Vec<Node<'a>>) {
+    // complex numbers are formatted as e.g. `3 + 4i` or `2 - 0.5i`
+    match s.find(" + ").or_else(|| s.find(" - ")) {
+        Some(idx) => {
+            parse_term(&s[..idx], format, nodes);
+            nodes.push(Node::Operator(&s[idx + 1..idx + 2]));
+            parse_term(&s[idx + 3..], format, nodes);
+        }
+        None => parse_term(s, format, nodes),
+    }
+}
+
+fn parse_term<'a>(mut s: &'a str, format: NumberFormat, nodes: &mut Vec<Node<'a>>) {
+    if let Some(remaining) = s.strip_prefix('-') {
+        nodes.push(Node::Operator("-"));
+        s = remaining;
+    }
+    if let NumberFormat::OtherBase(base) = format {
+        // numbers in other bases may contain letters, and from base 19
+        // onwards `i` is a digit so the imaginary unit follows a space
+        let real = s
+            .strip_suffix(" i")
+            .or_else(|| s.strip_suffix('i').filter(|_| base < 19));
+        nodes.push(Node::Monospace(real.unwrap_or(s).trim_end()));
+        if real.is_some() {
+            nodes.push(Node::ImaginaryUnit);
+        }
+        return;
+    }
+    if format == NumberFormat::Scientific {
+        if let Some((mantissa, exp)) = s.split_once(" \u{d7} 10^").or_else(|| s.split_once('e')) {
+            // imaginary numbers are written as e.g. `1.5e3i`
+            let (exp, imag) = match exp.strip_suffix('i') {
+                Some(exp) => (exp.trim_end(), true),
+                None => (exp, false),
+            };
+            parse_decimal(mantissa, nodes);
+            nodes.push(Node::TimesTenTo(exp));
+            if imag {
+                nodes.push(Node::ImaginaryUnit);
+            }
+            return;
+        }
+    }
+    match s.split_once('/') {
+        Some((num, den)) => {
+            // mixed fractions are written as e.g. `1 2/3` or `2 1/3 π`
+            let num = match num.split_once(' ') {
+                Some((whole, num)) => {
+                    parse_decimal(whole, nodes);
+                    num
+                }
+                None => num,
+            };
+            let (den, suffix) = den.split_once(' ').unwrap_or((den, ""));
+            let mut num_nodes = vec![];
+            parse_factor(num, &mut num_nodes);
+            let mut den_nodes = vec![];
+            parse_factor(den, &mut den_nodes);
+            nodes.push(Node::Fraction(num_nodes, den_nodes));
+            parse_factor(suffix, nodes);
+        }
+        None => parse_factor(s, nodes),
+    }
+}
+
+/// Parses a decimal number followed by any symbolic factors, e.g. `2√2`,
+/// `1.5π` or `i`
+fn parse_factor<'a>(s: &'a str, nodes: &mut Vec<Node<'a>>) {
+    let symbols_start = s.find(['\u{3c0}', '\u{221a}', 'i']).unwrap_or(s.len());
+    let (digits, mut symbols) = s.split_at(symbols_start);
+    if !digits.trim().is_empty() {
+        parse_decimal(digits.trim(), nodes);
+    }
+    while let Some(ch) = symbols.chars().next() {
+        symbols = &symbols[ch.len_utf8()..];
+        match ch {
+            '\u{3c0}' => nodes.push(Node::Pi),
+            'i' => nodes.push(Node::ImaginaryUnit),
+            '\u{221a}' => {
+                let end = symbols
+                    .find(|ch: char| !ch.is_ascii_digit())
+                    .unwrap_or(symbols.len());
+                nodes.push(Node::Sqrt(&symbols[..end]));
+                symbols = &symbols[end..];
+            }
+            _ => (),
+        }
+    }
+}
+
+fn parse_decimal<'a>(s: &'a str, nodes: &mut Vec<Node<'a>>) {
+    // recurring digits are formatted as e.g. `0.(3)`
+    match s.split_once('(') {
+        Some((prefix, recurring)) => {
+            nodes.push(Node::Number(prefix));
+            nodes.push(Node::Recurring(recurring.trim_end_matches(')')));
+        }
+        None => nodes.push(Node::Number(s)),
+    }
+}
+
+fn parse_unit<'a>(s: &'a str, nodes: &mut Vec<Node<'a>>) {
+    if s.starts_with(' ') {
+        nodes.push(Node::Space);
+    }
+    let mut first = true;
+    for component in s.split(' ').filter(|c| !c.is_empty()) {
+        if !first {
+            nodes.push(Node::ThinSpace);
+        }
+        first = false;
+        if component == "/" {
+            nodes.push(Node::Operator("/"));
+            continue;
+        }
+        let (name, exponent) = match component.split_once('^') {
+            Some((name, exp)) => {
+                let exp = exp
+                    .strip_prefix('(')
+                    .and_then(|e| e.strip_suffix(')'))
+                    .unwrap_or(exp);
+                (name, Some(exp))
+            }
+            None => (component, None),
+        };
+        let (name, degree) = match name.strip_prefix('\u{b0}') {
+            Some(name) => (name, true),
+            None => (name, false),
+        };
+        nodes.push(Node::Unit {
+            name,
+            degree,
+            exponent,
+        });
+    }
+}
+
+/// Converts result spans into LaTeX markup, e.g. `1 2/3 kg m^2` becomes
+/// `1\frac{2}{3}\ \mathrm{kg}\,\mathrm{m}^{2}`.
+pub(crate) fn spans_to_latex(spans: &[Span]) -> String {
+    let mut out = String::new();
+    write_latex(&parse_spans(spans), &mut out);
+    out
+}
+
+fn write_latex(nodes: &[Node<'_>], out: &mut String) {
+    for (i, node) in nodes.iter().enumerate() {
+        match node {
+            Node::Number(n) => out.push_str(n),
+            Node::Recurring(n) => {
+                out.push_str("\\overline{");
+                out.push_str(n);
+                out.push('}');
+            }
+            Node::Monospace(n) => {
+                out.push_str("\\mathtt{");
+                out.push_str(n);
+                out.push('}');
+            }
+            Node::Fraction(num, den) => {
+                out.push_str("\\frac{");
+                write_latex(num, out);
+                out.push_str("}{");
+                write_latex(den, out);
+                out.push('}');
+            }
+            Node::TimesTenTo(exp) => {
+                out.push_str(" \\times 10^{");
+                out.push_str(exp);
+                out.push('}');
+            }
+            // binary operators between two parts of a complex number
+            Node::Operator(op @ ("+" | "-")) if i > 0 && nodes[i - 1] != Node::Approx => {
+                out.push(' ');
+                out.push_str(op);
+                out.push(' ');
+            }
+            Node::Operator(op) => out.push_str(op),
+            Node::ImaginaryUnit => {
+                // `\pii` would be an unknown command
+                if i > 0 && nodes[i - 1] == Node::Pi {
+                    out.push(' ');
+                }
+                out.push('i');
+            }
+            Node::Pi => out.push_str("\\pi"),
+            Node::Sqrt(n) => {
+                out.push_str("\\sqrt{");
+                out.push_str(n);
+                out.push('}');
+            }
+            Node::Approx => out.push_str("\\approx "),
+            Node::Space => out.push_str("\\ "),
+            Node::ThinSpace => out.push_str("\\,"),
+            Node::Unit {
+                name,
+                degree,
+                exponent,
+            } => {
+                if *degree {
+                    out.push_str("{}^{\\circ}");
+                }
+                if !name.is_empty() {
+                    out.push_str("\\mathrm{");
+                    escape_latex(name, out);
+                    out.push('}');
+                }
+                if let Some(exp) = exponent {
+                    out.push_str("^{");
+                    out.push_str(exp);
+                    out.push('}');
+                }
+            }
+            Node::Function(name) => {
+                out.push_str("\\operatorname{");
+                escape_latex(name, out);
+                out.push('}');
+            }
+            Node::Text(s) => {
+                out.push_str("\\text{");
+                escape_latex(s, out);
+                out.push('}');
+            }
+        }
+    }
+}
+
+fn escape_latex(s: &str, out: &mut String) {
+    for ch in s.chars() {
+        match ch {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '%' | '$' | '#' | '_' | '&' | '{' | '}' => {
+                out.push('\\');
+                out.push(ch);
+            }
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            _ => out.push(ch),
+        }
+    }
+}
+
+/// Converts result spans into a `MathML` `<math>` element.
+pub(crate) fn spans_to_mathml(spans: &[Span]) -> String {
+    let mut out = String::from("<math>");
+    write_mathml(&parse_spans(spans), &mut out);
+    out.push_str("</math>");
+    out
+}
+
+fn write_mathml(nodes: &[Node<'_>], out: &mut String) {
+    for node in nodes {
+        match node {
+            // `<mn></mn>` isn't valid
+            Node::Number("") => (),
+            Node::Number(n) => write_element("mn", "", n, out),
+            Node::Recurring(n) => {
+                out.push_str("<mover>");
+                write_element("mn", "", n, out);
+                write_element("mo", "", "\u{af}", out);
+                out.push_str("</mover>");
+            }
+            Node::Monospace(n) => write_element("mn", r#" mathvariant="monospace""#, n, out),
+            Node::Fraction(num, den) => {
+                out.push_str("<mfrac><mrow>");
+                write_mathml(num, out);
+                out.push_str("</mrow><mrow>");
+                write_mathml(den, out);
+                out.push_str("</mrow></mfrac>");
+            }
+            Node::TimesTenTo(exp) => {
+                write_element("mo", "", "\u{d7}", out);
+                out.push_str("<msup>");
+                write_element("mn", "", "10", out);
+                write_element("mn", "", exp, out);
+                out.push_str("</msup>");
+            }
+            Node::Operator(op) => write_element("mo", "", op, out),
+            Node::ImaginaryUnit => write_element("mi", "", "i", out),
+            Node::Pi => write_element("mi", "", "\u{3c0}", out),
+            Node::Sqrt(n) => {
+                out.push_str("<msqrt>");
+                write_element("mn", "", n, out);
+                out.push_str("</msqrt>");
+            }
+            Node::Approx => write_element("mo", "", "\u{2248}", out),
+            Node::Space => out.push_str(r#"<mspace width="0.5em"/>"#),
+            Node::ThinSpace => out.push_str(r#"<mspace width="0.17em"/>"#),
+            Node::Unit {
+                name,
+                degree,
+                exponent,
+            } => {
+                let name = if *degree {
+                    format!("\u{b0}{name}")
+                } else {
+                    (*name).to_string()
+                };
+                let attrs = r#" mathvariant="normal""#;
+                if let Some(exp) = exponent {
+                    out.push_str("<msup>");
+                    write_element("mi", attrs, &name, out);
+                    write_element("mn", "", exp, out);
+                    out.push_str("</msup>");
+                } else {
+                    write_element("mi", attrs, &name, out);
+                }
+            }
+            Node::Function(name) => write_element("mi", "", name, out),
+            Node::Text(s) => write_element("mtext", "", s, out),
+        }
+    }
+}
+
+fn write_element(tag: &str, attrs: &str, content: &str, out: &mut String) {
+    out.push('<');
+    out.push_str(tag);
+    out.push_str(attrs);
+    out.push('>');
+    for ch in content.chars() {
+        match ch {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(ch),
+        }
+    }
+    out.push_str("</");
+    out.push_str(tag);
+    out.push('>');
+}
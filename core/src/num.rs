@@ -9,13 +9,16 @@ mod exact;
 mod formatting_style;
 mod real;
 mod unit;
+mod unit_display_preference;
 
-pub(crate) use formatting_style::FormattingStyle;
+pub use base::Base;
+pub use formatting_style::{FormattingStyle, ScientificNotationStyle};
+pub use unit_display_preference::UnitDisplayPreference;
 
 use crate::error::FendError;
 
 pub(crate) type Number = unit::Value;
-pub(crate) type Base = base::Base;
+pub(crate) type UnitSystem = unit::UnitSystem;
 pub(crate) type Exact<T> = exact::Exact<T>;
 
 #[derive(Debug)]
@@ -85,3 +88,16 @@ fn out_of_range<T: fmt::Display + fmt::Debug + 'static, U: fmt::Display + fmt::D
         },
     }
 }
+
+fn inside_range<T: fmt::Display + fmt::Debug + 'static, U: fmt::Display + fmt::Debug + 'static>(
+    value: T,
+    range: Range<U>,
+) -> FendError {
+    FendError::InsideRange {
+        value: Box::new(value),
+        range: Range {
+            start: range.start.into_dyn(),
+            end: range.end.into_dyn(),
+        },
+    }
+}
@@ -1,12 +1,14 @@
-use std::{fmt, io};
+use std::{fmt, io, str::FromStr};
 
 use crate::{
     error::FendError,
     serialize::{deserialize_u8, serialize_u8},
 };
 
+/// The base that numbers are written in, e.g. `hex` or `base 36`. Bases
+/// can be parsed from the same names that can be used with `to`.
 #[derive(Copy, Clone, PartialEq, Eq)]
-pub(crate) struct Base(BaseEnum);
+pub struct Base(BaseEnum);
 
 #[derive(Copy, Clone, PartialEq, Eq)]
 enum BaseEnum {
@@ -76,6 +78,19 @@ impl Base {
         !matches!(self.0, BaseEnum::Plain(_))
     }
 
+    /// Returns the same base, but written with a prefix (e.g. `0x` for hex
+    /// or `36#` for base 36) so that numbers can be entered again. Decimal
+    /// is left unchanged.
+    pub(crate) const fn with_prefix(self) -> Self {
+        Self(match self.0 {
+            BaseEnum::Plain(2) => BaseEnum::Binary,
+            BaseEnum::Plain(8) => BaseEnum::Octal,
+            BaseEnum::Plain(16) => BaseEnum::Hex,
+            BaseEnum::Plain(b) if b != 10 => BaseEnum::Custom(b),
+            b => b,
+        })
+    }
+
     pub(crate) const fn digit_as_char(digit: u64) -> Option<char> {
         Some(match digit {
             0 => '0',
@@ -136,12 +151,20 @@ impl Base {
     }
 
     pub(crate) fn deserialize(read: &mut impl io::Read) -> Result<Self, FendError> {
-        Ok(Self(match deserialize_u8(read)? {
+        let tag = deserialize_u8(read)?;
+        Self::deserialize_with_tag(tag, read)
+    }
+
+    pub(crate) fn deserialize_with_tag(
+        tag: u8,
+        read: &mut impl io::Read,
+    ) -> Result<Self, FendError> {
+        Ok(Self(match tag {
             1 => BaseEnum::Binary,
             2 => BaseEnum::Octal,
             3 => BaseEnum::Hex,
-            4 => BaseEnum::Custom(deserialize_u8(read)?),
-            5 => BaseEnum::Plain(deserialize_u8(read)?),
+            4 => return Self::from_custom_base(deserialize_u8(read)?),
+            5 => return Self::from_plain_base(deserialize_u8(read)?),
             _ => return Err(FendError::DeserializationError),
         }))
     }
@@ -153,6 +176,58 @@ impl Default for Base {
     }
 }
 
+impl fmt::Display for Base {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            BaseEnum::Binary => write!(f, "0b"),
+            BaseEnum::Octal => write!(f, "0o"),
+            BaseEnum::Hex => write!(f, "0x"),
+            BaseEnum::Custom(b) => write!(f, "{b}#"),
+            BaseEnum::Plain(2) => write!(f, "binary"),
+            BaseEnum::Plain(3) => write!(f, "ternary"),
+            BaseEnum::Plain(6) => write!(f, "senary"),
+            BaseEnum::Plain(8) => write!(f, "octal"),
+            BaseEnum::Plain(10) => write!(f, "decimal"),
+            BaseEnum::Plain(16) => write!(f, "hex"),
+            BaseEnum::Plain(b) => write!(f, "base {b}"),
+        }
+    }
+}
+
+impl FromStr for Base {
+    type Err = String;
+
+    /// Parses names like `hex` or `base 36`, as well as prefixes like `0x`
+    /// or `36#` for bases that are written with a prefix.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let parse_base = |base: &str| -> Result<u8, FendError> {
+            let base: u64 = base
+                .trim()
+                .parse()
+                .map_err(|_| FendError::UnknownBase(s.to_string()))?;
+            base.try_into().map_err(|_| FendError::BaseTooLarge)
+        };
+        let result = match s {
+            "0b" => Ok(Self(BaseEnum::Binary)),
+            "0o" => Ok(Self(BaseEnum::Octal)),
+            "0x" => Ok(Self(BaseEnum::Hex)),
+            "binary" => Self::from_plain_base(2),
+            "ternary" => Self::from_plain_base(3),
+            "senary" | "seximal" => Self::from_plain_base(6),
+            "oct" | "octal" => Self::from_plain_base(8),
+            "dec" | "decimal" => Self::from_plain_base(10),
+            "hex" | "hexadecimal" => Self::from_plain_base(16),
+            _ => match (s.strip_prefix("base "), s.strip_suffix('#')) {
+                (Some(base), _) => parse_base(base).and_then(Self::from_plain_base),
+                (None, Some(base)) => parse_base(base).and_then(Self::from_custom_base),
+                (None, None) => Err(FendError::UnknownBase(s.to_string())),
+            },
+        };
+        result.map_err(|e| e.to_string())
+    }
+}
+
 impl fmt::Debug for Base {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self.0 {
@@ -164,3 +239,42 @@ impl fmt::Debug for Base {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_bases() -> Vec<Base> {
+        let mut bases = vec![
+            Base(BaseEnum::Binary),
+            Base(BaseEnum::Octal),
+            Base(BaseEnum::Hex),
+        ];
+        for b in 2..=36 {
+            bases.push(Base::from_custom_base(b).unwrap());
+            bases.push(Base::from_plain_base(b).unwrap());
+        }
+        bases
+    }
+
+    #[test]
+    fn serialization_roundtrip() {
+        for base in all_bases() {
+            let mut data = vec![];
+            base.serialize(&mut data).unwrap();
+            assert_eq!(Base::deserialize(&mut data.as_slice()).unwrap(), base);
+        }
+        // out-of-range bases are rejected
+        assert!(Base::deserialize(&mut [5, 37].as_slice()).is_err());
+        assert!(Base::deserialize(&mut [4, 1].as_slice()).is_err());
+    }
+
+    #[test]
+    fn display_roundtrip() {
+        for base in all_bases() {
+            assert_eq!(base.to_string().parse::<Base>(), Ok(base), "{base}");
+        }
+        assert_eq!(Base::from_plain_base(36).unwrap().to_string(), "base 36");
+        assert_eq!(Base::from_custom_base(36).unwrap().to_string(), "36#");
+    }
+}
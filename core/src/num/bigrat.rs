@@ -2,7 +2,7 @@ use crate::error::{FendError, Interrupt};
 use crate::format::Format;
 use crate::interrupt::test_int;
 use crate::num::biguint::BigUint;
-use crate::num::{Base, Exact, FormattingStyle, Range, RangeBound};
+use crate::num::{Base, Exact, FormattingStyle, Range, RangeBound, ScientificNotationStyle};
 use std::{cmp, fmt, hash, io, ops};
 
 mod sign {
@@ -58,9 +58,20 @@ mod sign {
 }
 
 use super::biguint::{self, FormattedBigUint};
-use super::out_of_range;
+use super::{inside_range, out_of_range};
 use sign::Sign;
 
+mod series;
+
+/// An `f64` has about 15 significant digits
+const F64_DIGITS: usize = 15;
+
+/// Returns the number of decimal places that transcendental functions
+/// should be computed to, if that's more than an `f64` can provide
+fn high_precision<I: Interrupt>(int: &I) -> Option<usize> {
+    int.precision().filter(|&digits| digits > F64_DIGITS)
+}
+
 #[derive(Clone)]
 pub(crate) struct BigRat {
     sign: Sign,
@@ -163,10 +174,32 @@ impl BigRat {
         clippy::cast_precision_loss
     )]
     pub(crate) fn from_f64<I: Interrupt>(mut f: f64, int: &I) -> Result<Self, FendError> {
+        if f.is_nan() {
+            return Err(FendError::ResultIsNaN);
+        }
+        if f.is_infinite() {
+            return Err(FendError::ResultIsInfinite);
+        }
         let negative = f < 0.0;
         if negative {
             f = -f;
         }
+        if f >= 2_f64.powi(63) {
+            // large floats are integers, so we can convert them exactly
+            // from their mantissa and exponent
+            let bits = f.to_bits();
+            let mantissa = (bits & ((1 << 52) - 1)) | (1 << 52);
+            let exponent = (bits >> 52) - 1075;
+            return Ok(Self {
+                sign: if negative {
+                    Sign::Negative
+                } else {
+                    Sign::Positive
+                },
+                num: BigUint::from(mantissa).lshift_n(&BigUint::from(exponent), int)?,
+                den: BigUint::from(1),
+            });
+        }
         let i = (f * u64::MAX as f64) as u128;
         let part1 = i as u64;
         let part2 = (i >> 64) as u64;
@@ -182,10 +215,30 @@ impl BigRat {
         })
     }
 
+    /// An approximation of pi, accurate to the current precision or to 18
+    /// decimal places
+    pub(crate) fn pi<I: Interrupt>(int: &I) -> Result<Self, FendError> {
+        if let Some(digits) = high_precision(int) {
+            return series::pi(digits, int);
+        }
+        Self::from(3_141_592_653_589_793_238).div(&Self::from(1_000_000_000_000_000_000), int)
+    }
+
+    /// An approximation of e, accurate to the current precision or to 18
+    /// decimal places
+    pub(crate) fn e<I: Interrupt>(int: &I) -> Result<Self, FendError> {
+        if let Some(digits) = high_precision(int) {
+            return series::exp(1.into(), digits, int);
+        }
+        Self::from(2_718_281_828_459_045_235).div(&Self::from(1_000_000_000_000_000_000), int)
+    }
+
     // sin works for all real numbers
     pub(crate) fn sin<I: Interrupt>(self, int: &I) -> Result<Exact<Self>, FendError> {
         Ok(if self == 0.into() {
             Exact::new(Self::from(0), true)
+        } else if let Some(digits) = high_precision(int) {
+            Exact::new(series::sin(self, digits, int)?, false)
         } else {
             Exact::new(Self::from_f64(f64::sin(self.into_f64(int)?), int)?, false)
         })
@@ -197,6 +250,9 @@ impl BigRat {
         if self > one || self < -one {
             return Err(out_of_range(self.fm(int)?, Range::open(-1, 1)));
         }
+        if let Some(digits) = high_precision(int) {
+            return series::asin(self, digits, int);
+        }
         Self::from_f64(f64::asin(self.into_f64(int)?), int)
     }
 
@@ -205,27 +261,108 @@ impl BigRat {
         if self > one || self < -one {
             return Err(out_of_range(self.fm(int)?, Range::open(-1, 1)));
         }
+        if let Some(digits) = high_precision(int) {
+            // acos x = pi/2 - asin x
+            let half_pi = series::pi(digits, int)?.div(&2.into(), int)?;
+            return half_pi.add(-series::asin(self, digits, int)?, int);
+        }
         Self::from_f64(f64::acos(self.into_f64(int)?), int)
     }
 
     // note that this works for any real number, unlike asin and acos
     pub(crate) fn atan<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        if let Some(digits) = high_precision(int) {
+            return series::atan(self, digits, int);
+        }
         Self::from_f64(f64::atan(self.into_f64(int)?), int)
     }
 
+    // asec(x) == acos(1/x), so x must not be between -1 and 1
+    pub(crate) fn asec<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        let one = Self::from(1);
+        if self < one && self > -one {
+            return Err(inside_range(self.fm(int)?, Range::open(-1, 1)));
+        }
+        if high_precision(int).is_some() {
+            return Self::from(1).div(&self, int)?.acos(int);
+        }
+        Self::from_f64(f64::acos(1.0 / self.into_f64(int)?), int)
+    }
+
+    // acsc(x) == asin(1/x), so x must not be between -1 and 1
+    pub(crate) fn acsc<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        let one = Self::from(1);
+        if self < one && self > -one {
+            return Err(inside_range(self.fm(int)?, Range::open(-1, 1)));
+        }
+        if high_precision(int).is_some() {
+            return Self::from(1).div(&self, int)?.asin(int);
+        }
+        Self::from_f64(f64::asin(1.0 / self.into_f64(int)?), int)
+    }
+
+    // acot(x) == atan(1/x), and acot(0) == pi/2
+    pub(crate) fn acot<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        if let Some(digits) = high_precision(int) {
+            if self == 0.into() {
+                return series::pi(digits, int)?.div(&2.into(), int);
+            }
+            return Self::from(1).div(&self, int)?.atan(int);
+        }
+        if self == 0.into() {
+            return Self::from_f64(std::f64::consts::FRAC_PI_2, int);
+        }
+        Self::from_f64(f64::atan(1.0 / self.into_f64(int)?), int)
+    }
+
     pub(crate) fn sinh<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        if let Some(digits) = high_precision(int) {
+            // sinh x = (e^x - e^-x) / 2
+            let exp = series::exp(self.clone(), digits, int)?;
+            let exp_neg = series::exp(-self, digits, int)?;
+            return exp.add(-exp_neg, int)?.div(&2.into(), int);
+        }
         Self::from_f64(f64::sinh(self.into_f64(int)?), int)
     }
 
     pub(crate) fn cosh<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        if let Some(digits) = high_precision(int) {
+            // cosh x = (e^x + e^-x) / 2
+            let exp = series::exp(self.clone(), digits, int)?;
+            let exp_neg = series::exp(-self, digits, int)?;
+            return exp.add(exp_neg, int)?.div(&2.into(), int);
+        }
         Self::from_f64(f64::cosh(self.into_f64(int)?), int)
     }
 
     pub(crate) fn tanh<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        if let Some(digits) = high_precision(int) {
+            // tanh x = (e^2x - 1) / (e^2x + 1), computed with e^-2x for
+            // positive x so that it doesn't overflow
+            let negative = self < 0.into();
+            let x = if negative { self } else { -self };
+            let exp = series::exp(x.mul(&2.into(), int)?, digits, int)?;
+            let tanh = Self::from(1)
+                .add(-exp.clone(), int)?
+                .div(&exp.add(1.into(), int)?, int)?;
+            return Ok(if negative { -tanh } else { tanh });
+        }
         Self::from_f64(f64::tanh(self.into_f64(int)?), int)
     }
 
     pub(crate) fn asinh<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        if let Some(digits) = high_precision(int) {
+            // asinh x = ln(x + sqrt(x^2 + 1)), and asinh(-x) = -asinh x
+            if self < 0.into() {
+                return Ok(-(-self).asinh(int)?);
+            }
+            let root = series::sqrt(
+                self.clone().mul(&self, int)?.add(1.into(), int)?,
+                digits,
+                int,
+            )?;
+            return series::ln(self.add(root, int)?, digits, int);
+        }
         Self::from_f64(f64::asinh(self.into_f64(int)?), int)
     }
 
@@ -240,6 +377,15 @@ impl BigRat {
                 },
             ));
         }
+        if let Some(digits) = high_precision(int) {
+            // acosh x = ln(x + sqrt(x^2 - 1))
+            let root = series::sqrt(
+                self.clone().mul(&self, int)?.add(-Self::from(1), int)?,
+                digits,
+                int,
+            )?;
+            return series::ln(self.add(root, int)?, digits, int);
+        }
         Self::from_f64(f64::acosh(self.into_f64(int)?), int)
     }
 
@@ -249,6 +395,13 @@ impl BigRat {
         if self >= one || self <= -one {
             return Err(out_of_range(self.fm(int)?, Range::open(-1, 1)));
         }
+        if let Some(digits) = high_precision(int) {
+            // atanh x = ln((1 + x) / (1 - x)) / 2
+            let ratio = Self::from(1)
+                .add(self.clone(), int)?
+                .div(&Self::from(1).add(-self, int)?, int)?;
+            return series::ln(ratio, digits, int)?.div(&2.into(), int);
+        }
         Self::from_f64(f64::atanh(self.into_f64(int)?), int)
     }
 
@@ -263,6 +416,9 @@ impl BigRat {
                 },
             ));
         }
+        if let Some(digits) = high_precision(int) {
+            return series::ln(self, digits, int);
+        }
         Self::from_f64(f64::ln(self.into_f64(int)?), int)
     }
 
@@ -276,6 +432,10 @@ impl BigRat {
                 },
             ));
         }
+        if let Some(digits) = high_precision(int) {
+            let ln_2 = series::ln(2.into(), digits, int)?;
+            return series::ln(self, digits, int)?.div(&ln_2, int);
+        }
         Self::from_f64(f64::log2(self.into_f64(int)?), int)
     }
 
@@ -289,6 +449,10 @@ impl BigRat {
                 },
             ));
         }
+        if let Some(digits) = high_precision(int) {
+            let ln_10 = series::ln(10.into(), digits, int)?;
+            return series::ln(self, digits, int)?.div(&ln_10, int);
+        }
         Self::from_f64(f64::log10(self.into_f64(int)?), int)
     }
 
@@ -330,6 +494,10 @@ impl BigRat {
                         BitwiseBop::Xor => lhs.bitwise_xor(&rhs),
                         BitwiseBop::LeftShift => lhs.lshift_n(&rhs, int)?,
                         BitwiseBop::RightShift => lhs.rshift_n(&rhs, int)?,
+                        // the complement of an unbounded integer is undefined
+                        BitwiseBop::Nand | BitwiseBop::Nor | BitwiseBop::Xnor => {
+                            return Err(FendError::ExpectedABool("number"));
+                        }
                     };
                     Ok(result)
                 },
@@ -401,6 +569,51 @@ impl BigRat {
         Ok(self)
     }
 
+    /// Returns the number of digits in the repeating part of this number's
+    /// expansion in the given base, or zero if the expansion terminates.
+    /// This is the multiplicative order of the base modulo the denominator,
+    /// after removing any factors that the denominator shares with the base.
+    pub(crate) fn period<I: Interrupt>(self, base: Base, int: &I) -> Result<Self, FendError> {
+        let base = BigUint::from(u64::from(base.base_as_u8()));
+        let mut den = self.simplify(int)?.den;
+        loop {
+            let gcd = BigUint::gcd(den.clone(), base.clone(), int)?;
+            if gcd == 1.into() {
+                break;
+            }
+            den = den.div(&gcd, int)?;
+        }
+        if den == 1.into() {
+            return Ok(0.into());
+        }
+        let mut remainder = base.divmod(&den, int)?.1;
+        let mut period = BigUint::from(1);
+        while remainder != 1.into() {
+            test_int(int)?;
+            remainder = remainder.mul(&base, int)?.divmod(&den, int)?.1;
+            period = period.add(&1.into());
+        }
+        Ok(period.into())
+    }
+
+    /// Returns the numerator and denominator in lowest terms. The numerator
+    /// has the same sign as this number.
+    pub(crate) fn into_fraction<I: Interrupt>(self, int: &I) -> Result<(Self, Self), FendError> {
+        let simplified = self.simplify(int)?;
+        Ok((
+            Self {
+                sign: simplified.sign,
+                num: simplified.num,
+                den: 1.into(),
+            },
+            Self {
+                sign: Sign::Positive,
+                num: simplified.den,
+                den: 1.into(),
+            },
+        ))
+    }
+
     pub(crate) fn div<I: Interrupt>(self, rhs: &Self, int: &I) -> Result<Self, FendError> {
         if rhs.num == 0.into() {
             return Err(FendError::DivideByZero);
@@ -436,6 +649,127 @@ impl BigRat {
         })
     }
 
+    /// Rounds to the nearest integer, rounding halves away from zero
+    pub(crate) fn round<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        let simplified = self.simplify(int)?;
+        let (mut quotient, remainder) = simplified.num.divmod(&simplified.den, int)?;
+        if remainder.mul(&2.into(), int)? >= simplified.den {
+            quotient = quotient.add(&1.into());
+        }
+        Ok(Self {
+            sign: if quotient == 0.into() {
+                Sign::Positive
+            } else {
+                simplified.sign
+            },
+            num: quotient,
+            den: 1.into(),
+        })
+    }
+
+    /// Rounds away the noise that `f64` computations leave in the last
+    /// couple of digits, e.g. `1.9999999999999998` becomes `2`. Values
+    /// computed to a higher precision are left unchanged.
+    pub(crate) fn round_f64_noise<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        if high_precision(int).is_some() {
+            return Ok(self);
+        }
+        self.round_to_significant_figures(F64_DIGITS - 2, int)
+    }
+
+    fn round_to_significant_figures<I: Interrupt>(
+        self,
+        sf: usize,
+        int: &I,
+    ) -> Result<Self, FendError> {
+        let simplified = self.simplify(int)?;
+        if simplified.num == 0.into() {
+            return Ok(simplified);
+        }
+        let sign = simplified.sign;
+        let power_of_ten = |exponent: u64| -> Result<Self, FendError> {
+            Ok(BigUint::pow(&10.into(), &exponent.into(), int)?.into())
+        };
+        // estimate the number of integer digits from the number of bits,
+        // using log10(2) ~= 0.30103
+        let decimal_digits =
+            |n: &BigUint| i64::try_from(n.bit_length() * 30103 / 100_000).unwrap_or(i64::MAX);
+        let mut shift = i64::try_from(sf).unwrap_or(i64::MAX) - decimal_digits(&simplified.num)
+            + decimal_digits(&simplified.den);
+        let abs = Self {
+            sign: Sign::Positive,
+            ..simplified
+        };
+        let mut scaled = if shift >= 0 {
+            abs.mul(&power_of_ten(shift.unsigned_abs())?, int)?
+        } else {
+            abs.div(&power_of_ten(shift.unsigned_abs())?, int)?
+        };
+        // correct the estimate so that exactly `sf` digits are kept
+        let upper = power_of_ten(sf as u64)?;
+        let lower = power_of_ten(sf as u64 - 1)?;
+        while scaled >= upper {
+            scaled = scaled.div(&10.into(), int)?;
+            shift -= 1;
+        }
+        while scaled < lower {
+            scaled = scaled.mul(&10.into(), int)?;
+            shift += 1;
+        }
+        let rounded = scaled.round(int)?;
+        let result = if shift >= 0 {
+            rounded.div(&power_of_ten(shift.unsigned_abs())?, int)?
+        } else {
+            rounded.mul(&power_of_ten(shift.unsigned_abs())?, int)?
+        };
+        Ok(Self { sign, ..result })
+    }
+
+    /// Integer division that rounds towards negative infinity. Returns the
+    /// quotient and the remainder, which has the same sign as `rhs`.
+    pub(crate) fn floor_divmod<I: Interrupt>(
+        mut self,
+        mut rhs: Self,
+        int: &I,
+    ) -> Result<(Self, Self), FendError> {
+        if rhs.num == 0.into() {
+            return Err(FendError::DivideByZero);
+        }
+        self = self.simplify(int)?;
+        rhs = rhs.simplify(int)?;
+        if self.den != 1.into() || rhs.den != 1.into() {
+            return Err(FendError::FloorDivisionForIntegers);
+        }
+        let (mut quotient, mut remainder) = self.num.divmod(&rhs.num, int)?;
+        if self.sign != rhs.sign && remainder != 0.into() {
+            // round away from zero instead of truncating
+            quotient = quotient.add(&1.into());
+            remainder = rhs.num.clone().sub(&remainder);
+        }
+        let quotient_sign = if quotient == 0.into() {
+            Sign::Positive
+        } else {
+            Sign::sign_of_product(self.sign, rhs.sign)
+        };
+        let remainder_sign = if remainder == 0.into() {
+            Sign::Positive
+        } else {
+            rhs.sign
+        };
+        Ok((
+            Self {
+                sign: quotient_sign,
+                num: quotient,
+                den: 1.into(),
+            },
+            Self {
+                sign: remainder_sign,
+                num: remainder,
+                den: 1.into(),
+            },
+        ))
+    }
+
     // test if this fraction has a terminating representation
     // e.g. in base 10: 1/4 = 0.25, but not 1/3
     fn terminates_in_base<I: Interrupt>(&self, base: Base, int: &I) -> Result<bool, FendError> {
@@ -461,13 +795,16 @@ impl BigRat {
         num: &BigUint,
         base: Base,
         sign: Sign,
-        term: &'static str,
+        term: &str,
         use_parens_if_product: bool,
         sf_limit: Option<usize>,
         int: &I,
     ) -> Result<Exact<FormattedBigRat>, FendError> {
         let (ty, exact) = if !term.is_empty() && !base.has_prefix() && num == &1.into() {
-            (FormattedBigRatType::Integer(None, false, term, false), true)
+            (
+                FormattedBigRatType::Integer(None, false, term.to_string(), false),
+                true,
+            )
         } else {
             let formatted_int = num.format(
                 &biguint::FormatOptions {
@@ -481,7 +818,7 @@ impl BigRat {
                 FormattedBigRatType::Integer(
                     Some(formatted_int.value),
                     !term.is_empty() && base.base_as_u8() > 10,
-                    term,
+                    term.to_string(),
                     // print surrounding parentheses if the number is imaginary
                     use_parens_if_product && !term.is_empty(),
                 ),
@@ -495,7 +832,7 @@ impl BigRat {
         &self,
         base: Base,
         sign: Sign,
-        term: &'static str,
+        term: &str,
         mixed: bool,
         use_parens: bool,
         int: &I,
@@ -526,21 +863,21 @@ impl BigRat {
                         pref,
                         None,
                         false,
-                        term,
+                        term.to_string(),
                         formatted_den.value,
-                        "",
+                        String::new(),
                         use_parens,
                     ),
                     true,
                 )
             } else {
                 let formatted_num = num.format(&format_options, int)?;
-                let i_suffix = term;
+                let i_suffix = term.to_string();
                 let space = !term.is_empty() && (base.base_as_u8() >= 19 || actually_mixed);
                 let (isuf1, isuf2) = if actually_mixed {
-                    ("", i_suffix)
+                    (String::new(), i_suffix)
                 } else {
-                    (i_suffix, "")
+                    (i_suffix, String::new())
                 };
                 (
                     FormattedBigRatType::Fraction(
@@ -561,12 +898,14 @@ impl BigRat {
         ))
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn format_as_decimal<I: Interrupt>(
         &self,
         style: FormattingStyle,
         base: Base,
         sign: Sign,
-        term: &'static str,
+        term: &str,
+        digit_limits: DigitLimits,
         mut terminating: impl FnMut() -> Result<bool, FendError>,
         int: &I,
     ) -> Result<Exact<FormattedBigRat>, FendError> {
@@ -589,7 +928,16 @@ impl BigRat {
             || (style == FormattingStyle::Auto && terminating()?)
             || style == FormattingStyle::Exact
         {
-            MaxDigitsToPrint::AllDigits
+            // only `float` is cut off after a maximum number of digits
+            let max_float_digits = if style == FormattingStyle::ExactFloat {
+                digit_limits.max_float_digits
+            } else {
+                None
+            };
+            MaxDigitsToPrint::AllDigits(DigitLimits {
+                max_float_digits,
+                ..digit_limits
+            })
         } else if let FormattingStyle::DecimalPlaces(n) = style {
             MaxDigitsToPrint::DecimalPlaces(n)
         } else if let FormattingStyle::SignificantFigures(sf) = style {
@@ -648,13 +996,82 @@ impl BigRat {
                 ty: FormattedBigRatType::Decimal(
                     formatted_trailing_digits.value,
                     !term.is_empty() && base.base_as_u8() > 10,
-                    term,
+                    term.to_string(),
                 ),
             },
             formatted_integer_part.exact && formatted_trailing_digits.exact,
         ))
     }
 
+    /// Prints a positive, non-zero number as a decimal mantissa between
+    /// 1 and 10 (with at most 10 decimal places), followed by an exponent.
+    /// In engineering notation the exponent is a multiple of 3 instead, so
+    /// the mantissa is between 1 and 1000.
+    fn format_as_scientific<I: Interrupt>(
+        &self,
+        base: Base,
+        notation: ScientificNotationStyle,
+        engineering: bool,
+        sign: Sign,
+        term: &str,
+        int: &I,
+    ) -> Result<Exact<FormattedBigRat>, FendError> {
+        let ten = Self::from(10);
+        let one = Self::from(1);
+        // estimate the exponent from the number of bits in the numerator
+        // and denominator (log10(2) is about 0.30103), then correct it below
+        let bits = |n: &BigUint| i64::try_from(n.bit_length()).unwrap_or(i64::MAX);
+        let mut exponent = (bits(&self.num) - bits(&self.den)) * 30103 / 100_000;
+        let power = Self {
+            sign: Sign::Positive,
+            num: BigUint::pow(&10.into(), &exponent.unsigned_abs().into(), int)?,
+            den: 1.into(),
+        };
+        let mut mantissa = if exponent >= 0 {
+            self.clone().div(&power, int)?
+        } else {
+            self.clone().mul(&power, int)?
+        };
+        while mantissa >= ten {
+            test_int(int)?;
+            mantissa = mantissa.div(&ten, int)?;
+            exponent += 1;
+        }
+        while mantissa < one {
+            test_int(int)?;
+            mantissa = mantissa.mul(&ten, int)?;
+            exponent -= 1;
+        }
+        if engineering {
+            while exponent.rem_euclid(3) != 0 {
+                mantissa = mantissa.mul(&ten, int)?;
+                exponent -= 1;
+            }
+        }
+        let formatted_mantissa = mantissa.format(
+            &FormatOptions {
+                base,
+                style: FormattingStyle::DecimalPlaces(10),
+                term: String::new(),
+                use_parens_if_fraction: false,
+                digit_limits: DigitLimits::default(),
+            },
+            int,
+        )?;
+        Ok(Exact::new(
+            FormattedBigRat {
+                sign,
+                ty: FormattedBigRatType::Scientific(
+                    formatted_mantissa.value.to_string(),
+                    exponent,
+                    notation,
+                    term.to_string(),
+                ),
+            },
+            formatted_mantissa.exact,
+        ))
+    }
+
     /// Prints the decimal expansion of num/den, where num < den, in the given base.
     fn format_trailing_digits<I: Interrupt>(
         base: Base,
@@ -667,12 +1084,28 @@ impl BigRat {
     ) -> Result<(Sign, Exact<String>), FendError> {
         let base_as_u64: u64 = base.base_as_u8().into();
         let b: BigUint = base_as_u64.into();
+        let max_float_digits = match max_digits {
+            MaxDigitsToPrint::AllDigits(limits) => limits.max_float_digits,
+            _ => None,
+        };
+        let cut_off = |max: usize| {
+            Self::format_cut_off(base, numerator, denominator, max, &print_integer_part, int)
+        };
+        if let Some(max) = max_float_digits {
+            if terminating()? && !Self::terminates_within(numerator, denominator, &b, max, int)? {
+                return cut_off(max);
+            }
+        }
         let next_digit =
             |i: usize, num: BigUint, base: &BigUint| -> Result<(BigUint, BigUint), NextDigitErr> {
                 test_int(int)?;
+                // Brent's algorithm looks at no more than about four times
+                // as many digits as the cycle it finds, so cycles that
+                // wouldn't be printed in full are never searched for
                 if num == 0.into()
                     || max_digits == MaxDigitsToPrint::DecimalPlaces(i)
                     || max_digits == MaxDigitsToPrint::DpButIgnoreLeadingZeroes(i)
+                    || matches!(max_float_digits, Some(max) if i > 4 * max + 4)
                 {
                     return Err(NextDigitErr::Terminated);
                 }
@@ -698,7 +1131,8 @@ impl BigRat {
             s.push_str(digit_str.as_str());
             Ok(s)
         };
-        let skip_cycle_detection = max_digits != MaxDigitsToPrint::AllDigits || terminating()?;
+        let skip_cycle_detection =
+            !matches!(max_digits, MaxDigitsToPrint::AllDigits(_)) || terminating()?;
         if skip_cycle_detection {
             let ignore_number_of_leading_zeroes =
                 matches!(max_digits, MaxDigitsToPrint::DpButIgnoreLeadingZeroes(_));
@@ -707,7 +1141,7 @@ impl BigRat {
                 base,
                 ignore_number_of_leading_zeroes,
                 next_digit,
-                print_integer_part,
+                &print_integer_part,
                 int,
             );
         }
@@ -720,24 +1154,93 @@ impl BigRat {
         ) {
             Ok((cycle_length, location, output)) => {
                 let (ab, _) = output.split_at(location + cycle_length);
+                if let Some(max) = max_float_digits.filter(|&max| ab.len() > max) {
+                    return cut_off(max);
+                }
                 let (a, b) = ab.split_at(location);
                 let (sign, formatted_int) = print_integer_part(false)?;
                 let mut trailing_digits = String::new();
                 trailing_digits.push_str(&formatted_int);
                 trailing_digits.push('.');
+                if let MaxDigitsToPrint::AllDigits(DigitLimits {
+                    max_recurring_digits: Some(max),
+                    ..
+                }) = max_digits
+                {
+                    if ab.len() > max {
+                        // print the first few digits, followed by an ellipsis
+                        // (every digit is a single ASCII character)
+                        trailing_digits.push_str(&ab[..max]);
+                        trailing_digits.push_str("...");
+                        return Ok((sign, Exact::new(trailing_digits, true)));
+                    }
+                }
                 trailing_digits.push_str(a);
                 trailing_digits.push('(');
                 trailing_digits.push_str(b);
                 trailing_digits.push(')');
                 Ok((sign, Exact::new(trailing_digits, true))) // the recurring decimal is exact
             }
-            Err(NextDigitErr::Terminated) => {
-                panic!("decimal number terminated unexpectedly");
-            }
+            Err(NextDigitErr::Terminated) => match max_float_digits {
+                // no cycle was found within the digit budget
+                Some(max) => {
+                    Self::format_cut_off(base, numerator, denominator, max, print_integer_part, int)
+                }
+                None => panic!("decimal number terminated unexpectedly"),
+            },
             Err(NextDigitErr::Error(e)) => Err(e),
         }
     }
 
+    /// Returns whether num/den has at most `max` digits after the decimal
+    /// point in the given base
+    fn terminates_within<I: Interrupt>(
+        numerator: &BigUint,
+        denominator: &BigUint,
+        base: &BigUint,
+        max: usize,
+        int: &I,
+    ) -> Result<bool, FendError> {
+        let max = BigUint::from(u64::try_from(max).unwrap_or(u64::MAX));
+        let shifted = numerator
+            .clone()
+            .mul(&BigUint::pow(base, &max, int)?, int)?;
+        Ok(shifted.divmod(denominator, int)?.1 == 0.into())
+    }
+
+    /// Prints the first `max` digits of num/den followed by an ellipsis,
+    /// marked as approximate
+    fn format_cut_off<I: Interrupt>(
+        base: Base,
+        numerator: &BigUint,
+        denominator: &BigUint,
+        max: usize,
+        print_integer_part: impl Fn(bool) -> Result<(Sign, String), FendError>,
+        int: &I,
+    ) -> Result<(Sign, Exact<String>), FendError> {
+        let next_digit =
+            |i: usize, num: BigUint, base: &BigUint| -> Result<(BigUint, BigUint), NextDigitErr> {
+                test_int(int)?;
+                if num == 0.into() || i == max {
+                    return Err(NextDigitErr::Terminated);
+                }
+                let bnum = num.mul(base, int)?;
+                let digit = bnum.clone().div(denominator, int)?;
+                let next_num = bnum.sub(&digit.clone().mul(denominator, int)?);
+                Ok((next_num, digit))
+            };
+        let (sign, digits) =
+            Self::format_nonrecurring(numerator, base, false, next_digit, print_integer_part, int)?;
+        let mut digits = digits.value;
+        if !digits.contains('.') {
+            // all printed digits were zero
+            digits.push('.');
+            digits.push_str(&"0".repeat(max));
+        }
+        digits.push_str("...");
+        Ok((sign, Exact::new(digits, false)))
+    }
+
     fn format_nonrecurring<I: Interrupt>(
         numerator: &BigUint,
         base: Base,
@@ -959,6 +1462,11 @@ impl BigRat {
                 true,
             ));
         }
+        if let Some(digits) = high_precision(int) {
+            if n == &2.into() {
+                return Ok(Exact::new(series::sqrt(self, digits, int)?, false));
+            }
+        }
         // TODO check in which cases this might still be exact
         let num_rat = if num.exact {
             Self::from(num.value)
@@ -983,6 +1491,42 @@ impl BigRat {
         Ok(Exact::new(num_rat.div(&den_rat, int)?, false))
     }
 
+    /// Splits `sqrt(self)` into `a * sqrt(b)`, where `b` is an integer
+    /// without small square factors, e.g. `sqrt(8/3)` becomes `2/3 * sqrt(6)`.
+    /// `self` must not be negative.
+    pub(crate) fn split_sqrt<I: Interrupt>(self, int: &I) -> Result<(Self, Self), FendError> {
+        let x = self.simplify(int)?;
+        // sqrt(p/q) == sqrt(p * q) / q
+        let mut radicand = x.num.mul(&x.den, int)?;
+        let mut outside = BigUint::from(1);
+        // only small factors are removed, since this is just to make
+        // results like `2√2` look nicer
+        for factor in 2..1000_u64 {
+            let factor = BigUint::from(factor);
+            let square = factor.clone().mul(&factor, int)?;
+            if square > radicand {
+                break;
+            }
+            loop {
+                test_int(int)?;
+                let (quotient, remainder) = radicand.divmod(&square, int)?;
+                if remainder != 0.into() {
+                    break;
+                }
+                radicand = quotient;
+                outside = outside.mul(&factor, int)?;
+            }
+        }
+        Ok((
+            Self {
+                sign: Sign::Positive,
+                num: outside,
+                den: x.den,
+            },
+            Self::from(radicand),
+        ))
+    }
+
     pub(crate) fn mul<I: Interrupt>(self, rhs: &Self, int: &I) -> Result<Self, FendError> {
         Ok(Self {
             sign: Sign::sign_of_product(self.sign, rhs.sign),
@@ -1014,10 +1558,23 @@ impl From<FendError> for NextDigitErr {
     }
 }
 
+/// Limits on the number of digits printed when printing all digits, as set
+/// in the context
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub(crate) struct DigitLimits {
+    /// Recurring decimals that need more than this many digits are cut off
+    /// with an ellipsis
+    pub(crate) max_recurring_digits: Option<usize>,
+    /// `float` outputs that need more than this many digits are cut off with
+    /// an ellipsis and marked as approximate
+    pub(crate) max_float_digits: Option<usize>,
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 enum MaxDigitsToPrint {
-    /// Print all digits, possibly by writing recurring decimals in parentheses
-    AllDigits,
+    /// Print all digits, possibly by writing recurring decimals in parentheses,
+    /// subject to the given limits
+    AllDigits(DigitLimits),
     /// Print only the given number of decimal places, omitting any trailing zeroes
     DecimalPlaces(usize),
     /// Print only the given number of dps, but ignore leading zeroes after the decimal point
@@ -1059,8 +1616,9 @@ impl From<BigUint> for BigRat {
 pub(crate) struct FormatOptions {
     pub(crate) base: Base,
     pub(crate) style: FormattingStyle,
-    pub(crate) term: &'static str,
+    pub(crate) term: String,
     pub(crate) use_parens_if_fraction: bool,
+    pub(crate) digit_limits: DigitLimits,
 }
 
 impl Format for BigRat {
@@ -1076,8 +1634,8 @@ impl Format for BigRat {
         int: &I,
     ) -> Result<Exact<Self::Out>, FendError> {
         let base = params.base;
-        let style = params.style;
-        let term = params.term;
+        let mut style = params.style;
+        let term = params.term.as_str();
         let use_parens_if_fraction = params.use_parens_if_fraction;
 
         let mut x = self.clone().simplify(int)?;
@@ -1088,6 +1646,33 @@ impl Format for BigRat {
         };
         x.sign = Sign::Positive;
 
+        if let FormattingStyle::Scientific(notation) | FormattingStyle::Engineering(notation) =
+            style
+        {
+            // scientific notation is only supported for non-zero decimal numbers
+            if base.base_as_u8() == 10 && !base.has_prefix() && x != 0.into() {
+                let engineering = matches!(style, FormattingStyle::Engineering(_));
+                return x.format_as_scientific(base, notation, engineering, sign, term, int);
+            }
+            style = FormattingStyle::Auto;
+        }
+
+        if let FormattingStyle::FixedDecimalPlaces(dp) = style {
+            let params = FormatOptions {
+                base,
+                style: FormattingStyle::DecimalPlaces(dp),
+                term: term.to_string(),
+                use_parens_if_fraction,
+                digit_limits: params.digit_limits,
+            };
+            let mut formatted = self.format(&params, int)?;
+            // small negative numbers like `-0.005` are truncated to zero,
+            // but should still be shown as negative
+            formatted.value.sign = sign;
+            formatted.value.pad_decimal_places(dp);
+            return Ok(formatted);
+        }
+
         // try as integer if possible
         if x.den == 1.into() {
             let sf_limit = if let FormattingStyle::SignificantFigures(sf) = style {
@@ -1124,7 +1709,15 @@ impl Format for BigRat {
         }
 
         // not a fraction, will be printed as a decimal
-        x.format_as_decimal(style, base, sign, term, terminating, int)
+        x.format_as_decimal(
+            style,
+            base,
+            sign,
+            term,
+            params.digit_limits,
+            terminating,
+            int,
+        )
     }
 }
 
@@ -1134,7 +1727,7 @@ enum FormattedBigRatType {
     // bool whether to add a space before the string
     // followed by a string (empty, "i" or "pi"),
     // followed by whether to wrap the number in parentheses
-    Integer(Option<FormattedBigUint>, bool, &'static str, bool),
+    Integer(Option<FormattedBigUint>, bool, String, bool),
     // optional int (for mixed fractions)
     // optional int (numerator)
     // space
@@ -1147,15 +1740,20 @@ enum FormattedBigRatType {
         Option<FormattedBigUint>,
         Option<FormattedBigUint>,
         bool,
-        &'static str,
+        String,
         FormattedBigUint,
-        &'static str,
+        String,
         bool,
     ),
     // string representation of decimal number (may or may not contain recurring digits)
     // space
     // string (empty, "i", "pi", etc.)
-    Decimal(String, bool, &'static str),
+    Decimal(String, bool, String),
+    // mantissa (a decimal number between 1 and 10)
+    // exponent
+    // how to write the exponent
+    // string (empty, "i", etc.)
+    Scientific(String, i64, ScientificNotationStyle, String),
 }
 
 #[must_use]
@@ -1166,6 +1764,39 @@ pub(crate) struct FormattedBigRat {
     ty: FormattedBigRatType,
 }
 
+impl FormattedBigRat {
+    /// Appends zeroes so that there are `dp` digits after the decimal point,
+    /// e.g. for `1234.5 to fixed 2`
+    fn pad_decimal_places(&mut self, dp: usize) {
+        if dp == 0 {
+            return;
+        }
+        let decimal = match &self.ty {
+            FormattedBigRatType::Integer(Some(int), space, term, false) => {
+                Some((int.to_string(), *space, term.clone()))
+            }
+            // recurring digits are already written out in full
+            FormattedBigRatType::Decimal(s, space, term) if !s.contains('(') => {
+                Some((s.clone(), *space, term.clone()))
+            }
+            _ => None,
+        };
+        let Some((mut s, space, term)) = decimal else {
+            return;
+        };
+        let digits = if let Some(point) = s.find('.') {
+            s.len() - point - 1
+        } else {
+            s.push('.');
+            0
+        };
+        for _ in digits..dp {
+            s.push('0');
+        }
+        self.ty = FormattedBigRatType::Decimal(s, space, term);
+    }
+}
+
 impl fmt::Display for FormattedBigRat {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         if self.sign == Sign::Negative {
@@ -1216,6 +1847,18 @@ impl fmt::Display for FormattedBigRat {
                 }
                 write!(f, "{term}")?;
             }
+            FormattedBigRatType::Scientific(mantissa, exponent, notation, term) => {
+                match notation {
+                    ScientificNotationStyle::Exponent => write!(f, "{mantissa}e{exponent}")?,
+                    ScientificNotationStyle::TimesTenTo => {
+                        write!(f, "{mantissa} \u{d7} 10^{exponent}")?;
+                        if !term.is_empty() {
+                            write!(f, " ")?;
+                        }
+                    }
+                }
+                write!(f, "{term}")?;
+            }
         }
         Ok(())
     }
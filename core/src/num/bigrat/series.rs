@@ -0,0 +1,260 @@
+//! Series expansions of transcendental functions, used instead of `f64` when
+//! more digits are needed than an `f64` can provide. Intermediate values are
+//! kept as fixed-point numbers, i.e. rounded to a multiple of `10^-digits`.
+
+use super::{BigRat, Sign};
+use crate::error::{FendError, Interrupt};
+use crate::interrupt::test_int;
+use crate::num::biguint::BigUint;
+
+/// Extra digits that are computed to absorb rounding errors
+const GUARD_DIGITS: usize = 10;
+
+struct FixedPoint {
+    scale: BigUint,
+}
+
+impl FixedPoint {
+    fn new<I: Interrupt>(digits: usize, int: &I) -> Result<Self, FendError> {
+        let digits = BigUint::from(u64::try_from(digits).unwrap_or(u64::MAX));
+        Ok(Self {
+            scale: BigUint::pow(&10.into(), &digits, int)?,
+        })
+    }
+
+    /// Truncates `x` to a multiple of `1 / scale`. Truncating (rather than
+    /// rounding) guarantees that the terms of a series eventually become
+    /// zero.
+    fn fix<I: Interrupt>(&self, x: BigRat, int: &I) -> Result<BigRat, FendError> {
+        let num = x.num.mul(&self.scale, int)?.div(&x.den, int)?;
+        Ok(BigRat {
+            sign: if num == 0.into() {
+                Sign::Positive
+            } else {
+                x.sign
+            },
+            num,
+            den: self.scale.clone(),
+        })
+    }
+
+    fn mul<I: Interrupt>(&self, a: &BigRat, b: &BigRat, int: &I) -> Result<BigRat, FendError> {
+        self.fix(a.clone().mul(b, int)?, int)
+    }
+
+    fn div<I: Interrupt>(&self, a: &BigRat, b: &BigRat, int: &I) -> Result<BigRat, FendError> {
+        self.fix(a.clone().div(b, int)?, int)
+    }
+}
+
+/// Returns the number of decimal digits before the decimal point of `x`
+fn integer_digits(x: &BigRat) -> usize {
+    let bits = x.num.bit_length().saturating_sub(x.den.bit_length());
+    // log10(2) < 0.302
+    usize::try_from(bits * 302 / 1000 + 1).unwrap_or(usize::MAX)
+}
+
+fn is_negative(x: &BigRat) -> bool {
+    x.sign == Sign::Negative && x.num != 0.into()
+}
+
+pub(super) fn pi<I: Interrupt>(digits: usize, int: &I) -> Result<BigRat, FendError> {
+    // Machin's formula: pi = 16 atan(1/5) - 4 atan(1/239)
+    let a = atan_small(&BigRat::from(1).div(&5.into(), int)?, digits, int)?;
+    let b = atan_small(&BigRat::from(1).div(&239.into(), int)?, digits, int)?;
+    a.mul(&16.into(), int)?.add(-b.mul(&4.into(), int)?, int)
+}
+
+/// atan for values between 0 and 1, using Euler's series
+/// `atan x = sum (2^2n (n!)^2 / (2n + 1)!) x^(2n+1) / (1 + x^2)^(n+1)`,
+/// which converges at least as fast as `(1/2)^n`
+fn atan_small<I: Interrupt>(x: &BigRat, digits: usize, int: &I) -> Result<BigRat, FendError> {
+    let p = FixedPoint::new(digits + GUARD_DIGITS, int)?;
+    let x_squared = x.clone().mul(x, int)?;
+    let denominator = x_squared.clone().add(1.into(), int)?;
+    let ratio = p.div(&x_squared, &denominator, int)?;
+    let mut term = p.div(x, &denominator, int)?;
+    let mut sum = term.clone();
+    for n in 1_u64.. {
+        test_int(int)?;
+        term = p.mul(&term, &ratio, int)?;
+        term = p.div(&term.mul(&(2 * n).into(), int)?, &(2 * n + 1).into(), int)?;
+        if term.num == 0.into() {
+            break;
+        }
+        sum = sum.add(term.clone(), int)?;
+    }
+    Ok(sum)
+}
+
+pub(super) fn atan<I: Interrupt>(x: BigRat, digits: usize, int: &I) -> Result<BigRat, FendError> {
+    if is_negative(&x) {
+        return Ok(-atan(-x, digits, int)?);
+    }
+    if x > 1.into() {
+        // atan x = pi/2 - atan(1/x)
+        let half_pi = pi(digits, int)?.div(&2.into(), int)?;
+        let reciprocal = BigRat::from(1).div(&x, int)?;
+        return half_pi.add(-atan_small(&reciprocal, digits, int)?, int);
+    }
+    atan_small(&x, digits, int)
+}
+
+/// `x` must be between -1 and 1
+pub(super) fn asin<I: Interrupt>(x: BigRat, digits: usize, int: &I) -> Result<BigRat, FendError> {
+    // asin x = atan(x / sqrt(1 - x^2)), and asin(±1) = ±pi/2
+    let one_minus_x_squared = BigRat::from(1).add(-x.clone().mul(&x, int)?, int)?;
+    if one_minus_x_squared.num == 0.into() {
+        let half_pi = pi(digits, int)?.div(&2.into(), int)?;
+        return Ok(if is_negative(&x) { -half_pi } else { half_pi });
+    }
+    // the square root needs extra digits when x is close to ±1
+    let extra_digits = integer_digits(&BigRat::from(1).div(&one_minus_x_squared, int)?);
+    let root = sqrt(one_minus_x_squared, digits + extra_digits, int)?;
+    atan(x.div(&root, int)?, digits, int)
+}
+
+pub(super) fn sin<I: Interrupt>(x: BigRat, digits: usize, int: &I) -> Result<BigRat, FendError> {
+    // reducing x modulo 2pi loses as many digits as x has before the
+    // decimal point
+    let digits = digits + GUARD_DIGITS + integer_digits(&x);
+    let p = FixedPoint::new(digits, int)?;
+    let two_pi = pi(digits, int)?.mul(&2.into(), int)?;
+    let turns = x.clone().div(&two_pi, int)?.round(int)?;
+    let x = p.fix(x.add(-turns.mul(&two_pi, int)?, int)?, int)?;
+    // sin x = x - x^3/3! + x^5/5! - ...
+    let x_squared = p.mul(&x, &x, int)?;
+    let mut term = x.clone();
+    let mut sum = x;
+    for n in 1_u64.. {
+        test_int(int)?;
+        term = p.mul(&term, &x_squared, int)?;
+        term = -p.div(&term, &((2 * n) * (2 * n + 1)).into(), int)?;
+        if term.num == 0.into() {
+            break;
+        }
+        sum = sum.add(term.clone(), int)?;
+    }
+    Ok(sum)
+}
+
+pub(super) fn exp<I: Interrupt>(x: BigRat, digits: usize, int: &I) -> Result<BigRat, FendError> {
+    if is_negative(&x) {
+        let p = FixedPoint::new(digits + GUARD_DIGITS, int)?;
+        return p.div(&1.into(), &exp(-x, digits, int)?, int);
+    }
+    // exp x = exp(x / 2^k)^(2^k), where x / 2^k is small enough for the
+    // series to converge quickly. Every squaring doubles the relative error,
+    // and the result has about x / ln 10 digits before the decimal point.
+    if x > 1_000_000.into() {
+        return Err(FendError::ResultIsInfinite);
+    }
+    let halvings = x.num.bit_length().saturating_sub(x.den.bit_length()) + 1;
+    let halvings_usize = usize::try_from(halvings).unwrap_or(usize::MAX);
+    let result_digits = x.clone().into_f64(int)? * std::f64::consts::LOG10_E;
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let result_digits = result_digits.ceil() as usize;
+    let p = FixedPoint::new(digits + GUARD_DIGITS + halvings_usize + result_digits, int)?;
+    let divisor = BigRat::from(BigUint::from(1).lshift_n(&halvings.into(), int)?);
+    let x = p.div(&x, &divisor, int)?;
+    // exp x = 1 + x + x^2/2! + ...
+    let mut term = BigRat::from(1);
+    let mut sum = BigRat::from(1);
+    for n in 1_u64.. {
+        test_int(int)?;
+        term = p.mul(&term, &x, int)?;
+        term = p.div(&term, &n.into(), int)?;
+        if term.num == 0.into() {
+            break;
+        }
+        sum = sum.add(term.clone(), int)?;
+    }
+    for _ in 0..halvings {
+        test_int(int)?;
+        sum = p.mul(&sum, &sum, int)?;
+    }
+    Ok(sum)
+}
+
+/// `atanh y = y + y^3/3 + y^5/5 + ...`, for small y
+fn atanh_small<I: Interrupt>(y: &BigRat, p: &FixedPoint, int: &I) -> Result<BigRat, FendError> {
+    let y_squared = p.mul(y, y, int)?;
+    let mut power = y.clone();
+    let mut sum = y.clone();
+    for n in 1_u64.. {
+        test_int(int)?;
+        power = p.mul(&power, &y_squared, int)?;
+        let term = p.div(&power, &(2 * n + 1).into(), int)?;
+        if term.num == 0.into() {
+            break;
+        }
+        sum = sum.add(term, int)?;
+    }
+    Ok(sum)
+}
+
+/// `x` must be positive
+pub(super) fn ln<I: Interrupt>(x: BigRat, digits: usize, int: &I) -> Result<BigRat, FendError> {
+    // ln x = k ln 2 + ln m, where x = 2^k m and m is between 1/2 and 2
+    let x = x.simplify(int)?;
+    let (mantissa, exponent, exponent_negative) = if x.num >= x.den {
+        let shift = x.num.bit_length() - x.den.bit_length();
+        let den = x.den.clone().lshift_n(&shift.into(), int)?;
+        (BigRat { den, ..x }, shift, false)
+    } else {
+        let shift = x.den.bit_length() - x.num.bit_length();
+        let num = x.num.clone().lshift_n(&shift.into(), int)?;
+        (BigRat { num, ..x }, shift, true)
+    };
+    let mantissa = mantissa.simplify(int)?;
+    let p = FixedPoint::new(
+        digits + GUARD_DIGITS + integer_digits(&BigRat::from(exponent)),
+        int,
+    )?;
+    // ln m = 2 atanh((m - 1) / (m + 1)), and ln 2 = 2 atanh(1/3)
+    let y = p.div(
+        &mantissa.clone().add(-BigRat::from(1), int)?,
+        &mantissa.add(1.into(), int)?,
+        int,
+    )?;
+    let ln_mantissa = atanh_small(&y, &p, int)?.mul(&2.into(), int)?;
+    let ln_2 = atanh_small(&p.div(&1.into(), &3.into(), int)?, &p, int)?.mul(&2.into(), int)?;
+    let shift_ln_2 = ln_2.mul(&BigRat::from(exponent), int)?;
+    ln_mantissa.add(
+        if exponent_negative {
+            -shift_ln_2
+        } else {
+            shift_ln_2
+        },
+        int,
+    )
+}
+
+/// `x` must not be negative
+pub(super) fn sqrt<I: Interrupt>(x: BigRat, digits: usize, int: &I) -> Result<BigRat, FendError> {
+    if x.num == 0.into() {
+        return Ok(x);
+    }
+    let p = FixedPoint::new(digits + GUARD_DIGITS + integer_digits(&x), int)?;
+    let estimate = x.clone().into_f64(int)?.sqrt();
+    let mut y = if estimate.is_normal() {
+        BigRat::from_f64(estimate, int)?
+    } else {
+        BigRat::from(1)
+    };
+    // Newton's method doubles the number of correct digits on each step.
+    // After the first step, the estimates decrease towards the root.
+    let step = |y: &BigRat| -> Result<BigRat, FendError> {
+        let sum = y.clone().add(x.clone().div(y, int)?, int)?;
+        p.div(&sum, &2.into(), int)
+    };
+    y = step(&y)?;
+    loop {
+        test_int(int)?;
+        let next = step(&y)?;
+        if next >= y {
+            return Ok(y);
+        }
+        y = next;
+    }
+}
@@ -1,6 +1,6 @@
 use crate::error::{FendError, Interrupt};
 use crate::format::Format;
-use crate::interrupt::test_int;
+use crate::interrupt::{test_int, test_memory_limit};
 use crate::num::{out_of_range, Base, Exact, Range, RangeBound};
 use crate::serialize::{
     deserialize_u64, deserialize_u8, deserialize_usize, serialize_u64, serialize_u8,
@@ -149,6 +149,19 @@ impl BigUint {
         }
     }
 
+    /// Returns the number of bits needed to represent this number
+    pub(crate) fn bit_length(&self) -> u64 {
+        let mut idx = self.value_len();
+        while idx > 0 {
+            idx -= 1;
+            let word = self.get(idx);
+            if word != 0 {
+                return idx as u64 * 64 + u64::from(64 - word.leading_zeros());
+            }
+        }
+        0
+    }
+
     fn value_len(&self) -> usize {
         match self {
             Small(_) => 1,
@@ -178,9 +191,7 @@ impl BigUint {
     }
 
     pub(crate) fn pow<I: Interrupt>(a: &Self, b: &Self, int: &I) -> Result<Self, FendError> {
-        if a.is_zero() && b.is_zero() {
-            return Err(FendError::ZeroToThePowerOfZero);
-        }
+        // this includes 0^0, which is defined as 1
         if b.is_zero() {
             return Ok(Self::from(1));
         }
@@ -326,6 +337,7 @@ impl BigUint {
             *self = Self::from(0);
             return Ok(());
         }
+        test_memory_limit((self.value_len() + other.value_len()) * 8, int)?;
         let self_clone = self.clone();
         self.make_large();
         match self {
@@ -543,6 +555,7 @@ impl BigUint {
 
     pub(crate) fn lshift_n<I: Interrupt>(mut self, rhs: &Self, int: &I) -> Result<Self, FendError> {
         let mut rhs = rhs.try_as_usize(int)?;
+        test_memory_limit((self.value_len() + rhs / 64 + 1).saturating_mul(8), int)?;
         if rhs > 64 {
             self.make_large();
             match &mut self {
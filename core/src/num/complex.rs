@@ -1,4 +1,5 @@
 use crate::error::{FendError, Interrupt};
+use crate::num::bigrat::DigitLimits;
 use crate::num::real::{self, Real};
 use crate::num::Exact;
 use crate::num::{Base, FormattingStyle};
@@ -50,6 +51,52 @@ impl Complex {
         self.real.try_as_usize(int)
     }
 
+    pub(crate) fn period<I: Interrupt>(self, base: Base, int: &I) -> Result<Self, FendError> {
+        if self.imag != 0.into() {
+            return Err(FendError::ExpectedARealNumber);
+        }
+        Ok(self.real.period(base, int)?.into())
+    }
+
+    pub(crate) fn into_fraction<I: Interrupt>(self, int: &I) -> Result<(Self, Self), FendError> {
+        if self.imag != 0.into() {
+            return Err(FendError::ExpectedARealNumber);
+        }
+        let (num, den) = self.real.into_fraction(int)?;
+        Ok((num.into(), den.into()))
+    }
+
+    pub(crate) fn into_f64<I: Interrupt>(self, int: &I) -> Result<f64, FendError> {
+        if self.imag != 0.into() {
+            return Err(FendError::ExpectedARealNumber);
+        }
+        self.real.into_f64(int)
+    }
+
+    /// Complex numbers have no total order, so this returns an error unless
+    /// both numbers are real.
+    pub(crate) fn compare(&self, other: &Self) -> Result<Ordering, FendError> {
+        if !self.imag.is_zero() || !other.imag.is_zero() {
+            return Err(FendError::ComplexNumbersNotOrdered);
+        }
+        Ok(self.real.cmp(&other.real))
+    }
+
+    pub(crate) fn is_real(&self) -> bool {
+        self.imag == 0.into()
+    }
+
+    pub(crate) fn is_rational(&self) -> bool {
+        self.is_real() && self.real.is_rational()
+    }
+
+    pub(crate) fn round_f64_noise<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        Ok(Self {
+            real: self.real.round_f64_noise(int)?,
+            imag: self.imag.round_f64_noise(int)?,
+        })
+    }
+
     pub(crate) fn conjugate(self) -> Self {
         Self {
             real: self.real,
@@ -57,6 +104,20 @@ impl Complex {
         }
     }
 
+    pub(crate) fn real_part(self) -> Self {
+        Self::from(self.real)
+    }
+
+    pub(crate) fn imaginary_part(self) -> Self {
+        Self::from(self.imag)
+    }
+
+    /// The angle between the positive real axis and `self`, which is exact
+    /// for numbers on either axis, e.g. pi/2 for `i`
+    pub(crate) fn arg<I: Interrupt>(self, int: &I) -> Result<Exact<Self>, FendError> {
+        Ok(self.imag.atan2(self.real, int)?.apply(Self::from))
+    }
+
     pub(crate) fn factorial<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
         if self.imag != 0.into() {
             return Err(FendError::FactorialComplex);
@@ -71,6 +132,33 @@ impl Complex {
         if self.imag != 0.into() || rhs.imag != 0.into() {
             return Err(FendError::ExpComplex);
         }
+        if self.real < 0.into() {
+            // (-x)^(n/2) = x^(n/2) * i^n, e.g. (-4)^0.5 = 2i
+            let two_rhs = Exact::new(rhs.real.clone(), true)
+                .mul(Exact::new(&Real::from(2), true), int)?
+                .value;
+            let negative_exponent = two_rhs < 0.into();
+            let n = if negative_exponent { -two_rhs } else { two_rhs };
+            // this fails if n is not an integer
+            if let Ok(n_mod_4) = n.modulo(4.into(), int) {
+                if n_mod_4 == 1.into() || n_mod_4 == 3.into() {
+                    let magnitude = (-self.real).pow(rhs.real, int)?;
+                    // i^-n is the complex conjugate of i^n
+                    let positive_imag = (n_mod_4 == 1.into()) != negative_exponent;
+                    return Ok(Exact::new(
+                        Self {
+                            real: 0.into(),
+                            imag: if positive_imag {
+                                magnitude.value
+                            } else {
+                                -magnitude.value
+                            },
+                        },
+                        magnitude.exact,
+                    ));
+                }
+            }
+        }
         let real = self.real.pow(rhs.real, int)?;
         Ok(Exact::new(
             Self {
@@ -95,6 +183,13 @@ impl Complex {
         }
     }
 
+    pub(crate) fn e<I: Interrupt>(int: &I) -> Result<Self, FendError> {
+        Ok(Self {
+            real: Real::e(int)?,
+            imag: 0.into(),
+        })
+    }
+
     pub(crate) fn abs<I: Interrupt>(self, int: &I) -> Result<Exact<Self>, FendError> {
         Ok(if self.imag.is_zero() {
             if self.real < 0.into() {
@@ -145,9 +240,13 @@ impl Complex {
         style: FormattingStyle,
         base: Base,
         use_parentheses: UseParentheses,
+        digit_limits: DigitLimits,
         int: &I,
     ) -> Result<Exact<Formatted>, FendError> {
-        let style = if !exact && style == FormattingStyle::Auto {
+        // square roots are only written symbolically when asked for
+        // explicitly, so e.g. `sqrt(-2)` is approx. 1.4142135619i
+        let sqrt = self.real.is_sqrt() || self.imag.is_sqrt();
+        let style = if (!exact || sqrt) && style == FormattingStyle::Auto {
             FormattingStyle::DecimalPlaces(10)
         } else if self.imag != 0.into() && style == FormattingStyle::Auto {
             FormattingStyle::Exact
@@ -157,7 +256,9 @@ impl Complex {
 
         if self.imag.is_zero() {
             let use_parens = use_parentheses == UseParentheses::IfComplexOrFraction;
-            let x = self.real.format(base, style, false, use_parens, int)?;
+            let x = self
+                .real
+                .format(base, style, false, use_parens, digit_limits, int)?;
             return Ok(Exact::new(
                 Formatted {
                     first_component: x.value,
@@ -171,7 +272,9 @@ impl Complex {
 
         Ok(if self.real.is_zero() {
             let use_parens = use_parentheses == UseParentheses::IfComplexOrFraction;
-            let x = self.imag.format(base, style, true, use_parens, int)?;
+            let x = self
+                .imag
+                .format(base, style, true, use_parens, digit_limits, int)?;
             Exact::new(
                 Formatted {
                     first_component: x.value,
@@ -183,14 +286,20 @@ impl Complex {
             )
         } else {
             let mut exact = exact;
-            let real_part = self.real.format(base, style, false, false, int)?;
+            let real_part = self
+                .real
+                .format(base, style, false, false, digit_limits, int)?;
             exact = exact && real_part.exact;
             let (positive, imag_part) = if self.imag > 0.into() {
-                (true, self.imag.format(base, style, true, false, int)?)
+                (
+                    true,
+                    self.imag
+                        .format(base, style, true, false, digit_limits, int)?,
+                )
             } else {
                 (
                     false,
-                    (-self.imag.clone()).format(base, style, true, false, int)?,
+                    (-self.imag.clone()).format(base, style, true, false, digit_limits, int)?,
                 )
             };
             exact = exact && imag_part.exact;
@@ -253,6 +362,36 @@ impl Complex {
         num.div(den, int)
     }
 
+    /// Returns `1 / value`, e.g. `sec x` is `1 / cos x`. `func` is the name
+    /// of the reciprocal function, which is undefined where `value` is zero.
+    fn reciprocal<I: Interrupt>(
+        value: Exact<Self>,
+        func: &'static str,
+        int: &I,
+    ) -> Result<Exact<Self>, FendError> {
+        if value.value.real.is_zero() && value.value.imag.is_zero() {
+            return Err(FendError::UndefinedAtPole(func));
+        }
+        Exact::new(Self::from(1), true).div(value, int)
+    }
+
+    pub(crate) fn sec<I: Interrupt>(self, int: &I) -> Result<Exact<Self>, FendError> {
+        Self::reciprocal(self.cos(int)?, "sec", int)
+    }
+
+    pub(crate) fn csc<I: Interrupt>(self, int: &I) -> Result<Exact<Self>, FendError> {
+        Self::reciprocal(self.sin(int)?, "csc", int)
+    }
+
+    pub(crate) fn cot<I: Interrupt>(self, int: &I) -> Result<Exact<Self>, FendError> {
+        let num = self.clone().cos(int)?;
+        let den = self.sin(int)?;
+        if den.value.real.is_zero() {
+            return Err(FendError::UndefinedAtPole("cot"));
+        }
+        num.div(den, int)
+    }
+
     pub(crate) fn asin<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
         Ok(Self::from(self.expect_real()?.asin(int)?))
     }
@@ -265,6 +404,25 @@ impl Complex {
         Ok(Self::from(self.expect_real()?.atan(int)?))
     }
 
+    pub(crate) fn atan2<I: Interrupt>(self, x: Self, int: &I) -> Result<Exact<Self>, FendError> {
+        Ok(self
+            .expect_real()?
+            .atan2(x.expect_real()?, int)?
+            .apply(Self::from))
+    }
+
+    pub(crate) fn asec<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        Ok(Self::from(self.expect_real()?.asec(int)?))
+    }
+
+    pub(crate) fn acsc<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        Ok(Self::from(self.expect_real()?.acsc(int)?))
+    }
+
+    pub(crate) fn acot<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        Ok(Self::from(self.expect_real()?.acot(int)?))
+    }
+
     pub(crate) fn sinh<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
         Ok(Self::from(self.expect_real()?.sinh(int)?))
     }
@@ -277,6 +435,21 @@ impl Complex {
         Ok(Self::from(self.expect_real()?.tanh(int)?))
     }
 
+    pub(crate) fn sech<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        let cosh = Exact::new(self.cosh(int)?, false);
+        Ok(Self::reciprocal(cosh, "sech", int)?.value)
+    }
+
+    pub(crate) fn csch<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        let sinh = Exact::new(self.sinh(int)?, false);
+        Ok(Self::reciprocal(sinh, "csch", int)?.value)
+    }
+
+    pub(crate) fn coth<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        let tanh = Exact::new(self.tanh(int)?, false);
+        Ok(Self::reciprocal(tanh, "coth", int)?.value)
+    }
+
     pub(crate) fn asinh<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
         Ok(Self::from(self.expect_real()?.asinh(int)?))
     }
@@ -311,6 +484,19 @@ impl Complex {
         ))
     }
 
+    pub(crate) fn round<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        Ok(Self::from(self.expect_real()?.round(int)?))
+    }
+
+    pub(crate) fn floor_divmod<I: Interrupt>(
+        self,
+        rhs: Self,
+        int: &I,
+    ) -> Result<(Self, Self), FendError> {
+        let (quotient, remainder) = self.expect_real()?.floor_divmod(rhs.expect_real()?, int)?;
+        Ok((Self::from(quotient), Self::from(remainder)))
+    }
+
     pub(crate) fn bitwise<I: Interrupt>(
         self,
         rhs: Self,
@@ -1,6 +1,6 @@
 use crate::error::{FendError, Interrupt};
 use crate::interrupt::test_int;
-use crate::num::bigrat::BigRat;
+use crate::num::bigrat::{BigRat, DigitLimits};
 use crate::num::complex::{self, Complex};
 use crate::serialize::{deserialize_usize, serialize_usize};
 use std::cmp::Ordering;
@@ -80,6 +80,57 @@ impl Dist {
         Ok(Self { parts: hashmap })
     }
 
+    /// Sum of the `keep` highest (or lowest) of `count` dice, e.g. `4d6kh3`
+    pub(crate) fn new_die_keep<I: Interrupt>(
+        count: u32,
+        faces: u32,
+        keep: u32,
+        highest: bool,
+        int: &I,
+    ) -> Result<Self, FendError> {
+        assert!(count != 0);
+        assert!(faces != 0);
+        assert!(keep != 0 && keep <= count);
+        let mut face_order: Vec<u32> = (1..=faces).collect();
+        if highest {
+            face_order.reverse();
+        }
+        // maps (dice assigned so far, sum of kept dice) to the number of
+        // ordered rolls that lead there; faces are assigned from most to least
+        // preferred, so the first `keep` dice to be assigned are the kept ones
+        let mut ways: HashMap<(u32, u64), BigRat> = HashMap::new();
+        ways.insert((0, 0), 1.into());
+        for face in face_order {
+            let mut next: HashMap<(u32, u64), BigRat> = HashMap::new();
+            for ((assigned, sum), n) in ways {
+                let remaining = count - assigned;
+                let mut binomial = BigRat::from(1);
+                for c in 0..=remaining {
+                    test_int(int)?;
+                    let kept = c.min(keep.saturating_sub(assigned));
+                    let key = (assigned + c, sum + u64::from(kept) * u64::from(face));
+                    let n = n.clone().mul(&binomial, int)?;
+                    let entry = next.entry(key).or_insert_with(|| 0.into());
+                    *entry = entry.clone().add(n, int)?;
+                    binomial = binomial
+                        .mul(&u64::from(remaining - c).into(), int)?
+                        .div(&u64::from(c + 1).into(), int)?;
+                }
+            }
+            ways = next;
+        }
+        ways.retain(|&(assigned, _), _| assigned == count);
+        let mut total = BigRat::from(0);
+        for n in ways.values() {
+            total = total.add(n.clone(), int)?;
+        }
+        let mut hashmap = HashMap::new();
+        for ((_, sum), n) in ways {
+            hashmap.insert(Complex::from(sum), n.div(&total, int)?);
+        }
+        Ok(Self { parts: hashmap })
+    }
+
     pub(crate) fn equals_int(&self, val: u64) -> bool {
         self.parts.len() == 1 && self.parts.keys().next().unwrap() == &val.into()
     }
@@ -93,9 +144,15 @@ impl Dist {
         if self.parts.len() == 1 {
             return Ok(self);
         }
-        let mut random = ctx.random_u32.ok_or(FendError::RandomNumbersNotAvailable)?();
+        let mut random = ctx
+            .next_random_u32()
+            .ok_or(FendError::RandomNumbersNotAvailable)?;
+        // iterate in a fixed order, so that a seeded random number generator
+        // always gives the same results
+        let mut parts: Vec<_> = self.parts.into_iter().collect();
+        parts.sort_unstable_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal));
         let mut res = None;
-        for (k, v) in self.parts {
+        for (k, v) in parts {
             random = random.saturating_sub((v.into_f64(int)? * f64::from(u32::MAX)) as u32);
             if random == 0 {
                 return Ok(Self::from(k));
@@ -105,6 +162,15 @@ impl Dist {
         Ok(res.expect("there must be at least one part in a dist"))
     }
 
+    /// Rounds away the noise in the last digits of an `f64` approximation.
+    /// Distributions with more than one value are left unchanged.
+    pub(crate) fn round_f64_noise<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        if self.parts.len() != 1 {
+            return Ok(self);
+        }
+        Ok(self.one_point()?.round_f64_noise(int)?.into())
+    }
+
     #[allow(
         clippy::cast_possible_truncation,
         clippy::cast_sign_loss,
@@ -120,12 +186,17 @@ impl Dist {
         ctx: &crate::Context,
         int: &I,
     ) -> Result<Exact<()>, FendError> {
+        let digit_limits = DigitLimits {
+            max_recurring_digits: ctx.max_recurring_digits,
+            max_float_digits: ctx.max_float_digits,
+        };
         if self.parts.len() == 1 {
             let res = self.parts.iter().next().unwrap().0.format(
                 exact,
                 style,
                 base,
                 use_parentheses,
+                digit_limits,
                 int,
             )?;
             write!(out, "{}", res.value)?;
@@ -149,7 +220,7 @@ impl Dist {
             let mut first = true;
             for (num, _prob, prob_f64) in ordered_kvs {
                 let num = num
-                    .format(exact, style, base, use_parentheses, int)?
+                    .format(exact, style, base, use_parentheses, digit_limits, int)?
                     .value
                     .to_string();
                 let prob_percentage = prob_f64 * 100.0;
@@ -1,13 +1,17 @@
-use std::{fmt, io};
+use std::{fmt, io, str::FromStr};
 
 use crate::{
     error::FendError,
     serialize::{deserialize_u8, deserialize_usize, serialize_u8, serialize_usize},
 };
 
+/// This determines how numbers are formatted, e.g. as decimals or fractions.
+/// It corresponds to the formats that can be chosen with `to`, e.g.
+/// `1/3 to fraction`.
 #[derive(PartialEq, Eq, Clone, Copy, Default)]
 #[must_use]
-pub(crate) enum FormattingStyle {
+#[non_exhaustive]
+pub enum FormattingStyle {
     /// Print value as an improper fraction
     ImproperFraction,
     /// Print as a mixed fraction, e.g. 1 1/2
@@ -26,6 +30,25 @@ pub(crate) enum FormattingStyle {
     /// If not exact: DecimalPlaces(10). If no recurring digits: ExactFloat.
     /// Other numbers: MixedFraction, albeit possibly including fractions of pi
     Exact,
+    /// Print in scientific notation, e.g. 1.5e10 or 1.5 × 10^10
+    Scientific(ScientificNotationStyle),
+    /// Print in engineering notation, where the exponent is a multiple of
+    /// 3, e.g. 15e9 or 15 × 10^9
+    Engineering(ScientificNotationStyle),
+    /// Print with exactly the given number of decimal places, padding with
+    /// zeroes if necessary
+    FixedDecimalPlaces(usize),
+}
+
+/// This determines how numbers are written in scientific notation.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+#[non_exhaustive]
+pub enum ScientificNotationStyle {
+    /// Use `e` to separate the exponent, e.g. `1.5e10`
+    #[default]
+    Exponent,
+    /// Multiply by a power of ten, e.g. `1.5 × 10^10`
+    TimesTenTo,
 }
 
 impl fmt::Display for FormattingStyle {
@@ -38,10 +61,61 @@ impl fmt::Display for FormattingStyle {
             Self::DecimalPlaces(d) => write!(f, "{d} dp"),
             Self::SignificantFigures(s) => write!(f, "{s} sf"),
             Self::Auto => write!(f, "auto"),
+            Self::Scientific(ScientificNotationStyle::Exponent) => write!(f, "scientific"),
+            Self::Scientific(ScientificNotationStyle::TimesTenTo) => {
+                write!(f, "scientific_times_ten")
+            }
+            Self::Engineering(ScientificNotationStyle::Exponent) => write!(f, "engineering"),
+            Self::Engineering(ScientificNotationStyle::TimesTenTo) => {
+                write!(f, "engineering_times_ten")
+            }
+            Self::FixedDecimalPlaces(d) => write!(f, "fixed {d}"),
         }
     }
 }
 
+impl FromStr for FormattingStyle {
+    type Err = String;
+
+    /// Parses the names that can be used with `to`, e.g. `fraction`, `float`,
+    /// `5 dp` or `fixed 2`. `scientific_times_ten` selects scientific notation
+    /// written as e.g. `1.5 × 10^10`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        Ok(match s {
+            "frac" | "fraction" => Self::ImproperFraction,
+            "mixed_frac" | "mixed_fraction" => Self::MixedFraction,
+            "float" => Self::ExactFloat,
+            "exact" => Self::Exact,
+            "auto" => Self::Auto,
+            "sci" | "scientific" => Self::Scientific(ScientificNotationStyle::Exponent),
+            "scientific_times_ten" => Self::Scientific(ScientificNotationStyle::TimesTenTo),
+            "eng" | "engineering" => Self::Engineering(ScientificNotationStyle::Exponent),
+            "engineering_times_ten" => Self::Engineering(ScientificNotationStyle::TimesTenTo),
+            _ if s.starts_with("fixed ") => {
+                let count = s["fixed ".len()..]
+                    .trim()
+                    .parse()
+                    .map_err(|_| FendError::UnknownFormattingStyle(s.to_string()).to_string())?;
+                Self::FixedDecimalPlaces(count)
+            }
+            _ => {
+                let unknown = || FendError::UnknownFormattingStyle(s.to_string()).to_string();
+                let (count, kind) = s.split_once(' ').ok_or_else(unknown)?;
+                let count: usize = count.parse().map_err(|_| unknown())?;
+                match kind.trim() {
+                    "dp" => Self::DecimalPlaces(count),
+                    "sf" if count == 0 => {
+                        return Err(FendError::CannotFormatWithZeroSf.to_string());
+                    }
+                    "sf" => Self::SignificantFigures(count),
+                    _ => return Err(unknown()),
+                }
+            }
+        })
+    }
+}
+
 impl fmt::Debug for FormattingStyle {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         match self {
@@ -52,11 +126,30 @@ impl fmt::Debug for FormattingStyle {
             Self::DecimalPlaces(d) => write!(f, "{d} dp"),
             Self::SignificantFigures(s) => write!(f, "{s} sf"),
             Self::Auto => write!(f, "auto"),
+            Self::Scientific(ScientificNotationStyle::Exponent) => write!(f, "scientific"),
+            Self::Scientific(ScientificNotationStyle::TimesTenTo) => {
+                write!(f, "scientific (times ten to)")
+            }
+            Self::Engineering(ScientificNotationStyle::Exponent) => write!(f, "engineering"),
+            Self::Engineering(ScientificNotationStyle::TimesTenTo) => {
+                write!(f, "engineering (times ten to)")
+            }
+            Self::FixedDecimalPlaces(d) => write!(f, "fixed {d} dp"),
         }
     }
 }
 
 impl FormattingStyle {
+    /// The number of digits requested by `dp` or `sf` styles
+    pub(crate) fn digits(self) -> Option<usize> {
+        match self {
+            Self::DecimalPlaces(digits)
+            | Self::SignificantFigures(digits)
+            | Self::FixedDecimalPlaces(digits) => Some(digits),
+            _ => None,
+        }
+    }
+
     pub(crate) fn serialize(&self, write: &mut impl io::Write) -> Result<(), FendError> {
         match self {
             Self::ImproperFraction => serialize_u8(1, write)?,
@@ -72,12 +165,50 @@ impl FormattingStyle {
                 serialize_usize(*s, write)?;
             }
             Self::Auto => serialize_u8(7, write)?,
+            Self::Scientific(ScientificNotationStyle::Exponent) => serialize_u8(8, write)?,
+            Self::Scientific(ScientificNotationStyle::TimesTenTo) => serialize_u8(9, write)?,
+            Self::Engineering(ScientificNotationStyle::Exponent) => serialize_u8(11, write)?,
+            Self::Engineering(ScientificNotationStyle::TimesTenTo) => serialize_u8(12, write)?,
+            Self::FixedDecimalPlaces(d) => {
+                serialize_u8(13, write)?;
+                serialize_usize(*d, write)?;
+            }
         }
         Ok(())
     }
 
     pub(crate) fn deserialize(read: &mut impl io::Read) -> Result<Self, FendError> {
-        Ok(match deserialize_u8(read)? {
+        let tag = deserialize_u8(read)?;
+        Self::deserialize_with_tag(tag, read)
+    }
+
+    /// Serializes an optional formatting style, using the otherwise unused
+    /// tag `0` for `None`. Older versions wrote `7` (auto) for every number
+    /// without an explicit format, so an explicit `auto` uses tag `10` instead.
+    pub(crate) fn serialize_option(
+        style: Option<Self>,
+        write: &mut impl io::Write,
+    ) -> Result<(), FendError> {
+        match style {
+            Some(Self::Auto) => Ok(serialize_u8(10, write)?),
+            Some(style) => style.serialize(write),
+            None => Ok(serialize_u8(0, write)?),
+        }
+    }
+
+    pub(crate) fn deserialize_option(read: &mut impl io::Read) -> Result<Option<Self>, FendError> {
+        match deserialize_u8(read)? {
+            0 | 7 => Ok(None),
+            10 => Ok(Some(Self::Auto)),
+            tag => Ok(Some(Self::deserialize_with_tag(tag, read)?)),
+        }
+    }
+
+    pub(crate) fn deserialize_with_tag(
+        tag: u8,
+        read: &mut impl io::Read,
+    ) -> Result<Self, FendError> {
+        Ok(match tag {
             1 => Self::ImproperFraction,
             2 => Self::MixedFraction,
             3 => Self::ExactFloat,
@@ -85,7 +216,52 @@ impl FormattingStyle {
             5 => Self::DecimalPlaces(deserialize_usize(read)?),
             6 => Self::SignificantFigures(deserialize_usize(read)?),
             7 => Self::Auto,
+            8 => Self::Scientific(ScientificNotationStyle::Exponent),
+            9 => Self::Scientific(ScientificNotationStyle::TimesTenTo),
+            11 => Self::Engineering(ScientificNotationStyle::Exponent),
+            12 => Self::Engineering(ScientificNotationStyle::TimesTenTo),
+            13 => Self::FixedDecimalPlaces(deserialize_usize(read)?),
             _ => return Err(FendError::DeserializationError),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn optional_style_tags() {
+        let roundtrip = |style| {
+            let mut data = vec![];
+            FormattingStyle::serialize_option(style, &mut data).unwrap();
+            FormattingStyle::deserialize_option(&mut data.as_slice()).unwrap()
+        };
+        assert_eq!(roundtrip(None), None);
+        assert_eq!(
+            roundtrip(Some(FormattingStyle::Auto)),
+            Some(FormattingStyle::Auto)
+        );
+        assert_eq!(
+            roundtrip(Some(FormattingStyle::DecimalPlaces(3))),
+            Some(FormattingStyle::DecimalPlaces(3))
+        );
+        assert_eq!(
+            roundtrip(Some(FormattingStyle::FixedDecimalPlaces(2))),
+            Some(FormattingStyle::FixedDecimalPlaces(2))
+        );
+        assert_eq!(
+            roundtrip(Some(FormattingStyle::Engineering(
+                ScientificNotationStyle::TimesTenTo
+            ))),
+            Some(FormattingStyle::Engineering(
+                ScientificNotationStyle::TimesTenTo
+            ))
+        );
+        // older versions used `auto` for numbers without an explicit format
+        assert_eq!(
+            FormattingStyle::deserialize_option(&mut [7].as_slice()).unwrap(),
+            None
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use crate::error::{FendError, Interrupt};
 use crate::format::Format;
-use crate::num::bigrat::{BigRat, FormattedBigRat};
+use crate::interrupt::WithPrecision;
+use crate::num::bigrat::{BigRat, DigitLimits, FormattedBigRat};
 use crate::num::Exact;
 use crate::num::{Base, FormattingStyle};
 use crate::serialize::{deserialize_u8, serialize_u8};
@@ -26,6 +27,7 @@ impl fmt::Debug for Real {
                     write!(f, "{:?} * pi", x)
                 }
             }
+            Pattern::Sqrt(x, r) => write!(f, "{x:?} * sqrt({r:?})"),
         }
     }
 }
@@ -36,6 +38,8 @@ pub(crate) enum Pattern {
     Simple(BigRat),
     // n * pi
     Pi(BigRat),
+    // n * sqrt(r), where r is a positive integer that isn't a perfect square
+    Sqrt(BigRat, BigRat),
 }
 
 impl Ord for Real {
@@ -70,6 +74,10 @@ impl hash::Hash for Real {
     fn hash<H: hash::Hasher>(&self, state: &mut H) {
         match &self.pattern {
             Pattern::Simple(r) | Pattern::Pi(r) => r.hash(state),
+            Pattern::Sqrt(n, r) => {
+                n.hash(state);
+                r.hash(state);
+            }
         }
     }
 }
@@ -85,6 +93,11 @@ impl Real {
                 serialize_u8(2, write)?;
                 n.serialize(write)?;
             }
+            Pattern::Sqrt(n, r) => {
+                serialize_u8(3, write)?;
+                n.serialize(write)?;
+                r.serialize(write)?;
+            }
         }
         Ok(())
     }
@@ -94,6 +107,7 @@ impl Real {
             pattern: match deserialize_u8(read)? {
                 1 => Pattern::Simple(BigRat::deserialize(read)?),
                 2 => Pattern::Pi(BigRat::deserialize(read)?),
+                3 => Pattern::Sqrt(BigRat::deserialize(read)?, BigRat::deserialize(read)?),
                 _ => return Err(FendError::DeserializationError),
             },
         })
@@ -102,15 +116,33 @@ impl Real {
     fn approximate<I: Interrupt>(self, int: &I) -> Result<BigRat, FendError> {
         match self.pattern {
             Pattern::Simple(s) => Ok(s),
-            Pattern::Pi(n) => {
-                let num = BigRat::from(3_141_592_653_589_793_238);
-                let den = BigRat::from(1_000_000_000_000_000_000);
-                let pi = num.div(&den, int)?;
-                Ok(n.mul(&pi, int)?)
+            Pattern::Pi(n) => Ok(n.mul(&BigRat::pi(int)?, int)?),
+            Pattern::Sqrt(n, r) => {
+                // computed as sqrt(n^2 * r) so that e.g. `sqrt 8` (which is
+                // stored as 2 * sqrt(2)) is approximated as accurately as before
+                let negative = n < 0.into();
+                let half = BigRat::from(1).div(&2.into(), int)?;
+                let root = n.clone().mul(&n, int)?.mul(&r, int)?.pow(half, int)?.value;
+                Ok(if negative { -root } else { root })
             }
         }
     }
 
+    /// Returns `n * sqrt(r)`, simplifying it to a fraction if possible.
+    /// `r` must not be negative.
+    fn sqrt<I: Interrupt>(n: BigRat, r: BigRat, int: &I) -> Result<Self, FendError> {
+        let (outside, r) = r.split_sqrt(int)?;
+        let n = n.mul(&outside, int)?;
+        let root = r.clone().root_n(&2.into(), int)?;
+        Ok(if root.exact || n == 0.into() {
+            Self::from(n.mul(&root.value, int)?)
+        } else {
+            Self {
+                pattern: Pattern::Sqrt(n, r),
+            }
+        })
+    }
+
     pub(crate) fn try_as_usize<I: Interrupt>(self, int: &I) -> Result<usize, FendError> {
         match self.pattern {
             Pattern::Simple(s) => s.try_as_usize(int),
@@ -121,9 +153,33 @@ impl Real {
                     Err(FendError::CannotConvertToInteger)
                 }
             }
+            Pattern::Sqrt(..) => self.approximate(int)?.try_as_usize(int),
+        }
+    }
+
+    pub(crate) fn period<I: Interrupt>(self, base: Base, int: &I) -> Result<Self, FendError> {
+        match self.pattern {
+            Pattern::Simple(s) => Ok(s.period(base, int)?.into()),
+            Pattern::Pi(n) if n == 0.into() => Ok(0.into()),
+            Pattern::Pi(_) | Pattern::Sqrt(..) => Err(FendError::ExpectedARationalNumber),
+        }
+    }
+
+    pub(crate) fn into_fraction<I: Interrupt>(self, int: &I) -> Result<(Self, Self), FendError> {
+        match self.pattern {
+            Pattern::Simple(s) => {
+                let (num, den) = s.into_fraction(int)?;
+                Ok((num.into(), den.into()))
+            }
+            Pattern::Pi(n) if n == 0.into() => Ok((0.into(), 1.into())),
+            Pattern::Pi(_) | Pattern::Sqrt(..) => Err(FendError::ExpectedARationalNumber),
         }
     }
 
+    pub(crate) fn into_f64<I: Interrupt>(self, int: &I) -> Result<f64, FendError> {
+        self.approximate(int)?.into_f64(int)
+    }
+
     // sin works for all real numbers
     pub(crate) fn sin<I: Interrupt>(self, int: &I) -> Result<Exact<Self>, FendError> {
         Ok(match self.pattern {
@@ -157,6 +213,7 @@ impl Real {
                 };
                 s.approximate(int)?.sin(int)?.apply(Self::from)
             }
+            Pattern::Sqrt(..) => self.approximate(int)?.sin(int)?.apply(Self::from),
         })
     }
 
@@ -172,6 +229,43 @@ impl Real {
         Ok(Self::from(self.approximate(int)?.atan(int)?))
     }
 
+    /// Returns the angle of the point `(x, self)`, in the interval (-pi, pi].
+    /// Angles of points on the axes are exact, and `atan2(0, 0)` is 0.
+    pub(crate) fn atan2<I: Interrupt>(self, x: Self, int: &I) -> Result<Exact<Self>, FendError> {
+        let zero = Self::from(0);
+        let pi = Exact::new(Self::pi(), true);
+        let half_pi = pi.clone().div(&Exact::new(2.into(), true), int)?;
+        Ok(match (self.cmp(&zero), x.cmp(&zero)) {
+            (Ordering::Equal, Ordering::Less) => pi,
+            (Ordering::Equal, _) => Exact::new(zero, true),
+            (Ordering::Greater, Ordering::Equal) => half_pi,
+            (Ordering::Less, Ordering::Equal) => -half_pi,
+            (y_sign, x_sign) => {
+                let ratio = Exact::new(self, true).div(&Exact::new(x, true), int)?;
+                let atan = Exact::new(ratio.value.atan(int)?, false);
+                if x_sign == Ordering::Greater {
+                    atan
+                } else if y_sign == Ordering::Greater {
+                    atan.add(pi, int)?
+                } else {
+                    atan.add(-pi, int)?
+                }
+            }
+        })
+    }
+
+    pub(crate) fn asec<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        Ok(Self::from(self.approximate(int)?.asec(int)?))
+    }
+
+    pub(crate) fn acsc<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        Ok(Self::from(self.approximate(int)?.acsc(int)?))
+    }
+
+    pub(crate) fn acot<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        Ok(Self::from(self.approximate(int)?.acot(int)?))
+    }
+
     pub(crate) fn sinh<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
         Ok(Self::from(self.approximate(int)?.sinh(int)?))
     }
@@ -219,35 +313,53 @@ impl Real {
         mut style: FormattingStyle,
         imag: bool,
         use_parens_if_fraction: bool,
+        digit_limits: DigitLimits,
         int: &I,
     ) -> Result<Exact<Formatted>, FendError> {
         let mut pi = false;
+        let mut sqrt = false;
         if style == FormattingStyle::Exact && !self.is_zero() {
-            if let Pattern::Pi(_) = self.pattern {
-                pi = true;
+            match self.pattern {
+                Pattern::Pi(_) => pi = true,
+                // radicands are only written in base 10
+                Pattern::Sqrt(..) => sqrt = base.base_as_u8() == 10 && !base.has_prefix(),
+                Pattern::Simple(_) => (),
             }
         }
 
-        let term = match (imag, pi) {
-            (false, false) => "",
-            (false, true) => "\u{3c0}", // pi symbol
-            (true, false) => "i",
-            (true, true) => "\u{3c0}i",
+        let mut term = match (pi, &self.pattern) {
+            (true, _) => "\u{3c0}".to_string(), // pi symbol
+            (false, Pattern::Sqrt(_, r)) if sqrt => {
+                let r = r.format(&bigrat::FormatOptions::default(), int)?.value;
+                format!("\u{221a}{r}")
+            }
+            _ => String::new(),
         };
+        if imag {
+            term.push('i');
+        }
 
         let mut override_exact = true;
 
         let rat = match &self.pattern {
             Pattern::Simple(f) => f.clone(),
-            Pattern::Pi(f) => {
-                if pi {
+            Pattern::Pi(f) | Pattern::Sqrt(f, _) => {
+                // a zero coefficient is exactly zero, e.g. `pi - pi`
+                if pi || sqrt || self.is_zero() {
                     f.clone()
                 } else {
                     override_exact = false;
                     if style == FormattingStyle::Auto {
                         style = FormattingStyle::DecimalPlaces(10);
                     }
-                    self.clone().approximate(int)?
+                    match style.digits() {
+                        // e.g. `pi to 30 dp`
+                        Some(digits) if int.precision() < Some(digits) => {
+                            let int = WithPrecision::new(int, digits);
+                            self.clone().approximate(&int)?
+                        }
+                        _ => self.clone().approximate(int)?,
+                    }
                 }
             }
         };
@@ -258,6 +370,7 @@ impl Real {
                 style,
                 term,
                 use_parens_if_fraction,
+                digit_limits,
             },
             int,
         )?;
@@ -270,6 +383,13 @@ impl Real {
         ))
     }
 
+    pub(crate) fn round_f64_noise<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        Ok(match self.pattern {
+            Pattern::Simple(s) => s.round_f64_noise(int)?.into(),
+            Pattern::Pi(_) | Pattern::Sqrt(..) => self,
+        })
+    }
+
     pub(crate) fn pow<I: Interrupt>(self, rhs: Self, int: &I) -> Result<Exact<Self>, FendError> {
         // x^1 == x
         if let Pattern::Simple(n) = &rhs.pattern {
@@ -288,7 +408,12 @@ impl Real {
         if let (Pattern::Simple(a), Pattern::Simple(b)) =
             (self.clone().pattern, rhs.clone().pattern)
         {
-            Ok(a.pow(b, int)?.apply(Self::from))
+            let result = a.clone().pow(b.clone(), int)?;
+            // keep square roots like `sqrt(2)` symbolic
+            if !result.exact && a > 0.into() && b.mul(&2.into(), int)? == 1.into() {
+                return Ok(Exact::new(Self::sqrt(1.into(), a, int)?, true));
+            }
+            Ok(result.apply(Self::from))
         } else {
             Ok(self
                 .approximate(int)?
@@ -305,12 +430,12 @@ impl Real {
         Ok(match self.pattern {
             Pattern::Simple(a) => match &n.pattern {
                 Pattern::Simple(b) => a.root_n(b, int)?.apply(Self::from),
-                Pattern::Pi(_) => {
+                Pattern::Pi(_) | Pattern::Sqrt(..) => {
                     let b = n.clone().approximate(int)?;
                     a.root_n(&b, int)?.apply(Self::from).combine(false)
                 }
             },
-            Pattern::Pi(_) => {
+            Pattern::Pi(_) | Pattern::Sqrt(..) => {
                 let a = self.clone().approximate(int)?;
                 let b = n.clone().approximate(int)?;
                 a.root_n(&b, int)?.apply(Self::from).combine(false)
@@ -324,29 +449,45 @@ impl Real {
         }
     }
 
+    pub(crate) fn e<I: Interrupt>(int: &I) -> Result<Self, FendError> {
+        Ok(Self::from(BigRat::e(int)?))
+    }
+
+    pub(crate) fn is_sqrt(&self) -> bool {
+        matches!(self.pattern, Pattern::Sqrt(..))
+    }
+
+    /// Whether this is a rational number, rather than a multiple of pi or
+    /// a square root
+    pub(crate) fn is_rational(&self) -> bool {
+        self.is_zero() || matches!(self.pattern, Pattern::Simple(_))
+    }
+
     pub(crate) fn is_zero(&self) -> bool {
         match &self.pattern {
-            Pattern::Simple(a) | Pattern::Pi(a) => a.is_definitely_zero() || a == &0.into(),
+            Pattern::Simple(a) | Pattern::Pi(a) | Pattern::Sqrt(a, _) => {
+                a.is_definitely_zero() || a == &0.into()
+            }
         }
     }
 
     pub(crate) fn is_definitely_zero(&self) -> bool {
         match &self.pattern {
-            Pattern::Simple(a) | Pattern::Pi(a) => a.is_definitely_zero(),
+            Pattern::Simple(a) | Pattern::Pi(a) | Pattern::Sqrt(a, _) => a.is_definitely_zero(),
         }
     }
 
     pub(crate) fn is_definitely_one(&self) -> bool {
         match &self.pattern {
             Pattern::Simple(a) => a.is_definitely_one(),
-            Pattern::Pi(_) => false,
+            Pattern::Pi(_) | Pattern::Sqrt(..) => false,
         }
     }
 
     pub(crate) fn expect_rational(self) -> Result<BigRat, FendError> {
         match self.pattern {
             Pattern::Simple(a) => Ok(a),
-            Pattern::Pi(_) => Err(FendError::ExpectedARationalNumber),
+            Pattern::Pi(_) | Pattern::Sqrt(..) => Err(FendError::ExpectedARationalNumber),
         }
     }
 
@@ -357,6 +498,24 @@ impl Real {
         ))
     }
 
+    pub(crate) fn round<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        Ok(Self::from(match self.pattern {
+            Pattern::Simple(s) => s.round(int)?,
+            Pattern::Pi(_) | Pattern::Sqrt(..) => self.approximate(int)?.round(int)?,
+        }))
+    }
+
+    pub(crate) fn floor_divmod<I: Interrupt>(
+        self,
+        rhs: Self,
+        int: &I,
+    ) -> Result<(Self, Self), FendError> {
+        let (quotient, remainder) = self
+            .expect_rational()?
+            .floor_divmod(rhs.expect_rational()?, int)?;
+        Ok((Self::from(quotient), Self::from(remainder)))
+    }
+
     pub(crate) fn bitwise<I: Interrupt>(
         self,
         rhs: Self,
@@ -390,6 +549,9 @@ impl Exact<Real> {
                     },
                     args_exact,
                 ),
+                (Pattern::Sqrt(a, r), Pattern::Sqrt(b, s)) if r == s => {
+                    Self::new(Real::sqrt(a.add(b, int)?, r, int)?, args_exact)
+                }
                 _ => {
                     let a = self.value.approximate(int)?;
                     let b = rhs.value.approximate(int)?;
@@ -415,6 +577,9 @@ impl Exact<Real> {
                     },
                     args_exact,
                 ),
+                Pattern::Sqrt(b, r) => {
+                    Self::new(Real::sqrt(a.mul(b, int)?, r.clone(), int)?, args_exact)
+                }
             },
             Pattern::Pi(a) => match &rhs.value.pattern {
                 Pattern::Simple(b) => Self::new(
@@ -423,13 +588,32 @@ impl Exact<Real> {
                     },
                     args_exact,
                 ),
-                Pattern::Pi(_) => Self::new(
+                Pattern::Pi(_) | Pattern::Sqrt(..) => Self::new(
                     Real {
                         pattern: Pattern::Pi(a.mul(&rhs.value.clone().approximate(int)?, int)?),
                     },
                     false,
                 ),
             },
+            Pattern::Sqrt(a, r) => match &rhs.value.pattern {
+                Pattern::Simple(b) => Self::new(Real::sqrt(a.mul(b, int)?, r, int)?, args_exact),
+                // a * sqrt(r) * b * sqrt(s) == a * b * sqrt(r * s)
+                Pattern::Sqrt(b, s) => {
+                    Self::new(Real::sqrt(a.mul(b, int)?, r.mul(s, int)?, int)?, args_exact)
+                }
+                Pattern::Pi(b) => {
+                    let a = Real {
+                        pattern: Pattern::Sqrt(a, r),
+                    }
+                    .approximate(int)?;
+                    Self::new(
+                        Real {
+                            pattern: Pattern::Pi(a.mul(b, int)?),
+                        },
+                        false,
+                    )
+                }
+            },
         })
     }
 
@@ -447,6 +631,11 @@ impl Exact<Real> {
                     a.div(&rhs.value.clone().approximate(int)?, int)?.into(),
                     false,
                 ),
+                // a / (b * sqrt(r)) == a / (b * r) * sqrt(r)
+                Pattern::Sqrt(b, r) => Self::new(
+                    Real::sqrt(a.div(&b.clone().mul(r, int)?, int)?, r.clone(), int)?,
+                    self.exact && rhs.exact,
+                ),
             },
             Pattern::Pi(a) => match &rhs.value.pattern {
                 Pattern::Simple(b) => Self::new(
@@ -456,6 +645,32 @@ impl Exact<Real> {
                     self.exact && rhs.exact,
                 ),
                 Pattern::Pi(b) => Self::new(a.div(b, int)?.into(), self.exact && rhs.exact),
+                Pattern::Sqrt(..) => Self::new(
+                    Real {
+                        pattern: Pattern::Pi(a.div(&rhs.value.clone().approximate(int)?, int)?),
+                    },
+                    false,
+                ),
+            },
+            Pattern::Sqrt(a, r) => match &rhs.value.pattern {
+                Pattern::Simple(b) => {
+                    Self::new(Real::sqrt(a.div(b, int)?, r, int)?, self.exact && rhs.exact)
+                }
+                // a * sqrt(r) / (b * sqrt(s)) == a / (b * s) * sqrt(r * s)
+                Pattern::Sqrt(b, s) => Self::new(
+                    Real::sqrt(a.div(&b.clone().mul(s, int)?, int)?, r.mul(s, int)?, int)?,
+                    self.exact && rhs.exact,
+                ),
+                Pattern::Pi(_) => {
+                    let a = Real {
+                        pattern: Pattern::Sqrt(a, r),
+                    }
+                    .approximate(int)?;
+                    Self::new(
+                        a.div(&rhs.value.clone().approximate(int)?, int)?.into(),
+                        false,
+                    )
+                }
             },
         })
     }
@@ -470,6 +685,9 @@ impl Neg for Real {
             Pattern::Pi(n) => Self {
                 pattern: Pattern::Pi(-n),
             },
+            Pattern::Sqrt(n, r) => Self {
+                pattern: Pattern::Sqrt(-n, r),
+            },
         }
     }
 }
@@ -1,13 +1,16 @@
 use crate::ast::{BitwiseBop, Bop};
 use crate::error::{FendError, Interrupt};
+use crate::markup::NumberFormat;
+use crate::num::bigrat::DigitLimits;
 use crate::num::complex::{Complex, UseParentheses};
 use crate::num::dist::Dist;
-use crate::num::{Base, FormattingStyle};
+use crate::num::{out_of_range, Base, FormattingStyle, Range, RangeBound, UnitDisplayPreference};
 use crate::scope::Scope;
 use crate::serialize::{deserialize_bool, deserialize_usize, serialize_bool, serialize_usize};
 use crate::{ast, ident::Ident};
 use crate::{Span, SpanKind};
 use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::ops::Neg;
 use std::sync::Arc;
@@ -29,7 +32,9 @@ pub(crate) struct Value {
     unit: Unit,
     exact: bool,
     base: Base,
-    format: FormattingStyle,
+    /// `None` if no format was chosen explicitly, in which case the context's
+    /// default formatting style is used
+    format: Option<FormattingStyle>,
     simplifiable: bool,
 }
 
@@ -39,7 +44,7 @@ impl Value {
         self.unit.serialize(write)?;
         serialize_bool(self.exact, write)?;
         self.base.serialize(write)?;
-        self.format.serialize(write)?;
+        FormattingStyle::serialize_option(self.format, write)?;
         serialize_bool(self.simplifiable, write)?;
         Ok(())
     }
@@ -50,7 +55,7 @@ impl Value {
             unit: Unit::deserialize(read)?,
             exact: deserialize_bool(read)?,
             base: Base::deserialize(read)?,
-            format: FormattingStyle::deserialize(read)?,
+            format: FormattingStyle::deserialize_option(read)?,
             simplifiable: deserialize_bool(read)?,
         })
     }
@@ -103,10 +108,14 @@ impl Value {
             exact: self.exact,
             base: self.base,
             simplifiable: self.simplifiable,
-            format,
+            format: Some(format),
         }
     }
 
+    pub(crate) fn base(&self) -> Base {
+        self.base
+    }
+
     pub(crate) fn with_base(self, base: Base) -> Self {
         Self {
             value: self.value,
@@ -140,7 +149,7 @@ impl Value {
             },
             exact: true,
             base: Base::default(),
-            format: FormattingStyle::default(),
+            format: None,
             simplifiable: true,
         }
     }
@@ -157,7 +166,7 @@ impl Value {
             unit: self.unit,
             exact: self.exact && rhs.exact && value.exact,
             base: self.base,
-            format: self.format,
+            format: self.format.or(rhs.format),
             simplifiable: self.simplifiable,
         })
     }
@@ -201,6 +210,140 @@ impl Value {
         })
     }
 
+    /// Returns the unitless factor that converts a value in this unit into
+    /// the unit of `rhs`, e.g. 1000 from `km` to `m`. Units with different
+    /// zero points, like `°C` and `°F`, don't have a single factor.
+    pub(crate) fn conversion_factor<I: Interrupt>(
+        self,
+        rhs: Self,
+        int: &I,
+    ) -> Result<Self, FendError> {
+        let scale_factor = Unit::compute_scale_factor(&self.unit, &rhs.unit, int)?;
+        if scale_factor.offset.value != 0.into() {
+            return Err(FendError::NoConversionFactor);
+        }
+        let factor = Exact::new(self.value, self.exact)
+            .mul(&scale_factor.scale_1.apply(Dist::from), int)?
+            .div(&scale_factor.scale_2.apply(Dist::from), int)?
+            .div(&Exact::new(rhs.value, rhs.exact), int)?;
+        Ok(Self {
+            exact: factor.exact,
+            ..Self::new(factor.value, vec![])
+        })
+    }
+
+    /// Returns this value with the unit of `rhs` instead of its own unit
+    pub(crate) fn with_unit_of(self, rhs: &Self) -> Self {
+        Self {
+            unit: rhs.unit.clone(),
+            ..self
+        }
+    }
+
+    pub(crate) fn convert_to_unit_of<I: Interrupt>(
+        self,
+        rhs: &Self,
+        int: &I,
+    ) -> Result<Self, FendError> {
+        self.convert_to(Self::new(1, rhs.unit.components.clone()), int)
+    }
+
+    /// Converts `rhs` into the unit of `self` so the two values can be compared
+    fn converted_for_comparison<I: Interrupt>(
+        &self,
+        rhs: &Self,
+        int: &I,
+    ) -> Result<Complex, FendError> {
+        let unit = Self::new(1, self.unit.components.clone());
+        rhs.clone().convert_to(unit, int)?.value.one_point()
+    }
+
+    /// Returns an error unless this number lies between `start` and `end`
+    /// inclusive, e.g. a percentage for `percentile`
+    pub(crate) fn expect_in_range<I: Interrupt>(
+        self,
+        start: u64,
+        end: u64,
+        int: &I,
+    ) -> Result<Self, FendError> {
+        if self.compare(&start.into(), int)? == Ordering::Less
+            || self.compare(&end.into(), int)? == Ordering::Greater
+        {
+            let value = self.format(&crate::Context::new(), int)?.to_string();
+            return Err(out_of_range(
+                value,
+                Range {
+                    start: RangeBound::Closed(start),
+                    end: RangeBound::Closed(end),
+                },
+            ));
+        }
+        Ok(self)
+    }
+
+    pub(crate) fn compare<I: Interrupt>(&self, rhs: &Self, int: &I) -> Result<Ordering, FendError> {
+        let rhs = self.converted_for_comparison(rhs, int)?;
+        self.value.one_point_ref()?.compare(&rhs)
+    }
+
+    pub(crate) fn compare_eq<I: Interrupt>(&self, rhs: &Self, int: &I) -> Result<bool, FendError> {
+        let rhs = self.converted_for_comparison(rhs, int)?;
+        Ok(self.value.one_point_ref()? == &rhs)
+    }
+
+    /// Checks whether `self` and `rhs` agree to the given number of decimal
+    /// digits, similar to Python's `math.isclose`. They are equal if they
+    /// differ by at most `10^-digits` (in the units of `self`), so that values
+    /// close to zero can equal zero, or if they are within the same relative
+    /// tolerance. The relative tolerance is never tighter than `1e-9`, because
+    /// iterative approximations like `sqrt` are only accurate to about that,
+    /// e.g. `sqrt(2)^2` is 1.9999999987.
+    pub(crate) fn approx_eq<I: Interrupt>(
+        &self,
+        rhs: &Self,
+        digits: usize,
+        int: &I,
+    ) -> Result<bool, FendError> {
+        let tolerance = |digits: usize| {
+            Self::from(1).div(Self::from(10).pow(Self::from(digits as u64), int)?, int)
+        };
+        let abs_tol = Self {
+            unit: self.unit.clone(),
+            ..tolerance(digits)?
+        };
+        let rel_tol = tolerance(digits.min(9))?;
+        let diff = self.clone().sub(rhs.clone(), int)?.abs(int)?;
+        if diff.compare(&abs_tol, int)? != Ordering::Greater {
+            return Ok(true);
+        }
+        let lhs_abs = self.clone().abs(int)?;
+        let rhs_abs = rhs.clone().abs(int)?;
+        let larger = if lhs_abs.compare(&rhs_abs, int)? == Ordering::Less {
+            rhs_abs
+        } else {
+            lhs_abs
+        };
+        let max_diff = larger.mul(rel_tol, int)?;
+        Ok(diff.compare(&max_diff, int)? != Ordering::Greater)
+    }
+
+    /// Checks whether `|self - rhs| <= tolerance * (|self| + |rhs|)`, as used
+    /// by `approx_equal(a, b, tolerance)`
+    pub(crate) fn approx_eq_with_tolerance<I: Interrupt>(
+        &self,
+        rhs: &Self,
+        tolerance: Self,
+        int: &I,
+    ) -> Result<bool, FendError> {
+        let diff = self.clone().sub(rhs.clone(), int)?.abs(int)?;
+        let max_diff = self
+            .clone()
+            .abs(int)?
+            .add(rhs.clone().abs(int)?, int)?
+            .mul(tolerance, int)?;
+        Ok(diff.compare(&max_diff, int)? != Ordering::Greater)
+    }
+
     pub(crate) fn sub<I: Interrupt>(self, rhs: Self, int: &I) -> Result<Self, FendError> {
         let scale_factor = Unit::compute_scale_factor(&rhs.unit, &self.unit, int)?;
         let scaled = Exact::new(rhs.value, rhs.exact)
@@ -212,7 +355,7 @@ impl Value {
             unit: self.unit,
             exact: self.exact && rhs.exact && value.exact,
             base: self.base,
-            format: self.format,
+            format: self.format.or(rhs.format),
             simplifiable: self.simplifiable,
         })
     }
@@ -232,7 +375,7 @@ impl Value {
             unit: Unit { components },
             exact: value.exact && self.exact && rhs.exact,
             base: self.base,
-            format: self.format,
+            format: Self::product_format(self.format, rhs.format),
             simplifiable: self.simplifiable,
         })
     }
@@ -255,6 +398,68 @@ impl Value {
         })
     }
 
+    /// Integer division rounding towards negative infinity, returning both
+    /// the quotient and the remainder
+    pub(crate) fn floor_divmod<I: Interrupt>(
+        self,
+        rhs: Self,
+        int: &I,
+    ) -> Result<(Self, Self), FendError> {
+        if !self.is_unitless(int)? || !rhs.is_unitless(int)? {
+            return Err(FendError::ExpectedAUnitlessNumber);
+        }
+        let (quotient, remainder) = self
+            .value
+            .one_point()?
+            .floor_divmod(rhs.value.one_point()?, int)?;
+        let make_result = |value| Self {
+            value: Dist::from(value),
+            unit: self.unit.clone(),
+            exact: self.exact && rhs.exact,
+            base: self.base,
+            format: self.format,
+            simplifiable: self.simplifiable,
+        };
+        Ok((make_result(quotient), make_result(remainder)))
+    }
+
+    /// Rounds to the nearest integer, or to the nearest multiple of
+    /// `increment`, e.g. 125 for `round(137, nearest 25)`. Halves are rounded
+    /// away from zero.
+    pub(crate) fn round<I: Interrupt>(
+        self,
+        increment: Option<Self>,
+        int: &I,
+    ) -> Result<Self, FendError> {
+        match increment {
+            None => Ok(Self {
+                value: Dist::from(self.value.one_point()?.round(int)?),
+                ..self
+            }),
+            Some(increment) => {
+                if increment.is_zero() {
+                    return Err(FendError::ZeroRoundingIncrement);
+                }
+                // this also checks that the units are compatible
+                let value = if increment.is_unitless(int)? {
+                    self.clone()
+                } else {
+                    self.clone().convert_to_unit_of(&increment, int)?
+                };
+                let rounded = value
+                    .div(increment.clone(), int)?
+                    .simplify(int)?
+                    .round(None, int)?
+                    .mul(increment, int)?;
+                if self.is_unitless(int)? {
+                    Ok(rounded)
+                } else {
+                    rounded.convert_to_unit_of(&self, int)
+                }
+            }
+        }
+    }
+
     fn bitwise<I: Interrupt>(self, rhs: Self, op: BitwiseBop, int: &I) -> Result<Self, FendError> {
         if !self.is_unitless(int)? || !rhs.is_unitless(int)? {
             return Err(FendError::ExpectedAUnitlessNumber);
@@ -273,6 +478,60 @@ impl Value {
         })
     }
 
+    /// Returns a mask of `len` one bits, e.g. `0b111` for 3
+    fn bit_mask<I: Interrupt>(len: usize, int: &I) -> Result<Self, FendError> {
+        if len == 0 {
+            return Err(FendError::EmptyBitField);
+        }
+        Self::from(1)
+            .bitwise(Self::from(len as u64), BitwiseBop::LeftShift, int)?
+            .sub(Self::from(1), int)
+    }
+
+    /// Returns the `len` bits starting at bit `pos` (counting from the least
+    /// significant bit), for `bits`
+    pub(crate) fn extract_bits<I: Interrupt>(
+        self,
+        pos: usize,
+        len: usize,
+        int: &I,
+    ) -> Result<Self, FendError> {
+        let mask = Self::bit_mask(len, int)?;
+        self.bitwise(Self::from(pos as u64), BitwiseBop::RightShift, int)?
+            .bitwise(mask, BitwiseBop::And, int)
+    }
+
+    /// Replaces the `len` bits starting at bit `pos` with `value`, for
+    /// `set_bits`
+    pub(crate) fn insert_bits<I: Interrupt>(
+        self,
+        pos: usize,
+        len: usize,
+        value: Self,
+        int: &I,
+    ) -> Result<Self, FendError> {
+        let mask = Self::bit_mask(len, int)?;
+        if value
+            .clone()
+            .bitwise(mask.clone(), BitwiseBop::And, int)?
+            .compare(&value, int)?
+            != Ordering::Equal
+        {
+            let value = value.format(&crate::Context::new(), int)?.to_string();
+            return Err(FendError::ValueDoesNotFitInBits(value, len));
+        }
+        let pos = Self::from(pos as u64);
+        let field = mask.bitwise(pos.clone(), BitwiseBop::LeftShift, int)?;
+        // there's no bitwise not for unbounded integers, so the field is
+        // cleared by xor-ing away the bits that are set in it
+        let set_in_field = self.clone().bitwise(field, BitwiseBop::And, int)?;
+        self.bitwise(set_in_field, BitwiseBop::Xor, int)?.bitwise(
+            value.bitwise(pos, BitwiseBop::LeftShift, int)?,
+            BitwiseBop::Or,
+            int,
+        )
+    }
+
     pub(crate) fn bop<I: Interrupt>(
         self,
         op: Bop,
@@ -290,12 +549,14 @@ impl Value {
             Bop::Mul => self.mul(rhs, int),
             Bop::Div => self.div(rhs, int),
             Bop::Mod => self.modulo(rhs, int),
+            Bop::FloorDiv => Ok(self.floor_divmod(rhs, int)?.0),
             Bop::Pow => self.pow(rhs, int),
             Bop::Bitwise(bitwise_bop) => self.bitwise(rhs, bitwise_bop, int),
+            Bop::PlusMinus => Err(FendError::ExpectedANumber),
         }
     }
 
-    fn is_unitless<I: Interrupt>(&self, int: &I) -> Result<bool, FendError> {
+    pub(crate) fn is_unitless<I: Interrupt>(&self, int: &I) -> Result<bool, FendError> {
         // todo this is broken for unitless components
         if self.unit.components.is_empty() {
             return Ok(true);
@@ -307,6 +568,34 @@ impl Value {
         Ok(false)
     }
 
+    /// Returns the plural name of this number's unit if it's a single
+    /// discrete unit like `bits` (see `units::DISCRETE_UNITS`), and the
+    /// number isn't a whole number of that unit
+    pub(crate) fn fractional_discrete_unit<I: Interrupt>(
+        &self,
+        int: &I,
+    ) -> Result<Option<String>, FendError> {
+        let [component] = self.unit.components.as_slice() else {
+            return Ok(None);
+        };
+        let (prefix, name) = component.unit.prefix_and_name(false);
+        if !prefix.is_empty()
+            || component.exponent != 1.into()
+            || !crate::units::DISCRETE_UNITS.contains(&name)
+        {
+            return Ok(None);
+        }
+        if self.clone().round(None, int)?.compare_eq(self, int)? {
+            return Ok(None);
+        }
+        Ok(Some(component.unit.prefix_and_name(true).1.to_string()))
+    }
+
+    /// Unlike `is_unitless`, this is false for e.g. `30°` or `5%`
+    pub(crate) fn has_no_unit(&self) -> bool {
+        self.unit.components.is_empty()
+    }
+
     pub(crate) fn is_unitless_one<I: Interrupt>(&self, int: &I) -> Result<bool, FendError> {
         Ok(self.exact && self.value.equals_int(1) && self.is_unitless(int)?)
     }
@@ -346,7 +635,7 @@ impl Value {
             unit: Unit { components: vec![] },
             exact: true,
             base: Base::default(),
-            format: FormattingStyle::default(),
+            format: None,
             simplifiable: true,
         }
     }
@@ -357,11 +646,22 @@ impl Value {
             unit: Unit { components: vec![] },
             exact: true,
             base: Base::default(),
-            format: FormattingStyle::default(),
+            format: None,
             simplifiable: true,
         }
     }
 
+    pub(crate) fn e<I: Interrupt>(int: &I) -> Result<Self, FendError> {
+        Ok(Self {
+            value: Complex::e(int)?.into(),
+            unit: Unit { components: vec![] },
+            exact: false,
+            base: Base::default(),
+            format: None,
+            simplifiable: true,
+        })
+    }
+
     pub(crate) fn abs<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
         let value = self.value.one_point()?.abs(int)?;
         Ok(Self {
@@ -391,7 +691,7 @@ impl Value {
             unit: Unit::unitless(),
             exact: true,
             base,
-            format: FormattingStyle::default(),
+            format: None,
             simplifiable: true,
         }
     }
@@ -400,6 +700,10 @@ impl Value {
         self.value.equals_int(0)
     }
 
+    pub(crate) fn is_exact(&self) -> bool {
+        self.exact
+    }
+
     pub(crate) fn new_die<I: Interrupt>(
         count: u32,
         faces: u32,
@@ -408,6 +712,19 @@ impl Value {
         Ok(Self::new(Dist::new_die(count, faces, int)?, vec![]))
     }
 
+    pub(crate) fn new_die_keep<I: Interrupt>(
+        count: u32,
+        faces: u32,
+        keep: u32,
+        highest: bool,
+        int: &I,
+    ) -> Result<Self, FendError> {
+        Ok(Self::new(
+            Dist::new_die_keep(count, faces, keep, highest, int)?,
+            vec![],
+        ))
+    }
+
     fn apply_fn_exact<I: Interrupt>(
         self,
         f: impl FnOnce(Complex, &I) -> Result<Exact<Complex>, FendError>,
@@ -464,9 +781,11 @@ impl Value {
         context: &mut crate::Context,
         int: &I,
     ) -> Result<Self, FendError> {
-        let radians = ast::resolve_identifier(&Ident::new_str("radians"), scope, context, int)?
-            .expect_num()?;
-        self.convert_to(radians, int)
+        // looking up `radians` doesn't count as the input using units
+        let features_used = context.features_used;
+        let radians = ast::resolve_identifier(&Ident::new_str("radians"), scope, context, int);
+        context.features_used = features_used;
+        self.convert_to(radians?.expect_num()?, int)
     }
 
     fn unitless() -> Self {
@@ -475,11 +794,73 @@ impl Value {
             unit: Unit::unitless(),
             exact: true,
             base: Base::default(),
-            format: FormattingStyle::default(),
+            format: None,
             simplifiable: true,
         }
     }
 
+    /// Returns the numerator (or denominator) of a fraction in lowest terms,
+    /// e.g. 3 (or 4) for `6/8`
+    pub(crate) fn fraction_part<I: Interrupt>(
+        self,
+        numerator: bool,
+        int: &I,
+    ) -> Result<Self, FendError> {
+        if !self.is_unitless(int)? {
+            return Err(FendError::ExpectedAUnitlessNumber);
+        }
+        if !self.exact {
+            return Err(FendError::ExpectedARationalNumber);
+        }
+        let (num, den) = self.value.one_point()?.into_fraction(int)?;
+        Ok(Self::new(if numerator { num } else { den }, vec![]))
+    }
+
+    /// Approximates the numeric part of this number, ignoring its unit
+    pub(crate) fn into_f64<I: Interrupt>(self, int: &I) -> Result<f64, FendError> {
+        self.value.one_point()?.into_f64(int)
+    }
+
+    /// Returns the length of the repeating part of this number's expansion
+    /// in its base, e.g. 6 for `1/7`
+    pub(crate) fn period<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        if !self.is_unitless(int)? {
+            return Err(FendError::ExpectedAUnitlessNumber);
+        }
+        if !self.exact {
+            return Err(FendError::ExpectedARationalNumber);
+        }
+        let period = self.value.one_point()?.period(self.base, int)?;
+        Ok(Self::new(period, vec![]))
+    }
+
+    /// Whether this number has a nonzero imaginary part
+    pub(crate) fn is_complex(&self) -> Result<bool, FendError> {
+        Ok(!self.value.one_point_ref()?.is_real())
+    }
+
+    /// Whether this number is known to be rational. Approximated values,
+    /// like `e` or `sin 1`, are not.
+    pub(crate) fn is_rational<I: Interrupt>(&self, int: &I) -> Result<bool, FendError> {
+        if !self.is_unitless(int)? {
+            return Err(FendError::ExpectedAUnitlessNumber);
+        }
+        Ok(self.exact && self.value.one_point_ref()?.is_rational())
+    }
+
+    /// Whether this number is real but not rational, e.g. `pi`, `sqrt 2` or `e`
+    pub(crate) fn is_irrational<I: Interrupt>(&self, int: &I) -> Result<bool, FendError> {
+        Ok(!self.is_rational(int)? && !self.is_complex()?)
+    }
+
+    pub(crate) fn is_integer<I: Interrupt>(&self, int: &I) -> Result<bool, FendError> {
+        if !self.is_rational(int)? {
+            return Ok(false);
+        }
+        let (_, den) = self.value.one_point_ref()?.clone().into_fraction(int)?;
+        Ok(den == 1.into())
+    }
+
     pub(crate) fn conjugate(self) -> Result<Self, FendError> {
         Ok(Self {
             value: self.value.one_point()?.conjugate().into(),
@@ -487,6 +868,34 @@ impl Value {
         })
     }
 
+    pub(crate) fn real_part(self) -> Result<Self, FendError> {
+        Ok(Self {
+            value: self.value.one_point()?.real_part().into(),
+            ..self
+        })
+    }
+
+    pub(crate) fn imaginary_part(self) -> Result<Self, FendError> {
+        Ok(Self {
+            value: self.value.one_point()?.imaginary_part().into(),
+            ..self
+        })
+    }
+
+    /// Returns the argument (or phase) of a complex number, i.e. the angle
+    /// of the point `(re(z), im(z))`
+    pub(crate) fn arg<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        let angle = self.value.one_point()?.arg(int)?;
+        Ok(Self {
+            value: angle.value.into(),
+            unit: Unit::unitless(),
+            exact: self.exact && angle.exact,
+            base: self.base,
+            format: self.format,
+            simplifiable: true,
+        })
+    }
+
     pub(crate) fn sin<I: Interrupt>(
         self,
         scope: Option<Arc<Scope>>,
@@ -530,6 +939,48 @@ impl Value {
         }
     }
 
+    pub(crate) fn sec<I: Interrupt>(
+        self,
+        scope: Option<Arc<Scope>>,
+        context: &mut crate::Context,
+        int: &I,
+    ) -> Result<Self, FendError> {
+        if let Ok(rad) = self.clone().convert_angle_to_rad(scope, context, int) {
+            rad.apply_fn_exact(Complex::sec, false, int)?
+                .convert_to(Self::unitless(), int)
+        } else {
+            self.apply_fn_exact(Complex::sec, false, int)
+        }
+    }
+
+    pub(crate) fn csc<I: Interrupt>(
+        self,
+        scope: Option<Arc<Scope>>,
+        context: &mut crate::Context,
+        int: &I,
+    ) -> Result<Self, FendError> {
+        if let Ok(rad) = self.clone().convert_angle_to_rad(scope, context, int) {
+            rad.apply_fn_exact(Complex::csc, false, int)?
+                .convert_to(Self::unitless(), int)
+        } else {
+            self.apply_fn_exact(Complex::csc, false, int)
+        }
+    }
+
+    pub(crate) fn cot<I: Interrupt>(
+        self,
+        scope: Option<Arc<Scope>>,
+        context: &mut crate::Context,
+        int: &I,
+    ) -> Result<Self, FendError> {
+        if let Ok(rad) = self.clone().convert_angle_to_rad(scope, context, int) {
+            rad.apply_fn_exact(Complex::cot, false, int)?
+                .convert_to(Self::unitless(), int)
+        } else {
+            self.apply_fn_exact(Complex::cot, false, int)
+        }
+    }
+
     pub(crate) fn asin<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
         self.apply_fn(Complex::asin, false, int)
     }
@@ -542,6 +993,33 @@ impl Value {
         self.apply_fn(Complex::atan, false, int)
     }
 
+    /// Returns the angle of the point `(x, self)`. Both coordinates may have
+    /// a unit, as long as they have the same dimension.
+    pub(crate) fn atan2<I: Interrupt>(self, x: Self, int: &I) -> Result<Self, FendError> {
+        let y = self.convert_to_unit_of(&x, int)?;
+        let angle = y.value.one_point()?.atan2(x.value.one_point()?, int)?;
+        Ok(Self {
+            value: angle.value.into(),
+            unit: Unit::unitless(),
+            exact: y.exact && x.exact && angle.exact,
+            base: x.base,
+            format: x.format,
+            simplifiable: true,
+        })
+    }
+
+    pub(crate) fn asec<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        self.apply_fn(Complex::asec, false, int)
+    }
+
+    pub(crate) fn acsc<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        self.apply_fn(Complex::acsc, false, int)
+    }
+
+    pub(crate) fn acot<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        self.apply_fn(Complex::acot, false, int)
+    }
+
     pub(crate) fn sinh<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
         self.apply_fn(Complex::sinh, false, int)
     }
@@ -554,6 +1032,18 @@ impl Value {
         self.apply_fn(Complex::tanh, false, int)
     }
 
+    pub(crate) fn sech<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        self.apply_fn(Complex::sech, false, int)
+    }
+
+    pub(crate) fn csch<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        self.apply_fn(Complex::csch, false, int)
+    }
+
+    pub(crate) fn coth<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        self.apply_fn(Complex::coth, false, int)
+    }
+
     pub(crate) fn asinh<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
         self.apply_fn(Complex::asinh, false, int)
     }
@@ -588,12 +1078,19 @@ impl Value {
         } else {
             UseParentheses::IfComplex
         };
+        let format = self.format.unwrap_or(ctx.formatting_style);
+        let rounded;
+        let value = if ctx.display_rounding && !self.exact && format == FormattingStyle::Auto {
+            rounded = self.value.clone().round_f64_noise(int)?;
+            &rounded
+        } else {
+            &self.value
+        };
         let mut formatted_value = String::new();
-        let mut exact = self
-            .value
+        let mut exact = value
             .format(
                 self.exact,
-                self.format,
+                format,
                 self.base,
                 use_parentheses,
                 &mut formatted_value,
@@ -601,23 +1098,52 @@ impl Value {
                 int,
             )?
             .exact;
-        let unit_string = self.unit.format(
-            "",
-            self.value.equals_int(1),
-            self.base,
-            self.format,
-            true,
-            int,
-        )?;
+        let unit_string =
+            self.unit
+                .format("", self.value.equals_int(1), self.base, format, true, int)?;
         exact = exact && unit_string.exact;
+        let number_format = if self.base.base_as_u8() != 10 || self.base.has_prefix() {
+            NumberFormat::OtherBase(self.base.base_as_u8())
+        } else if let FormattingStyle::Scientific(_) | FormattingStyle::Engineering(_) = format {
+            NumberFormat::Scientific
+        } else {
+            NumberFormat::Decimal
+        };
+        formatted_value = match (ctx.locale, number_format) {
+            (Some(locale), NumberFormat::Decimal | NumberFormat::Scientific) => {
+                locale.localize(&formatted_value)
+            }
+            (None, NumberFormat::Decimal | NumberFormat::Scientific) => {
+                ctx.digit_grouping.group(&formatted_value, 10)
+            }
+            (_, NumberFormat::OtherBase(base)) => ctx.digit_grouping.group(&formatted_value, base),
+        };
         Ok(FormattedValue {
             number: formatted_value,
+            number_format,
             exact,
             unit_str: unit_string.value,
         })
     }
 
+    /// The formatting style of a product or quotient. It is only as precise
+    /// as its least precise operand, so the smaller number of significant
+    /// figures is used, e.g. `(3.14159 to 3 sf) * (2 to 2 sf)` is `6.3`.
+    fn product_format(
+        lhs: Option<FormattingStyle>,
+        rhs: Option<FormattingStyle>,
+    ) -> Option<FormattingStyle> {
+        match (lhs, rhs) {
+            (
+                Some(FormattingStyle::SignificantFigures(a)),
+                Some(FormattingStyle::SignificantFigures(b)),
+            ) => Some(FormattingStyle::SignificantFigures(a.min(b))),
+            (format @ Some(_), _) | (None, format) => format,
+        }
+    }
+
     pub(crate) fn mul<I: Interrupt>(self, rhs: Self, int: &I) -> Result<Self, FendError> {
+        let format = Self::product_format(self.format, rhs.format);
         let components = [self.unit.components, rhs.unit.components].concat();
         let value =
             Exact::new(self.value, self.exact).mul(&Exact::new(rhs.value, rhs.exact), int)?;
@@ -626,7 +1152,7 @@ impl Value {
             unit: Unit { components },
             exact: self.exact && rhs.exact && value.exact,
             base: self.base,
-            format: self.format,
+            format,
             simplifiable: self.simplifiable,
         })
     }
@@ -764,9 +1290,202 @@ impl Value {
         })
     }
 
+    /// Rewrites the unit in terms of SI derived or base units, depending on
+    /// the preference. Values that were converted with `to`, and values with
+    /// units that aren't SI units (e.g. currencies or °C), are left unchanged.
+    pub(crate) fn with_unit_display_preference<I: Interrupt>(
+        self,
+        preference: UnitDisplayPreference,
+        int: &I,
+    ) -> Result<Self, FendError> {
+        if preference == UnitDisplayPreference::AsEntered || !self.simplifiable {
+            return Ok(self);
+        }
+        // a single unit like `kWh` is already a derived unit
+        if preference == UnitDisplayPreference::Derived
+            && self.unit.components.len() == 1
+            && self.unit.components[0].exponent == 1.into()
+        {
+            return Ok(self);
+        }
+        let (hashmap, scale) = self.unit.to_hashmap_and_scale(int)?;
+        if hashmap.is_empty()
+            || hashmap
+                .keys()
+                .any(|base_unit| !SI_BASE_UNITS.iter().any(|(n, _)| *n == base_unit.name()))
+        {
+            return Ok(self);
+        }
+        let components = if preference == UnitDisplayPreference::Base {
+            SI_BASE_UNITS
+                .iter()
+                .filter_map(|&(name, symbol)| {
+                    let exponent = hashmap.get(&BaseUnit::new_static(name))?;
+                    let mut base_units = HashMap::new();
+                    base_units.insert(BaseUnit::new_static(name), 1.into());
+                    let unit = NamedUnit::new(
+                        Cow::Borrowed(""),
+                        Cow::Borrowed(symbol),
+                        Cow::Borrowed(symbol),
+                        base_units,
+                        1,
+                    );
+                    Some(UnitExponent::new(unit, exponent.clone()))
+                })
+                .collect()
+        } else if let Some((singular, plural, _)) = SI_DERIVED_UNITS
+            .iter()
+            .find(|(_, _, dimensions)| has_dimensions(&hashmap, *dimensions))
+        {
+            let unit = NamedUnit::new(
+                Cow::Borrowed(""),
+                Cow::Borrowed(singular),
+                Cow::Borrowed(plural),
+                hashmap,
+                1,
+            );
+            vec![UnitExponent::new(unit, 1)]
+        } else {
+            return Ok(self);
+        };
+        let value = Exact::new(self.value, self.exact)
+            .mul(&Exact::new(Dist::from(scale.value), scale.exact), int)?;
+        Ok(Self {
+            value: value.value,
+            unit: Unit { components },
+            exact: self.exact && value.exact,
+            base: self.base,
+            format: self.format,
+            simplifiable: self.simplifiable,
+        })
+    }
+
+    /// Returns the canonical units of the given unit system for this value's
+    /// dimension, e.g. `dyn` for a force in CGS units, or `kg`, `m` and `s`
+    /// with their exponents if the system has no unit for the dimension
+    pub(crate) fn units_in_system<I: Interrupt>(
+        &self,
+        system: UnitSystem,
+        int: &I,
+    ) -> Result<Vec<(&'static str, Self)>, FendError> {
+        let (hashmap, _) = self.unit.to_hashmap_and_scale(int)?;
+        let (base_units, derived_units) = system.units();
+        let mut components = vec![];
+        for (base_unit, exponent) in &hashmap {
+            // temperature scales are converted to kelvin
+            let name = match base_unit.name() {
+                "celsius" | "fahrenheit" => "kelvin",
+                name => name,
+            };
+            let idx = SI_BASE_UNITS.iter().position(|(n, _)| *n == name);
+            match idx.and_then(|idx| Some((idx, base_units[idx]?))) {
+                Some((idx, symbol)) => components.push((idx, symbol, exponent)),
+                None => {
+                    return Err(FendError::NoUnitInSystem {
+                        unit: base_unit.name().to_string(),
+                        system: system.name(),
+                    })
+                }
+            }
+        }
+        if let Some((name, _)) = derived_units
+            .iter()
+            .find(|(_, dimensions)| has_dimensions(&hashmap, *dimensions))
+        {
+            return Ok(vec![(name, 1.into())]);
+        }
+        components.sort_by_key(|&(idx, _, _)| idx);
+        Ok(components
+            .into_iter()
+            .map(|(_, symbol, exponent)| (symbol, Self::new(exponent.clone(), vec![])))
+            .collect())
+    }
+
     pub(crate) fn unit_equal_to(&self, rhs: &str) -> bool {
         self.unit.equal_to(rhs)
     }
+
+    /// Describes the dimensions of this number's unit in words, e.g.
+    /// `mass·length/time²` for newtons, or `dimensionless`
+    pub(crate) fn dimension_name<I: Interrupt>(&self, int: &I) -> Result<String, FendError> {
+        let (hashmap, _) = self.unit.to_hashmap_and_scale(int)?;
+        let mut dimensions: Vec<_> = hashmap
+            .iter()
+            .filter(|(_, exponent)| **exponent != 0.into())
+            .map(|(base_unit, exponent)| {
+                let name = base_unit.name();
+                match DIMENSION_NAMES.iter().position(|(n, _)| *n == name) {
+                    Some(idx) => (idx, DIMENSION_NAMES[idx].1, exponent),
+                    None => (DIMENSION_NAMES.len(), name, exponent),
+                }
+            })
+            .collect();
+        if dimensions.is_empty() {
+            return Ok("dimensionless".to_string());
+        }
+        dimensions.sort_unstable_by(|(a_idx, a, _), (b_idx, b, _)| (a_idx, a).cmp(&(b_idx, b)));
+        let mut numerator = vec![];
+        let mut denominator = vec![];
+        for (_, name, exponent) in dimensions {
+            let (list, exponent) = if *exponent < 0.into() {
+                (&mut denominator, -exponent.clone())
+            } else {
+                (&mut numerator, exponent.clone())
+            };
+            let mut dimension = name.replace('_', " ");
+            if exponent != 1.into() {
+                let exponent = exponent
+                    .format(
+                        true,
+                        FormattingStyle::default(),
+                        Base::default(),
+                        UseParentheses::IfComplexOrFraction,
+                        DigitLimits::default(),
+                        int,
+                    )?
+                    .value
+                    .to_string();
+                if exponent.chars().all(|ch| ch.is_ascii_digit()) {
+                    dimension.extend(exponent.chars().map(superscript_digit));
+                } else {
+                    dimension.push('^');
+                    dimension.push_str(&exponent);
+                }
+            }
+            list.push(dimension);
+        }
+        let numerator = if numerator.is_empty() {
+            "1".to_string()
+        } else {
+            numerator.join("\u{b7}")
+        };
+        Ok(match denominator.len() {
+            0 => numerator,
+            1 => format!("{numerator}/{}", denominator[0]),
+            _ => format!("{numerator}/({})", denominator.join("\u{b7}")),
+        })
+    }
+}
+
+/// Names of the quantities measured by each base unit, in the order in which
+/// they are listed by `dimension_name`
+const DIMENSION_NAMES: [(&str, &str); 7] = [
+    ("kilogram", "mass"),
+    ("meter", "length"),
+    ("second", "time"),
+    ("ampere", "electric current"),
+    ("kelvin", "temperature"),
+    ("mole", "amount of substance"),
+    ("candela", "luminous intensity"),
+];
+
+fn superscript_digit(digit: char) -> char {
+    match digit {
+        '1' => '\u{b9}',
+        '2' => '\u{b2}',
+        '3' => '\u{b3}',
+        _ => char::from_u32(0x2070 + digit.to_digit(10).unwrap_or(0)).unwrap_or(digit),
+    }
 }
 
 impl Neg for Value {
@@ -790,7 +1509,7 @@ impl From<u64> for Value {
             unit: Unit::unitless(),
             exact: true,
             base: Base::default(),
-            format: FormattingStyle::default(),
+            format: None,
             simplifiable: true,
         }
     }
@@ -805,7 +1524,10 @@ impl fmt::Debug for Value {
         write!(
             f,
             "{:?} {:?} ({:?}, {:?}, {simplifiable}simplifiable)",
-            self.value, self.unit, self.base, self.format
+            self.value,
+            self.unit,
+            self.base,
+            self.format.unwrap_or_default()
         )?;
         Ok(())
     }
@@ -815,6 +1537,7 @@ impl fmt::Debug for Value {
 pub(crate) struct FormattedValue {
     exact: bool,
     number: String,
+    number_format: NumberFormat,
     unit_str: String,
 }
 
@@ -824,26 +1547,64 @@ impl FormattedValue {
             spans.push(Span {
                 string: "approx. ".to_string(),
                 kind: SpanKind::Ident,
+                number_format: None,
             });
         }
         if self.unit_str == "$" || self.unit_str == "\u{a3}" {
             spans.push(Span {
                 string: self.unit_str,
                 kind: SpanKind::Ident,
+                number_format: None,
             });
             spans.push(Span {
                 string: self.number,
                 kind: SpanKind::Number,
+                number_format: Some(self.number_format),
             });
             return;
         }
         spans.push(Span {
             string: self.number.to_string(),
             kind: SpanKind::Number,
+            number_format: Some(self.number_format),
         });
         spans.push(Span {
             string: self.unit_str,
             kind: SpanKind::Ident,
+            number_format: None,
+        });
+    }
+}
+
+impl FormattedValue {
+    /// Writes e.g. `5 ± 0.1 cm`, where `uncertainty` has the same unit
+    pub(crate) fn spans_with_uncertainty(self, uncertainty: Self, spans: &mut Vec<Span>) {
+        if !self.exact || !uncertainty.exact {
+            spans.push(Span {
+                string: "approx. ".to_string(),
+                kind: SpanKind::Ident,
+                number_format: None,
+            });
+        }
+        spans.push(Span {
+            string: self.number,
+            kind: SpanKind::Number,
+            number_format: Some(self.number_format),
+        });
+        spans.push(Span {
+            string: " \u{b1} ".to_string(),
+            kind: SpanKind::Other,
+            number_format: None,
+        });
+        spans.push(Span {
+            string: uncertainty.number,
+            kind: SpanKind::Number,
+            number_format: Some(uncertainty.number_format),
+        });
+        spans.push(Span {
+            string: self.unit_str,
+            kind: SpanKind::Ident,
+            number_format: None,
         });
     }
 }
@@ -863,6 +1624,109 @@ struct Unit {
     components: Vec<UnitExponent>,
 }
 
+/// The SI base units along with their symbols, in the order they are displayed
+const SI_BASE_UNITS: [(&str, &str); 7] = [
+    ("kilogram", "kg"),
+    ("meter", "m"),
+    ("second", "s"),
+    ("ampere", "A"),
+    ("kelvin", "K"),
+    ("mole", "mol"),
+    ("candela", "cd"),
+];
+
+/// SI derived units (singular and plural names), with their exponents of
+/// each unit in `SI_BASE_UNITS`. Symbols like `C` or `T` are avoided where
+/// they have other meanings, and hertz is omitted because `1/s` is ambiguous.
+const SI_DERIVED_UNITS: [(&str, &str, [i8; 7]); 12] = [
+    ("N", "N", [1, 1, -2, 0, 0, 0, 0]),
+    ("Pa", "Pa", [1, -1, -2, 0, 0, 0, 0]),
+    ("J", "J", [1, 2, -2, 0, 0, 0, 0]),
+    ("W", "W", [1, 2, -3, 0, 0, 0, 0]),
+    ("coulomb", "coulomb", [0, 0, 1, 1, 0, 0, 0]),
+    ("V", "V", [1, 2, -3, -1, 0, 0, 0]),
+    ("farad", "farad", [-1, -2, 4, 2, 0, 0, 0]),
+    ("ohm", "ohms", [1, 2, -3, -2, 0, 0, 0]),
+    ("S", "S", [-1, -2, 3, 2, 0, 0, 0]),
+    ("Wb", "Wb", [1, 2, -2, -1, 0, 0, 0]),
+    ("tesla", "tesla", [1, 0, -2, -1, 0, 0, 0]),
+    ("H", "H", [1, 2, -2, -2, 0, 0, 0]),
+];
+
+/// CGS units with their exponents of each unit in `SI_BASE_UNITS`
+const CGS_DERIVED_UNITS: [(&str, [i8; 7]); 6] = [
+    ("dyn", [1, 1, -2, 0, 0, 0, 0]),
+    ("erg", [1, 2, -2, 0, 0, 0, 0]),
+    ("Ba", [1, -1, -2, 0, 0, 0, 0]),
+    ("poise", [1, -1, -1, 0, 0, 0, 0]),
+    ("stokes", [0, 2, -1, 0, 0, 0, 0]),
+    ("Gal", [0, 1, -2, 0, 0, 0, 0]),
+];
+
+type SystemUnits = ([Option<&'static str>; 7], Vec<(&'static str, [i8; 7])>);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum UnitSystem {
+    Si,
+    Cgs,
+}
+
+impl UnitSystem {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Si => "SI",
+            Self::Cgs => "CGS",
+        }
+    }
+
+    /// Returns the equivalents of each unit in `SI_BASE_UNITS` (or `None`
+    /// if there is none, like amperes in CGS), and the named units for
+    /// other dimensions
+    fn units(self) -> SystemUnits {
+        match self {
+            Self::Si => (
+                SI_BASE_UNITS.map(|(_, symbol)| Some(symbol)),
+                SI_DERIVED_UNITS
+                    .iter()
+                    .map(|&(singular, _, dimensions)| (singular, dimensions))
+                    .collect(),
+            ),
+            Self::Cgs => (
+                [
+                    Some("g"),
+                    Some("cm"),
+                    Some("s"),
+                    None,
+                    Some("K"),
+                    Some("mol"),
+                    Some("cd"),
+                ],
+                CGS_DERIVED_UNITS.to_vec(),
+            ),
+        }
+    }
+}
+
+/// Returns true if the base units in `hashmap` have the given exponents of
+/// each unit in `SI_BASE_UNITS`
+fn has_dimensions(hashmap: &HashMap<BaseUnit, Complex>, dimensions: [i8; 7]) -> bool {
+    hashmap.len() == dimensions.iter().filter(|&&d| d != 0).count()
+        && SI_BASE_UNITS.iter().zip(dimensions).all(|((name, _), d)| {
+            match hashmap.get(&BaseUnit::new_static(name)) {
+                Some(exponent) => *exponent == i64_to_complex(d.into()),
+                None => d == 0,
+            }
+        })
+}
+
+fn i64_to_complex(n: i64) -> Complex {
+    if n < 0 {
+        -Complex::from(n.unsigned_abs())
+    } else {
+        Complex::from(n.unsigned_abs())
+    }
+}
+
 type HashmapScale = (HashMap<BaseUnit, Complex>, Exact<Complex>);
 type HashmapScaleOffset = (HashMap<BaseUnit, Complex>, Exact<Complex>, Exact<Complex>);
 
@@ -965,7 +1829,16 @@ impl Unit {
         hash: HashMap<BaseUnit, Complex>,
         int: &I,
     ) -> Result<String, FendError> {
-        let from_base_units: Vec<_> = hash
+        let mut base_units: Vec<_> = hash.into_iter().collect();
+        // the hashmap has no order, so sort the units like SI units are
+        // conventionally written, e.g. `kilogram meter second^-2`, followed
+        // by any other base units in alphabetical order
+        base_units.sort_by_cached_key(|(base_unit, _)| {
+            let name = base_unit.name();
+            let idx = SI_BASE_UNITS.iter().position(|(n, _)| *n == name);
+            (idx.unwrap_or(SI_BASE_UNITS.len()), name.to_string())
+        });
+        let from_base_units: Vec<_> = base_units
             .into_iter()
             .map(|(base_unit, exponent)| {
                 UnitExponent::new(NamedUnit::new_from_base(base_unit), exponent)
@@ -1088,7 +1961,13 @@ impl Unit {
                 unit_string.push(' ');
             }
             let plural = last_component_plural && i == pluralised_idx;
-            let exp_format = if format == FormattingStyle::Auto {
+            let exp_format = if matches!(
+                format,
+                FormattingStyle::Auto
+                    | FormattingStyle::Scientific(_)
+                    | FormattingStyle::Engineering(_)
+                    | FormattingStyle::FixedDecimalPlaces(_)
+            ) {
                 FormattingStyle::Exact
             } else {
                 format
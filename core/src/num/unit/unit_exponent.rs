@@ -1,13 +1,13 @@
 use std::{collections::HashMap, fmt, io};
 
 use crate::{
-    error::FendError,
+    error::{FendError, Interrupt},
     interrupt::test_int,
     num::{
+        bigrat::DigitLimits,
         complex::{self, Complex, UseParentheses},
         Base, Exact, FormattingStyle,
     },
-    Interrupt,
 };
 
 use super::{base_unit::BaseUnit, named_unit::NamedUnit};
@@ -101,8 +101,14 @@ impl UnitExponent {
         let (exact, exponent) = if exp == 1.into() {
             (true, None)
         } else {
-            let formatted =
-                exp.format(true, format, base, UseParentheses::IfComplexOrFraction, int)?;
+            let formatted = exp.format(
+                true,
+                format,
+                base,
+                UseParentheses::IfComplexOrFraction,
+                DigitLimits::default(),
+                int,
+            )?;
             (formatted.exact, Some(formatted.value))
         };
         Ok(Exact::new(
@@ -0,0 +1,14 @@
+/// This determines how the units of a result are displayed, e.g. whether
+/// `1 W * 1 s` is shown as `1 W s`, `1 J` or `1 kg m^2 / s^2`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+#[must_use]
+#[non_exhaustive]
+pub enum UnitDisplayPreference {
+    /// Keep the units that were used in the calculation, e.g. `1 W s`
+    #[default]
+    AsEntered,
+    /// Show compound SI units as a single derived unit if possible, e.g. `1 J`
+    Derived,
+    /// Show SI units in terms of the SI base units, e.g. `1 kg m^2 / s^2`
+    Base,
+}
@@ -0,0 +1,59 @@
+//! Least-recently-used cache of parsed inputs, see
+//! [`crate::Context::set_parse_cache_size`]
+
+use std::collections::HashMap;
+
+use crate::ast;
+
+#[derive(Debug)]
+pub(crate) struct ParseCache {
+    capacity: usize,
+    // parsed inputs, along with the time they were last used
+    entries: HashMap<String, (ast::Expr, u64)>,
+    clock: u64,
+}
+
+impl ParseCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    pub(crate) fn get(&mut self, input: &str) -> Option<ast::Expr> {
+        self.clock += 1;
+        let (expr, last_used) = self.entries.get_mut(input)?;
+        *last_used = self.clock;
+        Some(expr.clone())
+    }
+
+    pub(crate) fn insert(&mut self, input: &str, expr: ast::Expr) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(input) {
+            // evicting is linear in the capacity, but it only happens on
+            // cache misses, which have to parse the input anyway
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(input, _)| input.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.clock += 1;
+        self.entries.insert(input.to_string(), (expr, self.clock));
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
@@ -1,6 +1,8 @@
-use crate::ast::{Bop, Expr};
+use crate::ast::{Bop, CompareBop, Expr};
+use crate::date::DayOfWeek;
+use crate::ident::Ident;
 use crate::lexer::{Symbol, Token};
-use crate::value::Value;
+use crate::value::{built_in_function::BuiltInFunction, Value};
 use std::fmt;
 
 #[derive(Debug)]
@@ -18,6 +20,7 @@ pub(crate) enum ParseError {
     ExpectedIdentifierInAssignment,
     ExpectedDotInLambda(Box<ParseError>),
     InvalidMixedFraction,
+    OneItemListInParens,
 }
 
 impl fmt::Display for ParseError {
@@ -44,6 +47,9 @@ impl fmt::Display for ParseError {
                 write!(f, "missing '.' in lambda (expected e.g. \\x.x)")
             }
             Self::InvalidMixedFraction => write!(f, "invalid mixed fraction"),
+            Self::OneItemListInParens => {
+                write!(f, "lists with one item need square brackets, e.g. [1]")
+            }
         }
     }
 }
@@ -102,13 +108,66 @@ fn parse_parens(input: &[Token]) -> ParseResult<'_> {
     if let Ok((_, remaining)) = parse_fixed_symbol(input, Symbol::CloseParens) {
         return Ok((Expr::Literal(Value::Unit), remaining));
     }
-    let (inner, mut input) = parse_expression(input)?;
+    let (inner, input) = parse_expression(input)?;
+    // a comma turns this into a list, e.g. `(3, 4)`
+    let (result, mut input) = if let Ok(((), remaining)) = parse_fixed_symbol(input, Symbol::Comma)
+    {
+        if remaining.is_empty() || parse_fixed_symbol(remaining, Symbol::CloseParens).is_ok() {
+            return Err(ParseError::OneItemListInParens);
+        }
+        let (items, remaining) = parse_list_items(remaining, inner)?;
+        (Expr::List(items), remaining)
+    } else {
+        (Expr::Parens(Box::new(inner)), input)
+    };
     // allow omitting closing parentheses at end of input
     if !input.is_empty() {
         let (_, remaining) = parse_fixed_symbol(input, Symbol::CloseParens)?;
         input = remaining;
     }
-    Ok((Expr::Parens(Box::new(inner)), input))
+    Ok((result, input))
+}
+
+/// Parses the remaining comma-separated items of a list (after `first` and
+/// its following comma). A trailing comma is allowed.
+fn parse_list_items(mut input: &[Token], first: Expr) -> ParseResult<'_, Vec<Expr>> {
+    let mut items = vec![first];
+    while !input.is_empty()
+        && !matches!(
+            input[0],
+            Token::Symbol(Symbol::CloseParens | Symbol::CloseBracket)
+        )
+    {
+        let (item, remaining) = parse_expression(input)?;
+        items.push(item);
+        input = remaining;
+        if let Ok(((), remaining)) = parse_fixed_symbol(input, Symbol::Comma) {
+            input = remaining;
+        } else {
+            break;
+        }
+    }
+    Ok((items, input))
+}
+
+fn parse_list(input: &[Token]) -> ParseResult<'_> {
+    let ((), input) = parse_fixed_symbol(input, Symbol::OpenBracket)?;
+    let (items, mut input) =
+        if input.is_empty() || matches!(input[0], Token::Symbol(Symbol::CloseBracket)) {
+            (vec![], input)
+        } else {
+            let (first, input) = parse_expression(input)?;
+            match parse_fixed_symbol(input, Symbol::Comma) {
+                Ok(((), remaining)) => parse_list_items(remaining, first)?,
+                Err(_) => (vec![first], input),
+            }
+        };
+    // allow omitting closing brackets at end of input
+    if !input.is_empty() {
+        let ((), remaining) = parse_fixed_symbol(input, Symbol::CloseBracket)?;
+        input = remaining;
+    }
+    Ok((Expr::List(items), input))
 }
 
 fn parse_backslash_lambda(input: &[Token]) -> ParseResult<'_> {
@@ -124,15 +183,100 @@ fn parse_backslash_lambda(input: &[Token]) -> ParseResult<'_> {
     Ok((Expr::Fn(ident, Box::new(rhs)), input))
 }
 
+// `√` binds to the operand directly after it, so `2√2` is `2 * sqrt(2)`
+// and `√2^2` is `(sqrt 2)^2`
+fn parse_sqrt(input: &[Token]) -> ParseResult<'_> {
+    let (operand, remaining) = if parse_fixed_symbol(input, Symbol::Sub).is_ok()
+        || parse_fixed_symbol(input, Symbol::Add).is_ok()
+    {
+        parse_power(input, true)?
+    } else {
+        parse_factorial(input)?
+    };
+    Ok((
+        Expr::ApplyFunctionCall(
+            Box::new(Expr::Ident(Ident::new_str("sqrt"))),
+            Box::new(operand),
+        ),
+        remaining,
+    ))
+}
+
+/// Parses e.g. `until @2024-12-25`. `until` is a keyword rather than a
+/// function so that `days until @2024-12-25` can't be affected by variables.
+fn parse_until(input: &[Token]) -> ParseResult<'_> {
+    let (date, remaining) = parse_power(input, true)?;
+    Ok((
+        Expr::ApplyFunctionCall(
+            Box::new(Expr::Literal(Value::BuiltInFunction(
+                BuiltInFunction::Until,
+            ))),
+            Box::new(date),
+        ),
+        remaining,
+    ))
+}
+
+/// Parses relative dates like `next friday`, `last month` or `first day of
+/// next month`. These are only recognised as whole phrases, so `next` etc.
+/// can still be used as variable names.
+fn parse_relative_date(input: &[Token]) -> ParseResult<'_> {
+    let (Token::Ident(word), remaining) = parse_token(input)? else {
+        return Err(ParseError::ExpectedIdentifier);
+    };
+    let apply = |func, arg| {
+        Expr::ApplyFunctionCall(
+            Box::new(Expr::Literal(Value::BuiltInFunction(func))),
+            Box::new(arg),
+        )
+    };
+    if let (Token::Ident(day), remaining) = parse_token(remaining)? {
+        if day.as_str() == "day" {
+            if let Ok(((), remaining)) = parse_fixed_symbol(remaining, Symbol::Of) {
+                let func = match word.as_str() {
+                    "first" => BuiltInFunction::FirstDayOf,
+                    "last" => BuiltInFunction::LastDayOf,
+                    _ => return Err(ParseError::ExpectedIdentifier),
+                };
+                let (date, remaining) = parse_parens_or_literal(remaining)?;
+                return Ok((apply(func, date), remaining));
+            }
+        }
+    }
+    let func = match word.as_str() {
+        "next" => BuiltInFunction::Next,
+        "last" => BuiltInFunction::Last,
+        "this" => BuiltInFunction::This,
+        _ => return Err(ParseError::ExpectedIdentifier),
+    };
+    let (Token::Ident(period), remaining) = parse_token(remaining)? else {
+        return Err(ParseError::ExpectedIdentifier);
+    };
+    let arg = if let Some(day) = DayOfWeek::from_name(period.as_str()) {
+        Expr::Literal(Value::DayOfWeek(day))
+    } else if matches!(period.as_str(), "day" | "week" | "month" | "year") {
+        Expr::Ident(period)
+    } else {
+        return Err(ParseError::ExpectedIdentifier);
+    };
+    Ok((apply(func, arg), remaining))
+}
+
 fn parse_parens_or_literal(input: &[Token]) -> ParseResult<'_> {
     let (token, remaining) = parse_token(input)?;
 
     match token {
         Token::Num(_) => parse_number(input),
-        Token::Ident(_) => parse_ident(input),
+        Token::Ident(_) => parse_relative_date(input).or_else(|_| parse_ident(input)),
         Token::StringLiteral(s) => Ok((Expr::Literal(Value::String(s)), remaining)),
+        Token::Date(d) => Ok((Expr::Literal(Value::Date(d)), remaining)),
+        Token::Ip(ip) => Ok((Expr::Literal(Value::Ip(ip)), remaining)),
+        Token::Ratio(r) => Ok((Expr::Literal(Value::Ratio(Box::new(r))), remaining)),
         Token::Symbol(Symbol::OpenParens) => parse_parens(input),
+        Token::Symbol(Symbol::OpenBracket) => parse_list(input),
         Token::Symbol(Symbol::Backslash) => parse_backslash_lambda(input),
+        Token::Symbol(Symbol::Sqrt) => parse_sqrt(remaining),
+        Token::Symbol(Symbol::Until) => parse_until(remaining),
         Token::Symbol(s) => Err(ParseError::UnexpectedSymbol(s)),
     }
 }
@@ -162,6 +306,12 @@ fn parse_power(input: &[Token], allow_unary: bool) -> ParseResult<'_> {
             let (result, remaining) = parse_power(remaining, true)?;
             return Ok((Expr::UnaryDiv(Box::new(result)), remaining));
         }
+        // a leading `//` is two unary divisions, not floor division
+        if let Ok(((), remaining)) = parse_fixed_symbol(input, Symbol::FloorDiv) {
+            let (result, remaining) = parse_power(remaining, true)?;
+            let result = Expr::UnaryDiv(Box::new(result));
+            return Ok((Expr::UnaryDiv(Box::new(result)), remaining));
+        }
     }
     let (mut result, mut input) = parse_factorial(input)?;
     if let Ok((_, remaining)) = parse_fixed_symbol(input, Symbol::Pow) {
@@ -193,6 +343,15 @@ fn parse_apply_cont<'a>(input: &'a [Token], lhs: &Expr) -> ParseResult<'a> {
                 }
                 Expr::Apply(Box::new(lhs.clone()), Box::new(rhs))
             }
+            // e.g. `days until @2024-12-25` is `(until @2024-12-25) to days`
+            (_, Expr::ApplyFunctionCall(f, _))
+                if matches!(
+                    **f,
+                    Expr::Literal(Value::BuiltInFunction(BuiltInFunction::Until))
+                ) =>
+            {
+                Expr::As(Box::new(rhs), Box::new(lhs.clone()))
+            }
             // support e.g. '$5' or '£3'
             (Expr::Ident(i), Expr::Literal(Value::Num(_))) if i.is_prefix_unit() => {
                 Expr::Apply(Box::new(lhs.clone()), Box::new(rhs))
@@ -271,6 +430,12 @@ fn parse_division_cont(input: &[Token]) -> ParseResult<'_> {
     Ok((b, input))
 }
 
+fn parse_floor_division_cont(input: &[Token]) -> ParseResult<'_> {
+    let ((), input) = parse_fixed_symbol(input, Symbol::FloorDiv)?;
+    let (b, input) = parse_power(input, true)?;
+    Ok((b, input))
+}
+
 fn parse_modulo_cont(input: &[Token]) -> ParseResult<'_> {
     let (_, input) = parse_fixed_symbol(input, Symbol::Mod)?;
     let (b, input) = parse_power(input, true)?;
@@ -286,6 +451,9 @@ fn parse_multiplicative(input: &[Token]) -> ParseResult<'_> {
         } else if let Ok((term, remaining)) = parse_division_cont(input) {
             res = Expr::Bop(Bop::Div, Box::new(res.clone()), Box::new(term));
             input = remaining;
+        } else if let Ok((term, remaining)) = parse_floor_division_cont(input) {
+            res = Expr::Bop(Bop::FloorDiv, Box::new(res.clone()), Box::new(term));
+            input = remaining;
         } else if let Ok((term, remaining)) = parse_modulo_cont(input) {
             res = Expr::Bop(Bop::Mod, Box::new(res.clone()), Box::new(term));
             input = remaining;
@@ -338,6 +506,22 @@ fn parse_to_cont(input: &[Token]) -> ParseResult<'_> {
     Ok((b, input))
 }
 
+// e.g. `3.14159 m to mm sigfigs 3`, which is short for `to 3 sf`
+fn parse_sigfigs_cont(input: &[Token]) -> ParseResult<'_> {
+    let ((), input) = parse_fixed_symbol(input, Symbol::SigFigs)?;
+    let (digits, input) = parse_implicit_addition(input)?;
+    Ok((
+        Expr::Apply(Box::new(digits), Box::new(Expr::Literal(Value::Sf))),
+        input,
+    ))
+}
+
+fn parse_plus_minus_cont(input: &[Token]) -> ParseResult<'_> {
+    let ((), input) = parse_fixed_symbol(input, Symbol::PlusMinus)?;
+    let (b, input) = parse_implicit_addition(input)?;
+    Ok((b, input))
+}
+
 fn parse_additive(input: &[Token]) -> ParseResult<'_> {
     let (mut res, mut input) = parse_implicit_addition(input)?;
     loop {
@@ -347,9 +531,15 @@ fn parse_additive(input: &[Token]) -> ParseResult<'_> {
         } else if let Ok((term, remaining)) = parse_subtraction_cont(input) {
             res = Expr::Bop(Bop::Minus, Box::new(res), Box::new(term));
             input = remaining;
+        } else if let Ok((term, remaining)) = parse_plus_minus_cont(input) {
+            res = Expr::Bop(Bop::PlusMinus, Box::new(res), Box::new(term));
+            input = remaining;
         } else if let Ok((term, remaining)) = parse_to_cont(input) {
             res = Expr::As(Box::new(res), Box::new(term));
             input = remaining;
+        } else if let Ok((format, remaining)) = parse_sigfigs_cont(input) {
+            res = Expr::As(Box::new(res), Box::new(format));
+            input = remaining;
         } else {
             break;
         }
@@ -383,50 +573,82 @@ fn parse_bitshifts(input: &[Token]) -> ParseResult<'_> {
     Ok((result, input))
 }
 
-fn parse_bitwise_and(input: &[Token]) -> ParseResult<'_> {
-    let (mut result, mut input) = parse_bitshifts(input)?;
-    while let Ok((_, remaining)) = parse_fixed_symbol(input, Symbol::BitwiseAnd) {
-        let (rhs, remaining) = parse_bitshifts(remaining)?;
-        result = Expr::Bop(
-            Bop::Bitwise(crate::ast::BitwiseBop::And),
-            Box::new(result),
-            Box::new(rhs),
-        );
-        input = remaining;
+/// Parses a left-associative chain of bitwise operators from `ops`, with
+/// operands parsed by `parse_operand`
+fn parse_bitwise_ops<'a>(
+    input: &'a [Token],
+    parse_operand: fn(&[Token]) -> ParseResult<'_>,
+    ops: &[(Symbol, crate::ast::BitwiseBop)],
+) -> ParseResult<'a> {
+    let (mut result, mut input) = parse_operand(input)?;
+    'outer: loop {
+        for &(symbol, op) in ops {
+            if let Ok((_, remaining)) = parse_fixed_symbol(input, symbol) {
+                let (rhs, remaining) = parse_operand(remaining)?;
+                result = Expr::Bop(Bop::Bitwise(op), Box::new(result), Box::new(rhs));
+                input = remaining;
+                continue 'outer;
+            }
+        }
+        break;
     }
     Ok((result, input))
 }
 
+fn parse_bitwise_and(input: &[Token]) -> ParseResult<'_> {
+    use crate::ast::BitwiseBop;
+    parse_bitwise_ops(
+        input,
+        parse_bitshifts,
+        &[
+            (Symbol::BitwiseAnd, BitwiseBop::And),
+            (Symbol::BitwiseNand, BitwiseBop::Nand),
+        ],
+    )
+}
+
 fn parse_bitwise_xor(input: &[Token]) -> ParseResult<'_> {
-    let (mut result, mut input) = parse_bitwise_and(input)?;
-    while let Ok((_, remaining)) = parse_fixed_symbol(input, Symbol::BitwiseXor) {
-        let (rhs, remaining) = parse_bitwise_and(remaining)?;
-        result = Expr::Bop(
-            Bop::Bitwise(crate::ast::BitwiseBop::Xor),
-            Box::new(result),
-            Box::new(rhs),
-        );
-        input = remaining;
-    }
-    Ok((result, input))
+    use crate::ast::BitwiseBop;
+    parse_bitwise_ops(
+        input,
+        parse_bitwise_and,
+        &[
+            (Symbol::BitwiseXor, BitwiseBop::Xor),
+            (Symbol::BitwiseXnor, BitwiseBop::Xnor),
+        ],
+    )
 }
 
 fn parse_bitwise_or(input: &[Token]) -> ParseResult<'_> {
-    let (mut result, mut input) = parse_bitwise_xor(input)?;
-    while let Ok((_, remaining)) = parse_fixed_symbol(input, Symbol::BitwiseOr) {
-        let (rhs, remaining) = parse_bitwise_xor(remaining)?;
-        result = Expr::Bop(
-            Bop::Bitwise(crate::ast::BitwiseBop::Or),
-            Box::new(result),
-            Box::new(rhs),
-        );
-        input = remaining;
-    }
-    Ok((result, input))
+    use crate::ast::BitwiseBop;
+    parse_bitwise_ops(
+        input,
+        parse_bitwise_xor,
+        &[
+            (Symbol::BitwiseOr, BitwiseBop::Or),
+            (Symbol::BitwiseNor, BitwiseBop::Nor),
+        ],
+    )
 }
 
-fn parse_function(input: &[Token]) -> ParseResult<'_> {
+fn parse_comparison(input: &[Token]) -> ParseResult<'_> {
     let (lhs, input) = parse_bitwise_or(input)?;
+    let op = match input.first() {
+        Some(Token::Symbol(Symbol::DoubleEquals)) => CompareBop::Equal,
+        Some(Token::Symbol(Symbol::NotEquals)) => CompareBop::NotEqual,
+        Some(Token::Symbol(Symbol::Less)) => CompareBop::Less,
+        Some(Token::Symbol(Symbol::LessOrEquals)) => CompareBop::LessOrEqual,
+        Some(Token::Symbol(Symbol::Greater)) => CompareBop::Greater,
+        Some(Token::Symbol(Symbol::GreaterOrEquals)) => CompareBop::GreaterOrEqual,
+        Some(Token::Symbol(Symbol::ApproxEquals)) => CompareBop::ApproxEqual,
+        _ => return Ok((lhs, input)),
+    };
+    let (rhs, remaining) = parse_bitwise_or(&input[1..])?;
+    Ok((Expr::Compare(op, Box::new(lhs), Box::new(rhs)), remaining))
+}
+
+fn parse_function(input: &[Token]) -> ParseResult<'_> {
+    let (lhs, input) = parse_comparison(input)?;
     if let Ok((_, remaining)) = parse_fixed_symbol(input, Symbol::Fn) {
         if let Expr::Ident(s) = lhs {
             let (rhs, remaining) = parse_function(remaining)?;
@@ -437,16 +659,55 @@ fn parse_function(input: &[Token]) -> ParseResult<'_> {
     Ok((lhs, input))
 }
 
+/// Returns the list and index of an indexing expression like `xs[2]`
+fn as_list_index(expr: &Expr) -> Option<(&Ident, &Expr)> {
+    let Expr::Apply(list, index) = expr else {
+        return None;
+    };
+    match (&**list, &**index) {
+        (Expr::Ident(list), Expr::List(index)) if index.len() == 1 => Some((list, &index[0])),
+        _ => None,
+    }
+}
+
 fn parse_assignment(input: &[Token]) -> ParseResult<'_> {
     let (lhs, input) = parse_function(input)?;
     if let Ok((_, remaining)) = parse_fixed_symbol(input, Symbol::Equals) {
+        if let Some((list, index)) = as_list_index(&lhs) {
+            let (rhs, remaining) = parse_assignment(remaining)?;
+            return Ok((
+                Expr::IndexAssign(list.clone(), Box::new(index.clone()), Box::new(rhs)),
+                remaining,
+            ));
+        }
         if let Expr::Ident(s) = lhs {
             let (rhs, remaining) = parse_assignment(remaining)?;
             return Ok((Expr::Assign(s, Box::new(rhs)), remaining));
         }
         return Err(ParseError::ExpectedIdentifierInAssignment);
     }
-    Ok((lhs, input))
+    // compound assignments like `x += 5`
+    let op = match input.first() {
+        Some(Token::Symbol(Symbol::AddAssign)) => Bop::Plus,
+        Some(Token::Symbol(Symbol::SubAssign)) => Bop::Minus,
+        Some(Token::Symbol(Symbol::MulAssign)) => Bop::Mul,
+        Some(Token::Symbol(Symbol::DivAssign)) => Bop::Div,
+        _ => return Ok((lhs, input)),
+    };
+    if let Some((list, index)) = as_list_index(&lhs) {
+        // e.g. `xs[2] += 1` is `xs[2] = xs[2] + 1`
+        let (rhs, remaining) = parse_assignment(&input[1..])?;
+        let rhs = Expr::Bop(op, Box::new(lhs.clone()), Box::new(rhs));
+        return Ok((
+            Expr::IndexAssign(list.clone(), Box::new(index.clone()), Box::new(rhs)),
+            remaining,
+        ));
+    }
+    if let Expr::Ident(s) = lhs {
+        let (rhs, remaining) = parse_assignment(&input[1..])?;
+        return Ok((Expr::CompoundAssign(s, op, Box::new(rhs)), remaining));
+    }
+    Err(ParseError::ExpectedIdentifierInAssignment)
 }
 
 fn parse_statements(mut input: &[Token]) -> ParseResult<'_> {
@@ -473,6 +734,40 @@ pub(crate) fn parse_expression(input: &[Token]) -> ParseResult<'_> {
     parse_statements(input)
 }
 
+// The parser and `ast::evaluate` both recurse once per nesting level, so
+// deeply nested input like `((((...))))` or `----...1` needs to be rejected
+// before it can overflow the stack (which may only be 1 MiB, e.g. in WASM).
+const MAX_NESTING_DEPTH: usize = 128;
+
+/// Returns true if parsing or evaluating these tokens might need more than
+/// [`MAX_NESTING_DEPTH`] levels of recursion. This is a conservative
+/// estimate: every token may add a level, except that a closed bracket
+/// counts as a single token, and commas and semicolons (which are parsed
+/// iteratively) reset the depth to that of the enclosing bracket.
+pub(crate) fn exceeds_max_nesting_depth(input: &[Token]) -> bool {
+    let mut enclosing_depths = vec![];
+    let mut depth = 0;
+    for token in input {
+        match token {
+            Token::Symbol(Symbol::OpenParens | Symbol::OpenBracket) => {
+                enclosing_depths.push(depth);
+                depth += 1;
+            }
+            Token::Symbol(Symbol::CloseParens | Symbol::CloseBracket) => {
+                depth = enclosing_depths.pop().unwrap_or(0) + 1;
+            }
+            Token::Symbol(Symbol::Comma | Symbol::Semicolon) => {
+                depth = enclosing_depths.last().map_or(0, |d| d + 1);
+            }
+            _ => depth += 1,
+        }
+        if depth > MAX_NESTING_DEPTH {
+            return true;
+        }
+    }
+    false
+}
+
 pub(crate) fn parse_tokens(input: &[Token]) -> Result<Expr, ParseError> {
     let (res, remaining) = parse_expression(input)?;
     if !remaining.is_empty() {
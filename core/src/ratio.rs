@@ -0,0 +1,69 @@
+//! Ratios like `16:9`, which can be simplified with `to ratio`
+
+use std::cmp::Ordering;
+use std::io;
+
+use crate::error::{FendError, Interrupt};
+use crate::num::Number;
+
+#[derive(Clone, Debug)]
+pub(crate) struct Ratio {
+    antecedent: Number,
+    consequent: Number,
+}
+
+impl Ratio {
+    pub(crate) fn new(antecedent: Number, consequent: Number) -> Result<Self, FendError> {
+        if antecedent.is_zero() || consequent.is_zero() {
+            return Err(FendError::ZeroInRatio);
+        }
+        Ok(Self {
+            antecedent,
+            consequent,
+        })
+    }
+
+    /// The number that the ratio represents, e.g. 1.777... for `16:9`
+    pub(crate) fn quotient<I: Interrupt>(self, int: &I) -> Result<Number, FendError> {
+        self.antecedent.div(self.consequent, int)
+    }
+
+    /// Reduces the ratio to its lowest terms, e.g. `1920:1080` to `16:9`.
+    /// Non-integer ratios become integer ratios, e.g. `2.39:1` is `239:100`.
+    pub(crate) fn simplify<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        Self::from_number(self.quotient(int)?, int)
+    }
+
+    /// Writes a number as a ratio in lowest terms, e.g. `1.5` as `3:2`
+    pub(crate) fn from_number<I: Interrupt>(n: Number, int: &I) -> Result<Self, FendError> {
+        Self::new(
+            n.clone().fraction_part(true, int)?,
+            n.fraction_part(false, int)?,
+        )
+    }
+
+    pub(crate) fn compare<I: Interrupt>(
+        &self,
+        other: &Self,
+        int: &I,
+    ) -> Result<Ordering, FendError> {
+        let rhs = other.clone().quotient(int)?;
+        self.clone().quotient(int)?.compare(&rhs, int)
+    }
+
+    /// Returns the two parts, e.g. 16 and 9 for `16:9`
+    pub(crate) fn parts(&self) -> [&Number; 2] {
+        [&self.antecedent, &self.consequent]
+    }
+
+    pub(crate) fn serialize(&self, write: &mut impl io::Write) -> Result<(), FendError> {
+        self.antecedent.serialize(write)?;
+        self.consequent.serialize(write)?;
+        Ok(())
+    }
+
+    pub(crate) fn deserialize(read: &mut impl io::Read) -> Result<Self, FendError> {
+        Self::new(Number::deserialize(read)?, Number::deserialize(read)?)
+            .map_err(|_| FendError::DeserializationError)
+    }
+}
@@ -0,0 +1,64 @@
+//! "Did you mean" suggestions for unknown identifiers
+
+/// Built-in constants that are neither functions nor units
+const CONSTANTS: &[&str] = &["pi", "tau", "phi", "true", "false"];
+
+/// Optimal string alignment distance, i.e. the Levenshtein distance where
+/// swapping two adjacent characters also counts as a single edit
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // rows[i][j] is the distance between the first i chars of a and the
+    // first j chars of b
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+/// Returns the known variable, function, constant or unit name closest to
+/// `ident`, if there is one within a couple of edits. Very short identifiers
+/// get no suggestion since almost anything is close to them.
+pub(crate) fn did_you_mean(ident: &str, context: &crate::Context) -> Option<String> {
+    let len = ident.chars().count();
+    let max_distance = match len {
+        0..=3 => return None,
+        4..=6 => 1,
+        _ => 2,
+    };
+    let mut best: Option<(usize, String)> = None;
+    let mut consider = |candidate: &str| {
+        if candidate == ident {
+            return;
+        }
+        let distance = edit_distance(ident, candidate);
+        if distance <= max_distance && !matches!(&best, Some((d, _)) if *d <= distance) {
+            best = Some((distance, candidate.to_string()));
+        }
+    };
+    // sorted so that ties between variables are broken consistently
+    let mut variables: Vec<&str> = context.variables.keys().map(String::as_str).collect();
+    variables.sort_unstable();
+    variables.into_iter().for_each(&mut consider);
+    crate::describe::function_names()
+        .chain(CONSTANTS.iter().copied())
+        .chain(crate::units::unit_names())
+        .for_each(&mut consider);
+    best.map(|(_, name)| name)
+}
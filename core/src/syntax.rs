@@ -0,0 +1,267 @@
+//! A simplified, public representation of fend's syntax tree, as returned by
+//! [`parse`](crate::parse). Identifiers are not resolved, so there is no
+//! distinction between units, variables and functions.
+
+use std::fmt::Write;
+
+use crate::ast::{Bop, Expr};
+use crate::value::Value;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AstNode {
+    /// A number literal as written in the input, e.g. `1.5e10` or `0x10`
+    Number(String),
+    /// A string literal (without quotes)
+    String(String),
+    /// A date literal (without the `@`), e.g. `Monday, 1 January 2024`
+    Date(String),
+    /// An IPv4 address or CIDR network as written in the input, e.g.
+    /// `192.168.1.0/24`
+    Ip(String),
+    /// A ratio literal as written in the input, e.g. `16:9`
+    Ratio(String),
+    /// The unit value `()`
+    Unit,
+    /// A unit, variable or function name
+    Ident(String),
+    Parens(Box<Self>),
+    UnaryMinus(Box<Self>),
+    UnaryPlus(Box<Self>),
+    UnaryDiv(Box<Self>),
+    Factorial(Box<Self>),
+    /// Arithmetic, bitwise and comparison operators, e.g. `+`, `mod` or `<=`
+    BinaryOp {
+        op: String,
+        lhs: Box<Self>,
+        rhs: Box<Self>,
+    },
+    /// Juxtaposition (e.g. `sin x` or `kg m`), which is either a function
+    /// call or a multiplication depending on what `function` evaluates to
+    Apply {
+        function: Box<Self>,
+        argument: Box<Self>,
+    },
+    /// A function applied to a number, e.g. `sin 2`
+    Call {
+        function: Box<Self>,
+        argument: Box<Self>,
+    },
+    /// An implicit multiplication, e.g. `2 pi` or `5 kg`
+    Multiply {
+        lhs: Box<Self>,
+        rhs: Box<Self>,
+    },
+    /// A conversion, e.g. `5 feet to m`
+    Conversion {
+        value: Box<Self>,
+        target: Box<Self>,
+    },
+    Lambda {
+        param: String,
+        body: Box<Self>,
+    },
+    /// Member access, e.g. `dayofweek of x`
+    Of {
+        member: String,
+        value: Box<Self>,
+    },
+    Assign {
+        name: String,
+        value: Box<Self>,
+    },
+    /// An assignment to a list item, e.g. `xs[2] = 9`
+    IndexAssign {
+        name: String,
+        index: Box<Self>,
+        value: Box<Self>,
+    },
+    Statements(Box<Self>, Box<Self>),
+    /// A list, e.g. `[1, 2, 3]` or `(3, 4)`
+    List(Vec<Self>),
+}
+
+impl AstNode {
+    /// Converts a parsed expression, taking the source text of number, IP
+    /// address and ratio literals from `literal_lexemes` (in the order they
+    /// appear in the input)
+    pub(crate) fn from_expr(
+        expr: Expr,
+        literal_lexemes: &mut impl Iterator<Item = String>,
+    ) -> Self {
+        macro_rules! convert {
+            ($e:expr) => {
+                Box::new(Self::from_expr(*$e, literal_lexemes))
+            };
+        }
+        match expr {
+            Expr::Literal(Value::String(s)) => Self::String(s.to_string()),
+            Expr::Literal(Value::Date(d)) => Self::Date(d.to_string()),
+            Expr::Literal(Value::Unit) => Self::Unit,
+            Expr::Literal(Value::Ip(_)) => Self::Ip(literal_lexemes.next().unwrap_or_default()),
+            Expr::Literal(Value::Ratio(_)) => {
+                Self::Ratio(literal_lexemes.next().unwrap_or_default())
+            }
+            // the parser only creates other literals from number tokens,
+            // whose source text is recorded while lexing
+            Expr::Literal(_) => Self::Number(literal_lexemes.next().unwrap_or_default()),
+            Expr::Ident(ident) => Self::Ident(ident.to_string()),
+            Expr::Parens(x) => Self::Parens(convert!(x)),
+            Expr::UnaryMinus(x) => Self::UnaryMinus(convert!(x)),
+            Expr::UnaryPlus(x) => Self::UnaryPlus(convert!(x)),
+            Expr::UnaryDiv(x) => Self::UnaryDiv(convert!(x)),
+            Expr::Factorial(x) => Self::Factorial(convert!(x)),
+            Expr::Bop(op, a, b) => Self::BinaryOp {
+                op: match op {
+                    Bop::ImplicitPlus => "+".to_string(),
+                    op => op.to_string().trim().to_string(),
+                },
+                lhs: convert!(a),
+                rhs: convert!(b),
+            },
+            Expr::Compare(op, a, b) => Self::BinaryOp {
+                op: op.to_string(),
+                lhs: convert!(a),
+                rhs: convert!(b),
+            },
+            Expr::Apply(a, b) => Self::Apply {
+                function: convert!(a),
+                argument: convert!(b),
+            },
+            Expr::ApplyFunctionCall(a, b) => Self::Call {
+                function: convert!(a),
+                argument: convert!(b),
+            },
+            Expr::ApplyMul(a, b) => Self::Multiply {
+                lhs: convert!(a),
+                rhs: convert!(b),
+            },
+            Expr::As(a, b) => Self::Conversion {
+                value: convert!(a),
+                target: convert!(b),
+            },
+            Expr::Fn(param, body) => Self::Lambda {
+                param: param.to_string(),
+                body: convert!(body),
+            },
+            Expr::Of(member, x) => Self::Of {
+                member: member.to_string(),
+                value: convert!(x),
+            },
+            Expr::Assign(name, x) => Self::Assign {
+                name: name.to_string(),
+                value: convert!(x),
+            },
+            // shown as the equivalent assignment, e.g. `x = x + 5` for `x += 5`
+            Expr::CompoundAssign(name, op, x) => Self::Assign {
+                name: name.to_string(),
+                value: Box::new(Self::BinaryOp {
+                    op: op.to_string(),
+                    lhs: Box::new(Self::Ident(name.to_string())),
+                    rhs: convert!(x),
+                }),
+            },
+            Expr::IndexAssign(name, index, x) => Self::IndexAssign {
+                name: name.to_string(),
+                index: convert!(index),
+                value: convert!(x),
+            },
+            Expr::Statements(a, b) => Self::Statements(convert!(a), convert!(b)),
+            Expr::List(items) => Self::List(
+                items
+                    .into_iter()
+                    .map(|item| Self::from_expr(item, literal_lexemes))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Returns a JSON representation of this node. Every node is an object
+    /// with a `"type"` field, e.g. `{"type":"ident","name":"x"}`.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(&mut out);
+        out
+    }
+
+    fn write_json(&self, out: &mut String) {
+        // node type, an optional string field, and any child nodes
+        type Fields<'a> = (
+            &'static str,
+            Option<(&'static str, &'a str)>,
+            Vec<(&'static str, &'a AstNode)>,
+        );
+
+        let (ty, string, nodes): Fields<'_> = match self {
+            Self::Number(n) => ("number", Some(("value", n)), vec![]),
+            Self::String(s) => ("string", Some(("value", s)), vec![]),
+            Self::Date(d) => ("date", Some(("value", d)), vec![]),
+            Self::Ip(ip) => ("ip", Some(("value", ip)), vec![]),
+            Self::Ratio(r) => ("ratio", Some(("value", r)), vec![]),
+            Self::Unit => ("unit", None, vec![]),
+            Self::Ident(name) => ("ident", Some(("name", name)), vec![]),
+            Self::Parens(x) => ("parens", None, vec![("value", x)]),
+            Self::UnaryMinus(x) => ("unary_minus", None, vec![("value", x)]),
+            Self::UnaryPlus(x) => ("unary_plus", None, vec![("value", x)]),
+            Self::UnaryDiv(x) => ("unary_div", None, vec![("value", x)]),
+            Self::Factorial(x) => ("factorial", None, vec![("value", x)]),
+            Self::BinaryOp { op, lhs, rhs } => (
+                "binary_op",
+                Some(("op", op)),
+                vec![("lhs", lhs), ("rhs", rhs)],
+            ),
+            Self::Apply { function, argument } => (
+                "apply",
+                None,
+                vec![("function", function), ("argument", argument)],
+            ),
+            Self::Call { function, argument } => (
+                "call",
+                None,
+                vec![("function", function), ("argument", argument)],
+            ),
+            Self::Multiply { lhs, rhs } => ("multiply", None, vec![("lhs", lhs), ("rhs", rhs)]),
+            Self::Conversion { value, target } => (
+                "conversion",
+                None,
+                vec![("value", value), ("target", target)],
+            ),
+            Self::Lambda { param, body } => {
+                ("lambda", Some(("param", param)), vec![("body", body)])
+            }
+            Self::Of { member, value } => ("of", Some(("member", member)), vec![("value", value)]),
+            Self::Assign { name, value } => {
+                ("assign", Some(("name", name)), vec![("value", value)])
+            }
+            Self::IndexAssign { name, index, value } => (
+                "index_assign",
+                Some(("name", name)),
+                vec![("index", index), ("value", value)],
+            ),
+            Self::Statements(a, b) => ("statements", None, vec![("first", a), ("second", b)]),
+            Self::List(items) => {
+                out.push_str(r#"{"type":"list","items":["#);
+                for (i, item) in items.iter().enumerate() {
+                    if i != 0 {
+                        out.push(',');
+                    }
+                    item.write_json(out);
+                }
+                out.push_str("]}");
+                return;
+            }
+        };
+        write!(out, r#"{{"type":"{ty}""#).unwrap();
+        if let Some((name, value)) = string {
+            write!(out, r#","{name}":""#).unwrap();
+            crate::json::escape_string(value, out);
+            out.push('"');
+        }
+        for (name, value) in nodes {
+            write!(out, r#","{name}":"#).unwrap();
+            value.write_json(out);
+        }
+        out.push('}');
+    }
+}
@@ -0,0 +1,90 @@
+//! Truth tables for boolean expressions, see `truthtable`
+
+use std::sync::Arc;
+
+use crate::ast::{self, Expr};
+use crate::error::{FendError, Interrupt};
+use crate::ident::Ident;
+use crate::interrupt::test_int;
+use crate::scope::Scope;
+use crate::value::Value;
+
+/// Tables grow exponentially, so this keeps them to at most 4096 rows
+const MAX_VARIABLES: usize = 12;
+
+/// Splits the argument of `truthtable(expr, [a, b])` into the expression
+/// and its variables, without evaluating either
+fn parse_args(arg: Expr) -> Result<(Expr, Vec<Ident>), FendError> {
+    let Expr::List(mut args) = arg.without_parens() else {
+        return Err(FendError::InvalidTruthTableArgs);
+    };
+    if args.len() != 2 {
+        return Err(FendError::InvalidTruthTableArgs);
+    }
+    let vars = match args.pop() {
+        Some(Expr::List(vars)) => vars,
+        // a single variable doesn't need to be wrapped in a list
+        Some(Expr::Ident(var)) => vec![Expr::Ident(var)],
+        _ => return Err(FendError::InvalidTruthTableArgs),
+    };
+    let vars = vars
+        .into_iter()
+        .map(|var| match var {
+            Expr::Ident(var) => Ok(var),
+            _ => Err(FendError::InvalidTruthTableArgs),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if vars.is_empty() {
+        return Err(FendError::InvalidTruthTableArgs);
+    }
+    if vars.len() > MAX_VARIABLES {
+        return Err(FendError::TooManyTruthTableVariables(MAX_VARIABLES));
+    }
+    let expr = args.pop().ok_or(FendError::InvalidTruthTableArgs)?;
+    Ok((expr, vars))
+}
+
+/// Evaluates `expr` for every combination of its variables, returning one
+/// line per row, e.g. `false | true  | false`, as a string
+pub(crate) fn truth_table<I: Interrupt>(
+    arg: Expr,
+    scope: Option<&Arc<Scope>>,
+    context: &mut crate::Context,
+    int: &I,
+) -> Result<Value, FendError> {
+    let (expr, vars) = parse_args(arg)?;
+    let widths = vars
+        .iter()
+        .map(|var| var.as_str().len().max("false".len()))
+        .collect::<Vec<_>>();
+    let mut header = vars
+        .iter()
+        .zip(&widths)
+        .map(|(var, &width)| format!("{:width$}", var.as_str()))
+        .collect::<Vec<_>>();
+    header.push("result".to_string());
+    let mut lines = vec![header.join(" | ")];
+    for row in 0..1_usize << vars.len() {
+        test_int(int)?;
+        let mut row_scope = scope.cloned();
+        let mut cells = vec![];
+        for (i, (var, &width)) in vars.iter().zip(&widths).enumerate() {
+            // the first variable changes slowest, and each starts out false
+            let value = (row >> (vars.len() - 1 - i)) & 1 == 1;
+            row_scope = Some(Arc::new(Scope::with_variable(
+                var.clone(),
+                Expr::Literal(Value::Bool(value)),
+                scope.cloned(),
+                row_scope,
+            )));
+            cells.push(format!("{value:width$}"));
+        }
+        let result = match ast::evaluate(expr.clone(), row_scope, context, int)? {
+            Value::Bool(b) => b,
+            value => return Err(FendError::ExpectedABool(value.type_name())),
+        };
+        cells.push(result.to_string());
+        lines.push(cells.join(" | "));
+    }
+    Ok(Value::String(lines.join("\n").into()))
+}
@@ -1,4 +1,6 @@
 use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
 
 use crate::error::{FendError, Interrupt};
 use crate::eval::evaluate_to_value;
@@ -6,8 +8,10 @@ use crate::num::Number;
 use crate::value::Value;
 
 mod builtin;
+mod unknown_unit_behavior;
 
-pub(crate) use builtin::IMPLICIT_UNIT_MAP;
+pub(crate) use builtin::{DISCRETE_UNITS, IMPLICIT_UNIT_MAP};
+pub use unknown_unit_behavior::UnknownUnitBehavior;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub(crate) enum PrefixRule {
@@ -113,13 +117,67 @@ pub(crate) fn query_unit<'a, I: Interrupt>(
             ident.to_string().into(),
         ))));
     }
-    query_unit_static(ident, context, int)
+    // unit definitions may use `l`, but only warn about user input
+    if ident == "l" && context.unit_dependencies.is_none() {
+        context.add_warning("`l` is interpreted as litres, not the number 1");
+    }
+    match query_unit_static(ident, context, int) {
+        // unit definitions never refer to symbolic units
+        Err(FendError::IdentifierNotFound(_))
+            if context.unknown_unit_behavior == UnknownUnitBehavior::Symbolic
+                && context.unit_dependencies.is_none() =>
+        {
+            Ok(Value::Num(Box::new(Number::new_base_unit(
+                ident.to_string().into(),
+                ident.to_string().into(),
+            ))))
+        }
+        result => result,
+    }
 }
 
+/// Looks up a unit, reusing the result of earlier lookups where possible.
+/// Units that depend on exchange rates are never cached, since rates
+/// may change between calls. Cached units remember which identifiers their
+/// definitions refer to, so that assigning a variable with one of those
+/// names only invalidates the units that depend on it.
 pub(crate) fn query_unit_static<'a, I: Interrupt>(
     ident: &'a str,
     context: &mut crate::Context,
     int: &I,
+) -> Result<Value, FendError> {
+    if let Some((value, dependencies)) = context.unit_cache.get(ident) {
+        let value = value.clone();
+        if let Some(outer_dependencies) = &mut context.unit_dependencies {
+            outer_dependencies.extend(dependencies.iter().cloned());
+        }
+        context.use_feature(crate::Feature::Units)?;
+        return Ok(value);
+    }
+    let outer_exchange_rate_used = std::mem::take(&mut context.exchange_rate_used);
+    let outer_dependencies = context.unit_dependencies.replace(vec![]);
+    let result = query_unit_static_uncached(ident, context, int);
+    let exchange_rate_used = context.exchange_rate_used;
+    context.exchange_rate_used = outer_exchange_rate_used || exchange_rate_used;
+    let dependencies =
+        std::mem::replace(&mut context.unit_dependencies, outer_dependencies).unwrap_or_default();
+    if let Some(outer_dependencies) = &mut context.unit_dependencies {
+        outer_dependencies.extend(dependencies.iter().cloned());
+    }
+    if let Ok(value) = &result {
+        context.use_feature(crate::Feature::Units)?;
+        if !exchange_rate_used {
+            Arc::make_mut(&mut context.unit_cache)
+                .insert(ident.to_string(), (value.clone(), dependencies));
+        }
+    }
+    result
+}
+
+fn query_unit_static_uncached<I: Interrupt>(
+    ident: &str,
+    context: &mut crate::Context,
+    int: &I,
 ) -> Result<Value, FendError> {
     match query_unit_case_sensitive(ident, true, context, int) {
         Err(FendError::IdentifierNotFound(_)) => (),
@@ -128,7 +186,148 @@ pub(crate) fn query_unit_static<'a, I: Interrupt>(
             return Ok(value);
         }
     }
-    query_unit_case_sensitive(ident, false, context, int)
+    // mixed case like `Nm` or `kVA` is most likely a product of unit
+    // symbols, while e.g. `KG` is more likely a case-insensitive `kg`
+    let mixed_case = ident.chars().any(char::is_uppercase) && ident.chars().any(char::is_lowercase);
+    if mixed_case {
+        match query_compound_unit(ident, context, int) {
+            Err(FendError::IdentifierNotFound(_)) => (),
+            result => return result,
+        }
+    }
+    match query_unit_case_sensitive(ident, false, context, int) {
+        Err(FendError::IdentifierNotFound(_)) if !mixed_case => {
+            query_compound_unit(ident, context, int)
+        }
+        result => result,
+    }
+}
+
+/// The maximum number of units that a compound unit like `kWh` or `Nm` can
+/// be split into
+const MAX_COMPOUND_UNIT_PARTS: usize = 3;
+
+/// Interprets an identifier like `Nm` as a product of units written without
+/// spaces (newton metres). Splits into fewer (and therefore longer) units
+/// are preferred, and if there are several of those the identifier is
+/// ambiguous.
+fn query_compound_unit<I: Interrupt>(
+    ident: &str,
+    context: &mut crate::Context,
+    int: &I,
+) -> Result<Value, FendError> {
+    let not_found = || FendError::IdentifierNotFound(ident.to_string().into());
+    if !ident.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(not_found());
+    }
+    let mut units = HashMap::new();
+    let mut splits = vec![];
+    // the whole identifier has already been tried, so split it at least once
+    for first_end in 1..ident.len() {
+        if compound_unit_part(ident, 0, first_end, &mut units, context, int)? {
+            for mut split in compound_unit_splits(
+                ident,
+                first_end,
+                MAX_COMPOUND_UNIT_PARTS - 1,
+                &mut units,
+                context,
+                int,
+            )? {
+                split.insert(0, first_end);
+                splits.push(split);
+            }
+        }
+    }
+    let fewest_parts = splits.iter().map(Vec::len).min().ok_or_else(not_found)?;
+    splits.retain(|split| split.len() == fewest_parts);
+    let describe_split = |split: &[usize]| {
+        let mut start = 0;
+        let mut parts = vec![];
+        for &end in split {
+            parts.push(&ident[start..end]);
+            start = end;
+        }
+        parts.join(" ")
+    };
+    if splits.len() > 1 {
+        return Err(FendError::AmbiguousCompoundUnit(
+            ident.to_string(),
+            describe_split(&splits[0]),
+            describe_split(&splits[1]),
+        ));
+    }
+    let mut start = 0;
+    let mut result: Option<Number> = None;
+    for &end in &splits[0] {
+        let unit = units[&(start, end)].clone().unwrap().expect_num()?;
+        result = Some(match result {
+            Some(product) => product.mul(unit, int)?,
+            None => unit,
+        });
+        start = end;
+    }
+    Ok(Value::Num(Box::new(result.ok_or_else(not_found)?)))
+}
+
+/// Returns every way of splitting `ident[start..]` into at most `max_parts`
+/// units, as lists of the end index of each unit
+fn compound_unit_splits<I: Interrupt>(
+    ident: &str,
+    start: usize,
+    max_parts: usize,
+    units: &mut HashMap<(usize, usize), Option<Value>>,
+    context: &mut crate::Context,
+    int: &I,
+) -> Result<Vec<Vec<usize>>, FendError> {
+    let mut splits = vec![];
+    if compound_unit_part(ident, start, ident.len(), units, context, int)? {
+        splits.push(vec![ident.len()]);
+    }
+    if max_parts > 1 {
+        for end in start + 1..ident.len() {
+            if !compound_unit_part(ident, start, end, units, context, int)? {
+                continue;
+            }
+            for mut split in compound_unit_splits(ident, end, max_parts - 1, units, context, int)? {
+                split.insert(0, end);
+                splits.push(split);
+            }
+        }
+    }
+    Ok(splits)
+}
+
+/// Returns whether `ident[start..end]` is a unit, remembering the unit so
+/// that each part is only looked up once
+fn compound_unit_part<I: Interrupt>(
+    ident: &str,
+    start: usize,
+    end: usize,
+    units: &mut HashMap<(usize, usize), Option<Value>>,
+    context: &mut crate::Context,
+    int: &I,
+) -> Result<bool, FendError> {
+    if let Some(unit) = units.get(&(start, end)) {
+        return Ok(unit.is_some());
+    }
+    let part = &ident[start..end];
+    let unit = match query_unit_internal(part, false, true, true, context, int) {
+        // numbers like `G` and constants like `c` can't be part of a
+        // compound unit (nor take a prefix)
+        Ok(unit) if unit.prefix_rule == PrefixRule::NoPrefixesAllowed => None,
+        Ok(unit) => Some(unit.value),
+        Err(FendError::IdentifierNotFound(_)) => {
+            match query_unit_case_sensitive(part, true, context, int) {
+                Ok(unit) => Some(unit),
+                Err(FendError::IdentifierNotFound(_)) => None,
+                Err(e) => return Err(e),
+            }
+        }
+        Err(e) => return Err(e),
+    };
+    let found = unit.is_some();
+    units.insert((start, end), unit);
+    Ok(found)
 }
 
 fn query_unit_case_sensitive<I: Interrupt>(
@@ -198,6 +397,7 @@ fn query_unit_internal<'a, I: Interrupt>(
                 Some(f) => f,
                 None => return Err(FendError::NoExchangeRatesAvailable),
             };
+            context.exchange_rate_used = true;
             let one_usd_in_currency = exchange_rate_fn(s)?;
             let value = evaluate_to_value(
                 format!("(1/{one_usd_in_currency}) USD").as_str(),
@@ -253,3 +453,154 @@ pub(crate) fn get_completions_for_prefix(prefix: &str) -> Vec<crate::Completion>
 
     result
 }
+
+pub(crate) fn unit_names() -> impl Iterator<Item = &'static str> {
+    builtin::unit_names()
+}
+
+/// Describes a unit for `describe`/`help`, showing its definition and
+/// dimensions, e.g. `newton = kg m / s^2 (mass\u{b7}length/time\u{b2})`
+pub(crate) fn describe_unit<I: Interrupt>(
+    ident: &str,
+    context: &mut crate::Context,
+    int: &I,
+) -> Result<String, FendError> {
+    let definition = builtin::query_unit(ident, false, true)
+        .or_else(|| builtin::query_unit(ident, false, false))
+        .map(|(_, _, definition)| {
+            ["l@", "s@", "="]
+                .iter()
+                .fold(definition.trim(), |def, rule| {
+                    def.strip_prefix(rule).unwrap_or(def)
+                })
+        });
+    if definition == Some("$CURRENCY") {
+        return Ok(format!(
+            "{ident}: a currency, converted using exchange rates"
+        ));
+    }
+    let dimension = match query_unit_static(ident, context, int)? {
+        Value::Num(n) => n.dimension_name(int)?,
+        _ => return Err(FendError::IdentifierNotFound(ident.to_string().into())),
+    };
+    Ok(match definition {
+        Some("!") => format!("{ident}: a base unit ({dimension})"),
+        Some(definition) => match definition
+            .strip_prefix("lp@")
+            .or_else(|| definition.strip_prefix("sp@"))
+        {
+            Some(factor) => format!("{ident} = {factor} (a prefix)"),
+            None => format!("{ident} = {definition} ({dimension})"),
+        },
+        None => format!("{ident} ({dimension})"),
+    })
+}
+
+/// Computes the factor between two units for `conversion_factor`. Both units
+/// can be expressions like `km/h`.
+pub(crate) fn conversion_factor<I: Interrupt>(
+    from: &str,
+    to: &str,
+    context: &mut crate::Context,
+    int: &I,
+) -> Result<f64, FendError> {
+    let from = crate::eval::evaluate_to_value(from, None, context, int)?.expect_num()?;
+    let to = crate::eval::evaluate_to_value(to, None, context, int)?.expect_num()?;
+    from.conversion_factor(to, int)?.into_f64(int)
+}
+
+/// Lists the built-in units, with alternative names (such as plurals and
+/// abbreviations) grouped together. Prefixes are skipped, and so are units
+/// that can't currently be evaluated, e.g. currencies without exchange rates.
+pub(crate) fn list_units<I: Interrupt>(
+    context: &mut crate::Context,
+    int: &I,
+) -> Vec<crate::UnitInfo> {
+    // (singular, plural, definition) for each unit, without prefix rules
+    let mut defs = vec![];
+    for group in builtin::ALL_UNIT_DEFS {
+        for &(singular, plural, definition, _) in *group {
+            let definition = definition.trim();
+            if definition.starts_with("lp@") || definition.starts_with("sp@") {
+                continue;
+            }
+            let definition = ["l@", "s@", "="].iter().fold(definition, |def, rule| {
+                def.strip_prefix(rule).unwrap_or(def)
+            });
+            defs.push((singular, plural, definition));
+        }
+    }
+    let names: HashMap<&str, usize> = defs
+        .iter()
+        .enumerate()
+        .flat_map(|(i, &(singular, plural, _))| [(singular, i), (plural, i)])
+        .filter(|(name, _)| !name.is_empty())
+        .collect();
+    // definitions that consist of another unit's name are aliases for it,
+    // e.g. `m` for `meter`
+    let canonical = |mut idx: usize| {
+        for _ in 0..defs.len() {
+            match names.get(defs[idx].2) {
+                Some(&target) if target != idx => idx = target,
+                _ => break,
+            }
+        }
+        idx
+    };
+    let mut aliases: HashMap<usize, Vec<String>> = HashMap::new();
+    for (i, &(singular, plural, _)) in defs.iter().enumerate() {
+        let entry = aliases.entry(canonical(i)).or_default();
+        for name in [singular, plural] {
+            if !name.is_empty() && name != defs[canonical(i)].0 && !entry.iter().any(|a| a == name)
+            {
+                entry.push(name.to_string());
+            }
+        }
+    }
+    let mut result = vec![];
+    for (i, &(singular, _, definition)) in defs.iter().enumerate() {
+        if canonical(i) != i {
+            continue;
+        }
+        let dimension = match query_unit_static(singular, context, int) {
+            Ok(Value::Num(n)) => match n.dimension_name(int) {
+                Ok(dimension) => dimension,
+                Err(_) => continue,
+            },
+            _ => continue,
+        };
+        result.push(crate::UnitInfo {
+            name: singular.to_string(),
+            aliases: aliases.remove(&i).unwrap_or_default(),
+            dimension,
+            definition: if definition == "!" {
+                String::new()
+            } else {
+                definition.to_string()
+            },
+        });
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{evaluate, Context};
+
+    #[test]
+    fn assignments_only_invalidate_dependent_units() {
+        let mut ctx = Context::new();
+        evaluate("1 foot + 1 inch; 1 kg", &mut ctx).unwrap();
+        assert!(ctx.unit_cache.contains_key("foot"));
+        assert!(ctx.unit_cache.contains_key("kg"));
+
+        evaluate("x = 5", &mut ctx).unwrap();
+        assert!(ctx.unit_cache.contains_key("foot"));
+        assert!(ctx.unit_cache.contains_key("kg"));
+
+        // a foot is defined as 12 inches
+        evaluate("inch = 2 cm", &mut ctx).unwrap();
+        assert!(!ctx.unit_cache.contains_key("foot"));
+        assert!(ctx.unit_cache.contains_key("kg"));
+    }
+}
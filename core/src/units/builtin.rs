@@ -56,6 +56,10 @@ const BITS_AND_BYTES: &[UnitTuple] = &[
     ("octet", "octets", "l@8 bits", ""),
 ];
 
+// units that only come in whole numbers, since e.g. there's no such thing
+// as half a bit
+pub(crate) const DISCRETE_UNITS: &[&str] = &["bit", "b", "byte", "B", "octet"];
+
 const STANDARD_PREFIXES: &[UnitTuple] = &[
     ("yotta", "", "lp@1e24", ""),
     ("zetta", "", "lp@1e21", ""),
@@ -225,6 +229,19 @@ const CONSTANTS: &[UnitTuple] = &[
         "size of a mole (exact)",
     ),
     ("N_A", "", "=avogadro", ""),
+    (
+        "caesium_frequency",
+        "",
+        "=9192631770 Hz",
+        "hyperfine transition frequency of caesium-133 (exact)",
+    ),
+    ("cesium_frequency", "", "=caesium_frequency", ""),
+    (
+        "luminous_efficacy",
+        "",
+        "=683 lm/W",
+        "luminous efficacy of 540 THz monochromatic radiation (exact)",
+    ),
     (
         "gravitational_constant",
         "",
@@ -260,6 +277,7 @@ const ANGLES: &[UnitTuple] = &[
     ("turn", "turns", "l@circle", ""),
     ("revolution", "revolutions", "l@circle", ""),
     ("rev", "revs", "l@circle", ""),
+    ("cycle", "cycles", "l@circle", ""),
     ("gradian", "gradians", "l@1/100 rightangle", ""),
     ("gon", "gons", "l@gradian", ""),
     ("grad", "", "l@gradian", ""),
@@ -498,6 +516,28 @@ const COMMON_PHYSICAL_UNITS: &[UnitTuple] = &[
     ("light", "", "c", ""),
 ];
 
+const CGS_UNITS: &[UnitTuple] = &[
+    ("dyne", "dynes", "l@g cm / s^2", "CGS unit of force"),
+    ("dyn", "", "s@dyne", ""),
+    ("erg", "ergs", "l@dyne cm", "CGS unit of energy"),
+    ("barye", "baryes", "l@dyne / cm^2", "CGS unit of pressure"),
+    ("Ba", "", "s@barye", ""),
+    ("poise", "", "l@g / (cm s)", "CGS unit of dynamic viscosity"),
+    (
+        "stokes",
+        "",
+        "l@cm^2 / s",
+        "CGS unit of kinematic viscosity",
+    ),
+    (
+        "galileo",
+        "galileos",
+        "l@cm / s^2",
+        "CGS unit of acceleration",
+    ),
+    ("Gal", "", "s@galileo", ""),
+];
+
 const IMPERIAL_UNITS: &[UnitTuple] = &[
     ("inch", "inches", "2.54 cm", ""),
     ("mil", "mils", "1/1000 inch", ""),
@@ -652,6 +692,7 @@ pub(crate) const ALL_UNIT_DEFS: &[&[UnitTuple]] = &[
     TIME_UNITS,
     RATIOS,
     COMMON_PHYSICAL_UNITS,
+    CGS_UNITS,
     IMPERIAL_UNITS,
     LIQUID_UNITS,
     AVOIRDUPOIS_WEIGHT,
@@ -747,6 +788,15 @@ pub(crate) fn query_unit<'a>(
     None
 }
 
+/// Singular and plural names of all built-in units
+pub(crate) fn unit_names() -> impl Iterator<Item = &'static str> {
+    ALL_UNIT_DEFS
+        .iter()
+        .flat_map(|group| group.iter())
+        .flat_map(|&(singular, plural, _, _)| [singular, plural])
+        .filter(|name| !name.is_empty())
+}
+
 /// used for implicit unit addition, e.g. 5'5 -> 5'5"
 pub(crate) const IMPLICIT_UNIT_MAP: &[(&str, &str)] = &[("'", "\""), ("foot", "inches")];
 
@@ -0,0 +1,14 @@
+/// This determines what happens when an expression uses an identifier that
+/// isn't a known unit, variable or function, e.g. `foo` in `5 foo`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+#[must_use]
+#[non_exhaustive]
+pub enum UnknownUnitBehavior {
+    /// Report an error, e.g. `unknown identifier 'foo'`
+    #[default]
+    Error,
+    /// Treat the identifier as an opaque symbolic unit, so that e.g.
+    /// `5 foo / foo` is `5`. Symbolic units can't be converted to any
+    /// other unit.
+    Symbolic,
+}
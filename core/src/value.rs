@@ -1,15 +1,17 @@
-use crate::ast::Bop;
+use crate::ast::{Bop, CompareBop};
 use crate::date::{Date, DayOfWeek, Month};
 use crate::error::{FendError, Interrupt};
-use crate::num::{Base, FormattingStyle, Number};
+use crate::interrupt::test_int;
+use crate::num::{Base, FormattingStyle, Number, UnitSystem};
 use crate::scope::Scope;
 use crate::serialize::{
     deserialize_bool, deserialize_string, deserialize_u8, deserialize_usize, serialize_bool,
     serialize_string, serialize_u8, serialize_usize,
 };
 use crate::{ast::Expr, ident::Ident};
-use crate::{date, Span, SpanKind};
+use crate::{date, encoding, hash, ip, ratio, Span, SpanKind};
 use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::io;
 use std::{
     fmt::{self, Write},
@@ -17,8 +19,10 @@ use std::{
 };
 
 pub(crate) mod built_in_function;
+pub(crate) mod uncertain;
 
 use built_in_function::BuiltInFunction;
+use uncertain::Uncertain;
 
 #[derive(Clone)]
 pub(crate) enum Value {
@@ -37,6 +41,10 @@ pub(crate) enum Value {
     Month(date::Month),
     DayOfWeek(date::DayOfWeek),
     Date(date::Date),
+    List(Vec<Self>),
+    Uncertain(Box<Uncertain>),
+    Ip(ip::Ipv4),
+    Ratio(Box<ratio::Ratio>),
 }
 
 #[derive(Copy, Clone, Eq, PartialEq)]
@@ -107,6 +115,25 @@ impl Value {
                 serialize_u8(13, write)?;
                 d.serialize(write)?;
             }
+            Self::List(items) => {
+                serialize_u8(14, write)?;
+                serialize_usize(items.len(), write)?;
+                for item in items {
+                    item.serialize(write)?;
+                }
+            }
+            Self::Uncertain(u) => {
+                serialize_u8(15, write)?;
+                u.serialize(write)?;
+            }
+            Self::Ip(ip) => {
+                serialize_u8(16, write)?;
+                ip.serialize(write)?;
+            }
+            Self::Ratio(r) => {
+                serialize_u8(17, write)?;
+                r.serialize(write)?;
+            }
         }
         Ok(())
     }
@@ -145,13 +172,24 @@ impl Value {
             11 => Self::Month(Month::deserialize(read)?),
             12 => Self::DayOfWeek(DayOfWeek::deserialize(read)?),
             13 => Self::Date(Date::deserialize(read)?),
+            14 => Self::List({
+                let len = deserialize_usize(read)?;
+                let mut items = Vec::with_capacity(len);
+                for _ in 0..len {
+                    items.push(Self::deserialize(read)?);
+                }
+                items
+            }),
+            15 => Self::Uncertain(Box::new(Uncertain::deserialize(read)?)),
+            16 => Self::Ip(ip::Ipv4::deserialize(read)?),
+            17 => Self::Ratio(Box::new(ratio::Ratio::deserialize(read)?)),
             _ => return Err(FendError::DeserializationError),
         })
     }
 
     pub(crate) fn type_name(&self) -> &'static str {
         match self {
-            Self::Num(_) => "number",
+            Self::Num(_) | Self::Uncertain(_) => "number",
             Self::BuiltInFunction(_) | Self::Fn(_, _, _) => "function",
             Self::Format(_) => "formatting style",
             Self::Dp => "decimal places",
@@ -164,7 +202,21 @@ impl Value {
             Self::Month(_) => "month",
             Self::DayOfWeek(_) => "day of week",
             Self::Date(_) => "date",
+            Self::List(_) => "list",
+            Self::Ip(_) => "IP address",
+            Self::Ratio(_) => "ratio",
+        }
+    }
+
+    /// Returns the arguments of a built-in function that takes several
+    /// arguments, which are passed as a list, e.g. `divmod(7, 2)`
+    fn expect_args<const N: usize>(self, func: BuiltInFunction) -> Result<[Self; N], FendError> {
+        if let Self::List(items) = self {
+            if let Ok(args) = items.try_into() {
+                return Ok(args);
+            }
         }
+        Err(FendError::WrongNumberOfArguments(func.as_str(), N))
     }
 
     fn as_bool(&self) -> Result<bool, FendError> {
@@ -175,6 +227,104 @@ impl Value {
         }
     }
 
+    fn expect_coordinates(self) -> Result<[Number; 2], FendError> {
+        if let Self::List(items) = self {
+            if let Ok([a, b]) = <[Self; 2]>::try_from(items) {
+                return Ok([a.expect_num()?, b.expect_num()?]);
+            }
+        }
+        Err(FendError::ExpectedCoordinates)
+    }
+
+    /// Converts the point `(x, y)` to polar coordinates `(r, theta)`, where
+    /// `theta` is in radians
+    pub(crate) fn into_polar<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        let [x, y] = self.expect_coordinates()?;
+        let y = y.convert_to_unit_of(&x, int)?;
+        let angle = y.clone().atan2(x.clone(), int)?;
+        let r = x
+            .clone()
+            .mul(x, int)?
+            .add(y.clone().mul(y, int)?, int)?
+            .pow(Number::from(1).div(2.into(), int)?, int)?;
+        Ok(Self::List(vec![
+            Self::Num(Box::new(r)),
+            Self::Num(Box::new(angle)),
+        ]))
+    }
+
+    /// Converts the polar coordinates `(r, theta)` to `(x, y)`. `theta` may
+    /// have an angle unit, e.g. `(2, 90 degrees) to cartesian`.
+    pub(crate) fn into_cartesian<I: Interrupt>(
+        self,
+        scope: Option<Arc<Scope>>,
+        context: &mut crate::Context,
+        int: &I,
+    ) -> Result<Self, FendError> {
+        let [r, angle] = self.expect_coordinates()?;
+        let x = r
+            .clone()
+            .mul(angle.clone().cos(scope.clone(), context, int)?, int)?;
+        let y = r.mul(angle.sin(scope, context, int)?, int)?;
+        Ok(Self::List(vec![
+            Self::Num(Box::new(x)),
+            Self::Num(Box::new(y)),
+        ]))
+    }
+
+    /// Converts a number to the canonical units of the given unit system,
+    /// e.g. `1 dyn to si` is `0.00001 N` and `1 J to cgs` is `10000000 erg`
+    pub(crate) fn convert_to_unit_system<I: Interrupt>(
+        self,
+        system: UnitSystem,
+        context: &mut crate::Context,
+        int: &I,
+    ) -> Result<Self, FendError> {
+        let n = self.expect_num()?;
+        let units = n.units_in_system(system, int)?;
+        if units.is_empty() {
+            return Ok(Self::Num(Box::new(n)));
+        }
+        let mut target = Number::from(1);
+        for (name, exponent) in units {
+            let unit = crate::units::query_unit(name, context, int)?.expect_num()?;
+            target = target.mul(unit.pow(exponent, int)?, int)?;
+        }
+        Ok(Self::Num(Box::new(n.convert_to(target, int)?)))
+    }
+
+    pub(crate) fn expect_list(self) -> Result<Vec<Self>, FendError> {
+        if let Self::List(items) = self {
+            Ok(items)
+        } else {
+            Err(FendError::ExpectedAList(self.type_name()))
+        }
+    }
+
+    pub(crate) fn expect_date(self) -> Result<Date, FendError> {
+        if let Self::Date(date) = self {
+            Ok(date)
+        } else {
+            Err(FendError::ExpectedADate(self.type_name()))
+        }
+    }
+
+    pub(crate) fn expect_string(self) -> Result<Cow<'static, str>, FendError> {
+        match self {
+            Self::String(s) => Ok(s),
+            _ => Err(FendError::ExpectedAString),
+        }
+    }
+
+    /// Treats a ratio like `16:9` as the number it represents, so that it
+    /// can be converted with e.g. `to decimal`
+    pub(crate) fn ratio_to_num<I: Interrupt>(self, int: &I) -> Result<Self, FendError> {
+        Ok(match self {
+            Self::Ratio(r) => Self::Num(Box::new(r.quotient(int)?)),
+            value => value,
+        })
+    }
+
     pub(crate) fn expect_num(self) -> Result<Number, FendError> {
         match self {
             Self::Num(bigrat) => Ok(*bigrat),
@@ -186,6 +336,22 @@ impl Value {
         matches!(self, Self::Unit)
     }
 
+    /// Approximates a numeric result in the unit it's displayed in. Returns
+    /// `None` for other values, complex numbers and distributions, and if
+    /// the approximation isn't finite.
+    pub(crate) fn approx_value<I: Interrupt>(&self, ctx: &crate::Context, int: &I) -> Option<f64> {
+        let n = match self {
+            Self::Num(n) => n.clone(),
+            _ => return None,
+        };
+        let value = n
+            .simplify(int)
+            .and_then(|n| n.with_unit_display_preference(ctx.unit_display_preference(), int))
+            .and_then(|n| n.into_f64(int))
+            .ok()?;
+        Some(value).filter(|f| f.is_finite())
+    }
+
     pub(crate) fn handle_num(
         self,
         eval_fn: impl FnOnce(Number) -> Result<Number, FendError>,
@@ -222,6 +388,83 @@ impl Value {
         })
     }
 
+    pub(crate) fn compare<I: Interrupt>(
+        &self,
+        other: &Self,
+        op: CompareBop,
+        ctx: &crate::Context,
+        int: &I,
+    ) -> Result<bool, FendError> {
+        Ok(match op {
+            CompareBop::Equal => self.equals(other, int)?,
+            CompareBop::NotEqual => !self.equals(other, int)?,
+            CompareBop::Less => self.order(other, int)? == Ordering::Less,
+            CompareBop::LessOrEqual => self.order(other, int)? != Ordering::Greater,
+            CompareBop::Greater => self.order(other, int)? == Ordering::Greater,
+            CompareBop::GreaterOrEqual => self.order(other, int)? != Ordering::Less,
+            CompareBop::ApproxEqual => match (self, other) {
+                (Self::Num(a), Self::Num(b)) => {
+                    // approximate results are shown with 10 decimal places
+                    // unless a different precision has been set
+                    let digits = match ctx.formatting_style() {
+                        FormattingStyle::DecimalPlaces(n)
+                        | FormattingStyle::SignificantFigures(n) => n,
+                        _ => 10,
+                    };
+                    a.approx_eq(b, digits, int)?
+                }
+                _ => self.equals(other, int)?,
+            },
+        })
+    }
+
+    fn order<I: Interrupt>(&self, other: &Self, int: &I) -> Result<Ordering, FendError> {
+        match (self, other) {
+            (Self::Num(a), Self::Num(b)) => a.compare(b, int),
+            (Self::String(a), Self::String(b)) => Ok(a.cmp(b)),
+            (Self::Date(a), Self::Date(b)) => {
+                Ok(a.seconds_since_epoch().cmp(&b.seconds_since_epoch()))
+            }
+            (Self::Ip(a), Self::Ip(b)) => Ok(a.order(*b)),
+            (Self::Ratio(a), Self::Ratio(b)) => a.compare(b, int),
+            _ if self.type_name() == other.type_name() => {
+                Err(FendError::CannotOrder(self.type_name()))
+            }
+            _ => Err(FendError::CannotCompare(
+                self.type_name(),
+                other.type_name(),
+            )),
+        }
+    }
+
+    fn equals<I: Interrupt>(&self, other: &Self, int: &I) -> Result<bool, FendError> {
+        Ok(match (self, other) {
+            (Self::Num(a), Self::Num(b)) => a.compare_eq(b, int)?,
+            (Self::Bool(a), Self::Bool(b)) => a == b,
+            (Self::String(a), Self::String(b)) => a == b,
+            (Self::Date(a), Self::Date(b)) => a == b,
+            (Self::Ip(a), Self::Ip(b)) => a == b,
+            (Self::Ratio(a), Self::Ratio(b)) => a.compare(b, int)? == Ordering::Equal,
+            (Self::List(a), Self::List(b)) => {
+                if a.len() != b.len() {
+                    return Ok(false);
+                }
+                for (a, b) in a.iter().zip(b) {
+                    if !a.equals(b, int)? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            _ => {
+                return Err(FendError::CannotCompare(
+                    self.type_name(),
+                    other.type_name(),
+                ))
+            }
+        })
+    }
+
     pub(crate) fn apply<I: Interrupt>(
         self,
         other: Expr,
@@ -251,6 +494,9 @@ impl Value {
                         self_.format_to_plain_string(0, context, int)?,
                     ));
                 }
+                if let Self::Uncertain(_) = other {
+                    return Uncertain::bop(Bop::Mul, Self::Num(n), other, int);
+                }
                 let n2 = n.clone();
                 other.handle_num(
                     |x| n.mul(x, int),
@@ -258,17 +504,104 @@ impl Value {
                     scope,
                 )?
             }
+            Self::Uncertain(u) => {
+                if apply_mul_handling == ApplyMulHandling::OnlyApply {
+                    return Err(FendError::IsNotAFunction(stringified_self));
+                }
+                let other = crate::ast::evaluate(other, scope, context, int)?;
+                Uncertain::bop(Bop::Mul, Self::Uncertain(u), other, int)?
+            }
             Self::BuiltInFunction(func) => {
+                if let Some(feature) = func.feature() {
+                    context.use_feature(feature)?;
+                }
                 Self::apply_built_in_function(func, other, scope, context, int)?
             }
             Self::Fn(param, expr, custom_scope) => {
+                if context.recursion_depth >= context.max_recursion_depth {
+                    return Err(FendError::MaxRecursionDepthExceeded);
+                }
                 let new_scope = Scope::with_variable(param, other, scope, custom_scope);
-                return crate::ast::evaluate(*expr, Some(Arc::new(new_scope)), context, int);
+                context.recursion_depth += 1;
+                let result = crate::ast::evaluate(*expr, Some(Arc::new(new_scope)), context, int);
+                context.recursion_depth -= 1;
+                return result;
+            }
+            // indexing, e.g. `xs[2]`
+            Self::List(mut items) => {
+                let Expr::List(index) = other else {
+                    return Err(FendError::IsNotAFunctionOrNumber(stringified_self));
+                };
+                let Ok([index]) = <[Expr; 1]>::try_from(index) else {
+                    return Err(FendError::InvalidListIndex);
+                };
+                let index = crate::ast::evaluate(index, scope, context, int)?.expect_num()?;
+                items.swap_remove(Self::list_position(index, items.len(), int)?)
             }
             _ => return Err(FendError::IsNotAFunctionOrNumber(stringified_self)),
         })
     }
 
+    /// Converts a list index into a position in a list of `len` items.
+    /// Indices start at 1 (like `argmax`), and negative indices count from
+    /// the end of the list, so `-1` is the last item.
+    fn list_position<I: Interrupt>(index: Number, len: usize, int: &I) -> Result<usize, FendError> {
+        let negative = index.compare(&0.into(), int)? == Ordering::Less;
+        let magnitude = index.abs(int)?.try_as_usize(int)?;
+        let position = if negative {
+            len.checked_sub(magnitude)
+        } else {
+            magnitude.checked_sub(1)
+        };
+        match position {
+            Some(position) if position < len => Ok(position),
+            _ => Err(FendError::IndexOutOfBounds {
+                index: format!("{}{magnitude}", if negative { "-" } else { "" }),
+                len,
+            }),
+        }
+    }
+
+    /// Returns this list with the item at `index` replaced, for assignments
+    /// like `xs[2] = 9`
+    pub(crate) fn with_item_replaced<I: Interrupt>(
+        self,
+        index: Number,
+        item: Self,
+        int: &I,
+    ) -> Result<Self, FendError> {
+        let mut items = self.expect_list()?;
+        let position = Self::list_position(index, items.len(), int)?;
+        items[position] = item;
+        Ok(Self::List(items))
+    }
+
+    /// `approx_equal` can also be called one argument at a time, e.g.
+    /// `approx_equal 3.14 pi 0.001`, so this returns a function that takes
+    /// the remaining two arguments
+    fn curry_approx_equal(a: Self) -> Self {
+        let b = Ident::new_str("b");
+        let tolerance = Ident::new_str("tolerance");
+        Self::Fn(
+            b.clone(),
+            Box::new(Expr::Fn(
+                tolerance.clone(),
+                Box::new(Expr::ApplyFunctionCall(
+                    Box::new(Expr::Literal(Self::BuiltInFunction(
+                        BuiltInFunction::ApproxEqual,
+                    ))),
+                    Box::new(Expr::List(vec![
+                        Expr::Literal(a),
+                        Expr::Ident(b),
+                        Expr::Ident(tolerance),
+                    ])),
+                )),
+            )),
+            None,
+        )
+    }
+
+    #[allow(clippy::too_many_lines)]
     fn apply_built_in_function<I: Interrupt>(
         func: BuiltInFunction,
         arg: Expr,
@@ -283,12 +616,21 @@ impl Value {
             BuiltInFunction::Sin => arg.expect_num()?.sin(scope, context, int)?,
             BuiltInFunction::Cos => arg.expect_num()?.cos(scope, context, int)?,
             BuiltInFunction::Tan => arg.expect_num()?.tan(scope, context, int)?,
+            BuiltInFunction::Sec => arg.expect_num()?.sec(scope, context, int)?,
+            BuiltInFunction::Csc => arg.expect_num()?.csc(scope, context, int)?,
+            BuiltInFunction::Cot => arg.expect_num()?.cot(scope, context, int)?,
             BuiltInFunction::Asin => arg.expect_num()?.asin(int)?,
             BuiltInFunction::Acos => arg.expect_num()?.acos(int)?,
             BuiltInFunction::Atan => arg.expect_num()?.atan(int)?,
+            BuiltInFunction::Asec => arg.expect_num()?.asec(int)?,
+            BuiltInFunction::Acsc => arg.expect_num()?.acsc(int)?,
+            BuiltInFunction::Acot => arg.expect_num()?.acot(int)?,
             BuiltInFunction::Sinh => arg.expect_num()?.sinh(int)?,
             BuiltInFunction::Cosh => arg.expect_num()?.cosh(int)?,
             BuiltInFunction::Tanh => arg.expect_num()?.tanh(int)?,
+            BuiltInFunction::Sech => arg.expect_num()?.sech(int)?,
+            BuiltInFunction::Csch => arg.expect_num()?.csch(int)?,
+            BuiltInFunction::Coth => arg.expect_num()?.coth(int)?,
             BuiltInFunction::Asinh => arg.expect_num()?.asinh(int)?,
             BuiltInFunction::Acosh => arg.expect_num()?.acosh(int)?,
             BuiltInFunction::Atanh => arg.expect_num()?.atanh(int)?,
@@ -303,12 +645,458 @@ impl Value {
                     .map_err(|_| FendError::UnableToConvertToBase)?;
                 return Ok(Self::Base(Base::from_plain_base(n)?));
             }
+            BuiltInFunction::Fixed => {
+                let dp = arg.expect_num()?.try_as_usize(int)?;
+                return Ok(Self::Format(FormattingStyle::FixedDecimalPlaces(dp)));
+            }
             BuiltInFunction::Sample => arg.expect_num()?.sample(context, int)?,
             BuiltInFunction::Not => return Ok(Self::Bool(!arg.as_bool()?)),
             BuiltInFunction::Conjugate => arg.expect_num()?.conjugate()?,
+            BuiltInFunction::Arg => arg.expect_num()?.arg(int)?,
+            BuiltInFunction::Re => arg.expect_num()?.real_part()?,
+            BuiltInFunction::Im => arg.expect_num()?.imaginary_part()?,
+            BuiltInFunction::Numerator => arg.expect_num()?.fraction_part(true, int)?,
+            BuiltInFunction::Denominator => arg.expect_num()?.fraction_part(false, int)?,
+            BuiltInFunction::Round => match arg {
+                Self::List(_) => {
+                    let [x, increment] = arg.expect_args(func)?;
+                    x.expect_num()?.round(Some(increment.expect_num()?), int)?
+                }
+                arg => arg.expect_num()?.round(None, int)?,
+            },
+            // only makes `round(x, nearest 5)` easier to read
+            BuiltInFunction::Nearest => arg.expect_num()?,
+            BuiltInFunction::Period => arg.expect_num()?.period(int)?,
+            BuiltInFunction::FromBase64 => {
+                let s = arg.expect_string()?;
+                return Ok(Self::String(encoding::decode_base64(&s)?.into()));
+            }
+            BuiltInFunction::FromHex => {
+                let s = arg.expect_string()?;
+                return Ok(Self::String(encoding::decode_hex(&s)?.into()));
+            }
+            BuiltInFunction::Md5 => {
+                return Ok(Self::String(hash::md5(&arg.expect_string()?).into()))
+            }
+            BuiltInFunction::Sha256 => {
+                return Ok(Self::String(hash::sha256(&arg.expect_string()?).into()));
+            }
+            BuiltInFunction::Crc32 => {
+                return Ok(Self::String(hash::crc32(&arg.expect_string()?).into()));
+            }
+            // operations that would produce NaN or infinity return an error
+            // instead, so every number is finite
+            BuiltInFunction::IsNan | BuiltInFunction::IsInfinite | BuiltInFunction::IsFinite => {
+                arg.expect_num()?;
+                return Ok(Self::Bool(func == BuiltInFunction::IsFinite));
+            }
+            BuiltInFunction::IsInteger => {
+                return Ok(Self::Bool(arg.expect_num()?.is_integer(int)?));
+            }
+            BuiltInFunction::IsRational => {
+                return Ok(Self::Bool(arg.expect_num()?.is_rational(int)?));
+            }
+            BuiltInFunction::IsIrrational => {
+                return Ok(Self::Bool(arg.expect_num()?.is_irrational(int)?));
+            }
+            BuiltInFunction::IsComplex => {
+                return Ok(Self::Bool(arg.expect_num()?.is_complex()?));
+            }
+            BuiltInFunction::IsLeapYear => {
+                return Ok(Self::Bool(match arg {
+                    Self::Date(d) => d.is_leap_year(),
+                    arg => {
+                        // only the year modulo 400 matters
+                        let year = arg.expect_num()?.try_as_usize(int)? % 400;
+                        date::is_leap_year(year.try_into().unwrap())
+                    }
+                }));
+            }
+            BuiltInFunction::Simplify => {
+                // like terms of pi and square roots are already combined
+                // during evaluation, so this only needs to show the
+                // symbolic form
+                let n = arg.expect_num()?;
+                if n.is_exact() {
+                    n.with_format(FormattingStyle::Exact)
+                } else {
+                    context.add_warning("unable to simplify this value any further");
+                    n
+                }
+            }
+            BuiltInFunction::Atan2 => {
+                let [y, x] = arg.expect_args(func)?;
+                y.expect_num()?.atan2(x.expect_num()?, int)?
+            }
+            BuiltInFunction::ApproxEqual => {
+                let Self::List(_) = arg else {
+                    return Ok(Self::curry_approx_equal(arg));
+                };
+                let [a, b, tolerance] = arg.expect_args(func)?;
+                let a = a.expect_num()?;
+                return Ok(Self::Bool(a.approx_eq_with_tolerance(
+                    &b.expect_num()?,
+                    tolerance.expect_num()?,
+                    int,
+                )?));
+            }
+            BuiltInFunction::DivMod => {
+                let [a, b] = arg.expect_args(func)?;
+                let (quotient, remainder) = a.expect_num()?.floor_divmod(b.expect_num()?, int)?;
+                return Ok(Self::List(vec![
+                    Self::Num(Box::new(quotient)),
+                    Self::Num(Box::new(remainder)),
+                ]));
+            }
+            BuiltInFunction::Change => {
+                let [old, new] = arg.expect_args(func)?;
+                new.expect_num()?.sub(old.expect_num()?, int)?
+            }
+            BuiltInFunction::PctChange => {
+                let [old, new] = arg.expect_args(func)?;
+                let old = old.expect_num()?;
+                if old.is_zero() {
+                    return Err(FendError::PercentChangeFromZero);
+                }
+                let percent = crate::units::query_unit("%", context, int)?.expect_num()?;
+                new.expect_num()?
+                    .sub(old.clone(), int)?
+                    .div(old, int)?
+                    .convert_to(percent, int)?
+            }
+            BuiltInFunction::Bits => {
+                let [x, pos, len] = arg.expect_args(func)?;
+                x.expect_num()?.extract_bits(
+                    pos.expect_num()?.try_as_usize(int)?,
+                    len.expect_num()?.try_as_usize(int)?,
+                    int,
+                )?
+            }
+            BuiltInFunction::SetBits => {
+                let [x, pos, len, value] = arg.expect_args(func)?;
+                x.expect_num()?.insert_bits(
+                    pos.expect_num()?.try_as_usize(int)?,
+                    len.expect_num()?.try_as_usize(int)?,
+                    value.expect_num()?,
+                    int,
+                )?
+            }
+            BuiltInFunction::Min
+            | BuiltInFunction::Max
+            | BuiltInFunction::ArgMin
+            | BuiltInFunction::ArgMax => return Self::extremum(func, arg.expect_list()?, int),
+            BuiltInFunction::Span => {
+                let items = arg.expect_list()?;
+                if items.is_empty() {
+                    return Err(FendError::EmptyList(func.as_str()));
+                }
+                let min = Self::extremum(BuiltInFunction::Min, items.clone(), int)?;
+                let max = Self::extremum(BuiltInFunction::Max, items, int)?;
+                max.expect_num()?.sub(min.expect_num()?, int)?
+            }
+            BuiltInFunction::Percentile | BuiltInFunction::Quartile => {
+                let [p, items] = arg.expect_args(func)?;
+                Self::percentile(func, p.expect_num()?, items.expect_list()?, int)?
+            }
+            BuiltInFunction::Solve => {
+                let [matrix, vector] = arg.expect_args(func)?;
+                return Self::solve_linear_system(
+                    matrix.expect_list()?,
+                    vector.expect_list()?,
+                    int,
+                );
+            }
+            BuiltInFunction::Zip => {
+                // the result is as long as the shorter list
+                let [a, b] = arg.expect_args(func)?;
+                return Ok(Self::List(
+                    a.expect_list()?
+                        .into_iter()
+                        .zip(b.expect_list()?)
+                        .map(|(a, b)| Self::List(vec![a, b]))
+                        .collect(),
+                ));
+            }
+            BuiltInFunction::Enumerate => {
+                // positions start at 1, like indices and `argmax`
+                return Ok(Self::List(
+                    arg.expect_list()?
+                        .into_iter()
+                        .zip(1..)
+                        .map(|(item, i)| {
+                            Self::List(vec![Self::Num(Box::new(Number::from(i))), item])
+                        })
+                        .collect(),
+                ));
+            }
+            BuiltInFunction::Hist => {
+                let index = arg.expect_num()?.try_as_usize(int)?;
+                return context.history_entry(index);
+            }
+            BuiltInFunction::Distance => {
+                Self::great_circle_distance(arg.expect_list()?, context, int)?
+            }
+            BuiltInFunction::Choose | BuiltInFunction::Shuffle => {
+                return Self::random_choice(func, arg.expect_list()?, context, int)
+            }
+            BuiltInFunction::Next | BuiltInFunction::Last | BuiltInFunction::This => {
+                let direction = match func {
+                    BuiltInFunction::Next => 1,
+                    BuiltInFunction::Last => -1,
+                    _ => 0,
+                };
+                let today = Date::today(context)?;
+                return Ok(Self::Date(match arg {
+                    Self::DayOfWeek(day) => today.relative_day_of_week(day, direction)?,
+                    arg => today.relative_period(&arg.expect_num()?, direction, int)?,
+                }));
+            }
+            BuiltInFunction::FirstDayOf => {
+                return Ok(Self::Date(arg.expect_date()?.first_day_of_month()));
+            }
+            BuiltInFunction::LastDayOf => {
+                return Ok(Self::Date(arg.expect_date()?.last_day_of_month()));
+            }
+            BuiltInFunction::Until => {
+                let date = arg.expect_date()?;
+                let today = Self::Date(Date::today(context)?);
+                return date.sub(today, context, int);
+            }
+            BuiltInFunction::Sum | BuiltInFunction::Product => {
+                let mut items = arg.expect_list()?.into_iter();
+                // start from the first item rather than 0 or 1 so that
+                // lists of numbers with units work
+                let first = match items.next() {
+                    Some(first) => first.expect_num()?,
+                    None if func == BuiltInFunction::Sum => Number::from(0),
+                    None => Number::from(1),
+                };
+                items.try_fold(first, |acc, item| {
+                    if func == BuiltInFunction::Sum {
+                        acc.add(item.expect_num()?, int)
+                    } else {
+                        acc.mul(item.expect_num()?, int)
+                    }
+                })?
+            }
         })))
     }
 
+    /// Returns the smallest or largest item of a list for `min` and `max`,
+    /// or its position for `argmin` and `argmax`. Positions start at 1 like
+    /// in spreadsheets, and ties return the first position.
+    fn extremum<I: Interrupt>(
+        func: BuiltInFunction,
+        mut items: Vec<Self>,
+        int: &I,
+    ) -> Result<Self, FendError> {
+        if items.is_empty() {
+            return Err(FendError::EmptyList(func.as_str()));
+        }
+        let ordering = match func {
+            BuiltInFunction::Min | BuiltInFunction::ArgMin => Ordering::Less,
+            _ => Ordering::Greater,
+        };
+        let mut idx = 0;
+        for (i, item) in items.iter().enumerate().skip(1) {
+            if item.order(&items[idx], int)? == ordering {
+                idx = i;
+            }
+        }
+        Ok(match func {
+            BuiltInFunction::Min | BuiltInFunction::Max => items.swap_remove(idx),
+            _ => Self::Num(Box::new(Number::from(idx as u64 + 1))),
+        })
+    }
+
+    /// Returns the `p`th percentile of a list of numbers, or the `p`th
+    /// quartile for `quartile`. Percentiles that fall between two items are
+    /// linearly interpolated between them: the result is the item at
+    /// position `(n - 1) p / 100` of the sorted list (counting from 0), like
+    /// `PERCENTILE.INC` in spreadsheets.
+    fn percentile<I: Interrupt>(
+        func: BuiltInFunction,
+        p: Number,
+        items: Vec<Self>,
+        int: &I,
+    ) -> Result<Number, FendError> {
+        if items.is_empty() {
+            return Err(FendError::EmptyList(func.as_str()));
+        }
+        let p = if func == BuiltInFunction::Quartile {
+            p.expect_in_range(0, 4, int)?.mul(25.into(), int)?
+        } else {
+            p.expect_in_range(0, 100, int)?
+        };
+        let mut items = items
+            .into_iter()
+            .map(Self::expect_num)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut error = None;
+        items.sort_by(|a, b| {
+            a.compare(b, int).unwrap_or_else(|e| {
+                error.get_or_insert(e);
+                Ordering::Equal
+            })
+        });
+        if let Some(e) = error {
+            return Err(e);
+        }
+        let position = Number::from(items.len() as u64 - 1)
+            .mul(p, int)?
+            .div(100.into(), int)?;
+        let nearest = position.clone().round(None, int)?;
+        let mut fraction = position.sub(nearest.clone(), int)?;
+        let mut index = nearest.try_as_usize(int)?;
+        if fraction.compare(&0.into(), int)? == Ordering::Less {
+            // the position was rounded up
+            index -= 1;
+            fraction = fraction.add(1.into(), int)?;
+        }
+        let lower = items[index].clone();
+        if fraction.is_zero() {
+            return Ok(lower);
+        }
+        let difference = items[index + 1].clone().sub(lower.clone(), int)?;
+        lower.add(fraction.mul(difference, int)?, int)
+    }
+
+    /// Returns the great-circle distance between two `(latitude, longitude)`
+    /// points on a sphere for `distance`, using the haversine formula.
+    /// Coordinates without a unit are in degrees. The sphere's radius is an
+    /// optional third argument, and defaults to the mean radius of the
+    /// Earth.
+    fn great_circle_distance<I: Interrupt>(
+        args: Vec<Self>,
+        context: &mut crate::Context,
+        int: &I,
+    ) -> Result<Number, FendError> {
+        let (a, b, radius) = if args.len() == 3 {
+            let [a, b, radius] = Self::List(args).expect_args(BuiltInFunction::Distance)?;
+            (a, b, radius.expect_num()?)
+        } else {
+            let [a, b] = Self::List(args).expect_args(BuiltInFunction::Distance)?;
+            let km = crate::units::query_unit("km", context, int)?.expect_num()?;
+            (a, b, Number::from(6371).mul(km, int)?)
+        };
+        let degree = crate::units::query_unit("°", context, int)?.expect_num()?;
+        let to_angle = |n: Number| {
+            if n.has_no_unit() {
+                n.mul(degree.clone(), int)
+            } else {
+                Ok(n)
+            }
+        };
+        let [lat1, lon1] = a.expect_coordinates()?;
+        let [lat2, lon2] = b.expect_coordinates()?;
+        let (lat1, lon1) = (to_angle(lat1)?, to_angle(lon1)?);
+        let (lat2, lon2) = (to_angle(lat2)?, to_angle(lon2)?);
+        let sin_squared_half = |angle: Number, context: &mut crate::Context| {
+            let sin = angle.div(2.into(), int)?.sin(None, context, int)?;
+            sin.clone().mul(sin, int)
+        };
+        // haversine of the central angle
+        let lat_term = sin_squared_half(lat2.clone().sub(lat1.clone(), int)?, context)?;
+        let lon_term = sin_squared_half(lon2.sub(lon1, int)?, context)?;
+        let h = lat1
+            .cos(None, context, int)?
+            .mul(lat2.cos(None, context, int)?, int)?
+            .mul(lon_term, int)?
+            .add(lat_term, int)?;
+        if h.is_zero() {
+            // identical points, which would otherwise be approximately 0
+            return radius.mul(0.into(), int);
+        }
+        // rounding errors can push h slightly above 1 for antipodal points
+        let h = if h.compare(&1.into(), int)? == Ordering::Greater {
+            Number::from(1)
+        } else {
+            h
+        };
+        let central_angle = h
+            .pow(Number::from(1).div(2.into(), int)?, int)?
+            .asin(int)?
+            .mul(2.into(), int)?;
+        radius.mul(central_angle, int)
+    }
+
+    /// Returns a random item of a list for `choose`, or the whole list in a
+    /// random order for `shuffle`
+    fn random_choice<I: Interrupt>(
+        func: BuiltInFunction,
+        mut items: Vec<Self>,
+        context: &crate::Context,
+        int: &I,
+    ) -> Result<Self, FendError> {
+        if items.is_empty() {
+            return Err(FendError::EmptyList(func.as_str()));
+        }
+        if func == BuiltInFunction::Choose {
+            let idx = context.random_below(items.len())?;
+            return Ok(items.swap_remove(idx));
+        }
+        // Fisher-Yates shuffle
+        for i in (1..items.len()).rev() {
+            test_int(int)?;
+            items.swap(i, context.random_below(i + 1)?);
+        }
+        Ok(Self::List(items))
+    }
+
+    /// Solves the linear system `matrix x = vector` for `solve`, using
+    /// Gaussian elimination. The arithmetic is exact, so singular matrices
+    /// are detected reliably.
+    fn solve_linear_system<I: Interrupt>(
+        matrix: Vec<Self>,
+        vector: Vec<Self>,
+        int: &I,
+    ) -> Result<Self, FendError> {
+        let size = vector.len();
+        if size == 0 || matrix.len() != size {
+            return Err(FendError::InvalidLinearSystem);
+        }
+        // each row of the augmented matrix ends with the item of the vector
+        let mut rows = matrix
+            .into_iter()
+            .zip(vector)
+            .map(|(row, item)| {
+                let mut row = row.expect_list()?;
+                if row.len() != size {
+                    return Err(FendError::InvalidLinearSystem);
+                }
+                row.push(item);
+                row.into_iter().map(Self::expect_num).collect()
+            })
+            .collect::<Result<Vec<Vec<Number>>, _>>()?;
+        for col in 0..size {
+            let pivot = (col..size)
+                .find(|&row| !rows[row][col].is_zero())
+                .ok_or(FendError::NoUniqueSolution)?;
+            rows.swap(col, pivot);
+            let pivot_row = rows[col].clone();
+            for (i, row) in rows.iter_mut().enumerate() {
+                test_int(int)?;
+                if i == col || row[col].is_zero() {
+                    continue;
+                }
+                let factor = row[col].clone().div(pivot_row[col].clone(), int)?;
+                for (item, pivot_item) in row.iter_mut().zip(&pivot_row).skip(col) {
+                    let scaled = factor.clone().mul(pivot_item.clone(), int)?;
+                    *item = item.clone().sub(scaled, int)?;
+                }
+            }
+        }
+        let solution = rows
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut row)| {
+                let rhs = row.pop().unwrap();
+                Ok(Self::Num(Box::new(rhs.div(row.swap_remove(i), int)?)))
+            })
+            .collect::<Result<_, FendError>>()?;
+        Ok(Self::List(solution))
+    }
+
     pub(crate) fn format_to_plain_string<I: Interrupt>(
         &self,
         indent: usize,
@@ -324,6 +1112,7 @@ impl Value {
         Ok(res)
     }
 
+    #[allow(clippy::too_many_lines)]
     pub(crate) fn format<I: Interrupt>(
         &self,
         indent: usize,
@@ -333,40 +1122,50 @@ impl Value {
     ) -> Result<(), FendError> {
         match self {
             Self::Num(n) => {
-                n.clone().simplify(int)?.format(ctx, int)?.spans(spans);
+                n.clone()
+                    .simplify(int)?
+                    .with_unit_display_preference(ctx.unit_display_preference(), int)?
+                    .format(ctx, int)?
+                    .spans(spans);
             }
             Self::BuiltInFunction(name) => {
                 spans.push(Span {
                     string: name.to_string(),
                     kind: SpanKind::BuiltInFunction,
+                    number_format: None,
                 });
             }
             Self::Format(fmt) => {
                 spans.push(Span {
                     string: fmt.to_string(),
                     kind: SpanKind::Keyword,
+                    number_format: None,
                 });
             }
             Self::Dp => {
                 spans.push(Span {
                     string: "dp".to_string(),
                     kind: SpanKind::Keyword,
+                    number_format: None,
                 });
             }
             Self::Sf => {
                 spans.push(Span {
                     string: "sf".to_string(),
                     kind: SpanKind::Keyword,
+                    number_format: None,
                 });
             }
             Self::Base(b) => {
                 spans.push(Span {
                     string: "base ".to_string(),
                     kind: SpanKind::Keyword,
+                    number_format: None,
                 });
                 spans.push(Span {
                     string: b.base_as_u8().to_string(),
                     kind: SpanKind::Number,
+                    number_format: None,
                 });
             }
             Self::Fn(name, expr, _scope) => {
@@ -379,6 +1178,7 @@ impl Value {
                 spans.push(Span {
                     string: res,
                     kind: SpanKind::Other,
+                    number_format: None,
                 });
             }
             Self::Object(kv) => {
@@ -396,34 +1196,71 @@ impl Value {
                 }
                 spans.push(Span::from_string("\n}".to_string()));
             }
+            Self::Uncertain(u) => u.format(spans, ctx, int)?,
             Self::String(s) => {
                 spans.push(Span {
                     string: s.to_string(),
                     kind: SpanKind::String,
+                    number_format: None,
                 });
             }
             Self::Unit => {
                 spans.push(crate::Span {
                     string: "()".to_string(),
                     kind: crate::SpanKind::Ident,
+                    number_format: None,
                 });
             }
             Self::Bool(b) => spans.push(crate::Span {
                 string: b.to_string(),
                 kind: crate::SpanKind::Boolean,
+                number_format: None,
             }),
             Self::Month(m) => spans.push(crate::Span {
                 string: m.to_string(),
                 kind: crate::SpanKind::Date,
+                number_format: None,
             }),
             Self::DayOfWeek(d) => spans.push(crate::Span {
                 string: d.to_string(),
                 kind: crate::SpanKind::Date,
+                number_format: None,
             }),
             Self::Date(d) => spans.push(crate::Span {
                 string: d.to_string(),
                 kind: crate::SpanKind::Date,
+                number_format: None,
             }),
+            Self::Ip(ip) => spans.push(crate::Span {
+                string: ip.to_string(),
+                kind: crate::SpanKind::Other,
+                number_format: None,
+            }),
+            Self::Ratio(r) => {
+                let [antecedent, consequent] = r.parts();
+                Self::Num(Box::new(antecedent.clone())).format(indent, spans, ctx, int)?;
+                spans.push(Span::from_string(":".to_string()));
+                Self::Num(Box::new(consequent.clone())).format(indent, spans, ctx, int)?;
+            }
+            Self::List(items) => {
+                spans.push(Span::from_string("[".to_string()));
+                for (i, item) in items.iter().enumerate() {
+                    if i != 0 {
+                        spans.push(Span::from_string(", ".to_string()));
+                    }
+                    if let Self::String(s) = item {
+                        // quote strings so that e.g. `["a, b"]` is unambiguous
+                        spans.push(Span {
+                            string: format!("{s:?}"),
+                            kind: SpanKind::String,
+                            number_format: None,
+                        });
+                    } else {
+                        item.format(indent, spans, ctx, int)?;
+                    }
+                }
+                spans.push(Span::from_string("]".to_string()));
+            }
         }
         Ok(())
     }
@@ -439,6 +1276,7 @@ impl Value {
                 Err(FendError::CouldNotFindKeyInObject)
             }
             Self::Date(d) => d.get_object_member(key),
+            Self::Ip(ip) => ip.get_object_member(key),
             _ => Err(FendError::ExpectedAnObject),
         }
     }
@@ -473,6 +1311,13 @@ impl fmt::Debug for Value {
             Self::Month(m) => write!(f, "{m}"),
             Self::DayOfWeek(d) => write!(f, "{d}"),
             Self::Date(d) => write!(f, "{d:?}"),
+            Self::List(items) => write!(f, "{items:?}"),
+            Self::Uncertain(u) => write!(f, "{u:?}"),
+            Self::Ip(ip) => write!(f, "{ip}"),
+            Self::Ratio(r) => {
+                let [antecedent, consequent] = r.parts();
+                write!(f, "{antecedent:?}:{consequent:?}")
+            }
         }
     }
 }
@@ -9,6 +9,7 @@ use std::{fmt, io};
 use std::sync::Arc;
 
 use crate::value::Value;
+use crate::Feature;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub(crate) enum BuiltInFunction {
@@ -17,12 +18,22 @@ pub(crate) enum BuiltInFunction {
     Sin,
     Cos,
     Tan,
+    Sec,
+    Csc,
+    Cot,
     Asin,
     Acos,
     Atan,
+    Atan2,
+    Asec,
+    Acsc,
+    Acot,
     Sinh,
     Cosh,
     Tanh,
+    Sech,
+    Csch,
+    Coth,
     Asinh,
     Acosh,
     Atanh,
@@ -30,9 +41,60 @@ pub(crate) enum BuiltInFunction {
     Log2,
     Log10,
     Base,
+    Fixed,
     Sample,
     Not,
     Conjugate,
+    Arg,
+    Re,
+    Im,
+    IsLeapYear,
+    DivMod,
+    ApproxEqual,
+    Change,
+    PctChange,
+    Bits,
+    SetBits,
+    Sum,
+    Product,
+    Min,
+    Max,
+    ArgMin,
+    ArgMax,
+    Span,
+    Percentile,
+    Quartile,
+    Distance,
+    Solve,
+    Zip,
+    Enumerate,
+    Hist,
+    Until,
+    Simplify,
+    Choose,
+    Shuffle,
+    Numerator,
+    Denominator,
+    Round,
+    Nearest,
+    Period,
+    IsNan,
+    IsInfinite,
+    IsFinite,
+    IsInteger,
+    IsRational,
+    IsIrrational,
+    IsComplex,
+    FromBase64,
+    FromHex,
+    Md5,
+    Sha256,
+    Crc32,
+    Next,
+    Last,
+    This,
+    FirstDayOf,
+    LastDayOf,
 }
 
 impl BuiltInFunction {
@@ -44,13 +106,51 @@ impl BuiltInFunction {
         Value::Fn(
             Ident::new_str("x"),
             Box::new(lazy_fn(Box::new(Expr::ApplyFunctionCall(
-                Box::new(Expr::Ident(Ident::new_str(self.as_str()))),
+                Box::new(Expr::Literal(Value::BuiltInFunction(self))),
                 Box::new(Expr::Ident(Ident::new_str("x"))),
             )))),
             scope,
         )
     }
 
+    /// The feature that calling this function counts as using, if any
+    pub(crate) fn feature(self) -> Option<Feature> {
+        Some(match self {
+            Self::Sin
+            | Self::Cos
+            | Self::Tan
+            | Self::Sec
+            | Self::Csc
+            | Self::Cot
+            | Self::Asin
+            | Self::Acos
+            | Self::Atan
+            | Self::Atan2
+            | Self::Asec
+            | Self::Acsc
+            | Self::Acot
+            | Self::Sinh
+            | Self::Cosh
+            | Self::Tanh
+            | Self::Sech
+            | Self::Csch
+            | Self::Coth
+            | Self::Asinh
+            | Self::Acosh
+            | Self::Atanh
+            | Self::Distance => Feature::Trigonometry,
+            Self::Sample | Self::Choose | Self::Shuffle => Feature::Random,
+            Self::Conjugate | Self::Arg | Self::Re | Self::Im => Feature::ComplexNumbers,
+            Self::IsLeapYear
+            | Self::Next
+            | Self::Last
+            | Self::This
+            | Self::FirstDayOf
+            | Self::LastDayOf => Feature::Dates,
+            _ => return None,
+        })
+    }
+
     pub(crate) fn invert(self) -> Result<Value, FendError> {
         Ok(match self {
             Self::Sin => Value::BuiltInFunction(Self::Asin),
@@ -59,6 +159,12 @@ impl BuiltInFunction {
             Self::Asin => Value::BuiltInFunction(Self::Sin),
             Self::Acos => Value::BuiltInFunction(Self::Cos),
             Self::Atan => Value::BuiltInFunction(Self::Tan),
+            Self::Sec => Value::BuiltInFunction(Self::Asec),
+            Self::Csc => Value::BuiltInFunction(Self::Acsc),
+            Self::Cot => Value::BuiltInFunction(Self::Acot),
+            Self::Asec => Value::BuiltInFunction(Self::Sec),
+            Self::Acsc => Value::BuiltInFunction(Self::Csc),
+            Self::Acot => Value::BuiltInFunction(Self::Cot),
             Self::Sinh => Value::BuiltInFunction(Self::Asinh),
             Self::Cosh => Value::BuiltInFunction(Self::Acosh),
             Self::Tanh => Value::BuiltInFunction(Self::Atanh),
@@ -76,12 +182,22 @@ impl BuiltInFunction {
             Self::Sin => "sin",
             Self::Cos => "cos",
             Self::Tan => "tan",
+            Self::Sec => "sec",
+            Self::Csc => "csc",
+            Self::Cot => "cot",
             Self::Asin => "asin",
             Self::Acos => "acos",
             Self::Atan => "atan",
+            Self::Atan2 => "atan2",
+            Self::Asec => "asec",
+            Self::Acsc => "acsc",
+            Self::Acot => "acot",
             Self::Sinh => "sinh",
             Self::Cosh => "cosh",
             Self::Tanh => "tanh",
+            Self::Sech => "sech",
+            Self::Csch => "csch",
+            Self::Coth => "coth",
             Self::Asinh => "asinh",
             Self::Acosh => "acosh",
             Self::Atanh => "atanh",
@@ -89,9 +205,60 @@ impl BuiltInFunction {
             Self::Log2 => "log2",
             Self::Log10 => "log10",
             Self::Base => "base",
+            Self::Fixed => "fixed",
             Self::Sample => "sample",
             Self::Not => "not",
             Self::Conjugate => "conjugate",
+            Self::Arg => "arg",
+            Self::Re => "re",
+            Self::Im => "im",
+            Self::IsLeapYear => "is_leap_year",
+            Self::DivMod => "divmod",
+            Self::ApproxEqual => "approx_equal",
+            Self::Change => "change",
+            Self::PctChange => "pct_change",
+            Self::Bits => "bits",
+            Self::SetBits => "set_bits",
+            Self::Sum => "sum",
+            Self::Product => "product",
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::ArgMin => "argmin",
+            Self::ArgMax => "argmax",
+            Self::Span => "span",
+            Self::Percentile => "percentile",
+            Self::Quartile => "quartile",
+            Self::Distance => "distance",
+            Self::Solve => "solve",
+            Self::Zip => "zip",
+            Self::Enumerate => "enumerate",
+            Self::Hist => "hist",
+            Self::Until => "until",
+            Self::Simplify => "simplify",
+            Self::Choose => "choose",
+            Self::Shuffle => "shuffle",
+            Self::Numerator => "numerator",
+            Self::Denominator => "denominator",
+            Self::Round => "round",
+            Self::Nearest => "nearest",
+            Self::Period => "period",
+            Self::IsNan => "is_nan",
+            Self::IsInfinite => "is_infinite",
+            Self::IsFinite => "is_finite",
+            Self::IsInteger => "is_integer",
+            Self::IsRational => "is_rational",
+            Self::IsIrrational => "is_irrational",
+            Self::IsComplex => "is_complex",
+            Self::FromBase64 => "from_base64",
+            Self::FromHex => "from_hex",
+            Self::Md5 => "md5",
+            Self::Sha256 => "sha256",
+            Self::Crc32 => "crc32",
+            Self::Next => "next",
+            Self::Last => "last",
+            Self::This => "this",
+            Self::FirstDayOf => "first_day_of",
+            Self::LastDayOf => "last_day_of",
         }
     }
 
@@ -102,12 +269,22 @@ impl BuiltInFunction {
             "sin" => Self::Sin,
             "cos" => Self::Cos,
             "tan" => Self::Tan,
+            "sec" => Self::Sec,
+            "csc" => Self::Csc,
+            "cot" => Self::Cot,
             "asin" => Self::Asin,
             "acos" => Self::Acos,
             "atan" => Self::Atan,
+            "atan2" => Self::Atan2,
+            "asec" => Self::Asec,
+            "acsc" => Self::Acsc,
+            "acot" => Self::Acot,
             "sinh" => Self::Sinh,
             "cosh" => Self::Cosh,
             "tanh" => Self::Tanh,
+            "sech" => Self::Sech,
+            "csch" => Self::Csch,
+            "coth" => Self::Coth,
             "asinh" => Self::Asinh,
             "acosh" => Self::Acosh,
             "atanh" => Self::Atanh,
@@ -115,9 +292,60 @@ impl BuiltInFunction {
             "log2" => Self::Log2,
             "log10" => Self::Log10,
             "base" => Self::Base,
+            "fixed" => Self::Fixed,
             "sample" => Self::Sample,
             "not" => Self::Not,
             "conjugate" => Self::Conjugate,
+            "arg" => Self::Arg,
+            "re" => Self::Re,
+            "im" => Self::Im,
+            "is_leap_year" => Self::IsLeapYear,
+            "divmod" => Self::DivMod,
+            "approx_equal" => Self::ApproxEqual,
+            "change" => Self::Change,
+            "pct_change" => Self::PctChange,
+            "bits" => Self::Bits,
+            "set_bits" => Self::SetBits,
+            "sum" => Self::Sum,
+            "product" => Self::Product,
+            "min" => Self::Min,
+            "max" => Self::Max,
+            "argmin" => Self::ArgMin,
+            "argmax" => Self::ArgMax,
+            "span" => Self::Span,
+            "percentile" => Self::Percentile,
+            "quartile" => Self::Quartile,
+            "distance" => Self::Distance,
+            "solve" => Self::Solve,
+            "zip" => Self::Zip,
+            "enumerate" => Self::Enumerate,
+            "hist" => Self::Hist,
+            "until" => Self::Until,
+            "simplify" => Self::Simplify,
+            "choose" => Self::Choose,
+            "shuffle" => Self::Shuffle,
+            "numerator" => Self::Numerator,
+            "denominator" => Self::Denominator,
+            "round" => Self::Round,
+            "nearest" => Self::Nearest,
+            "period" => Self::Period,
+            "is_nan" => Self::IsNan,
+            "is_infinite" => Self::IsInfinite,
+            "is_finite" => Self::IsFinite,
+            "is_integer" => Self::IsInteger,
+            "is_rational" => Self::IsRational,
+            "is_irrational" => Self::IsIrrational,
+            "is_complex" => Self::IsComplex,
+            "from_base64" => Self::FromBase64,
+            "from_hex" => Self::FromHex,
+            "md5" => Self::Md5,
+            "sha256" => Self::Sha256,
+            "crc32" => Self::Crc32,
+            "next" => Self::Next,
+            "last" => Self::Last,
+            "this" => Self::This,
+            "first_day_of" => Self::FirstDayOf,
+            "last_day_of" => Self::LastDayOf,
             _ => return Err(FendError::DeserializationError),
         })
     }
@@ -0,0 +1,187 @@
+use crate::ast::Bop;
+use crate::error::{FendError, Interrupt};
+use crate::num::{FormattingStyle, Number};
+use crate::value::Value;
+use crate::Span;
+use std::{fmt, io};
+
+/// A number with an absolute uncertainty, e.g. `5 ± 0.1 cm`. Uncertainties
+/// are propagated using the standard first-order formulas, assuming that
+/// the uncertainties of different values are independent.
+#[derive(Clone)]
+pub(crate) struct Uncertain {
+    value: Number,
+    uncertainty: Number,
+}
+
+impl Uncertain {
+    /// If only the uncertainty has a unit, it also applies to the value, so
+    /// `5 ± 0.1 cm` is the same as `(5 ± 0.1) cm`
+    pub(crate) fn new<I: Interrupt>(
+        value: Number,
+        uncertainty: Number,
+        int: &I,
+    ) -> Result<Self, FendError> {
+        let value = if value.is_unitless(int)? {
+            value.with_unit_of(&uncertainty)
+        } else {
+            value
+        };
+        let uncertainty = uncertainty.abs(int)?.convert_to_unit_of(&value, int)?;
+        Ok(Self { value, uncertainty })
+    }
+
+    fn from_value<I: Interrupt>(value: Value, int: &I) -> Result<Self, FendError> {
+        match value {
+            Value::Num(n) => Ok(Self {
+                uncertainty: n.clone().mul(0.into(), int)?,
+                value: *n,
+            }),
+            Value::Uncertain(u) => Ok(*u),
+            _ => Err(FendError::ExpectedANumber),
+        }
+    }
+
+    pub(crate) fn serialize(&self, write: &mut impl io::Write) -> Result<(), FendError> {
+        self.value.serialize(write)?;
+        self.uncertainty.serialize(write)?;
+        Ok(())
+    }
+
+    pub(crate) fn deserialize(read: &mut impl io::Read) -> Result<Self, FendError> {
+        Ok(Self {
+            value: Number::deserialize(read)?,
+            uncertainty: Number::deserialize(read)?,
+        })
+    }
+
+    /// Applies a binary operator where at least one operand has an
+    /// uncertainty
+    pub(crate) fn bop<I: Interrupt>(
+        op: Bop,
+        lhs: Value,
+        rhs: Value,
+        int: &I,
+    ) -> Result<Value, FendError> {
+        if op == Bop::Pow {
+            if let Value::Uncertain(_) = rhs {
+                return Err(FendError::UncertainExponent);
+            }
+            return Ok(Value::Uncertain(Box::new(
+                Self::from_value(lhs, int)?.pow(rhs.expect_num()?, int)?,
+            )));
+        }
+        let (a, b) = (Self::from_value(lhs, int)?, Self::from_value(rhs, int)?);
+        let result = match op {
+            Bop::Plus => Self {
+                value: a.value.add(b.value, int)?,
+                uncertainty: hypot(a.uncertainty, b.uncertainty, int)?,
+            },
+            Bop::Minus => Self {
+                value: a.value.sub(b.value, int)?,
+                uncertainty: hypot(a.uncertainty, b.uncertainty, int)?,
+            },
+            // σ² = (b σa)² + (a σb)²
+            Bop::Mul => Self {
+                uncertainty: hypot(
+                    b.value.clone().mul(a.uncertainty, int)?,
+                    a.value.clone().mul(b.uncertainty, int)?,
+                    int,
+                )?,
+                value: a.value.mul(b.value, int)?,
+            },
+            // σ² = (σa / b)² + (a σb / b²)²
+            Bop::Div => Self {
+                uncertainty: hypot(
+                    a.uncertainty.div(b.value.clone(), int)?,
+                    a.value
+                        .clone()
+                        .mul(b.uncertainty, int)?
+                        .div(b.value.clone().mul(b.value.clone(), int)?, int)?,
+                    int,
+                )?,
+                value: a.value.div(b.value, int)?,
+            },
+            _ => return Err(FendError::UnsupportedUncertainOperation(op.to_string())),
+        };
+        Ok(Value::Uncertain(Box::new(result)))
+    }
+
+    /// σ = |n a^(n - 1) σa|
+    fn pow<I: Interrupt>(self, exponent: Number, int: &I) -> Result<Self, FendError> {
+        let derivative = self
+            .value
+            .clone()
+            .pow(exponent.clone().sub(1.into(), int)?, int)?
+            .mul(exponent.clone(), int)?;
+        Ok(Self {
+            uncertainty: derivative.mul(self.uncertainty, int)?.abs(int)?,
+            value: self.value.pow(exponent, int)?,
+        })
+    }
+
+    pub(crate) fn neg(self) -> Self {
+        Self {
+            value: -self.value,
+            uncertainty: self.uncertainty,
+        }
+    }
+
+    pub(crate) fn convert_to<I: Interrupt>(self, unit: Number, int: &I) -> Result<Self, FendError> {
+        Ok(Self {
+            value: self.value.convert_to(unit.clone(), int)?,
+            uncertainty: self.uncertainty.convert_to(unit, int)?,
+        })
+    }
+
+    pub(crate) fn with_format(self, format: FormattingStyle) -> Self {
+        Self {
+            value: self.value.with_format(format),
+            uncertainty: self.uncertainty.with_format(format),
+        }
+    }
+
+    pub(crate) fn format<I: Interrupt>(
+        &self,
+        spans: &mut Vec<Span>,
+        ctx: &crate::Context,
+        int: &I,
+    ) -> Result<(), FendError> {
+        let value = self
+            .value
+            .clone()
+            .simplify(int)?
+            .with_unit_display_preference(ctx.unit_display_preference(), int)?;
+        let uncertainty = self
+            .uncertainty
+            .clone()
+            .convert_to_unit_of(&value, int)?
+            .format(ctx, int)?;
+        value
+            .format(ctx, int)?
+            .spans_with_uncertainty(uncertainty, spans);
+        Ok(())
+    }
+}
+
+/// Returns `sqrt(a² + b²)`, where `a` and `b` have the same unit
+fn hypot<I: Interrupt>(a: Number, b: Number, int: &I) -> Result<Number, FendError> {
+    if b.is_zero() {
+        return a.abs(int);
+    }
+    if a.is_zero() {
+        return b.abs(int);
+    }
+    // a sqrt(1 + (b/a)²) avoids taking the square root of a unit
+    let ratio = b.div(a.clone(), int)?;
+    let root = Number::from(1)
+        .add(ratio.clone().mul(ratio, int)?, int)?
+        .pow(Number::from(1).div(2.into(), int)?, int)?;
+    a.abs(int)?.mul(root, int)
+}
+
+impl fmt::Debug for Uncertain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} ± {:?}", self.value, self.uncertainty)
+    }
+}
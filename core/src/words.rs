@@ -0,0 +1,213 @@
+//! Spelling out numbers in English, for `to words` and `to ordinal`, and
+//! reading them back in, e.g. `forty-two plus eight`
+
+const ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+/// Names of successive powers of 1000 (short scale)
+const SCALES: [&str; 7] = [
+    "",
+    "thousand",
+    "million",
+    "billion",
+    "trillion",
+    "quadrillion",
+    "quintillion",
+];
+
+/// Spells out a number from 1 to 999
+fn push_below_thousand(n: usize, words: &mut Vec<String>) {
+    if n >= 100 {
+        words.push(ONES[n / 100].to_string());
+        words.push("hundred".to_string());
+    }
+    match n % 100 {
+        0 => (),
+        rest @ 1..=19 => words.push(ONES[rest].to_string()),
+        rest if rest % 10 == 0 => words.push(TENS[rest / 10].to_string()),
+        rest => words.push(format!("{}-{}", TENS[rest / 10], ONES[rest % 10])),
+    }
+}
+
+/// Spells out `n`, e.g. "one thousand two hundred thirty-four"
+pub(crate) fn to_words(n: usize) -> String {
+    if n == 0 {
+        return ONES[0].to_string();
+    }
+    let mut groups = vec![];
+    let mut rest = n;
+    while rest > 0 {
+        groups.push(rest % 1000);
+        rest /= 1000;
+    }
+    let mut words = vec![];
+    for (scale, &group) in groups.iter().enumerate().rev() {
+        if group == 0 {
+            continue;
+        }
+        push_below_thousand(group, &mut words);
+        if scale > 0 {
+            words.push(SCALES[scale].to_string());
+        }
+    }
+    words.join(" ")
+}
+
+/// Spells out `n` as an ordinal, e.g. "forty-second"
+pub(crate) fn to_ordinal(n: usize) -> String {
+    let cardinal = to_words(n);
+    // only the last word changes, e.g. "twenty-one" becomes "twenty-first"
+    let split = cardinal.rfind([' ', '-']).map_or(0, |i| i + 1);
+    let (start, last) = cardinal.split_at(split);
+    let last = match last {
+        "one" => "first".to_string(),
+        "two" => "second".to_string(),
+        "three" => "third".to_string(),
+        "five" => "fifth".to_string(),
+        "eight" => "eighth".to_string(),
+        "nine" => "ninth".to_string(),
+        "twelve" => "twelfth".to_string(),
+        word => match word.strip_suffix('y') {
+            Some(stem) => format!("{stem}ieth"),
+            None => format!("{word}th"),
+        },
+    };
+    format!("{start}{last}")
+}
+
+#[derive(Copy, Clone)]
+enum NumberWord {
+    // from 1 to 19
+    Ones(u64),
+    // from 20 to 90
+    Tens(u64),
+    Hundred,
+    // index into `SCALES`
+    Scale(usize),
+}
+
+/// Splits a word off the start of the input, returning `None` if it is
+/// not a number word. "zero" isn't a number word here, since it can't be
+/// combined with others.
+fn parse_number_word(input: &str) -> Option<(NumberWord, &str)> {
+    let len = input
+        .find(|ch: char| !ch.is_ascii_lowercase())
+        .unwrap_or(input.len());
+    let (word, rest) = input.split_at(len);
+    // e.g. `tenth` or `one_two`
+    if let (Some(last), Some(next)) = (word.chars().last(), rest.chars().next()) {
+        if crate::lexer::is_valid_in_ident(next, Some(last)) {
+            return None;
+        }
+    }
+    let word = if word == "hundred" {
+        NumberWord::Hundred
+    } else if let Some(n) = ONES[1..].iter().position(|&w| w == word) {
+        NumberWord::Ones(n as u64 + 1)
+    } else if let Some(n) = TENS[2..].iter().position(|&w| w == word) {
+        NumberWord::Tens(10 * (n as u64 + 2))
+    } else if let Some(scale) = SCALES[1..].iter().position(|&w| w == word) {
+        NumberWord::Scale(scale + 1)
+    } else {
+        return None;
+    };
+    Some((word, rest))
+}
+
+/// Reads a sequence of at least two English number words, e.g. "one
+/// hundred twenty-three" or "two thousand and five", returning the number
+/// and the remaining input. A single word like `ten` is left alone, so it
+/// can still be used as a variable name.
+pub(crate) fn parse_number_words(input: &str) -> Option<(u64, &str)> {
+    let (mut word, mut rest) = parse_number_word(input)?;
+    let mut last = None;
+    let mut end = rest;
+    let mut count = 0;
+    // `total` holds the completed scales, e.g. 2000 of "two thousand five"
+    let mut total = 0_u64;
+    let mut current = 0_u64;
+    let mut smallest_scale = SCALES.len();
+    loop {
+        match word {
+            // e.g. the "one" of "twenty one"
+            NumberWord::Ones(n)
+                if current.is_multiple_of(100)
+                    || n < 10 && matches!(last, Some(NumberWord::Tens(_))) =>
+            {
+                current += n;
+            }
+            NumberWord::Tens(n) if current.is_multiple_of(100) => current += n,
+            NumberWord::Hundred if (1..100).contains(&current) => current *= 100,
+            NumberWord::Scale(scale) if current > 0 && scale < smallest_scale => {
+                let scale_value = 1000_u64.checked_pow(u32::try_from(scale).ok()?)?;
+                total = total.checked_add(current.checked_mul(scale_value)?)?;
+                current = 0;
+                smallest_scale = scale;
+            }
+            _ => break,
+        }
+        count += 1;
+        last = Some(word);
+        end = rest;
+        // e.g. "forty-two"
+        let hyphenated = matches!(word, NumberWord::Tens(_))
+            .then(|| rest.strip_prefix('-'))
+            .flatten()
+            .and_then(parse_number_word)
+            .filter(|(next, _)| matches!(next, NumberWord::Ones(1..=9)));
+        if let Some((next, after)) = hyphenated {
+            (word, rest) = (next, after);
+            continue;
+        }
+        let Some(after_space) = skip_spaces(rest) else {
+            break;
+        };
+        // e.g. "one hundred and five", but not "one and two"
+        let after_and = after_space
+            .strip_prefix("and")
+            .filter(|_| matches!(word, NumberWord::Hundred | NumberWord::Scale(_)))
+            .and_then(skip_spaces);
+        let Some((next, after)) = parse_number_word(after_and.unwrap_or(after_space)) else {
+            break;
+        };
+        if after_and.is_some() && !matches!(next, NumberWord::Ones(_) | NumberWord::Tens(_)) {
+            break;
+        }
+        (word, rest) = (next, after);
+    }
+    if count < 2 {
+        return None;
+    }
+    Some((total.checked_add(current)?, end))
+}
+
+/// Skips spaces between words on the same line, returning `None` if there
+/// are none
+fn skip_spaces(input: &str) -> Option<&str> {
+    let rest = input.trim_start_matches(|ch: char| ch.is_whitespace() && ch != '\n');
+    (rest.len() < input.len()).then_some(rest)
+}
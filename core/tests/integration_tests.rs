@@ -1,4 +1,4 @@
-use fend_core::{evaluate, Context};
+use fend_core::{conversion_factor, evaluate, Context};
 
 #[track_caller]
 fn test_serialization_roundtrip(context: &mut Context) {
@@ -92,6 +92,15 @@ fn ten_whitespace_both() {
 #[test]
 fn blank_input() {
     test_eval("", "");
+    test_eval("   ", "");
+    test_eval("\t\n ", "");
+    test_eval("# just a comment", "");
+    let mut ctx = Context::new();
+    for input in ["", "   ", "  # comment"] {
+        let result = evaluate(input, &mut ctx).unwrap();
+        assert!(result.is_unit_type());
+        assert!(result.warnings().is_empty());
+    }
 }
 
 #[test]
@@ -701,6 +710,50 @@ fn sqrt_kg_squared_2() {
     test_eval("(sqrt kg)^2", "1 kg");
 }
 
+#[test]
+fn sqrt_m_squared() {
+    test_eval("sqrt(m^2)", "1 m");
+    test_eval("sqrt(1 m^2)", "1 m");
+    test_eval("(1 m^2)^(1/2)", "1 m");
+}
+
+#[test]
+fn cbrt_m_cubed() {
+    test_eval("cbrt(m^3)", "1 m");
+    test_eval("(8 m^3)^(1/3)", "2 m");
+}
+
+#[test]
+fn sqrt_m_has_fractional_dimension() {
+    test_eval("sqrt(m)", "1 m^0.5");
+    test_eval("sqrt(m) * sqrt(m)", "1 m");
+    test_eval("(m^2)^(1/3)", "1 m^(2/3)");
+}
+
+#[test]
+fn negative_and_fractional_unit_exponents() {
+    test_eval("kg m^-2", "1 kg / m^2");
+    test_eval("kg m^(-2)", "1 kg / m^2");
+    test_eval("kg m^-2 + kg/m^2", "2 kg / m^2");
+    test_eval("3 kg m^-2 to g/cm^2", "0.3 g / cm^2");
+    test_eval_simple("kg m^-2 == kg/m^2", "true");
+    test_eval("m^(1/2)", "1 m^0.5");
+    test_eval("2 m^(1/2) + 3 m^0.5", "5 m^0.5");
+    test_eval("m^(1/2) * m^(1/2)", "1 m");
+    test_eval("m^(1/3) m^(2/3) kg", "1 m kg");
+    test_eval("m^-0.5 kg", "1 kg / m^0.5");
+    expect_error(
+        "m^(1/2) to m",
+        Some("cannot convert from m^0.5 to m: units 'meter^0.5' and 'meter' are incompatible"),
+    );
+}
+
+#[test]
+fn m_to_the_fourth_to_the_half() {
+    test_eval("(m^4)^(1/2)", "1 m^2");
+    test_eval("sqrt(m^2 kg^4)", "1 m kg^2");
+}
+
 #[test]
 fn lightyear_to_parsecs() {
     test_eval("1 lightyear to parsecs", "approx. 0.3066013937 parsecs");
@@ -1220,7 +1273,15 @@ fn pi_to_the_power_of_ten() {
 
 #[test]
 fn zero_to_the_power_of_zero() {
-    expect_error("0^0", None);
+    test_eval("0^0", "1");
+    test_eval("0.0^0", "1");
+}
+
+#[test]
+fn zero_to_fractional_and_negative_powers() {
+    test_eval("0^0.5", "0");
+    expect_error("0^-1", Some("division by zero"));
+    expect_error("0^-2", Some("division by zero"));
 }
 
 #[test]
@@ -3194,6 +3255,123 @@ fn atanh_minus_one_point_zero_one() {
     expect_error("atanh (-1.01)", None);
 }
 
+#[test]
+fn reciprocal_trig_functions() {
+    test_eval("sec 0", "1");
+    test_eval("sec(pi/3)", "2");
+    test_eval("sec(60 degrees)", "2");
+    test_eval("csc(pi/2)", "1");
+    test_eval("csc(30\u{b0})", "2");
+    test_eval("cot(pi/2)", "0");
+    test_eval("cot 1", "approx. 0.6420926159");
+    test_eval("sec 2", "approx. -2.4029979617");
+    expect_error("sec(pi/2)", Some("sec is undefined for this value"));
+    expect_error("csc 0", Some("csc is undefined for this value"));
+    expect_error("cot 0", Some("cot is undefined for this value"));
+    expect_error("cot pi", Some("cot is undefined for this value"));
+}
+
+#[test]
+fn sec_is_still_a_unit() {
+    test_eval("5 sec", "5 secs");
+    test_eval("sec", "1 sec");
+    test_eval("3 sec (2)", "6 secs");
+    test_eval("sec = x: x + 1; sec 2", "3");
+}
+
+#[test]
+fn inverse_reciprocal_trig_functions() {
+    test_eval("asec 2", "approx. 1.0471975511");
+    test_eval("asec 2 ~= pi/3", "true");
+    test_eval("asec 1", "approx. 0");
+    test_eval("asec (-1)", "approx. 3.1415926535");
+    test_eval("acsc 2", "approx. 0.5235987755");
+    test_eval("acsc 1", "approx. 1.5707963267");
+    test_eval("acsc (-1)", "approx. -1.5707963267");
+    test_eval("acot 1", "approx. 0.7853981633");
+    test_eval("acot 0", "approx. 1.5707963267");
+    test_eval("acot (-1)", "approx. -0.7853981633");
+    expect_error("asec 0.5", Some("0.5 must not lie in the interval (-1, 1)"));
+    expect_error("asec 0", Some("0 must not lie in the interval (-1, 1)"));
+    expect_error(
+        "acsc (-0.99)",
+        Some("-0.99 must not lie in the interval (-1, 1)"),
+    );
+    test_eval("sec^-1", "1 sec^-1");
+    test_eval("(asec^-1)(pi/3)", "2");
+    test_eval("csc^-1", "acsc");
+    test_eval("acot^-1", "cot");
+}
+
+#[test]
+fn atan2() {
+    test_eval("atan2(1, 0)", "approx. 1.5707963267");
+    test_eval("atan2(1, 0) to degrees", "90 degrees");
+    test_eval("atan2(-1, 0) to degrees", "-90 degrees");
+    test_eval("atan2(0, -1) to degrees", "180 degrees");
+    test_eval("atan2(0, -1) == pi", "true");
+    test_eval("atan2(0, 5)", "0");
+    test_eval("atan2(0, 0)", "0");
+    test_eval("atan2(1, 1)", "approx. 0.7853981633");
+    test_eval("atan2(1, -1)", "approx. 2.3561944901");
+    test_eval("atan2(-1, -1)", "approx. -2.3561944901");
+    test_eval("atan2(-1, 1)", "approx. -0.7853981633");
+    test_eval("atan2(3 m, 400 cm)", "approx. 0.6435011087");
+    expect_error("atan2(1)", Some("atan2 expects 2 arguments"));
+    expect_error("atan2(1 m, 1 s)", None);
+}
+
+#[test]
+fn polar_and_cartesian_coordinates() {
+    test_eval("(3, 4) to polar", "[5, approx. 0.927295218]");
+    test_eval("[-1, 0] to polar", "[1, approx. 3.1415926535]");
+    test_eval("(0, 0) to polar", "[0, 0]");
+    test_eval("(3 m, 4 m) to polar", "[5 m, approx. 0.927295218]");
+    test_eval("(2, 90 degrees) to cartesian", "[0, 2]");
+    test_eval("(2, pi/3) to cartesian", "[1, approx. 1.7320508075]");
+    test_eval(
+        "((3, 4) to polar) to cartesian",
+        "[approx. 3, approx. 3.9999999999]",
+    );
+    expect_error(
+        "(1, 2, 3) to polar",
+        Some("expected a list of two coordinates, e.g. (3, 4)"),
+    );
+    expect_error(
+        "5 to cartesian",
+        Some("expected a list of two coordinates, e.g. (3, 4)"),
+    );
+}
+
+#[test]
+fn unit_system_conversions() {
+    test_eval("1 dyn to si", "0.00001 N");
+    test_eval("1 erg to si", "0.0000001 J");
+    test_eval("1 kWh to SI", "3600000 J");
+    test_eval("1 mile to si", "1609.344 m");
+    test_eval("20 °C to si", "293.15 K");
+    test_eval("1 J to cgs", "10000000 ergs");
+    test_eval("1 N to cgs", "100000 dyn");
+    test_eval("1 atm to cgs", "1013250 Ba");
+    test_eval("9.8 m/s^2 to cgs", "980 Gal");
+    test_eval("1 Pa s to cgs", "10 poise");
+    test_eval("1 W to CGS", "10000000 g cm^2 / s^3");
+    test_eval("60 mph to cgs", "2682.24 cm / s");
+    test_eval("5 to si", "5");
+    expect_error("1 A to cgs", Some("ampere has no equivalent in CGS units"));
+    expect_error("1 byte to si", Some("bit has no equivalent in SI units"));
+}
+
+#[test]
+fn reciprocal_hyperbolic_functions() {
+    test_eval("sech 0", "approx. 1");
+    test_eval("sech 1", "approx. 0.6480542736");
+    test_eval("csch 1", "approx. 0.8509181282");
+    test_eval("coth 1", "approx. 1.3130352854");
+    expect_error("csch 0", Some("csch is undefined for this value"));
+    expect_error("coth 0", Some("coth is undefined for this value"));
+}
+
 #[test]
 fn atanh_1() {
     expect_error("atanh 1", None);
@@ -3274,9 +3452,34 @@ fn log2_minus_1() {
     expect_error("log2 (-1)", None);
 }
 
+#[test]
+fn symbolic_square_roots() {
+    test_eval_simple("sqrt 2 to exact", "\u{221a}2");
+    test_eval_simple("sqrt 8 to exact", "2\u{221a}2");
+    test_eval_simple("sqrt(2/3) to exact", "\u{221a}6/3");
+    test_eval_simple("sqrt(-2) to exact", "\u{221a}2i");
+    test_eval_simple("a = sqrt 3; 2a to exact", "2\u{221a}3");
+    test_eval("sqrt 2 * sqrt 2", "2");
+    test_eval("sqrt 2 * sqrt 8", "4");
+    test_eval_simple("sqrt 6 / sqrt 2 to exact", "\u{221a}3");
+    test_eval("sqrt 2", "approx. 1.4142135619");
+}
+
 #[test]
 fn sqrt_minus_two() {
-    expect_error("sqrt (-2)", None);
+    test_eval("sqrt (-2)", "approx. 1.4142135619i");
+}
+
+#[test]
+fn roots_of_negative_numbers() {
+    test_eval("(-1)^0.5", "i");
+    test_eval("sqrt(-4)", "2i");
+    test_eval("(-4)^1.5", "-8i");
+    test_eval("(-4)^-0.5", "-0.5i");
+    expect_error(
+        "(-8)^(1/3)",
+        Some("roots of negative numbers are not supported"),
+    );
 }
 
 #[test]
@@ -5084,7 +5287,6 @@ fn unicode_escape_aaa_uppercase() {
 }
 
 #[test]
-#[ignore]
 fn today() {
     let mut context = Context::new();
     context.set_current_time_v1(1617517099000, 0);
@@ -5095,10 +5297,9 @@ fn today() {
 }
 
 #[test]
-#[ignore]
 fn today_with_tz() {
     let mut context = Context::new();
-    context.set_current_time_v1(1619943083155, 43200);
+    context.set_current_time_v1(1619943083155, -43200);
     assert_eq!(
         evaluate("today", &mut context).unwrap().get_main_result(),
         "Sunday, 2 May 2021"
@@ -5140,6 +5341,16 @@ fn single_line_comment_and_linebreak_3() {
     test_eval("30.48cm to # converting cm\n ' # to feet", "1'");
 }
 
+#[test]
+fn block_comments() {
+    test_eval("1 + /* two */ 2", "3");
+    test_eval("/* multiple\nlines */ 5 kg", "5 kg");
+    test_eval("2 /* unterminated", "2");
+    test_eval("/* only a comment */", "");
+    test_eval_simple("\"a /* b */ # c\"", "a /* b */ # c");
+    test_eval("6/2 /**/", "3");
+}
+
 #[test]
 fn percent_plus_per_mille() {
     test_eval("4% + 3\u{2030}", "4.3%");
@@ -5397,6 +5608,47 @@ fn test_rolling_dice() {
     evaluate("roll d20", &mut ctx).unwrap();
 }
 
+#[test]
+fn host_functions() {
+    use fend_core::Value;
+
+    let mut ctx = Context::new();
+    ctx.define_function("price", |args| match args {
+        [Value::String(item), Value::Number(n)] if item == "apple" => Ok(Value::Number(0.5 * n)),
+        [Value::String(item), _] => Err(format!("unknown item '{item}'")),
+        _ => Err("price expects an item and a quantity".to_string()),
+    });
+    ctx.define_function("describe_args", |args| {
+        Ok(Value::String(format!("{args:?}")))
+    });
+    ctx.define_function("broken", |_| panic!("oops"));
+    let mut eval = |input: &str| evaluate(input, &mut ctx).map(|r| r.get_main_result().to_string());
+    assert_eq!(eval("price('apple', 3)"), Ok("1.5".to_string()));
+    assert_eq!(eval("price('apple', 2 + 2) * 2"), Ok("4".to_string()));
+    assert_eq!(
+        eval("describe_args(1, 'a', true, [2, 3])"),
+        Ok(
+            "[Number(1.0), String(\"a\"), Bool(true), List([Number(2.0), Number(3.0)])]"
+                .to_string()
+        )
+    );
+    assert_eq!(
+        eval("price('pear', 1)"),
+        Err("unknown item 'pear'".to_string())
+    );
+    assert_eq!(
+        eval("price 'apple'"),
+        Err("price expects an item and a quantity".to_string())
+    );
+    assert_eq!(
+        eval("price('apple', 1 kg)"),
+        Err("expected a unitless number".to_string())
+    );
+    assert_eq!(eval("broken(1)"), Err("broken panicked".to_string()));
+    // variables take precedence
+    assert_eq!(eval("price = 5; price"), Ok("5".to_string()));
+}
+
 #[test]
 fn test_d6() {
     test_eval_simple(
@@ -5441,6 +5693,59 @@ fn test_invalid_dice_syntax_5() {
     );
 }
 
+#[test]
+fn keep_highest_and_lowest_dice() {
+    test_eval_simple(
+        "2d6kh1",
+        "{ 1: 2.78%, 2: 8.33%, 3: 13.89%, 4: 19.44%, 5: 25.00%, 6: 30.56% }",
+    );
+    test_eval_simple(
+        "2d6kl1",
+        "{ 1: 30.56%, 2: 25.00%, 3: 19.44%, 4: 13.89%, 5: 8.33%, 6: 2.78% }",
+    );
+    let mut ctx = Context::new();
+    for (a, b) in [("3d6kh3", "3d6"), ("1d6kl1", "d6")] {
+        assert_eq!(
+            evaluate(a, &mut ctx).unwrap().get_main_result(),
+            evaluate(b, &mut ctx).unwrap().get_main_result()
+        );
+    }
+    expect_error("4d6kh5", Some("invalid dice syntax, try e.g. `4d6`"));
+    expect_error("4d6kl0", Some("invalid dice syntax, try e.g. `4d6`"));
+}
+
+#[test]
+fn choose_and_shuffle() {
+    let mut ctx = Context::new();
+    // u32::MAX would make the first item more likely, so it is rejected
+    let mut randoms = vec![u32::MAX, 4].into_iter();
+    ctx.set_random_u32_fn(move || randoms.next().unwrap());
+    assert_eq!(
+        evaluate("choose [\"a\", \"b\", \"c\"]", &mut ctx)
+            .unwrap()
+            .get_main_result(),
+        "b"
+    );
+
+    ctx.set_random_u32_fn(|| 0);
+    assert_eq!(
+        evaluate("shuffle [1, 2, 3, 4]", &mut ctx)
+            .unwrap()
+            .get_main_result(),
+        "[2, 3, 4, 1]"
+    );
+    assert_eq!(
+        evaluate("choose [5 kg]", &mut ctx)
+            .unwrap()
+            .get_main_result(),
+        "5 kg"
+    );
+
+    expect_error("choose []", Some("choose is undefined for an empty list"));
+    expect_error("shuffle []", Some("shuffle is undefined for an empty list"));
+    expect_error("choose 5", Some("expected a list (found number)"));
+}
+
 #[test]
 fn unit_literal() {
     test_eval("()", "()");
@@ -5457,6 +5762,110 @@ fn empty_statements() {
     test_eval(";2;;3;a=4;;4a;;;()", "()");
 }
 
+#[test]
+fn date_literals() {
+    test_eval_simple("@2024-01-01", "Monday, 1 January 2024");
+    test_eval_simple("@2024-01-01 15:30", "Monday, 1 January 2024 15:30");
+    test_eval_simple("@January 1 2024", "Monday, 1 January 2024");
+    test_eval_simple("@1 Jan 2024", "Monday, 1 January 2024");
+    test_eval_simple("@unix:1704067200", "Monday, 1 January 2024 00:00");
+    test_eval_simple("@Jan 1, 2024 + 3 days", "Thursday, 4 January 2024");
+    test_eval_simple("day_of_week of @2024-07-04", "Thursday");
+}
+
+#[test]
+fn date_literals_round_trip() {
+    for input in [
+        "@2024-01-01",
+        "@2024-01-01 15:30",
+        "@January 1 2024",
+        "@1 Jan 2024",
+        "@unix:1704067200",
+    ] {
+        let mut context = Context::new();
+        let result = evaluate(input, &mut context).unwrap();
+        let result = result.get_main_result();
+        let round_trip = format!("@{result}");
+        assert_eq!(
+            evaluate(&round_trip, &mut context)
+                .unwrap()
+                .get_main_result(),
+            result
+        );
+        test_eval_simple(&format!("{input} == {round_trip}"), "true");
+    }
+    test_eval_simple("@2024-01-01 == @unix:1704067200", "true");
+    test_eval_simple("@2024-01-01 == @2024-01-01 15:30", "false");
+}
+
+#[test]
+fn invalid_date_literals() {
+    expect_error(
+        "@2024-02-30",
+        Some("failed to convert '2024-02-30' to a date"),
+    );
+    expect_error("@30 Feb 2024", None);
+    expect_error("@2023-02-29", None);
+    expect_error("@2024-01-01 25:00", None);
+    expect_error("@Tuesday, 1 January 2024", None);
+    expect_error("@2024-01-01x", None);
+    expect_error("@", None);
+}
+
+#[test]
+fn date_to_weekday() {
+    test_eval_simple("@2024-07-04 to weekday", "Thursday");
+    test_eval_simple("@2000-01-01 to day_of_week", "Saturday");
+    expect_error("5 to weekday", Some("cannot convert value to weekday"));
+}
+
+#[test]
+fn subtract_dates() {
+    test_eval_simple("@2024-12-25 - @2024-01-01", "359 days");
+    test_eval_simple("@2024-01-01 - @2024-12-25", "-359 days");
+    test_eval_simple("@2024-03-01 - @2024-02-28", "2 days");
+    test_eval_simple("@2023-03-01 - @2023-02-28", "1 day");
+    test_eval_simple("@2024-01-02 - @2024-01-01 12:00", "0.5 days");
+    test_eval_simple("@2024-01-01 - 1 day", "Sunday, 31 December 2023");
+    test_eval_simple("@2024-01-01 - 1000000 days", "Sunday, 3 February 715 BC");
+    expect_error(
+        "@2024-01-01 + 1000000000000 days",
+        Some("date is out of range"),
+    );
+}
+
+#[test]
+fn days_until() {
+    let mut context = Context::new();
+    // Sunday, 4 April 2021
+    context.set_current_time_v1(1617517099000, 0);
+    for (input, expected) in [
+        ("days until @2021-12-25", "265 days"),
+        ("until @2021-04-11", "7 days"),
+        ("weeks until @2021-04-18", "2 weeks"),
+        ("days until @2021-04-01", "-3 days"),
+    ] {
+        assert_eq!(
+            evaluate(input, &mut context).unwrap().get_main_result(),
+            expected
+        );
+    }
+    expect_error(
+        "days until @2021-12-25",
+        Some("unable to get the current date"),
+    );
+    expect_error("until 5", Some("expected a date (found number)"));
+}
+
+#[test]
+fn is_leap_year() {
+    test_eval_simple("is_leap_year 2024", "true");
+    test_eval_simple("is_leap_year 2023", "false");
+    test_eval_simple("is_leap_year 1900", "false");
+    test_eval_simple("is_leap_year 2000", "true");
+    test_eval_simple("is_leap_year @2024-07-04", "true");
+}
+
 #[test]
 fn add_days_to_date() {
     test_eval_simple(
@@ -5485,6 +5894,24 @@ fn planck() {
     test_eval("planck", "0.000000000000000000000000000000000662607015 J s");
 }
 
+#[test]
+fn exact_si_constants() {
+    test_eval("c * 1 s to 20 sf", "299792458 m");
+    test_eval("c to km/s to 10 dp", "299792.458 km / s");
+    test_eval_simple("1 / c to s/m to exact", "1/299792458 s / m");
+    test_eval(
+        "electron_charge to coulomb to 30 dp",
+        "0.0000000000000000001602176634 coulomb",
+    );
+    test_eval(
+        "boltzmann * avogadro to J / K / mol",
+        "8.31446261815324 J K^-1 mol^-1",
+    );
+    test_eval("caesium_frequency", "9192631770 Hz");
+    test_eval("cesium_frequency to GHz", "9.19263177 GHz");
+    test_eval("luminous_efficacy", "683 lm / W");
+}
+
 #[test]
 fn implicit_unit_fudging() {
     test_eval("5'1 to m to 2dp", "approx. 1.54 m");
@@ -5644,3 +6071,2828 @@ fn rshift() {
 fn shift_and_and() {
     test_eval("54 << 1 & 54 >> 1", "8");
 }
+
+// Mirrors `evaluate_fend_with_timeout_multiple` in the WASM API, which
+// evaluates '\0'-separated inputs in a single context
+fn evaluate_multiple(inputs: &str) -> String {
+    let mut ctx = Context::new();
+    let mut result = String::new();
+    for input in inputs.split('\0') {
+        if !result.is_empty() {
+            result.push('\0');
+        }
+        match evaluate(input, &mut ctx) {
+            Ok(res) => {
+                if !res.is_unit_type() {
+                    result.push_str(res.get_main_result());
+                }
+            }
+            Err(msg) => {
+                result.push_str("Error: ");
+                result.push_str(&msg);
+            }
+        }
+    }
+    result
+}
+
+#[test]
+fn ans_carries_over_in_multiple_inputs() {
+    assert_eq!(evaluate_multiple("2+2\0ans*2\0ans+1"), "4\08\09");
+    assert_eq!(
+        evaluate_multiple("2+2\0a = 3\01/0\0ans * a\0prev"),
+        "4\03\0Error: division by zero\012\012"
+    );
+}
+
+#[test]
+fn ans_carries_over_between_evaluations() {
+    let mut ctx = Context::new();
+    let results: Vec<_> = ["2+2", "ans*2", "ans+1"]
+        .iter()
+        .map(|input| {
+            evaluate(input, &mut ctx)
+                .unwrap()
+                .get_main_result()
+                .to_string()
+        })
+        .collect();
+    assert_eq!(results, ["4", "8", "9"]);
+    assert_eq!(evaluate("prev", &mut ctx).unwrap().get_main_result(), "9");
+}
+
+#[test]
+fn ans_is_not_updated_by_errors_or_assignments() {
+    let mut ctx = Context::new();
+    evaluate("5", &mut ctx).unwrap();
+    assert!(evaluate("1/0", &mut ctx).is_err());
+    assert_eq!(evaluate("ans", &mut ctx).unwrap().get_main_result(), "5");
+    evaluate("a = 3", &mut ctx).unwrap();
+    assert_eq!(
+        evaluate("ans + a", &mut ctx).unwrap().get_main_result(),
+        "8"
+    );
+    // inputs containing closing parentheses are wrapped in parentheses
+    evaluate("b = sin(0)", &mut ctx).unwrap();
+    assert_eq!(evaluate("ans", &mut ctx).unwrap().get_main_result(), "8");
+}
+
+#[test]
+fn abs_of_complex_number() {
+    test_eval("abs(3+4i)", "5");
+}
+
+#[test]
+fn comparison_operators() {
+    test_eval("2 > 1", "true");
+    test_eval("2 < 1", "false");
+    test_eval("2 <= 2", "true");
+    test_eval("2 >= 3", "false");
+    test_eval("1 + 1 == 2", "true");
+    test_eval("1 != 2", "true");
+    test_eval("2 ≤ 2", "true");
+    test_eval("0.1 + 0.2 == 0.3", "true");
+}
+
+#[test]
+fn comparisons_convert_units() {
+    test_eval("1 m == 100 cm", "true");
+    test_eval("3 m < 200 cm", "false");
+    test_eval("0 °C == 32 °F", "true");
+    expect_error(
+        "1 m < 1 kg",
+        Some("cannot convert from kg to m: units 'kilogram' and 'meter' are incompatible"),
+    );
+}
+
+#[test]
+fn comparing_complex_numbers_is_an_error() {
+    expect_error("(3+4i) > 2", Some("complex numbers are not ordered"));
+    expect_error("i <= i", Some("complex numbers are not ordered"));
+    test_eval("i == i", "true");
+    test_eval("i != 2", "true");
+}
+
+#[test]
+fn comparing_different_types_is_an_error() {
+    expect_error("1 < true", Some("cannot compare number with bool"));
+    test_eval("true == true", "true");
+    test_eval("\"a\" != \"b\"", "true");
+}
+
+#[test]
+fn ordering_strings_and_dates() {
+    test_eval("\"a\" < \"b\"", "true");
+    test_eval("'abc' >= 'abd'", "false");
+    test_eval("@2024-01-01 < @2024-01-02", "true");
+    test_eval("@2024-01-01 12:00 > @2024-01-01", "true");
+    test_eval("@2024-01-01 <= @2024-01-01 00:00", "true");
+    expect_error("true < false", Some("bool values cannot be ordered"));
+}
+
+#[test]
+fn mutually_recursive_functions() {
+    expect_error(
+        "f = x: g x; g = x: f x; f 1",
+        Some("maximum recursion depth exceeded"),
+    );
+}
+
+#[test]
+fn custom_max_recursion_depth() {
+    let mut ctx = Context::new();
+    ctx.set_max_recursion_depth(2);
+    assert_eq!(
+        evaluate("f = x: x + 1; f (f 1)", &mut ctx)
+            .unwrap()
+            .get_main_result(),
+        "3"
+    );
+    assert_eq!(
+        evaluate("g = x: f (f x); g 1", &mut ctx),
+        Err("maximum recursion depth exceeded".to_string())
+    );
+}
+
+#[test]
+fn deeply_nested_input() {
+    let parens = format!("{}1{}", "(".repeat(5000), ")".repeat(5000));
+    let lists = format!("{}1{}", "[".repeat(5000), "]".repeat(5000));
+    for input in [
+        parens.as_str(),
+        &lists,
+        &"(".repeat(5000),
+        &format!("{}1", "-".repeat(5000)),
+        &format!("{}1", "1 + ".repeat(5000)),
+        &format!("{}1", "sqrt ".repeat(5000)),
+    ] {
+        expect_error(input, Some("maximum recursion depth exceeded"));
+    }
+    assert_eq!(
+        fend_core::parse(&parens),
+        Err("maximum recursion depth exceeded".to_string())
+    );
+    // long lists are not nested
+    let list = format!("[{}1]", "1, ".repeat(5000));
+    assert!(evaluate(&list, &mut Context::new()).is_ok());
+    test_eval("((((((((((1 + 2) * 3))))))))) - --4", "5");
+}
+
+#[test]
+fn self_referential_assignment() {
+    expect_error("x = x + 1", Some("variable 'x' depends on itself"));
+    expect_error("x = 2 * (x + 1)", Some("variable 'x' depends on itself"));
+    test_eval("x = 1; x = x + 1; x", "2");
+}
+
+#[test]
+fn compound_assignment() {
+    test_eval("x = 10; x += 5; x", "15");
+    test_eval("x = 10; x -= 4", "6");
+    test_eval("x = 3; x *= 2 + 1; x", "9");
+    test_eval("x = 3; x /= 4", "0.75");
+    test_eval("x = 10 m; x += 5 cm; x", "10.05 m");
+    expect_error("y += 1", Some("variable 'y' is not defined"));
+    expect_error("kg *= 2", Some("variable 'kg' is not defined"));
+    expect_error(
+        "x = 10 m; x += 5 kg",
+        Some("cannot convert from kg to m: units 'kilogram' and 'meter' are incompatible"),
+    );
+    expect_error("2 += 3", Some("expected an identifier"));
+    assert_eq!(fend_core::normalize("x+=2*3").unwrap(), "x += (2*3)");
+}
+
+#[test]
+fn two_variable_assignment_cycle() {
+    expect_error(
+        "foo = qux; qux = foo",
+        Some("variable 'foo' depends on itself"),
+    );
+    expect_error(
+        "foo = qux; qux = quux; quux = foo + 1",
+        Some("variable 'foo' depends on itself"),
+    );
+    expect_error("foo = qux; qux = 5", Some("unknown identifier 'qux'"));
+}
+
+#[test]
+fn scientific_notation() {
+    test_eval_simple("1.5e10 to scientific", "1.5e10");
+    test_eval_simple("-0.00012 to sci", "-1.2e-4");
+    test_eval_simple("5 to sci", "5e0");
+    test_eval_simple("0 to sci", "0");
+    test_eval_simple("2^70 to sci", "approx. 1.1805916207e21");
+    test_eval_simple("3 kg m^2 to sci", "3e0 kg m^2");
+    test_eval_simple("1.5e10 i to sci", "1.5e10i");
+    test_eval_simple("1e2000 to sci", "1e2000");
+    test_eval_simple("3e-2000 to sci", "3e-2000");
+    test_eval_simple("2^200/3^100 to sci", "approx. 3.1179824102e12");
+}
+
+#[test]
+fn scientific_notation_times_ten_to() {
+    let mut ctx = Context::new();
+    ctx.set_scientific_notation_style(fend_core::ScientificNotationStyle::TimesTenTo);
+    let result = evaluate("1.5e10 to sci", &mut ctx).unwrap();
+    assert_eq!(result.get_main_result(), "1.5 × 10^10");
+    // both notations can be parsed again
+    for input in ["1.5 × 10^10", "1.5e10"] {
+        assert_eq!(
+            evaluate(input, &mut ctx).unwrap().get_main_result(),
+            "15000000000"
+        );
+    }
+}
+
+#[test]
+fn trig_with_turns() {
+    test_eval("sin(0.5 turn)", "0");
+    test_eval("sin(0.25 turn)", "1");
+    test_eval("cos(1 rev)", "1");
+    test_eval("1 turn to degrees", "360 degrees");
+    test_eval("1 cycle to degrees", "360 degrees");
+    test_eval("0.25 turns to rad", "approx. 1.5707963267 rad");
+    test_eval("0x0.4 turn to degrees", "0x5a degrees");
+    test_eval("sin(0x0.8 turn)", "0x0");
+}
+
+#[test]
+fn list_units() {
+    let units = fend_core::list_units(&Context::new());
+    let find = |name: &str| units.iter().find(|u| u.name() == name).unwrap();
+
+    let meter = find("meter");
+    assert!(meter.aliases().iter().any(|a| a == "m"));
+    assert!(meter.aliases().iter().any(|a| a == "metres"));
+    assert_eq!(meter.dimension(), "length");
+    assert_eq!(meter.definition(), "");
+
+    assert_eq!(find("newton").dimension(), "mass·length/time²");
+    assert_eq!(find("hertz").dimension(), "1/time");
+    assert_eq!(find("percent").dimension(), "dimensionless");
+    assert_eq!(find("gram").definition(), "1/1000 kilogram");
+
+    // aliases and prefixes aren't listed separately
+    assert!(!units.iter().any(|u| u.name() == "m" || u.name() == "kilo"));
+}
+
+#[test]
+fn assignment_name_and_value() {
+    let mut ctx = Context::new();
+    let res = evaluate("x = 5", &mut ctx).unwrap();
+    assert_eq!(res.assignment(), Some(("x", "5")));
+    let res = evaluate("y = 2; my_var = x y kg", &mut ctx).unwrap();
+    assert_eq!(res.assignment(), Some(("my_var", "10 kg")));
+    let res = evaluate("x + 1", &mut ctx).unwrap();
+    assert_eq!(res.assignment(), None);
+    let res = evaluate("z = 1; z + 1", &mut ctx).unwrap();
+    assert_eq!(res.assignment(), None);
+}
+
+#[test]
+fn evaluate_into_matches_main_result() {
+    struct NeverInterrupt;
+    impl fend_core::Interrupt for NeverInterrupt {
+        fn should_interrupt(&self) -> bool {
+            false
+        }
+    }
+
+    let mut ctx1 = Context::new();
+    let mut ctx2 = Context::new();
+    for input in ["1 + 1", "a = 5 kg", "a to g", "1/3", "2i * 3i", "'hi'", ""] {
+        let expected = evaluate(input, &mut ctx1).unwrap();
+        let mut out = String::new();
+        ctx2.evaluate_into(input, &mut out, &NeverInterrupt)
+            .unwrap();
+        assert_eq!(out, expected.get_main_result());
+    }
+    let mut out = String::new();
+    assert_eq!(
+        ctx2.evaluate_into("1 +", &mut out, &NeverInterrupt),
+        Err("unexpected input found".to_string())
+    );
+    assert_eq!(out, "");
+}
+
+#[test]
+fn evaluate_all_lines() {
+    struct NeverInterrupt;
+    impl fend_core::Interrupt for NeverInterrupt {
+        fn should_interrupt(&self) -> bool {
+            false
+        }
+    }
+
+    let mut ctx = Context::new();
+    let results = ctx.evaluate_all_lines("a = 2\nb = a*3\nb + 1\n\nqz + 1\nb kg", &NeverInterrupt);
+    let summary: Vec<_> = results
+        .iter()
+        .map(|line| (line.echo(), line.result(), line.is_error()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (Some("a = 2"), "2", false),
+            (Some("b = 6"), "6", false),
+            (None, "7", false),
+            (None, "", false),
+            (None, "unknown identifier 'qz'", true),
+            (None, "6 kg", false),
+        ]
+    );
+    assert_eq!(evaluate("a + b", &mut ctx).unwrap().get_main_result(), "8");
+}
+
+#[test]
+fn evaluate_preview_does_not_change_context() {
+    struct NeverInterrupt;
+    impl fend_core::Interrupt for NeverInterrupt {
+        fn should_interrupt(&self) -> bool {
+            false
+        }
+    }
+
+    let mut ctx = Context::new();
+    evaluate("a = 2", &mut ctx).unwrap();
+    let preview = ctx.evaluate_preview("a = 5; total = a + 1", false, &NeverInterrupt);
+    assert_eq!(preview.unwrap().get_main_result(), "6");
+    assert_eq!(
+        ctx.evaluate_preview("1 +", false, &NeverInterrupt)
+            .unwrap_err(),
+        "unexpected input found"
+    );
+    assert_eq!(evaluate("a", &mut ctx).unwrap().get_main_result(), "2");
+    assert_eq!(
+        evaluate("total", &mut ctx).unwrap_err(),
+        "unknown identifier 'total'"
+    );
+    assert_eq!(evaluate("ans", &mut ctx).unwrap().get_main_result(), "2");
+}
+
+#[test]
+fn evaluate_preview_allows_incomplete_input() {
+    struct NeverInterrupt;
+    impl fend_core::Interrupt for NeverInterrupt {
+        fn should_interrupt(&self) -> bool {
+            false
+        }
+    }
+
+    let ctx = Context::new();
+    for input in ["1 +", "(2 *", "sin(", "-", "5 to", "2 ^"] {
+        let preview = ctx.evaluate_preview(input, true, &NeverInterrupt).unwrap();
+        assert_eq!(preview.get_main_result(), "");
+    }
+    for (input, error) in [
+        ("1 + )", "found '+' while expecting ')'"),
+        ("1 + x", "unknown identifier 'x'"),
+    ] {
+        assert_eq!(
+            ctx.evaluate_preview(input, true, &NeverInterrupt)
+                .unwrap_err(),
+            error
+        );
+    }
+}
+
+#[test]
+fn unit_cache_is_invalidated_by_assignments() {
+    let mut ctx = Context::new();
+    assert_eq!(
+        evaluate("1 foot to cm", &mut ctx)
+            .unwrap()
+            .get_main_result(),
+        "30.48 cm"
+    );
+    evaluate("inch = 2 cm", &mut ctx).unwrap();
+    let mut fresh_ctx = Context::new();
+    evaluate("inch = 2 cm", &mut fresh_ctx).unwrap();
+    assert_eq!(
+        evaluate("1 foot to cm", &mut ctx)
+            .unwrap()
+            .get_main_result(),
+        evaluate("1 foot to cm", &mut fresh_ctx)
+            .unwrap()
+            .get_main_result()
+    );
+}
+
+// Run with `cargo test --release -- --ignored --nocapture unit_cache_benchmark`
+#[test]
+#[ignore]
+fn unit_cache_benchmark() {
+    use std::time::{Duration, Instant};
+
+    let lines: Vec<String> = (0..1000)
+        .map(|i| format!("{i} kg m / s^2 + {i} N to kg m/s^2"))
+        .collect();
+
+    let start = Instant::now();
+    let mut ctx = Context::new();
+    for line in &lines {
+        evaluate(line, &mut ctx).unwrap();
+    }
+    let cached = start.elapsed();
+
+    // each line gets a new context with an empty cache; the contexts are
+    // created up front so that only evaluation is timed
+    let contexts: Vec<_> = lines.iter().map(|_| Context::new()).collect();
+    let start = Instant::now();
+    for (line, mut ctx) in lines.iter().zip(contexts) {
+        evaluate(line, &mut ctx).unwrap();
+    }
+    let uncached = start.elapsed();
+
+    eprintln!("1000 lines with a shared unit cache: {cached:?}");
+    eprintln!("1000 lines without a unit cache: {uncached:?}");
+    assert!(cached < uncached + Duration::from_millis(10));
+}
+
+#[test]
+fn unit_cache_is_invalidated_by_coulomb_and_farad() {
+    let mut ctx = Context::new();
+    assert_eq!(
+        evaluate("1 C to K", &mut ctx).unwrap().get_main_result(),
+        "274.15 K"
+    );
+    ctx.use_coulomb_and_farad();
+    assert_eq!(
+        evaluate("1 C to A s", &mut ctx).unwrap().get_main_result(),
+        "1 A s"
+    );
+}
+
+#[test]
+fn parse_returns_syntax_tree() {
+    use fend_core::syntax::AstNode;
+
+    assert_eq!(
+        fend_core::parse("1 + x").unwrap(),
+        AstNode::BinaryOp {
+            op: "+".to_string(),
+            lhs: Box::new(AstNode::Number("1".to_string())),
+            rhs: Box::new(AstNode::Ident("x".to_string())),
+        }
+    );
+    assert_eq!(
+        fend_core::parse("5 feet to m").unwrap().to_json(),
+        r#"{"type":"conversion","value":{"type":"multiply","lhs":{"type":"number","value":"5"},"rhs":{"type":"ident","name":"feet"}},"target":{"type":"ident","name":"m"}}"#
+    );
+    assert_eq!(
+        fend_core::parse("a = sin('x\"')").unwrap().to_json(),
+        r#"{"type":"assign","name":"a","value":{"type":"apply","function":{"type":"ident","name":"sin"},"argument":{"type":"parens","value":{"type":"string","value":"x\""}}}}"#
+    );
+    assert_eq!(
+        fend_core::parse("(1 + 2)").unwrap(),
+        AstNode::Parens(Box::new(AstNode::BinaryOp {
+            op: "+".to_string(),
+            lhs: Box::new(AstNode::Number("1".to_string())),
+            rhs: Box::new(AstNode::Number("2".to_string())),
+        }))
+    );
+    // identifiers are not resolved
+    assert!(fend_core::parse("undefined_variable").is_ok());
+    assert_eq!(
+        fend_core::parse("1 +"),
+        Err("unexpected input found".to_string())
+    );
+}
+
+#[test]
+fn parse_keeps_number_literals_as_written() {
+    fn numbers(node: &fend_core::syntax::AstNode) -> Vec<String> {
+        // every number in these inputs appears in the JSON as "value":"..."
+        node.to_json()
+            .split(r#""type":"number","value":""#)
+            .skip(1)
+            .map(|s| s.split('"').next().unwrap().to_string())
+            .collect()
+    }
+    for (input, expected) in [
+        ("0.(3)", vec!["0.(3)"]),
+        ("1.5e10 + 0x10", vec!["1.5e10", "0x10"]),
+        ("1,000 * 1 2/3", vec!["1,000", "1", "2", "3"]),
+        ("2d6 - 16#ff", vec!["2d6", "16#ff"]),
+        ("f = x: x^2.50; f (3)", vec!["2.50", "3"]),
+        ("[1,2, .5]", vec!["1", "2", ".5"]),
+        ("# comment\n 7 feet 2 inches", vec!["7", "2"]),
+    ] {
+        assert_eq!(numbers(&fend_core::parse(input).unwrap()), expected);
+    }
+}
+
+#[test]
+fn unicode_math_operators() {
+    test_eval("√2", "approx. 1.4142135619");
+    test_eval("√(16)", "4");
+    test_eval("√9 kg", "3 kg");
+    test_eval("2√2", "approx. 2.8284271243");
+    test_eval("√2^2", "approx. 1.9999999987");
+    test_eval("√-4", "2i");
+    test_eval("Σ[1, 2, 3]", "6");
+    test_eval("Π[1, 2, 3, 4]", "24");
+    test_eval("3×4", "12");
+    test_eval("8÷2", "4");
+    test_eval("5−3", "2");
+}
+
+#[test]
+fn approximately_equal() {
+    test_eval("sqrt(2) ~= 1.414213562373", "true");
+    test_eval("sqrt(2) ~= 1.41421", "false");
+    test_eval("sqrt(3)^2 ≈ 3", "true");
+    test_eval("0 ~= 0", "true");
+    test_eval("1 km ~= 1000 m", "true");
+    test_eval("1 km ~= 1000.01 m", "false");
+    test_eval("x = approximately 3.14159265358979; sin(x) ~= 0", "true");
+    test_eval("1e-11 ~= 0", "true");
+    test_eval("1e-9 ~= 0", "false");
+    test_eval("approx_equal 3.14 pi 0.001", "true");
+    test_eval("approx_equal 3.14 pi 0.0001", "false");
+    expect_error("1 kg ~= 1 m", None);
+    expect_error("1 ~ 2", Some("unexpected character '~'"));
+}
+
+#[test]
+fn approximately_equal_uses_current_precision() {
+    use fend_core::FormattingStyle;
+
+    let mut ctx = Context::new();
+    ctx.set_formatting_style(FormattingStyle::DecimalPlaces(2));
+    assert_eq!(
+        evaluate("3.14 ~= pi", &mut ctx).unwrap().get_main_result(),
+        "true"
+    );
+    assert_eq!(
+        evaluate("0.001 ~= 0", &mut ctx).unwrap().get_main_result(),
+        "true"
+    );
+    assert_eq!(
+        evaluate("3.2 ~= pi", &mut ctx).unwrap().get_main_result(),
+        "false"
+    );
+}
+
+#[test]
+fn latex_output() {
+    #[track_caller]
+    fn test_latex(input: &str, expected: &str) {
+        let mut ctx = Context::new();
+        assert_eq!(evaluate(input, &mut ctx).unwrap().to_latex(), expected);
+    }
+
+    test_latex("1 + 1", "2");
+    test_latex("1/3 to fraction", "\\frac{1}{3}");
+    test_latex("-5/3 to mixed_fraction", "-1\\frac{2}{3}");
+    test_latex("1/3 to float", "0.\\overline{3}");
+    test_latex("1/3", "\\approx 0.3333333333");
+    test_latex("2e5 to sci", "2 \\times 10^{5}");
+    test_latex("3 - 4i", "3 - 4i");
+    test_latex(
+        "5 kg m / s^2",
+        "5\\ \\mathrm{kg}\\,\\mathrm{m}\\,/\\,\\mathrm{s}^{2}",
+    );
+    test_latex("1 m^(2/3)", "1\\ \\mathrm{m}^{2/3}");
+    test_latex("100 °C", "100\\ {}^{\\circ}\\mathrm{C}");
+    test_latex("50%", "50\\mathrm{\\%}");
+    test_latex("0x1f", "\\mathtt{0x1f}");
+    test_latex("238 to base 16", "\\mathtt{ee}");
+    test_latex("14 to base 16", "\\mathtt{e}");
+    test_latex("485 to base 16", "\\mathtt{1e5}");
+    test_latex("pi to exact", "\\pi");
+    test_latex("2pi/3 to exact", "\\frac{2\\pi}{3}");
+    test_latex("pi i to exact", "\\pi i");
+    test_latex("sqrt 2 to exact", "\\sqrt{2}");
+    test_latex("sqrt 8 to exact", "2\\sqrt{2}");
+    test_latex("sqrt(2/3) to exact", "\\frac{\\sqrt{6}}{3}");
+    test_latex("sqrt 2", "\\approx 1.4142135619");
+    test_latex("sin", "\\operatorname{sin}");
+    test_latex("'a_b'", "\\text{a\\_b}");
+}
+
+#[test]
+fn mathml_output() {
+    #[track_caller]
+    fn test_mathml(input: &str, expected: &str) {
+        let mut ctx = Context::new();
+        assert_eq!(
+            evaluate(input, &mut ctx).unwrap().to_mathml(),
+            format!("<math>{expected}</math>")
+        );
+    }
+
+    test_mathml("1 + 1", "<mn>2</mn>");
+    test_mathml(
+        "-1/3 to fraction",
+        "<mo>-</mo><mfrac><mrow><mn>1</mn></mrow><mrow><mn>3</mn></mrow></mfrac>",
+    );
+    test_mathml(
+        "1/3 to float",
+        "<mn>0.</mn><mover><mn>3</mn><mo>\u{af}</mo></mover>",
+    );
+    test_mathml("1/3", "<mo>\u{2248}</mo><mn>0.3333333333</mn>");
+    test_mathml(
+        "2e5 to sci",
+        "<mn>2</mn><mo>\u{d7}</mo><msup><mn>10</mn><mn>5</mn></msup>",
+    );
+    test_mathml("3 - 4i", "<mn>3</mn><mo>-</mo><mn>4</mn><mi>i</mi>");
+    test_mathml(
+        "5 kg / s^2",
+        concat!(
+            r#"<mn>5</mn><mspace width="0.5em"/><mi mathvariant="normal">kg</mi>"#,
+            r#"<mspace width="0.17em"/><mo>/</mo><mspace width="0.17em"/>"#,
+            r#"<msup><mi mathvariant="normal">s</mi><mn>2</mn></msup>"#,
+        ),
+    );
+    test_mathml("i", "<mi>i</mi>");
+    test_mathml("1 + i", "<mn>1</mn><mo>+</mo><mi>i</mi>");
+    test_mathml(
+        "1/3 i to fraction",
+        "<mfrac><mrow><mi>i</mi></mrow><mrow><mn>3</mn></mrow></mfrac>",
+    );
+    test_mathml(
+        "2/3 i",
+        "<mfrac><mrow><mn>2</mn><mi>i</mi></mrow><mrow><mn>3</mn></mrow></mfrac>",
+    );
+    test_mathml(
+        "4/3 i",
+        "<mn>1</mn><mfrac><mrow><mn>1</mn></mrow><mrow><mn>3</mn></mrow></mfrac><mi>i</mi>",
+    );
+    test_mathml("pi to exact", "<mi>\u{3c0}</mi>");
+    test_mathml("sqrt 2 to exact", "<msqrt><mn>2</mn></msqrt>");
+    test_mathml("'a < b'", "<mtext>a &lt; b</mtext>");
+}
+
+#[test]
+fn default_formatting_style() {
+    use fend_core::FormattingStyle;
+
+    assert_eq!(Context::new().formatting_style(), FormattingStyle::Auto);
+    let mut ctx = Context::with_formatting_style(FormattingStyle::ImproperFraction);
+    assert_eq!(ctx.formatting_style(), FormattingStyle::ImproperFraction);
+    let mut eval = |input| {
+        evaluate(input, &mut ctx)
+            .unwrap()
+            .get_main_result()
+            .to_string()
+    };
+    assert_eq!(eval("0.5"), "1/2");
+    assert_eq!(eval("3/2 kg"), "3/2 kg");
+    assert_eq!(eval("0.5 to auto"), "0.5");
+    assert_eq!(eval("1/3 to 3 dp"), "approx. 0.333");
+
+    let mut ctx = Context::new();
+    ctx.set_formatting_style(FormattingStyle::DecimalPlaces(2));
+    assert_eq!(ctx.formatting_style(), FormattingStyle::DecimalPlaces(2));
+    assert_eq!(
+        evaluate("pi", &mut ctx).unwrap().get_main_result(),
+        "approx. 3.14"
+    );
+}
+
+#[test]
+fn default_formatting_style_serialization() {
+    use fend_core::FormattingStyle;
+
+    let mut ctx = Context::with_formatting_style(FormattingStyle::ImproperFraction);
+    evaluate("a = 0.5; b = 0.25 to float", &mut ctx).unwrap();
+    let mut data = vec![];
+    ctx.serialize_variables(&mut data).unwrap();
+
+    // the default style is restored along with the variables, while
+    // explicit formats are kept
+    let mut ctx = Context::new();
+    ctx.deserialize_variables(&mut data.as_slice()).unwrap();
+    assert_eq!(ctx.formatting_style(), FormattingStyle::ImproperFraction);
+    assert_eq!(evaluate("a", &mut ctx).unwrap().get_main_result(), "1/2");
+    assert_eq!(evaluate("b", &mut ctx).unwrap().get_main_result(), "0.25");
+}
+
+#[test]
+fn deserialize_variables_without_formatting_style() {
+    use fend_core::FormattingStyle;
+
+    // older versions didn't store the default style after the variables
+    let mut data = vec![];
+    Context::new().serialize_variables(&mut data).unwrap();
+    data.pop();
+    let mut ctx = Context::with_formatting_style(FormattingStyle::ImproperFraction);
+    ctx.deserialize_variables(&mut data.as_slice()).unwrap();
+    assert_eq!(ctx.formatting_style(), FormattingStyle::ImproperFraction);
+}
+
+#[test]
+fn base_serialization() {
+    let mut ctx = Context::new();
+    evaluate(
+        "a = 35 to base 36; b = 36#z; c = 0xff; d = 255 to hex",
+        &mut ctx,
+    )
+    .unwrap();
+    let mut data = vec![];
+    ctx.serialize_variables(&mut data).unwrap();
+
+    let mut ctx = Context::new();
+    ctx.deserialize_variables(&mut data.as_slice()).unwrap();
+    assert_eq!(evaluate("a", &mut ctx).unwrap().get_main_result(), "z");
+    assert_eq!(evaluate("b", &mut ctx).unwrap().get_main_result(), "36#z");
+    assert_eq!(evaluate("c", &mut ctx).unwrap().get_main_result(), "0xff");
+    assert_eq!(evaluate("d", &mut ctx).unwrap().get_main_result(), "ff");
+}
+
+#[test]
+fn floor_division() {
+    test_eval("7 // 2", "3");
+    test_eval("-7 // 2", "-4");
+    test_eval("7 // -2", "-4");
+    test_eval("-7 // -2", "3");
+    test_eval("6 // 3", "2");
+    test_eval("1 + 7 // 2 * 2", "7");
+    test_eval("//4", "4");
+    expect_error("7 // 0", Some("division by zero"));
+    expect_error(
+        "7.5 // 2",
+        Some("floor division is only supported for integers"),
+    );
+    expect_error("7 kg // 2", None);
+}
+
+#[test]
+fn divmod() {
+    test_eval("divmod(7, 2)", "[3, 1]");
+    test_eval("divmod(7,2)", "[3, 1]");
+    test_eval("divmod(-7, 2)", "[-4, 1]");
+    test_eval("divmod(7, -2)", "[-4, -1]");
+    test_eval("divmod(-7, -2)", "[3, -1]");
+    test_eval("divmod(6, 3)", "[2, 0]");
+    test_eval("divmod [7,2]", "[3, 1]");
+    expect_error("divmod(7, 0)", Some("division by zero"));
+    expect_error(
+        "divmod(1.5, 1)",
+        Some("floor division is only supported for integers"),
+    );
+    expect_error("divmod 7", Some("divmod expects 2 arguments"));
+    expect_error("divmod(1, 2, 3)", Some("divmod expects 2 arguments"));
+}
+
+#[test]
+fn round_to_nearest() {
+    test_eval("round 2.4", "2");
+    test_eval("round 2.5", "3");
+    test_eval("round(-2.5)", "-3");
+    test_eval("round(-0.2)", "0");
+    test_eval("round pi", "3");
+    test_eval("round(137, nearest 25)", "125");
+    test_eval("round(-137, nearest 25)", "-125");
+    test_eval("round(2.7, nearest 0.5)", "2.5");
+    test_eval("round(137 cm, nearest 10 cm)", "140 cm");
+    test_eval("round(137 cm, nearest 10)", "140 cm");
+    test_eval("round(137 cm, nearest 1 foot)", "121.92 cm");
+    test_eval("round(7.5 min, nearest 5 min)", "10 mins");
+    expect_error(
+        "round(1.3, nearest 0)",
+        Some("cannot round to the nearest multiple of zero"),
+    );
+    expect_error("round(1, 2, 3)", Some("round expects 2 arguments"));
+    expect_error(
+        "round(5 kg, nearest 1 m)",
+        Some("cannot convert from kg to m: units 'kilogram' and 'meter' are incompatible"),
+    );
+}
+
+#[test]
+fn numerator_and_denominator() {
+    test_eval("numerator(6/8)", "3");
+    test_eval("denominator(6/8)", "4");
+    test_eval("numerator(-6/8)", "-3");
+    test_eval("denominator(-6/8)", "4");
+    test_eval("numerator 0.75", "3");
+    test_eval("denominator 5", "1");
+    test_eval("denominator(1/3 + 1/6)", "2");
+    expect_error("numerator pi", Some("expected a rational number"));
+    expect_error("denominator(sqrt 2)", Some("expected a rational number"));
+    expect_error("numerator(sin 1)", Some("expected a rational number"));
+    expect_error("numerator i", Some("expected a real number"));
+    expect_error("numerator(5 kg)", Some("expected a unitless number"));
+}
+
+#[test]
+fn lists() {
+    test_eval("[1, 2, 3]", "[1, 2, 3]");
+    test_eval("[3,1,4,1,5]", "[3, 1, 4, 1, 5]");
+    expect_error(
+        "[1,000, 2]",
+        Some("commas separate items inside brackets, so use '_' as a digit separator (e.g. 1_000)"),
+    );
+    test_eval("[1_000, 2]", "[1000, 2]");
+    test_eval("[1,0]", "[1, 0]");
+    test_eval("[]", "[]");
+    test_eval("[1 + 1, 2 kg,]", "[2, 2 kg]");
+    test_eval("(3, 4)", "[3, 4]");
+    test_eval("(3,4)", "[3, 4]");
+    expect_error("(1,2) + 1", Some("expected a number"));
+    expect_error(
+        "(1,)",
+        Some("lists with one item need square brackets, e.g. [1]"),
+    );
+    test_eval("(1, 2,)", "[1, 2]");
+    expect_error(
+        "2 * (1,000)",
+        Some("commas separate items inside brackets, so use '_' as a digit separator (e.g. 1_000)"),
+    );
+    test_eval("[[2,1],[1,3]]", "[[2, 1], [1, 3]]");
+    test_eval("['a', 1]", "[\"a\", 1]");
+    test_eval("[1, 2] == [1, 2]", "true");
+    test_eval("[1, 2] == [2, 1]", "false");
+    test_eval("1,000", "1000");
+    test_eval_simple("x = [1, 'a', [2, 3]]; x", "[1, \"a\", [2, 3]]");
+}
+
+#[test]
+fn sum_and_product() {
+    test_eval("sum [1, 2, 3]", "6");
+    test_eval("product [1, 2, 3, 4]", "24");
+    test_eval("sum [1 kg, 200 g]", "1.2 kg");
+    test_eval("product [2 m, 3 m]", "6 m^2");
+    test_eval("sum []", "0");
+    test_eval("product []", "1");
+    expect_error("sum 5", Some("expected a list (found number)"));
+}
+
+#[test]
+fn min_max_argmin_argmax() {
+    test_eval("max [3, 1, 4, 1, 5]", "5");
+    test_eval("min [3, 1, 4, 1, 5]", "1");
+    test_eval("min(3, 1)", "1");
+    test_eval("argmax [3, 1, 4, 1, 5]", "5");
+    test_eval("argmin [3, 1, 4, 1, 5]", "2");
+    test_eval("argmax [3, 5, 5]", "2");
+    test_eval("max [1 m, 50 cm]", "1 m");
+    test_eval_simple("max ['a', 'b']", "b");
+    test_eval("5 min", "5 mins");
+    test_eval("2 min to s", "120 s");
+    expect_error("max []", Some("max is undefined for an empty list"));
+    expect_error("argmin []", Some("argmin is undefined for an empty list"));
+    expect_error("max 5", Some("expected a list (found number)"));
+}
+
+#[test]
+fn percentiles_and_quartiles() {
+    let data = "[7, 1, 10, 3, 2, 9, 5, 4, 8, 6]";
+    test_eval(&format!("percentile(25, {data})"), "3.25");
+    test_eval(&format!("percentile(50, {data})"), "5.5");
+    test_eval(&format!("percentile(75, {data})"), "7.75");
+    test_eval(&format!("percentile(0, {data})"), "1");
+    test_eval(&format!("percentile(100, {data})"), "10");
+    test_eval(&format!("quartile(1, {data})"), "3.25");
+    test_eval(&format!("quartile(2, {data})"), "5.5");
+    test_eval(&format!("quartile(3, {data})"), "7.75");
+    // exactly on an item, and between two items
+    test_eval("percentile(25, [15, 20, 35, 40, 50])", "20");
+    test_eval("percentile(40, [15, 20, 35, 40, 50])", "29");
+    test_eval("percentile(90, [1, 2])", "1.9");
+    test_eval("percentile(50, [5])", "5");
+    test_eval("percentile(50, [3 kg, 1000 g])", "2000 g");
+    expect_error(
+        &format!("percentile(101, {data})"),
+        Some("101 must lie in the interval [0, 100]"),
+    );
+    expect_error(
+        &format!("percentile(-1, {data})"),
+        Some("-1 must lie in the interval [0, 100]"),
+    );
+    expect_error(
+        &format!("quartile(5, {data})"),
+        Some("5 must lie in the interval [0, 4]"),
+    );
+    expect_error(
+        "percentile(50, [])",
+        Some("percentile is undefined for an empty list"),
+    );
+    expect_error("percentile 50", Some("percentile expects 2 arguments"));
+}
+
+#[test]
+fn simplify() {
+    test_eval_simple("simplify(pi + pi)", "2\u{3c0}");
+    test_eval_simple("simplify(pi/2 + pi/3)", "5\u{3c0}/6");
+    test_eval_simple("simplify(sqrt(2) + sqrt(2))", "2\u{221a}2");
+    test_eval_simple("simplify(3 sqrt(8) - sqrt(2))", "5\u{221a}2");
+    test_eval_simple("simplify(pi) m", "\u{3c0} m");
+    test_eval("simplify(1/3 + 1/6)", "0.5");
+    test_eval("simplify(pi - pi)", "0");
+
+    // e and mixed terms have no symbolic form
+    let mut ctx = Context::new();
+    for (input, output) in [
+        ("simplify(e + e)", "approx. 5.4365636569"),
+        ("simplify(pi + 1)", "approx. 4.1415926535"),
+    ] {
+        let res = evaluate(input, &mut ctx).unwrap();
+        assert_eq!(res.get_main_result(), output);
+        assert_eq!(
+            res.warnings(),
+            ["unable to simplify this value any further"]
+        );
+    }
+    let res = evaluate("simplify(pi + pi)", &mut ctx).unwrap();
+    assert!(res.warnings().is_empty());
+    expect_error("simplify [pi]", Some("expected a number"));
+}
+
+#[test]
+fn unit_display_preference() {
+    use fend_core::UnitDisplayPreference;
+
+    let eval = |input: &str, preference| {
+        let mut context = Context::new();
+        context.set_unit_display_preference(preference);
+        evaluate(input, &mut context)
+            .unwrap()
+            .get_main_result()
+            .to_string()
+    };
+    for (input, as_entered, derived, base) in [
+        ("1 W * 1 s", "1 W s", "1 J", "1 kg m^2 / s^2"),
+        ("1 kg m^2 / s^2", "1 kg m^2 / s^2", "1 J", "1 kg m^2 / s^2"),
+        (
+            "2 kW * 3 h",
+            "6 kW h",
+            "21600000 J",
+            "21600000 kg m^2 / s^2",
+        ),
+        ("6 V * 2 A", "12 V A", "12 W", "12 kg m^2 / s^3"),
+        ("5 km", "5 km", "5 km", "5000 m"),
+        ("10 m / (2 s)", "5 m / s", "5 m / s", "5 m / s"),
+        (
+            "1 W * 1 s to kWh",
+            "approx. 0.0000002777 kWh",
+            "approx. 0.0000002777 kWh",
+            "approx. 0.0000002777 kWh",
+        ),
+        ("3 USD * 2", "6 USD", "6 USD", "6 USD"),
+    ] {
+        assert_eq!(
+            eval(input, UnitDisplayPreference::AsEntered),
+            as_entered,
+            "{input}"
+        );
+        assert_eq!(
+            eval(input, UnitDisplayPreference::Derived),
+            derived,
+            "{input}"
+        );
+        assert_eq!(eval(input, UnitDisplayPreference::Base), base, "{input}");
+    }
+}
+
+#[test]
+fn uncertainty() {
+    test_eval("5.0 \u{b1} 0.1 cm", "5 \u{b1} 0.1 cm");
+    test_eval("1 m \u{b1} 5 cm", "1 \u{b1} 0.05 m");
+    test_eval("(5 \u{b1} 0.1) cm to mm", "50 \u{b1} 1 mm");
+    test_eval(
+        "(5 \u{b1} 0.1) * (2 \u{b1} 0.05)",
+        "approx. 10 \u{b1} 0.3201562118",
+    );
+    test_eval(
+        "(5 \u{b1} 0.1) + (2 \u{b1} 0.2)",
+        "approx. 7 \u{b1} 0.2236067977",
+    );
+    test_eval(
+        "(5 \u{b1} 0.1) / (2 \u{b1} 0.05)",
+        "approx. 2.5 \u{b1} 0.0800390529",
+    );
+    test_eval("10 \u{b1} 1 - 4", "6 \u{b1} 1");
+    test_eval("-(2 \u{b1} 0.5)", "-2 \u{b1} 0.5");
+    test_eval("2 (3 \u{b1} 0.1)", "6 \u{b1} 0.2");
+    test_eval("(3 \u{b1} 0.1)^2", "9 \u{b1} 0.6");
+    expect_error(
+        "2 ^ (1 \u{b1} 0.1)",
+        Some("exponents with an uncertainty are not supported"),
+    );
+}
+
+#[test]
+fn number_scale_suffixes() {
+    test_eval("1.5k", "1500");
+    test_eval("3M", "3000000");
+    test_eval("2B", "2000000000");
+    test_eval("2B # comment", "2000000000");
+    test_eval("1.5 km", "1.5 km");
+    test_eval("1.5 km to m", "1500 m");
+    test_eval("2 B", "2 B");
+    test_eval("2B to kB", "0.002 kB");
+    test_eval("0x1B", "0x1b");
+}
+
+#[test]
+fn warnings() {
+    let mut ctx = Context::new();
+    let res = evaluate("2 * 3l", &mut ctx).unwrap();
+    assert_eq!(res.get_main_result(), "6 l");
+    assert_eq!(
+        res.warnings(),
+        ["`l` is interpreted as litres, not the number 1"]
+    );
+    let res = evaluate("5 ml + 1 l to l", &mut ctx).unwrap();
+    assert_eq!(res.warnings().len(), 1);
+    let res = evaluate("5 ml to liters", &mut ctx).unwrap();
+    assert!(res.warnings().is_empty());
+    let res = evaluate("l = 1; 2 l", &mut ctx).unwrap();
+    assert_eq!(res.get_main_result(), "2");
+    assert!(res.warnings().is_empty());
+}
+
+#[test]
+fn memory_limit() {
+    let mut ctx = Context::new();
+    ctx.set_memory_limit(1024);
+    for input in ["1 << 100000000", "10^10^10", "10000!"] {
+        assert_eq!(
+            evaluate(input, &mut ctx).unwrap_err(),
+            "computation exceeded memory limit",
+            "{input}"
+        );
+    }
+    assert_eq!(
+        evaluate("20!", &mut ctx).unwrap().get_main_result(),
+        "2432902008176640000"
+    );
+}
+
+#[test]
+fn base_inference() {
+    let mut ctx = Context::new();
+    assert_eq!(
+        evaluate("1 + 0xff", &mut ctx).unwrap().get_main_result(),
+        "256"
+    );
+    ctx.set_base_inference(true);
+    for (input, output) in [
+        ("0xff + 0x01", "0x100"),
+        ("0x1f - 0x2 * 0x3", "0x19"),
+        ("0xff + 1", "256"),
+        ("0b101 * 0b10", "0b1010"),
+        ("0xff + 0b1", "256"),
+        ("0x10 kg", "0x10 kg"),
+        ("0xff to decimal", "255"),
+        ("255 to hex", "ff"),
+        ("2 + 2", "4"),
+        ("pi", "approx. 3.1415926535"),
+    ] {
+        assert_eq!(evaluate(input, &mut ctx).unwrap().get_main_result(), output);
+    }
+}
+
+#[test]
+fn output_base() {
+    let mut ctx = Context::new();
+    assert_eq!(ctx.output_base().to_string(), "decimal");
+    // results are written with a prefix, so that they can be entered again
+    ctx.set_output_base("hex".parse().unwrap());
+    assert_eq!(ctx.output_base().to_string(), "hex");
+    for (input, output) in [
+        ("255", "0xff"),
+        ("255 to decimal", "255"),
+        ("255 to binary", "11111111"),
+        ("0b101 + 1", "0x6"),
+        ("256 kg to g", "0x3e800 g"),
+        ("'hi'", "hi"),
+        ("0xff + 1", "0x100"),
+    ] {
+        assert_eq!(evaluate(input, &mut ctx).unwrap().get_main_result(), output);
+    }
+    let mut base_36 = Context::new();
+    base_36.set_output_base("base 36".parse().unwrap());
+    assert_eq!(
+        evaluate("255", &mut base_36).unwrap().get_main_result(),
+        "36#73"
+    );
+    base_36.set_output_base("0b".parse().unwrap());
+    assert_eq!(
+        evaluate("5", &mut base_36).unwrap().get_main_result(),
+        "0b101"
+    );
+    test_serialization_roundtrip(&mut ctx);
+    let mut data = vec![];
+    ctx.serialize_variables(&mut data).unwrap();
+    let mut ctx2 = Context::new();
+    ctx2.deserialize_variables(&mut data.as_slice()).unwrap();
+    assert_eq!(ctx2.output_base().to_string(), "hex");
+    data.clear();
+    Context::new().serialize_variables(&mut data).unwrap();
+    ctx2.deserialize_variables(&mut data.as_slice()).unwrap();
+    assert_eq!(ctx2.output_base().to_string(), "decimal");
+}
+
+#[test]
+fn normalize() {
+    use fend_core::normalize;
+    for (inputs, expected) in [
+        (
+            &["2+2", "2 + 2", "2  +  2", "(2 + 2)", "((2)+(2))"][..],
+            "(2+2)",
+        ),
+        (&["2*3", "2 × 3"], "(2*3)"),
+        (&["6/2", "6 ÷ 2"], "(6/2)"),
+        (&["5-3", "5 \u{2212} 3"], "(5-3)"),
+        (&["1 km to m", "1km as m", "(1 km) to m"], "((1 km) as m)"),
+        (&["(3, 4)", "[3,4]"], "[3, 4]"),
+        (&["x=5;x*2", "x = 5; x * 2"], "x = 5; (x*2)"),
+        (&["1.50", "1.5"], "1.5"),
+        (&["'hi'", "\"hi\""], "\"hi\""),
+    ] {
+        for input in inputs {
+            assert_eq!(normalize(input).unwrap(), expected);
+        }
+        // the canonical form is also its own canonical form
+        assert_eq!(normalize(expected).unwrap(), expected);
+    }
+    assert_eq!(normalize("2 +"), Err("unexpected input found".to_string()));
+    // normalizing doesn't evaluate anything
+    assert_eq!(normalize("foo + 1").unwrap(), "(foo+1)");
+}
+
+#[test]
+fn periodic_interrupt() {
+    use fend_core::{Interrupt, PeriodicInterrupt};
+    use std::cell::Cell;
+
+    struct CountingInterrupt {
+        calls: Cell<u32>,
+        interrupt_after: u32,
+    }
+    impl Interrupt for CountingInterrupt {
+        fn should_interrupt(&self) -> bool {
+            self.calls.set(self.calls.get() + 1);
+            self.calls.get() > self.interrupt_after
+        }
+    }
+
+    let int = PeriodicInterrupt::new(
+        CountingInterrupt {
+            calls: Cell::new(0),
+            interrupt_after: 3,
+        },
+        10,
+    );
+    let results: Vec<bool> = (0..50).map(|_| int.should_interrupt()).collect();
+    // only every 10th check is passed on, and the result is kept afterwards
+    assert!(results[..39].iter().all(|&r| !r));
+    assert!(results[39..].iter().all(|&r| r));
+    assert_eq!(int.into_inner().calls.get(), 4);
+
+    let int = PeriodicInterrupt::new(
+        CountingInterrupt {
+            calls: Cell::new(0),
+            interrupt_after: 5,
+        },
+        100,
+    );
+    let mut ctx = Context::new();
+    assert_eq!(
+        fend_core::evaluate_with_interrupt("10000!", &mut ctx, &int),
+        Err("interrupted".to_string())
+    );
+}
+
+#[test]
+fn stateful_random_number_generator() {
+    fn seeded_context(seed: u32) -> Context {
+        let mut ctx = Context::new();
+        let mut state = seed;
+        ctx.set_random_u32_fn(move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        });
+        ctx
+    }
+
+    let rolls = |ctx: &mut Context| -> Vec<String> {
+        (0..20)
+            .map(|_| {
+                evaluate("roll d20", ctx)
+                    .unwrap()
+                    .get_main_result()
+                    .to_string()
+            })
+            .collect()
+    };
+    let first = rolls(&mut seeded_context(42));
+    assert_eq!(first, rolls(&mut seeded_context(42)));
+    assert_ne!(first, rolls(&mut seeded_context(7)));
+    // the generator's state advances between calls
+    assert!(first.iter().any(|roll| *roll != first[0]));
+
+    let mut ctx = seeded_context(42);
+    for _ in 0..20 {
+        let roll: u32 = evaluate("roll 4d6kh3 + 2", &mut ctx)
+            .unwrap()
+            .get_main_result()
+            .parse()
+            .unwrap();
+        assert!((5..=20).contains(&roll));
+    }
+
+    let mut ctx = seeded_context(42);
+    ctx.disable_rng();
+    assert_eq!(
+        evaluate("roll d6", &mut ctx),
+        Err("random numbers are not available".to_string())
+    );
+}
+
+#[test]
+fn describe_functions_and_units() {
+    test_eval_simple("describe newton", "newton = kg m / s^2 (mass·length/time²)");
+    test_eval_simple("describe N", "N = newton (mass·length/time²)");
+    test_eval_simple("describe meter", "meter: a base unit (length)");
+    test_eval_simple("describe kilo", "kilo = 1e3 (a prefix)");
+    test_eval_simple("describe km", "km (length)");
+    test_eval_simple(
+        "help sin",
+        "sin(x): sine of x (1 argument; domain: any number or angle)",
+    );
+    test_eval_simple(
+        "help(atan2)",
+        "atan2(y, x): angle of the point (x, y) from the positive x axis (2 arguments; domain: any real numbers)",
+    );
+    test_eval_simple(
+        "describe log10",
+        "log(x): base-10 logarithm of x (1 argument; domain: positive numbers)",
+    );
+    expect_error("describe foo", Some("unknown identifier 'foo'"));
+}
+
+#[test]
+fn features_used() {
+    use fend_core::Feature;
+
+    let mut ctx = Context::new();
+    ctx.set_random_u32_fn(|| 4);
+    let mut features = |input: &str| {
+        evaluate(input, &mut ctx)
+            .unwrap()
+            .features_used()
+            .iter()
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(features("1 + 2"), vec![]);
+    assert_eq!(features("sin 1"), vec![Feature::Trigonometry]);
+    assert_eq!(
+        features("5 km to miles"),
+        vec![Feature::Units, Feature::UnitConversion]
+    );
+    assert_eq!(features("2 + 3i"), vec![Feature::ComplexNumbers]);
+    assert_eq!(features("@2000-01-01"), vec![Feature::Dates]);
+    assert_eq!(features("roll d6"), vec![Feature::Random]);
+    assert_eq!(
+        features("a = 30°; sin a"),
+        vec![Feature::Trigonometry, Feature::Units, Feature::Variables]
+    );
+    assert_eq!(features(""), vec![]);
+}
+
+#[test]
+fn parse_bases_and_formatting_styles() {
+    use fend_core::{Base, FormattingStyle, ScientificNotationStyle};
+
+    assert_eq!("hex".parse::<Base>().unwrap().to_string(), "hex");
+    assert_eq!("hexadecimal".parse::<Base>().unwrap().to_string(), "hex");
+    assert_eq!("base 36".parse::<Base>().unwrap().to_string(), "base 36");
+    assert_eq!("base 16".parse::<Base>().unwrap().to_string(), "hex");
+    assert_eq!(" 0x ".parse::<Base>().unwrap().to_string(), "0x");
+    assert_eq!(
+        "base 1".parse::<Base>(),
+        Err("base must be at least 2".to_string())
+    );
+    assert_eq!(
+        "base 300".parse::<Base>(),
+        Err("base cannot be larger than 36".to_string())
+    );
+    assert_eq!("foo".parse::<Base>(), Err("unknown base 'foo'".to_string()));
+    for base in ["0b", "0o", "0x", "2#", "36#", "binary", "decimal", "base 7"] {
+        assert_eq!(base.parse::<Base>().unwrap().to_string(), base);
+    }
+
+    assert_eq!(
+        "frac".parse::<FormattingStyle>(),
+        Ok(FormattingStyle::ImproperFraction)
+    );
+    assert_eq!(
+        "5 dp".parse::<FormattingStyle>(),
+        Ok(FormattingStyle::DecimalPlaces(5))
+    );
+    assert_eq!(
+        "0 sf".parse::<FormattingStyle>(),
+        Err("cannot format a number with zero significant figures".to_string())
+    );
+    assert_eq!(
+        "approx 5".parse::<FormattingStyle>(),
+        Err("unknown formatting style 'approx 5'".to_string())
+    );
+    for style in [
+        FormattingStyle::ImproperFraction,
+        FormattingStyle::MixedFraction,
+        FormattingStyle::ExactFloat,
+        FormattingStyle::DecimalPlaces(3),
+        FormattingStyle::SignificantFigures(2),
+        FormattingStyle::Auto,
+        FormattingStyle::Exact,
+        FormattingStyle::Scientific(ScientificNotationStyle::Exponent),
+        FormattingStyle::Scientific(ScientificNotationStyle::TimesTenTo),
+    ] {
+        assert_eq!(style.to_string().parse::<FormattingStyle>(), Ok(style));
+    }
+}
+
+#[test]
+fn approx_value() {
+    let mut ctx = Context::new();
+    let mut approx = |input: &str| evaluate(input, &mut ctx).unwrap().approx_value();
+    assert_eq!(approx("pi"), Some(std::f64::consts::PI));
+    assert_eq!(approx("1 km + 1 m"), Some(1.001));
+    assert_eq!(approx("1 kg to g"), Some(1000.0));
+    assert_eq!(approx("-1/4"), Some(-0.25));
+    assert_eq!(approx("2i"), None);
+    assert_eq!(approx("\"5\""), None);
+    assert_eq!(approx("d6"), None);
+    assert_eq!(approx("10^400"), None);
+    assert_eq!(approx(""), None);
+}
+
+#[test]
+fn recurring_decimal_period() {
+    test_eval("period(1/3)", "1");
+    test_eval("period(1/7)", "6");
+    test_eval("period(1/13)", "6");
+    test_eval("period(1/97)", "96");
+    test_eval("period(1/6)", "1");
+    test_eval("period(1/8)", "0");
+    test_eval("period(5)", "0");
+    test_eval("period(1/7 to base 3)", "6");
+    test_eval("period(0xff/7)", "3");
+    expect_error("period(pi)", Some("expected a rational number"));
+    expect_error("period(1 kg)", Some("expected a unitless number"));
+}
+
+#[test]
+fn max_recurring_digits() {
+    let mut ctx = Context::new();
+    ctx.set_max_recurring_digits(10);
+    let mut eval = |input: &str| {
+        evaluate(input, &mut ctx)
+            .unwrap()
+            .get_main_result()
+            .to_string()
+    };
+    assert_eq!(eval("1/7 to float"), "0.(142857)");
+    assert_eq!(eval("1/97 to float"), "0.0103092783...");
+    assert_eq!(eval("-2/97 to float"), "-0.0206185567...");
+    assert_eq!(eval("1/1024 to float"), "0.0009765625");
+    assert_eq!(
+        evaluate("1/97 to float", &mut Context::new())
+            .unwrap()
+            .get_main_result()
+            .len(),
+        100
+    );
+}
+
+#[test]
+fn max_float_digits() {
+    let mut ctx = Context::new();
+    ctx.set_max_float_digits(6);
+    let mut eval = |input: &str| {
+        evaluate(input, &mut ctx)
+            .unwrap()
+            .get_main_result()
+            .to_string()
+    };
+    assert_eq!(eval("1/7 to float"), "0.(142857)");
+    assert_eq!(eval("1/1024 to float"), "approx. 0.000976...");
+    assert_eq!(eval("-1/1024 to float"), "approx. -0.000976...");
+    assert_eq!(eval("1/64 to float"), "0.015625");
+    assert_eq!(eval("1/97 to float"), "approx. 0.010309...");
+    assert_eq!(eval("1/999983 to float"), "approx. 0.000001...");
+    assert_eq!(eval("1/10000000 to float"), "approx. 0.000000...");
+    assert_eq!(eval("1/1024"), "0.0009765625");
+}
+
+#[test]
+fn non_finite_results() {
+    expect_error(
+        "sinh 1000",
+        Some("result is infinite or too large to approximate"),
+    );
+    expect_error(
+        "cosh (-1000)",
+        Some("result is infinite or too large to approximate"),
+    );
+    expect_error(
+        "ln(10^400)",
+        Some("result is infinite or too large to approximate"),
+    );
+    test_eval("sinh 50", "approx. 2592352764293536022528");
+    test_eval("sinh (-50)", "approx. -2592352764293536022528");
+    test_eval("tanh 1000", "approx. 1");
+    test_eval("is_finite(sinh 700)", "true");
+    test_eval("is_finite(1e400)", "true");
+    test_eval("is_finite(2i)", "true");
+    test_eval("is_finite(5 kg)", "true");
+    test_eval("is_nan(0)", "false");
+    test_eval("is_nan(1/3)", "false");
+    test_eval("is_infinite(1e400)", "false");
+    test_eval("is_infinite(-10^1000)", "false");
+    expect_error("is_nan(true)", Some("expected a number"));
+}
+
+#[test]
+fn base64_and_hex_encoding() {
+    test_eval_simple("\"hello\" to base64", "aGVsbG8=");
+    test_eval_simple("\"hi\" to base64", "aGk=");
+    test_eval_simple("\"h\" to base64", "aA==");
+    test_eval_simple("\"\" to base64", "");
+    test_eval_simple("from_base64(\"aGVsbG8=\")", "hello");
+    test_eval_simple("from_base64(\"aGVsbG8\")", "hello");
+    test_eval_simple("from_base64(\"\u{3c0}\" to base64)", "\u{3c0}");
+    test_eval_simple("\"hello\" to hex", "68656c6c6f");
+    test_eval_simple("\"\u{e9}\" to hex", "c3a9");
+    test_eval_simple("from_hex(\"68656C6C6F\")", "hello");
+    test_eval_simple("255 to hex", "ff");
+    expect_error("from_base64(\"a\")", Some("invalid base64 string"));
+    expect_error("from_base64(\"aGk===\")", Some("invalid base64 string"));
+    expect_error("from_base64(\"a-Gk\")", Some("invalid base64 string"));
+    expect_error(
+        "from_hex(\"686\")",
+        Some("invalid hex string: expected pairs of hex digits"),
+    );
+    expect_error(
+        "from_hex(\"zz\")",
+        Some("invalid hex string: expected pairs of hex digits"),
+    );
+    expect_error(
+        "from_hex(\"ff\")",
+        Some("decoded bytes are not valid UTF-8"),
+    );
+    expect_error("5 to base64", Some("expected a string"));
+}
+
+#[test]
+fn string_hashes() {
+    test_eval_simple("md5(\"\")", "d41d8cd98f00b204e9800998ecf8427e");
+    test_eval_simple("md5(\"hello\")", "5d41402abc4b2a76b9719d911017c592");
+    test_eval_simple(
+        "md5(\"The quick brown fox jumps over the lazy dog\")",
+        "9e107d9d372bb6826bd81d3542a419d6",
+    );
+    test_eval_simple(
+        "sha256(\"\")",
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+    );
+    test_eval_simple(
+        "sha256(\"hello\")",
+        "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824",
+    );
+    // spans two blocks after padding
+    test_eval_simple(
+        "sha256(\"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\")",
+        "2816597888e4a0d3a36b82b83316ab32680eb8f00f8cd3b904d681246d285a0e",
+    );
+    test_eval_simple("crc32(\"\")", "00000000");
+    test_eval_simple("crc32(\"hello\")", "3610a686");
+    test_eval_simple("crc32(\"\u{3c0}\")", "e0035a5e");
+    expect_error("md5(5)", Some("expected a string"));
+}
+
+#[test]
+fn set_and_get_variables() {
+    let mut ctx = Context::new();
+    ctx.set_variable("distance", "5 km").unwrap();
+    ctx.set_variable("time", "30 minutes").unwrap();
+    let distance = ctx.get_variable("distance").unwrap();
+    assert_eq!(distance.get_main_result(), "5 km");
+    assert_eq!(distance.approx_value(), Some(5.0));
+    assert_eq!(
+        evaluate("distance / time to km/h", &mut ctx)
+            .unwrap()
+            .get_main_result(),
+        "10 km / h"
+    );
+    evaluate("speed = distance / time", &mut ctx).unwrap();
+    assert_eq!(
+        ctx.get_variable("speed").unwrap().get_main_result(),
+        "approx. 0.1666666666 km / minute"
+    );
+    // setting a variable keeps the others
+    ctx.set_variable("time", "1 hour").unwrap();
+    assert_eq!(
+        ctx.get_variable("distance").unwrap().get_main_result(),
+        "5 km"
+    );
+    assert_eq!(
+        ctx.get_variable("time").unwrap().get_main_result(),
+        "1 hour"
+    );
+    assert_eq!(ctx.get_variable("undefined"), None);
+    assert_eq!(
+        ctx.set_variable("a b", "5"),
+        Err("'a b' is not a valid variable name".to_string())
+    );
+    assert_eq!(
+        ctx.set_variable("x", "5 +"),
+        Err("unexpected input found".to_string())
+    );
+    assert_eq!(ctx.get_variable("x"), None);
+}
+
+#[test]
+fn high_precision_functions() {
+    test_eval_simple("ln 2 to 30 dp", "approx. 0.693147180559945309417232121458");
+    test_eval_simple("sin 1 to 30 dp", "approx. 0.84147098480789650665250232163");
+    test_eval_simple("cos 1 to 30 dp", "approx. 0.540302305868139717400936607442");
+    test_eval_simple(
+        "atan 1 to 30 dp",
+        "approx. 0.785398163397448309615660845819",
+    );
+    test_eval_simple(
+        "asin 1 to 30 dp",
+        "approx. 1.570796326794896619231321691639",
+    );
+    test_eval_simple(
+        "sinh 1 to 30 dp",
+        "approx. 1.175201193643801456882381850595",
+    );
+    test_eval_simple(
+        "log10 2 to 30 dp",
+        "approx. 0.301029995663981195213738894724",
+    );
+    test_eval_simple(
+        "ln (10^400) to 30 dp",
+        "approx. 921.034037197618273607196581873745",
+    );
+    test_eval_simple(
+        "sin 1000 to 30 dp",
+        "approx. 0.826879540532002560255887429109",
+    );
+    test_eval_simple(
+        "pi to 40 dp",
+        "approx. 3.1415926535897932384626433832795028841971",
+    );
+    test_eval_simple("e to 30 dp", "approx. 2.718281828459045235360287471352");
+    test_eval_simple(
+        "sqrt 2 to 30 dp",
+        "approx. 1.414213562373095048801688724209",
+    );
+    test_eval_simple("sin 1 to 30 sf", "approx. 0.84147098480789650665250232163");
+    // f64 precision is still used by default
+    test_eval_simple("sin 1", "approx. 0.8414709848");
+
+    let mut ctx = Context::new();
+    ctx.set_precision(30);
+    assert_eq!(
+        evaluate("ln 2 * 1", &mut ctx).unwrap().get_main_result(),
+        "approx. 0.6931471805"
+    );
+    assert_eq!(
+        evaluate("x = tan 1; x to 30 dp", &mut ctx)
+            .unwrap()
+            .get_main_result(),
+        "approx. 1.557407724654902230506974807458"
+    );
+}
+
+#[test]
+fn unknown_identifier_suggestions() {
+    expect_error(
+        "5 metr",
+        Some("unknown identifier 'metr' (did you mean 'meter'?)"),
+    );
+    expect_error(
+        "fahrenheight",
+        Some("unknown identifier 'fahrenheight' (did you mean 'fahrenheit'?)"),
+    );
+    expect_error(
+        "sqrtt 4",
+        Some("unknown identifier 'sqrtt' (did you mean 'sqrt'?)"),
+    );
+    expect_error("aiusbdla", Some("unknown identifier 'aiusbdla'"));
+    expect_error("qwertyuiop", Some("unknown identifier 'qwertyuiop'"));
+    // identifiers this short are too ambiguous for a suggestion
+    expect_error("mtr", Some("unknown identifier 'mtr'"));
+
+    let mut ctx = Context::new();
+    evaluate("length = 5", &mut ctx).unwrap();
+    assert_eq!(
+        evaluate("lenght * 2", &mut ctx).unwrap_err(),
+        "unknown identifier 'lenght' (did you mean 'length'?)"
+    );
+}
+
+#[test]
+fn compound_units_without_spaces() {
+    test_eval_simple("5 kWh to J", "18000000 J");
+    test_eval("10 Nm", "10 N m");
+    test_eval("10 Nm to J", "10 J");
+    test_eval("2 kNm", "2 kN m");
+    test_eval("1 VA to W", "1 W");
+    test_eval("3 kgm", "3 kg m");
+    test_eval("1 Pas", "1 Pa s");
+    test_eval("1 mAs to coulomb", "0.001 coulomb");
+    test_eval("3 mph", "3 mph");
+    // whole units are preferred over splitting
+    test_eval("5 ms to s", "0.005 s");
+    // constants and numbers aren't part of compound units
+    expect_error("mc", None);
+    expect_error("KG", Some("unknown identifier 'KG'"));
+    expect_error(
+        "1 Whs",
+        Some("ambiguous unit 'Whs': could be 'W hs' or 'Wh s'"),
+    );
+}
+
+#[test]
+fn unix_timestamps() {
+    test_eval("@1970-01-01 to unix", "0");
+    test_eval("@2024-01-01 to unix", "1704067200");
+    test_eval("@2024-01-01 15:30 to timestamp", "1704123000");
+    test_eval("@1969-12-31 to unix", "-86400");
+    test_eval_simple("@unix 1704067200", "Monday, 1 January 2024 00:00");
+    test_eval_simple("@unix 1704123000 to unix", "1704123000");
+    test_eval_simple("@unix -1", "Wednesday, 31 December 1969 23:59:59");
+    expect_error("5 to unix", Some("cannot convert value to unix timestamp"));
+}
+
+#[test]
+fn relative_dates() {
+    let mut context = Context::new();
+    // Wednesday, 31 January 2024
+    context.set_current_time_v1(1_706_702_400_000, 0);
+    for (input, expected) in [
+        ("next friday", "Friday, 2 February 2024"),
+        ("next wednesday", "Wednesday, 7 February 2024"),
+        ("last monday", "Monday, 29 January 2024"),
+        ("last wednesday", "Wednesday, 24 January 2024"),
+        ("this wednesday", "Wednesday, 31 January 2024"),
+        ("this Friday", "Friday, 2 February 2024"),
+        ("next week", "Wednesday, 7 February 2024"),
+        ("next month", "Thursday, 29 February 2024"),
+        ("last month", "Sunday, 31 December 2023"),
+        ("next year", "Friday, 31 January 2025"),
+        ("first day of next month", "Thursday, 1 February 2024"),
+        ("last day of next month", "Thursday, 29 February 2024"),
+        ("last day of this month", "Wednesday, 31 January 2024"),
+        ("first day of last month", "Friday, 1 December 2023"),
+        ("first day of @2024-05-17", "Wednesday, 1 May 2024"),
+        ("next friday + 1 day", "Saturday, 3 February 2024"),
+        ("days until next friday", "2 days"),
+    ] {
+        assert_eq!(
+            evaluate(input, &mut context).unwrap().get_main_result(),
+            expected
+        );
+    }
+    // these words are only special in relative dates
+    assert_eq!(
+        evaluate("next = 5; next * 2", &mut context)
+            .unwrap()
+            .get_main_result(),
+        "10"
+    );
+    expect_error("next friday", Some("unable to get the current date"));
+}
+
+#[test]
+fn start_and_end_of_periods() {
+    test_eval_simple(
+        "@2024-03-15 14:37 to start of day",
+        "Friday, 15 March 2024 00:00",
+    );
+    test_eval_simple(
+        "@2024-03-15 14:37 to end of day",
+        "Friday, 15 March 2024 23:59:59",
+    );
+    test_eval_simple(
+        "@2024-03-15 14:37:12 to start of hour",
+        "Friday, 15 March 2024 14:00",
+    );
+    test_eval_simple(
+        "@2024-03-15 14:37:12 to end of minute",
+        "Friday, 15 March 2024 14:37:59",
+    );
+    test_eval_simple("@2024-03-15 to start of month", "Friday, 1 March 2024");
+    test_eval_simple("@2024-02-10 to end of month", "Thursday, 29 February 2024");
+    test_eval_simple("@2023-02-10 to end of month", "Tuesday, 28 February 2023");
+    test_eval_simple("@2024-04-30 to end of month", "Tuesday, 30 April 2024");
+    test_eval_simple(
+        "@2024-02-10 14:00 to end of month",
+        "Thursday, 29 February 2024 23:59:59",
+    );
+    test_eval_simple("@2024-03-17 to start of week", "Monday, 11 March 2024");
+    test_eval_simple("@2024-03-15 to end of week", "Sunday, 17 March 2024");
+    test_eval_simple("@2024-03-15 to start of year", "Monday, 1 January 2024");
+    test_eval_simple("@2024-03-15 to end of year", "Tuesday, 31 December 2024");
+    test_eval_simple("@2024-03-15 - (@2024-03-15 to start of month)", "14 days");
+    expect_error("5 to start of day", Some("expected a date (found number)"));
+    expect_error(
+        "@2024-03-15 to start of fortnight",
+        Some("unknown period 'fortnight': expected minute, hour, day, week, month or year"),
+    );
+}
+
+#[test]
+fn parse_cache() {
+    let inputs = [
+        "a = 2",
+        "a + 1",
+        "a = a * 3",
+        "a + 1",
+        "a + 1",
+        "1/0",
+        "1/0",
+        "5 C to F",
+        "a + 1",
+        "5 C to F",
+        "sqrt 16",
+        "a + 1",
+    ];
+    let mut uncached = Context::new();
+    let mut cached = Context::new();
+    cached.set_parse_cache_size(2);
+    for input in inputs {
+        let expected = evaluate(input, &mut uncached).map(|res| res.get_main_result().to_string());
+        let actual = evaluate(input, &mut cached).map(|res| res.get_main_result().to_string());
+        assert_eq!(actual, expected, "{input}");
+    }
+    assert_eq!(
+        evaluate("a + 1", &mut cached).unwrap().get_main_result(),
+        "7"
+    );
+
+    cached.use_coulomb_and_farad();
+    // coulomb and farad are incompatible
+    assert!(evaluate("5 C to F", &mut cached)
+        .unwrap_err()
+        .starts_with("cannot convert from C to F"));
+    cached.define_function("double", |args| match args {
+        [fend_core::Value::Number(n)] => Ok(fend_core::Value::Number(2.0 * n)),
+        _ => Err("double expects a number".to_string()),
+    });
+    assert_eq!(
+        evaluate("double(a + 1)", &mut cached)
+            .unwrap()
+            .get_main_result(),
+        "14"
+    );
+    cached.set_parse_cache_size(0);
+    assert_eq!(
+        evaluate("a + 1", &mut cached).unwrap().get_main_result(),
+        "7"
+    );
+}
+
+#[test]
+fn numbers_to_words() {
+    test_eval_simple("0 to words", "zero");
+    test_eval_simple("7 to words", "seven");
+    test_eval_simple("13 to words", "thirteen");
+    test_eval_simple("19 to words", "nineteen");
+    test_eval_simple("20 to words", "twenty");
+    test_eval_simple("42 to words", "forty-two");
+    test_eval_simple("99 to words", "ninety-nine");
+    test_eval_simple("100 to words", "one hundred");
+    test_eval_simple("101 to words", "one hundred one");
+    test_eval_simple("110 to words", "one hundred ten");
+    test_eval_simple("999 to words", "nine hundred ninety-nine");
+    test_eval_simple("1000 to words", "one thousand");
+    test_eval_simple("1001 to words", "one thousand one");
+    test_eval_simple("1000000 to words", "one million");
+    test_eval_simple("1000010 to words", "one million ten");
+    test_eval_simple(
+        "1234567 to words",
+        "one million two hundred thirty-four thousand five hundred sixty-seven",
+    );
+    test_eval_simple("-42 to words", "negative forty-two");
+    test_eval_simple("6 * 7 to words", "forty-two");
+    expect_error("1.5 to words", Some("cannot convert fraction to integer"));
+    expect_error(
+        "pi to words",
+        Some("number cannot be converted to an integer"),
+    );
+    expect_error(
+        "2 kg to words",
+        Some("cannot convert number with unit to integer"),
+    );
+    expect_error(
+        "10^30 to words",
+        Some("1000000000000000000000000000000 must lie in the interval [0, 18446744073709551615]"),
+    );
+}
+
+#[test]
+fn numbers_to_ordinals() {
+    test_eval_simple("0 to ordinal", "zeroth");
+    test_eval_simple("1 to ordinal", "first");
+    test_eval_simple("2 to ordinal", "second");
+    test_eval_simple("3 to ordinal", "third");
+    test_eval_simple("4 to ordinal", "fourth");
+    test_eval_simple("5 to ordinal", "fifth");
+    test_eval_simple("8 to ordinal", "eighth");
+    test_eval_simple("9 to ordinal", "ninth");
+    test_eval_simple("12 to ordinal", "twelfth");
+    test_eval_simple("13 to ordinal", "thirteenth");
+    test_eval_simple("20 to ordinal", "twentieth");
+    test_eval_simple("21 to ordinal", "twenty-first");
+    test_eval_simple("42 to ordinal", "forty-second");
+    test_eval_simple("100 to ordinal", "one hundredth");
+    test_eval_simple("103 to ordinal", "one hundred third");
+    test_eval_simple("1000000 to ordinal", "one millionth");
+    expect_error("-1 to ordinal", Some("negative numbers are not allowed"));
+}
+
+#[test]
+fn percent_sign_as_modulo_or_percent() {
+    test_eval("7 % 3", "1");
+    test_eval_simple("7%3", "1");
+    test_eval_simple("7% 3", "1");
+    test_eval_simple("(7)%(3)", "1");
+    test_eval_simple("2^3 % 5", "3");
+    test_eval_simple("1 + 10 % 4", "3");
+    test_eval("50% + 50%", "100%");
+    test_eval("50%", "50%");
+    test_eval("0.5 to %", "50%");
+    test_eval_simple("[50%, 3]", "[50%, 3]");
+    // `%` followed by an identifier is a percentage
+    test_eval("5% °C to °F", "32.09 °F");
+    let mut ctx = Context::new();
+    evaluate("x = 7", &mut ctx).unwrap();
+    assert_eq!(evaluate("x % 3", &mut ctx).unwrap().get_main_result(), "1");
+    assert_eq!(
+        evaluate("x mod 3", &mut ctx).unwrap().get_main_result(),
+        "1"
+    );
+}
+
+#[test]
+fn great_circle_distance() {
+    test_eval(
+        "distance((51.5074, -0.1278), (48.8566, 2.3522))",
+        "approx. 343.556060341 km",
+    );
+    test_eval(
+        "distance((40.7128, -74.0060), (-33.8688, 151.2093))",
+        "approx. 15988.7555070396 km",
+    );
+    test_eval("distance((0, 0), (0, 90))", "approx. 10007.5433980102 km");
+    // antipodal points are half the circumference apart
+    test_eval("distance((0, 0), (0, 180))", "approx. 20015.0867960205 km");
+    test_eval("distance((90, 0), (-90, 0))", "approx. 20015.0867960205 km");
+    test_eval("distance((12.5, 34), (12.5, 34))", "0 km");
+    test_eval("distance((12°, 34°), (12, 34))", "0 km");
+    test_eval(
+        "distance((0 rad, 0), ((pi/2) rad, 0))",
+        "approx. 10007.5433980102 km",
+    );
+    test_eval(
+        "distance((0, 0), (0, 180), 3389.5 km)",
+        "approx. 10648.4282993426 km",
+    );
+    test_eval(
+        "distance((51.5, 0), (48.8, 2.3)) to miles",
+        "approx. 212.5084561643 miles",
+    );
+    expect_error(
+        "distance((1, 2), (3, 4), (5, 6), (7, 8))",
+        Some("distance expects 2 arguments"),
+    );
+    expect_error(
+        "distance(1, 2)",
+        Some("expected a list of two coordinates, e.g. (3, 4)"),
+    );
+}
+
+#[test]
+fn fractional_discrete_unit_warnings() {
+    let warnings = |input: &str| {
+        let mut ctx = Context::new();
+        let res = evaluate(input, &mut ctx).unwrap();
+        (res.get_main_result().to_string(), res.warnings().to_vec())
+    };
+    assert_eq!(
+        warnings("0.3 bytes to bits"),
+        (
+            "2.4 bits".to_string(),
+            vec!["result is not a whole number of bits".to_string()]
+        )
+    );
+    assert_eq!(
+        warnings("12 bits to bytes"),
+        (
+            "1.5 bytes".to_string(),
+            vec!["result is not a whole number of bytes".to_string()]
+        )
+    );
+    assert_eq!(
+        warnings("3 b to B"),
+        (
+            "0.375 B".to_string(),
+            vec!["result is not a whole number of B".to_string()]
+        )
+    );
+    // whole numbers round-trip without a warning
+    assert_eq!(
+        warnings("1.5 bytes to bits"),
+        ("12 bits".to_string(), vec![])
+    );
+    assert_eq!(
+        warnings("16 bits to bytes"),
+        ("2 bytes".to_string(), vec![])
+    );
+    assert_eq!(warnings("1 kB to bits"), ("8000 bits".to_string(), vec![]));
+    // prefixed units may be fractional
+    assert_eq!(
+        warnings("3 bits to kB"),
+        ("0.000375 kB".to_string(), vec![])
+    );
+    // only conversions are checked
+    assert_eq!(warnings("2.4 bits"), ("2.4 bits".to_string(), vec![]));
+}
+
+#[test]
+fn result_history() {
+    let mut ctx = Context::new();
+    assert_eq!(
+        evaluate("hist(1)", &mut ctx).unwrap_err(),
+        "there are no previous results"
+    );
+    for input in ["1 + 1", "5 kg", "a = 3", "'hello'"] {
+        evaluate(input, &mut ctx).unwrap();
+    }
+    // assignments aren't stored, like with `ans`
+    assert_eq!(
+        evaluate("hist(1)", &mut ctx).unwrap().get_main_result(),
+        "hello"
+    );
+    // recalled results are stored again, so `1 + 1` is now 4 results back
+    assert_eq!(
+        evaluate("hist 3", &mut ctx).unwrap().get_main_result(),
+        "5 kg"
+    );
+    assert_eq!(
+        evaluate("hist(5) * hist(1)", &mut ctx)
+            .unwrap()
+            .get_main_result(),
+        "10 kg"
+    );
+    assert_eq!(
+        evaluate("hist(7)", &mut ctx).unwrap_err(),
+        "hist(7) is out of range: expected an index from 1 to 6"
+    );
+    assert_eq!(
+        evaluate("hist(0)", &mut ctx).unwrap_err(),
+        "hist(0) is out of range: expected an index from 1 to 6"
+    );
+
+    // the history is kept when the session is restored
+    let mut data = vec![];
+    ctx.serialize_variables(&mut data).unwrap();
+    let mut restored = Context::new();
+    restored
+        .deserialize_variables(&mut data.as_slice())
+        .unwrap();
+    assert_eq!(
+        evaluate("hist(1)", &mut restored)
+            .unwrap()
+            .get_main_result(),
+        "10 kg"
+    );
+    assert_eq!(
+        evaluate("hist(4)", &mut restored)
+            .unwrap()
+            .get_main_result(),
+        "hello"
+    );
+    assert_eq!(evaluate("a", &mut restored).unwrap().get_main_result(), "3");
+
+    // older results are dropped eventually
+    let mut ctx = Context::new();
+    for i in 0..150 {
+        evaluate(&i.to_string(), &mut ctx).unwrap();
+    }
+    assert_eq!(
+        evaluate("hist(100)", &mut ctx).unwrap().get_main_result(),
+        "50"
+    );
+    assert_eq!(
+        evaluate("hist(101)", &mut ctx).unwrap_err(),
+        "hist(101) is out of range: expected an index from 1 to 100"
+    );
+}
+
+#[test]
+fn boolean_logic_gates() {
+    test_eval("true and false", "false");
+    test_eval("true or false", "true");
+    test_eval("true xor true", "false");
+    test_eval("true nand true", "false");
+    test_eval("true nand false", "true");
+    test_eval("false nor false", "true");
+    test_eval("true nor false", "false");
+    test_eval("true xnor true", "true");
+    test_eval("false XNOR true", "false");
+    test_eval("true & false | true", "true");
+    test_eval("6 xor 3", "5");
+    expect_error("5 nand 3", Some("expected a bool (found number)"));
+    expect_error("true and 1", Some("expected a number"));
+}
+
+#[test]
+fn truth_tables() {
+    test_eval_simple(
+        "truthtable(a and b, [a, b])",
+        "a     | b     | result\n\
+         false | false | false\n\
+         false | true  | false\n\
+         true  | false | false\n\
+         true  | true  | true",
+    );
+    test_eval_simple(
+        "truthtable(not(p), p)",
+        "p     | result\nfalse | true\ntrue  | false",
+    );
+    test_eval_simple(
+        "truthtable(input xnor b, [input, b])",
+        "input | b     | result\n\
+         false | false | true\n\
+         false | true  | false\n\
+         true  | false | false\n\
+         true  | true  | true",
+    );
+    expect_error(
+        "truthtable(a and b)",
+        Some("truthtable expects an expression and a list of variable names, e.g. `truthtable(a and b, [a, b])`"),
+    );
+    expect_error("truthtable(1, [a])", Some("expected a bool (found number)"));
+}
+
+#[test]
+fn bit_fields() {
+    test_eval("bits(0xABCD, 4, 8)", "0xbc");
+    test_eval("bits(0xABCD, 0, 4)", "0xd");
+    test_eval("bits(0xABCD, 12, 4)", "0xa");
+    // bits 6 to 9 span both bytes
+    test_eval("bits(0xABCD, 6, 4)", "0xf");
+    test_eval("bits(0b1011, 1, 10)", "0b101");
+    test_eval("set_bits(0xABCD, 4, 8, 0x12)", "0xa12d");
+    test_eval("set_bits(0xff, 4, 4, 0)", "0xf");
+    test_eval("set_bits(0, 8, 1, 1)", "256");
+    test_eval("set_bits(0xABCD, 6, 4, bits(0xABCD, 6, 4))", "0xabcd");
+    test_eval("bits(set_bits(0xABCD, 6, 4, 0b0110), 6, 4)", "0x6");
+    // `bits` is still a unit when it isn't applied to a list
+    test_eval("5 bits", "5 bits");
+    expect_error(
+        "set_bits(0xABCD, 4, 8, 0x100)",
+        Some("0x100 does not fit in 8 bits"),
+    );
+    expect_error("set_bits(1, 1, 1, 2)", Some("2 does not fit in 1 bit"));
+    expect_error(
+        "bits(5, 0, 0)",
+        Some("bit fields must be at least 1 bit long"),
+    );
+    expect_error("bits(5, -1, 2)", Some("negative numbers are not allowed"));
+    expect_error("bits(1.5, 0, 2)", Some("1.5 is not an integer"));
+}
+
+#[test]
+fn ip_addresses() {
+    test_eval("192.168.1.5", "192.168.1.5");
+    test_eval("192.168.1.0/24", "192.168.1.0/24");
+    test_eval("192.168.1.5 in 192.168.1.0/24", "true");
+    test_eval("192.168.2.5 in 192.168.1.0/24", "false");
+    test_eval("10.20.0.0/16 in 10.0.0.0/8", "true");
+    test_eval("10.0.0.0/8 in 10.20.0.0/16", "false");
+    test_eval("hosts of 192.168.1.0/24", "254");
+    test_eval("hosts of 10.0.0.0/31", "2");
+    test_eval("hosts of 10.0.0.1", "1");
+    test_eval("network of 192.168.1.77/24", "192.168.1.0/24");
+    test_eval("broadcast of 192.168.1.77/24", "192.168.1.255");
+    test_eval("broadcast of 172.16.0.0/12", "172.31.255.255");
+    test_eval("netmask of 10.1.2.3/8", "255.0.0.0");
+    test_eval("netmask of 10.1.2.3/0", "0.0.0.0");
+    test_eval("192.168.1.255 + 1", "192.168.2.0");
+    test_eval("10.0.0.5 - 6", "9.255.255.255");
+    test_eval("10.0.1.0 - 10.0.0.0", "256");
+    test_eval("10.0.0.1 < 10.0.0.2", "true");
+    // other numbers are unaffected
+    test_eval("1.5 + 2.25", "3.75");
+    expect_error(
+        "192.168.1.0/33",
+        Some("invalid network '192.168.1.0/33': the prefix length must be from 0 to 32"),
+    );
+    expect_error(
+        "256.1.1.1",
+        Some("invalid IP address '256.1.1.1': each part must be from 0 to 255"),
+    );
+    expect_error("255.255.255.255 + 1", Some("IP address is out of range"));
+}
+
+#[test]
+fn serialize_ip_address() {
+    let mut ctx = Context::new();
+    evaluate("net = 10.0.0.0/8; ip = 192.168.1.5", &mut ctx).unwrap();
+    let mut data = vec![];
+    ctx.serialize_variables(&mut data).unwrap();
+    let mut restored = Context::new();
+    restored
+        .deserialize_variables(&mut data.as_slice())
+        .unwrap();
+    assert_eq!(
+        evaluate("net", &mut restored).unwrap().get_main_result(),
+        "10.0.0.0/8"
+    );
+    assert_eq!(
+        evaluate("ip in net", &mut restored)
+            .unwrap()
+            .get_main_result(),
+        "false"
+    );
+}
+
+#[test]
+fn engineering_notation() {
+    test_eval_simple("1234.5 to engineering", "1.2345e3");
+    test_eval_simple("12345 to engineering", "12.345e3");
+    test_eval_simple("123456 to eng", "123.456e3");
+    test_eval_simple("-12345.6 to engineering", "-12.3456e3");
+    test_eval_simple("0.00012345 to engineering", "123.45e-6");
+    test_eval_simple("-0.012 to eng", "-12e-3");
+    test_eval_simple("5 kg to eng", "5e0 kg");
+    test_eval_simple("0 to eng", "0");
+}
+
+#[test]
+fn fixed_decimal_places() {
+    test_eval_simple("1234.5 to fixed 2", "1234.50");
+    test_eval_simple("1234 to fixed 2", "1234.00");
+    test_eval_simple("-0.5 to fixed 3", "-0.500");
+    // small negative numbers are truncated, but keep their sign
+    test_eval_simple("-0.005 to fixed 2", "approx. -0.00");
+    test_eval_simple("-0.004 kg to fixed 0", "approx. -0 kg");
+    test_eval_simple("-1/3 to fixed 2", "approx. -0.33");
+    test_eval_simple("-0 to fixed 2", "0.00");
+    test_eval_simple("0.123456 to fixed 2", "approx. 0.12");
+    test_eval_simple("1/3 to fixed 4", "approx. 0.3333");
+    test_eval_simple("2 to fixed 0", "2");
+    test_eval_simple("1234.5 kg to fixed 2", "1234.50 kg");
+    test_eval_simple("3 m^2 to fixed 1", "3.0 m^2");
+    test_eval_simple("1 + 2i to fixed 2", "1.00 + 2.00i");
+    expect_error(
+        "5 to fixed",
+        Some("expected a number of decimal places, e.g. 'to fixed 2'"),
+    );
+    // only affects this result
+    let mut ctx = Context::new();
+    evaluate("1.5 to fixed 3", &mut ctx).unwrap();
+    assert_eq!(evaluate("1.5", &mut ctx).unwrap().get_main_result(), "1.5");
+}
+
+#[test]
+fn restricted_mode() {
+    let mut ctx = Context::new();
+    ctx.set_random_u32_fn(|| 5);
+    ctx.set_current_time_v1(1_000_000_000_000, 0);
+    ctx.set_restricted_mode(true);
+    for (input, feature) in [
+        ("roll d6", "random numbers"),
+        ("choose [1, 2, 3]", "random numbers"),
+        ("today", "dates"),
+        ("@2000-01-01", "dates"),
+        ("\"2000-01-01\" to date", "dates"),
+    ] {
+        assert_eq!(
+            evaluate(input, &mut ctx).unwrap_err(),
+            format!("feature disabled in restricted mode: {feature}"),
+            "{input}"
+        );
+    }
+    for input in ["10^10^10", "2^100000", "10000!"] {
+        assert_eq!(
+            evaluate(input, &mut ctx).unwrap_err(),
+            "computation exceeded memory limit",
+            "{input}"
+        );
+    }
+    assert!(evaluate("now", &mut ctx).is_err());
+    assert_eq!(
+        evaluate("(1 + 2) * 3 / 4", &mut ctx)
+            .unwrap()
+            .get_main_result(),
+        "2.25"
+    );
+    assert_eq!(
+        evaluate("5 km to m", &mut ctx).unwrap().get_main_result(),
+        "5000 m"
+    );
+    ctx.set_restricted_mode(false);
+    assert_eq!(
+        evaluate("roll d6", &mut ctx).unwrap().get_main_result(),
+        "1"
+    );
+    assert_eq!(
+        evaluate("today", &mut ctx).unwrap().get_main_result(),
+        "Sunday, 9 September 2001"
+    );
+}
+
+#[test]
+fn solve_linear_systems() {
+    test_eval("solve([[2, 1], [1, 3]], [5, 10])", "[1, 3]");
+    test_eval(
+        "solve([[1, 1, 1], [0, 2, 5], [2, 5, -1]], [6, -4, 27])",
+        "[5, 3, -2]",
+    );
+    // the first pivot is zero, so rows are swapped
+    test_eval("solve([[0, 1], [1, 0]], [2, 3])", "[3, 2]");
+    test_eval("solve([[2, 1], [1, 3]], [1, 0])", "[0.6, -0.2]");
+    test_eval("solve([[2 kg]], [4 kg m])", "[2 m]");
+    expect_error(
+        "solve([[1, 2], [2, 4]], [3, 6])",
+        Some("no unique solution"),
+    );
+    expect_error(
+        "solve([[1, 1, 1], [1, 2, 3], [2, 3, 4]], [1, 2, 3])",
+        Some("no unique solution"),
+    );
+    expect_error(
+        "solve([[1, 2], [3, 4]], [1])",
+        Some("solve expects a square matrix and a vector of the same size, e.g. `solve([[2, 1], [1, 3]], [5, 10])`"),
+    );
+    expect_error(
+        "solve([[1, 2]], [1])",
+        Some("solve expects a square matrix and a vector of the same size, e.g. `solve([[2, 1], [1, 3]], [5, 10])`"),
+    );
+}
+
+#[test]
+fn min_max_and_span_with_units() {
+    test_eval("max([3 m, 200 cm, 2.5 m])", "3 m");
+    // 200 cm is the largest raw value, but the smallest length
+    test_eval("max([200 cm, 2.5 m])", "2.5 m");
+    test_eval("min([200 cm, 2.5 m])", "200 cm");
+    test_eval("argmax [200 cm, 2.5 m]", "2");
+    test_eval("span([3 m, 200 cm, 2.5 m])", "1 m");
+    test_eval("range [200 cm, 2.5 m]", "0.5 m");
+    test_eval("span [4, 1, 3]", "3");
+    test_eval("span [5]", "0");
+    expect_error("span []", Some("span is undefined for an empty list"));
+    expect_error("span [1 m, 2 kg]", None);
+    expect_error("max [1 m, 2 kg]", None);
+}
+
+#[test]
+fn numbers_in_words() {
+    test_eval("one hundred twenty three", "123");
+    test_eval("forty-two plus eight", "50");
+    test_eval("forty two", "42");
+    test_eval("nine hundred ninety-nine", "999");
+    test_eval("one hundred and five", "105");
+    test_eval("two thousand three hundred", "2300");
+    test_eval("twelve hundred", "1200");
+    test_eval("one million two thousand and one", "1002001");
+    test_eval("twelve times two minus three", "21");
+    test_eval("minus five", "-5");
+    test_eval("two hundred fifty kg to g", "250000 g");
+    test_eval("(one hundred twenty three) / 3", "41");
+    test_eval_simple(
+        "one hundred twenty-three thousand four hundred fifty-six to words",
+        "one hundred twenty-three thousand four hundred fifty-six",
+    );
+    // single number words are still units that can be shadowed
+    test_eval("(one: one + one) 1", "2");
+    test_eval("one dozen", "12");
+    test_eval("5 hundred", "500");
+    // words that don't form a number aren't combined
+    test_eval("ten-two", "8");
+    test_eval("twenty thirty", "600");
+}
+
+#[test]
+fn numbers_in_words_are_reserved() {
+    let mut ctx = Context::new();
+    evaluate("one = 5", &mut ctx).unwrap();
+    assert_eq!(evaluate("one", &mut ctx).unwrap().get_main_result(), "5");
+    assert_eq!(
+        evaluate("one hundred", &mut ctx).unwrap().get_main_result(),
+        "100"
+    );
+}
+
+#[test]
+fn ratios() {
+    test_eval("1920:1080", "1920:1080");
+    test_eval("1920:1080 to ratio", "16:9");
+    test_eval("3840:2160 to ratio", "16:9");
+    test_eval("1024:768 to ratio", "4:3");
+    test_eval("1280:1024 to ratio", "5:4");
+    test_eval("2560:1080 to ratio", "64:27");
+    test_eval("2.39:1 to ratio", "239:100");
+    test_eval("1.5 to ratio", "3:2");
+    test_eval("16:9 to decimal", "approx. 1.7777777777");
+    test_eval_simple("16:9 to fraction", "16/9");
+    test_eval("16:9 == 32:18", "true");
+    test_eval("4:3 < 16:9", "true");
+    test_eval("[4:3, 16:9]", "[4:3, 16:9]");
+    // lambdas are unaffected
+    test_eval("(x:x) 5", "5");
+    expect_error("1920:0", Some("a ratio cannot have a zero component"));
+    expect_error("0:5 to ratio", Some("a ratio cannot have a zero component"));
+    expect_error("0 to ratio", Some("a ratio cannot have a zero component"));
+}
+
+#[test]
+fn serialize_ratio() {
+    let mut ctx = Context::new();
+    evaluate("screen = 1920:1080", &mut ctx).unwrap();
+    let mut data = vec![];
+    ctx.serialize_variables(&mut data).unwrap();
+    let mut restored = Context::new();
+    restored
+        .deserialize_variables(&mut data.as_slice())
+        .unwrap();
+    assert_eq!(
+        evaluate("screen to ratio", &mut restored)
+            .unwrap()
+            .get_main_result(),
+        "16:9"
+    );
+}
+
+#[test]
+fn significant_figures_annotation() {
+    test_eval("(3.14159 m) to mm sigfigs 3", "approx. 3140 mm");
+    test_eval("3.14159 m sigfigs 3", "approx. 3.14 m");
+    // the annotation survives arithmetic on either side
+    test_eval("(3.14159 m sigfigs 3) * 2", "approx. 6.28 m");
+    test_eval("2 * (3.14159 m sigfigs 3)", "approx. 6.28 m");
+    test_eval("(3.14159 m sigfigs 3) + 1 m", "approx. 4.14 m");
+    test_eval("1 m + (3.14159 m sigfigs 3)", "approx. 4.14 m");
+    // products are only as precise as their least precise factor
+    test_eval("(3.14159 m sigfigs 3) * (2.0 sigfigs 2)", "approx. 6.2 m");
+    test_eval("(3.14159 m sigfigs 3) / (2.0 m sigfigs 2)", "approx. 1.5");
+    expect_error(
+        "3.14159 sigfigs 0",
+        Some("cannot format a number with zero significant figures"),
+    );
+}
+
+#[test]
+fn absolute_and_percentage_change() {
+    test_eval("change(80, 100)", "20");
+    test_eval("change(100, 80)", "-20");
+    test_eval("delta(80 cm, 1 m)", "0.2 m");
+    test_eval("pct_change(80, 100)", "25%");
+    test_eval("pct_change(100, 80)", "-20%");
+    test_eval("pct_change(80 cm, 1 m)", "25%");
+    test_eval("pct_change(3, 4)", "approx. 33.3333333333%");
+    test_eval("pct_change(-50, -25)", "-50%");
+    expect_error(
+        "pct_change(0, 5)",
+        Some("the percentage change from zero is undefined"),
+    );
+    expect_error("pct_change(1 m, 2 kg)", None);
+    expect_error("change(1 m, 2 kg)", None);
+    expect_error("change(5)", Some("change expects 2 arguments"));
+}
+
+#[test]
+fn load_prelude() {
+    let mut ctx = Context::new();
+    ctx.load_prelude(
+        "# units and functions for this application
+smoot = 1.7018 m
+
+double = x: 2x
+hypot = a: b: sqrt(a^2 + b^2)
+",
+    )
+    .unwrap();
+    assert_eq!(
+        evaluate("10 smoot to cm", &mut ctx)
+            .unwrap()
+            .get_main_result(),
+        "1701.8 cm"
+    );
+    assert_eq!(
+        evaluate("double (hypot 3 4)", &mut ctx)
+            .unwrap()
+            .get_main_result(),
+        "10"
+    );
+}
+
+#[test]
+fn load_prelude_errors() {
+    let mut ctx = Context::new();
+    assert_eq!(
+        ctx.load_prelude("a = 2\nb = a + qqq\n3 + 4\nd = a * 5"),
+        Err("line 2: unknown identifier 'qqq'".to_string())
+    );
+    // results aren't kept, but the other lines are still applied
+    assert!(evaluate("hist(1)", &mut ctx).is_err());
+    assert_eq!(evaluate("d", &mut ctx).unwrap().get_main_result(), "10");
+}
+
+#[test]
+fn number_type_predicates() {
+    test_eval("is_integer(4/2)", "true");
+    test_eval("is_integer(1/2)", "false");
+    test_eval("is_integer(pi)", "false");
+    test_eval("is_rational(0.75)", "true");
+    test_eval("is_rational(sqrt 2)", "false");
+    test_eval("is_irrational(pi)", "true");
+    test_eval("is_irrational(sqrt 2)", "true");
+    test_eval("is_irrational(e)", "true");
+    test_eval("is_irrational(sqrt 4)", "false");
+    test_eval("is_irrational(i)", "false");
+    test_eval("is_complex(2 + 3i)", "true");
+    test_eval("is_complex(i * i)", "false");
+    test_eval("is_complex(5)", "false");
+    expect_error("is_integer(5 kg)", None);
+}
+
+#[test]
+fn unit_conversion_factor() {
+    let ctx = Context::new();
+    assert_eq!(conversion_factor("km", "m", &ctx).unwrap(), 1000.0);
+    assert_eq!(conversion_factor("m", "km", &ctx).unwrap(), 0.001);
+    assert_eq!(conversion_factor("hour", "s", &ctx).unwrap(), 3600.0);
+    assert!((conversion_factor("km/h", "m/s", &ctx).unwrap() - 1.0 / 3.6).abs() < 1e-12);
+    assert_eq!(conversion_factor("kelvin", "kelvin", &ctx).unwrap(), 1.0);
+    assert_eq!(
+        conversion_factor("°C", "°F", &ctx).unwrap_err(),
+        "there is no single conversion factor between units with different zero points, like °C and °F"
+    );
+    assert!(conversion_factor("celsius", "kelvin", &ctx).is_err());
+    assert!(conversion_factor("km", "kg", &ctx).is_err());
+    assert!(conversion_factor("qqq", "m", &ctx).is_err());
+}
+
+#[test]
+fn display_rounding() {
+    let mut ctx = Context::new();
+    let mut eval = |input: &str, ctx: &mut Context| {
+        evaluate(input, ctx).unwrap().get_main_result().to_string()
+    };
+    assert_eq!(eval("sinh(asinh 2)", &mut ctx), "approx. 1.9999999999");
+    ctx.set_display_rounding(true);
+    assert_eq!(eval("sinh(asinh 2)", &mut ctx), "approx. 2");
+    assert_eq!(eval("acos(cos 0.4)", &mut ctx), "approx. 0.4");
+    assert_eq!(eval("-acos(cos 0.4) kg", &mut ctx), "approx. -0.4 kg");
+    assert_eq!(eval("sin 1", &mut ctx), "approx. 0.8414709848");
+    assert_eq!(
+        eval("sin(1) * 10^20", &mut ctx),
+        "approx. 84147098480790000000"
+    );
+    assert_eq!(eval("exp(-20)", &mut ctx), "approx. 0.000000002");
+    // exact results are not rounded
+    assert_eq!(eval("2/3", &mut ctx), "approx. 0.6666666666");
+    assert_eq!(eval("0.1 + 0.2", &mut ctx), "0.3");
+    // neither are explicitly formatted results
+    assert_eq!(
+        eval("acos(cos 0.4) to 17 dp", &mut ctx),
+        eval("acos(cos 0.4) to 17 dp", &mut Context::new())
+    );
+    // or results computed to a higher precision
+    ctx.set_precision(30);
+    assert_eq!(
+        eval("sin(1) * 10^20", &mut ctx),
+        "approx. 84147098480789650665.250232163"
+    );
+}
+
+#[test]
+fn chained_unit_conversions() {
+    test_eval("100 °C to °F to K", "373.15 K");
+    test_eval("1 mile to km to m", "1609.344 m");
+    test_eval("1 km to m in cm as mm", "1000000 mm");
+    expect_error(
+        "1 km to m to kg to g",
+        Some("cannot convert from m to kg: units 'meter' and 'kilogram' are incompatible"),
+    );
+}
+
+#[test]
+fn locale_separators() {
+    let mut ctx = Context::new();
+    let mut eval = |input: &str, ctx: &mut Context| {
+        evaluate(input, ctx).unwrap().get_main_result().to_string()
+    };
+    assert_eq!(eval("1234567.5", &mut ctx), "1234567.5");
+    ctx.set_locale("de-DE").unwrap();
+    assert_eq!(eval("1234567.5", &mut ctx), "1.234.567,5");
+    assert_eq!(eval("1234.5 kg", &mut ctx), "1.234,5 kg");
+    assert_eq!(eval("123", &mut ctx), "123");
+    assert_eq!(eval("-0.125", &mut ctx), "-0,125");
+    assert_eq!(eval("1/3", &mut ctx), "approx. 0,3333333333");
+    assert_eq!(eval("1/3 to float", &mut ctx), "0,(3)");
+    assert_eq!(eval("1.5e20 to sci", &mut ctx), "1,5e20");
+    assert_eq!(eval("1.5 + 2.5i", &mut ctx), "1,5 + 2,5i");
+    assert_eq!(eval("0xffff", &mut ctx), "0xffff");
+    ctx.set_locale("en_us").unwrap();
+    assert_eq!(eval("1234567.5", &mut ctx), "1,234,567.5");
+    ctx.set_locale("fr-FR").unwrap();
+    assert_eq!(eval("12345.25", &mut ctx), "12\u{202f}345,25");
+    assert_eq!(
+        ctx.set_locale("xx-YY").unwrap_err(),
+        "unknown locale: xx-YY"
+    );
+    assert_eq!(eval("12345.25", &mut ctx), "12\u{202f}345,25");
+}
+
+#[test]
+fn evaluation_steps() {
+    let mut ctx = Context::new();
+    let trivial = evaluate("1 + 1", &mut ctx).unwrap().steps();
+    let heavy = evaluate("3^5000 * 7^5000", &mut ctx).unwrap().steps();
+    assert!(trivial > 0);
+    assert!(heavy > 100 * trivial, "{heavy} steps vs {trivial}");
+    assert_eq!(evaluate("", &mut ctx).unwrap().steps(), 0);
+}
+
+#[test]
+fn digit_grouping_by_base() {
+    let mut ctx = Context::new();
+    let mut eval = |input: &str, ctx: &mut Context| {
+        evaluate(input, ctx).unwrap().get_main_result().to_string()
+    };
+    assert_eq!(eval("0b11111111", &mut ctx), "0b11111111");
+    ctx.set_digit_grouping(true);
+    assert_eq!(eval("0b11111111", &mut ctx), "0b1111_1111");
+    assert_eq!(eval("0b111111111", &mut ctx), "0b1_1111_1111");
+    assert_eq!(eval("255 to binary", &mut ctx), "1111_1111");
+    assert_eq!(eval("2^32 - 1 to hex", &mut ctx), "ffff_ffff");
+    assert_eq!(eval("0xffff", &mut ctx), "0xffff");
+    assert_eq!(eval("255.5 to binary", &mut ctx), "1111_1111.1");
+    assert_eq!(eval("123456 to octal", &mut ctx), "361_100");
+    assert_eq!(eval("2^40 to base 36", &mut ctx), "e13wu1og");
+    assert_eq!(eval("1234567.1234567", &mut ctx), "1,234,567.1234567");
+    assert_eq!(eval("-1000 kg", &mut ctx), "-1,000 kg");
+    assert_eq!(eval("123", &mut ctx), "123");
+    assert_eq!(eval("1.5e20 to sci", &mut ctx), "1.5e20");
+    ctx.set_digit_group_size(16, 2);
+    ctx.set_digit_group_size(36, 4);
+    assert_eq!(eval("0xffff", &mut ctx), "0xff_ff");
+    assert_eq!(eval("2^40 to base 36", &mut ctx), "e13w_u1og");
+    ctx.set_digit_group_size(10, 0);
+    ctx.set_digit_group_separator(' ');
+    assert_eq!(eval("1234567", &mut ctx), "1234567");
+    assert_eq!(eval("0b11111111", &mut ctx), "0b1111 1111");
+    // grouped results can be used as input again
+    ctx.set_digit_group_separator('_');
+    assert_eq!(eval("0b1111_1111 + 1", &mut ctx), "0b1_0000_0000");
+}
+
+#[test]
+fn list_indexing() {
+    let mut ctx = Context::new();
+    let mut eval = |input: &str, ctx: &mut Context| {
+        evaluate(input, ctx).map(|r| r.get_main_result().to_string())
+    };
+    assert_eq!(eval("xs = [1, 2, 3]", &mut ctx).unwrap(), "[1, 2, 3]");
+    assert_eq!(eval("xs[2]", &mut ctx).unwrap(), "2");
+    assert_eq!(eval("xs[-1]", &mut ctx).unwrap(), "3");
+    assert_eq!(eval("[4, 5, 6][1] kg", &mut ctx).unwrap(), "4 kg");
+    assert_eq!(eval("xs[2] = 9; xs", &mut ctx).unwrap(), "[1, 9, 3]");
+    assert_eq!(eval("xs[-3] = 7", &mut ctx).unwrap(), "[7, 9, 3]");
+    assert_eq!(eval("xs[3] += 1; xs", &mut ctx).unwrap(), "[7, 9, 4]");
+    // indices start at 1, like the positions returned by `argmax`
+    assert_eq!(eval("xs[argmax(xs)]", &mut ctx).unwrap(), "9");
+    assert_eq!(eval("xs[argmin(xs)]", &mut ctx).unwrap(), "4");
+    assert_eq!(
+        eval("xs[4]", &mut ctx).unwrap_err(),
+        "index 4 is out of bounds for a list of 3 items"
+    );
+    assert_eq!(
+        eval("xs[0] = 1", &mut ctx).unwrap_err(),
+        "index 0 is out of bounds for a list of 3 items"
+    );
+    assert_eq!(
+        eval("xs[-4]", &mut ctx).unwrap_err(),
+        "index -4 is out of bounds for a list of 3 items"
+    );
+    assert!(eval("xs[1.5]", &mut ctx).is_err());
+    assert!(eval("xs[1, 2]", &mut ctx).is_err());
+    assert_eq!(
+        eval("ys[1] = 2", &mut ctx).unwrap_err(),
+        "variable 'ys' is not defined"
+    );
+    assert_eq!(
+        eval("n = 5; n[1] = 2", &mut ctx).unwrap_err(),
+        "expected a list (found number)"
+    );
+    assert_eq!(eval("xs", &mut ctx).unwrap(), "[7, 9, 4]");
+}
+
+#[test]
+fn zip_and_enumerate() {
+    test_eval_simple("zip([1, 2, 3], [4, 5, 6])", "[[1, 4], [2, 5], [3, 6]]");
+    test_eval_simple("zip([1, 2, 3], [4, 5])", "[[1, 4], [2, 5]]");
+    test_eval_simple("zip([1], [])", "[]");
+    test_eval_simple("enumerate([\"a\", \"b\"])", "[[1, \"a\"], [2, \"b\"]]");
+    test_eval_simple("enumerate([])", "[]");
+    // positions match indexing and `argmax`
+    test_eval("xs = [3, 9, 4]; enumerate(xs)[argmax(xs)]", "[2, 9]");
+    test_eval("xs = [3, 9, 4]; enumerate(xs)[2][2] == xs[2]", "true");
+    expect_error("zip([1, 2])", None);
+    expect_error("enumerate(5)", Some("expected a list (found number)"));
+}
+
+#[test]
+fn canonical_unit_order() {
+    use fend_core::UnitDisplayPreference;
+
+    // units that fend reduces to base units are always in the same order,
+    // while units as entered keep the order they were written in
+    let mut ctx = Context::new();
+    ctx.set_unit_display_preference(UnitDisplayPreference::Base);
+    for (inputs, expected) in [
+        (["kg m / s^2", "m kg / s^2"], "1 kg m / s^2"),
+        (["J/(kg K)", "J/(K kg)"], "1 m^2 s^-2 K^-1"),
+        (["A s", "s A"], "1 s A"),
+    ] {
+        for input in inputs {
+            assert_eq!(
+                evaluate(input, &mut ctx).unwrap().get_main_result(),
+                expected
+            );
+        }
+    }
+    for input in ["kg m / s^2 to K", "m kg / s^2 to K"] {
+        let error = evaluate(input, &mut Context::new()).unwrap_err();
+        assert!(
+            error.ends_with("units 'kilogram meter / second^2' and 'kelvin' are incompatible"),
+            "{error}"
+        );
+    }
+    test_eval("m kg / s^2", "1 m kg / s^2");
+    test_eval("1 coulomb to A s", "1 A s");
+}
+
+#[test]
+fn unknown_unit_behavior() {
+    use fend_core::UnknownUnitBehavior;
+
+    let eval = |input: &str, behavior| {
+        let mut context = Context::new();
+        context.set_unknown_unit_behavior(behavior);
+        evaluate(input, &mut context).map(|res| res.get_main_result().to_string())
+    };
+    assert_eq!(
+        eval("5 foo / foo", UnknownUnitBehavior::Error),
+        Err("unknown identifier 'foo'".to_string())
+    );
+    assert_eq!(
+        eval("5 foo / foo", UnknownUnitBehavior::Symbolic).unwrap(),
+        "5"
+    );
+    assert_eq!(
+        eval("2 foo * 3 foo", UnknownUnitBehavior::Symbolic).unwrap(),
+        "6 foo^2"
+    );
+    assert_eq!(
+        eval("6 foo / 2 m", UnknownUnitBehavior::Symbolic).unwrap(),
+        "3 foo m"
+    );
+    assert!(eval("5 foo to m", UnknownUnitBehavior::Symbolic).is_err());
+    assert_eq!(
+        eval("5 kg to g", UnknownUnitBehavior::Symbolic).unwrap(),
+        "5000 g"
+    );
+}
+
+#[test]
+fn complex_parts_and_argument() {
+    test_eval_simple("arg(i) == pi / 2", "true");
+    test_eval("arg(i) to degrees", "90 degrees");
+    test_eval("arg(-i) to degrees", "-90 degrees");
+    test_eval("arg(-1) to degrees", "180 degrees");
+    test_eval("arg(3 + 4i)", "approx. 0.927295218");
+    test_eval("arg(0)", "0");
+    test_eval("re(3 + 4i)", "3");
+    test_eval("im(3 + 4i)", "4");
+    test_eval("im(5)", "0");
+    test_eval("re((3 + 4i) m)", "3 m");
+    test_eval("conj(3 + 4i)", "3 - 4i");
+    test_eval("conjugate(3 + 4i)", "3 - 4i");
+}
+
+#[test]
+fn parse_keeps_ip_literals_as_written() {
+    use fend_core::syntax::AstNode;
+
+    assert_eq!(
+        fend_core::parse("192.168.1.1 + 5").unwrap(),
+        AstNode::BinaryOp {
+            op: "+".to_string(),
+            lhs: Box::new(AstNode::Ip("192.168.1.1".to_string())),
+            rhs: Box::new(AstNode::Number("5".to_string())),
+        }
+    );
+    assert_eq!(
+        fend_core::parse("2 * 10.0.0.0/8").unwrap().to_json(),
+        r#"{"type":"binary_op","op":"*","lhs":{"type":"number","value":"2"},"rhs":{"type":"ip","value":"10.0.0.0/8"}}"#
+    );
+}
+
+#[test]
+fn parse_keeps_ratio_literals_as_written() {
+    use fend_core::syntax::AstNode;
+
+    assert_eq!(
+        fend_core::parse("1920:1080 + 5").unwrap(),
+        AstNode::BinaryOp {
+            op: "+".to_string(),
+            lhs: Box::new(AstNode::Ratio("1920:1080".to_string())),
+            rhs: Box::new(AstNode::Number("5".to_string())),
+        }
+    );
+    assert_eq!(
+        fend_core::parse("2 * 16:9 to ratio").unwrap().to_json(),
+        r#"{"type":"conversion","value":{"type":"binary_op","op":"*","lhs":{"type":"number","value":"2"},"rhs":{"type":"ratio","value":"16:9"}},"target":{"type":"ident","name":"ratio"}}"#
+    );
+}
+
+#[test]
+fn parse_keeps_number_words_as_written() {
+    use fend_core::syntax::AstNode;
+
+    assert_eq!(
+        fend_core::parse("one hundred + 5").unwrap(),
+        AstNode::BinaryOp {
+            op: "+".to_string(),
+            lhs: Box::new(AstNode::Number("one hundred".to_string())),
+            rhs: Box::new(AstNode::Number("5".to_string())),
+        }
+    );
+    assert_eq!(
+        fend_core::parse("/* c */ twenty-one thousand * 2")
+            .unwrap()
+            .to_json(),
+        r#"{"type":"binary_op","op":"*","lhs":{"type":"number","value":"twenty-one thousand"},"rhs":{"type":"number","value":"2"}}"#
+    );
+    assert_eq!(fend_core::normalize("one hundred + 5").unwrap(), "(100+5)");
+}
+
+#[test]
+fn approx_equal_with_parentheses() {
+    test_eval_simple("approx_equal(3.14, pi, 0.001)", "true");
+    test_eval_simple("approx_equal(3.14, pi, 0.0001)", "false");
+    test_eval_simple("approx_equal(1 km, 1001 m, 0.001)", "true");
+    test_eval_simple("approx_equal 3.14 pi 0.001", "true");
+    expect_error(
+        "approx_equal(3.14, pi)",
+        Some("approx_equal expects 3 arguments"),
+    );
+}
+
+#[test]
+fn max_recurring_digits_default() {
+    let mut ctx = Context::new();
+    let result = evaluate("1/223 to float", &mut ctx).unwrap();
+    let result = result.get_main_result();
+    // the period of 1/223 is 222 digits, which is more than the default 200
+    assert!(result.starts_with("0.0044843049"), "{result}");
+    assert!(result.ends_with("..."), "{result}");
+    assert_eq!(result.len(), "0.".len() + 200 + "...".len());
+    ctx.set_max_recurring_digits(usize::MAX);
+    assert!(evaluate("1/223 to float", &mut ctx)
+        .unwrap()
+        .get_main_result()
+        .ends_with(')'));
+}
+
+#[test]
+fn max_float_digits_default() {
+    let mut ctx = Context::new();
+    let mut eval = |input: &str| {
+        evaluate(input, &mut ctx)
+            .unwrap()
+            .get_main_result()
+            .to_string()
+    };
+    // the period of 1/999983 is 999982 digits
+    let result = eval("1/999983 to float");
+    assert!(result.starts_with("approx. 0.000001000017"), "{result}");
+    assert!(result.ends_with("..."), "{result}");
+    assert_eq!(result.len(), "approx. 0.".len() + 500 + "...".len());
+    // 3e-600 has 600 digits after the decimal point
+    let result = eval("3e-600 to float");
+    assert!(result.starts_with("approx. 0.0000"), "{result}");
+    assert_eq!(result.len(), "approx. 0.".len() + 500 + "...".len());
+    assert_eq!(eval("1/7 to float"), "0.(142857)");
+    assert_eq!(eval("1/1024 to float"), "0.0009765625");
+}
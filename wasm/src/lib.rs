@@ -1,5 +1,6 @@
 #![allow(unused_unsafe)]
 
+use fend_core::PeriodicInterrupt;
 use instant::Instant;
 use std::fmt::Write;
 use wasm_bindgen::prelude::*;
@@ -10,11 +11,16 @@ struct TimeoutInterrupt {
 }
 
 impl TimeoutInterrupt {
-    fn new_with_timeout(timeout: u128) -> Self {
-        Self {
-            start: Instant::now(),
-            timeout,
-        }
+    // reading the current time is relatively slow, so it's only checked
+    // once every 100 calls
+    fn new_with_timeout(timeout: u128) -> PeriodicInterrupt<Self> {
+        PeriodicInterrupt::new(
+            Self {
+                start: Instant::now(),
+                timeout,
+            },
+            100,
+        )
     }
 }
 
@@ -48,6 +54,8 @@ fn create_context() -> fend_core::Context {
         date.get_timezone_offset() as i64 * 60,
     );
     ctx.set_random_u32_fn(random_u32);
+    // fail instead of running out of memory, which would crash the page
+    ctx.set_memory_limit(16 * 1024 * 1024);
     ctx
 }
 
@@ -66,10 +74,45 @@ pub fn evaluate_fend_with_timeout(input: &str, timeout: u32) -> String {
     }
 }
 
+/// Like `evaluateFendWithTimeout`, but returns the result as LaTeX markup
+#[wasm_bindgen(js_name = evaluateFendLatex)]
+pub fn evaluate_fend_latex(input: &str, timeout: u32) -> String {
+    let mut ctx = create_context();
+    let interrupt = TimeoutInterrupt::new_with_timeout(u128::from(timeout));
+    match fend_core::evaluate_with_interrupt(input, &mut ctx, &interrupt) {
+        Ok(res) => {
+            if res.is_unit_type() {
+                return "".to_string();
+            }
+            res.to_latex()
+        }
+        Err(msg) => format!("Error: {msg}"),
+    }
+}
+
+/// Like `evaluateFendWithTimeout`, but returns the result as a MathML element
+#[wasm_bindgen(js_name = evaluateFendMathml)]
+pub fn evaluate_fend_mathml(input: &str, timeout: u32) -> String {
+    let mut ctx = create_context();
+    let interrupt = TimeoutInterrupt::new_with_timeout(u128::from(timeout));
+    match fend_core::evaluate_with_interrupt(input, &mut ctx, &interrupt) {
+        Ok(res) => {
+            if res.is_unit_type() {
+                return "".to_string();
+            }
+            res.to_mathml()
+        }
+        Err(msg) => format!("Error: {msg}"),
+    }
+}
+
 /// Takes a '\0'-separated string of inputs, and returns a '\0'-separated string of results
+/// Each input can refer to the previous result via `ans` or `prev`
 #[wasm_bindgen(js_name = evaluateFendWithTimeoutMultiple)]
 pub fn evaluate_fend_with_timeout_multiple(inputs: &str, timeout: u32) -> String {
     let mut ctx = create_context();
+    // worksheets often repeat the same lines
+    ctx.set_parse_cache_size(256);
     let mut result = String::new();
     for input in inputs.split('\0') {
         if !result.is_empty() {
@@ -127,9 +170,32 @@ pub fn evaluate_fend_with_variables_json(input: &str, timeout: u32, variables: &
                 }
                 hex
             };
+            let warnings = {
+                let mut warnings = String::new();
+                for (i, warning) in res.warnings().iter().enumerate() {
+                    if i > 0 {
+                        warnings.push(',');
+                    }
+                    warnings.push('"');
+                    fend_core::json::escape_string(warning, &mut warnings);
+                    warnings.push('"');
+                }
+                warnings
+            };
+            let assignment = match res.assignment() {
+                Some((name, value)) => {
+                    let mut assignment = r#"{"name":""#.to_string();
+                    fend_core::json::escape_string(name, &mut assignment);
+                    assignment.push_str(r#"","value":""#);
+                    fend_core::json::escape_string(value, &mut assignment);
+                    assignment.push_str(r#""}"#);
+                    assignment
+                }
+                None => "null".to_string(),
+            };
             format!(
-                r#"{{"ok":true,"result":"{}","variables":"{}"}}"#,
-                escaped_result, variables
+                r#"{{"ok":true,"result":"{}","variables":"{}","warnings":[{}],"assignment":{}}}"#,
+                escaped_result, variables, warnings, assignment
             )
         }
         Err(msg) => {
@@ -140,6 +206,34 @@ pub fn evaluate_fend_with_variables_json(input: &str, timeout: u32, variables: &
     }
 }
 
+/// Evaluates the input with the given variables (as returned by
+/// `evaluateFendWithVariablesJson`), but discards any assignments, so that
+/// input can be previewed while it is being typed
+#[wasm_bindgen(js_name = fendPreview)]
+pub fn fend_preview(input: &str, timeout: u32, variables: &str) -> String {
+    let mut ctx = create_context();
+    if !variables.is_empty() {
+        if let Ok(variables) = decode_hex(variables) {
+            let _ = ctx.deserialize_variables(&mut variables.as_slice());
+        }
+    }
+    let interrupt = TimeoutInterrupt::new_with_timeout(u128::from(timeout));
+    match ctx.evaluate_preview(input, true, &interrupt) {
+        Ok(res) => {
+            let mut escaped_result = String::new();
+            if !res.is_unit_type() {
+                fend_core::json::escape_string(res.get_main_result(), &mut escaped_result);
+            }
+            format!(r#"{{"ok":true,"result":"{}"}}"#, escaped_result)
+        }
+        Err(msg) => {
+            let mut escaped = String::new();
+            fend_core::json::escape_string(&msg, &mut escaped);
+            format!(r#"{{"ok":false,"message":"{}"}}"#, escaped)
+        }
+    }
+}
+
 #[wasm_bindgen(js_name = substituteInlineFendExpressions)]
 pub fn substitute_inline_fend_expressions(input: &str, timeout: u32) -> String {
     let mut ctx = create_context();
@@ -147,3 +241,16 @@ pub fn substitute_inline_fend_expressions(input: &str, timeout: u32) -> String {
     let res = fend_core::substitute_inline_fend_expressions(input, &mut ctx, &interrupt);
     res.to_json()
 }
+
+/// Returns the syntax tree of the given input as JSON, without evaluating it
+#[wasm_bindgen(js_name = fendParse)]
+pub fn fend_parse(input: &str) -> String {
+    match fend_core::parse(input) {
+        Ok(ast) => format!(r#"{{"ok":true,"ast":{}}}"#, ast.to_json()),
+        Err(msg) => {
+            let mut escaped = String::new();
+            fend_core::json::escape_string(&msg, &mut escaped);
+            format!(r#"{{"ok":false,"message":"{}"}}"#, escaped)
+        }
+    }
+}